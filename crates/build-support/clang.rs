@@ -0,0 +1,28 @@
+// Helpers for the build scripts of the PUTs which are compiled with clang. This file is included
+// through `include!` by crates/libressl-src, crates/libssh-sys and crates/wolfssl-sys.
+
+fn clang_resource_dir() -> String {
+    let output = std::process::Command::new("clang")
+        .args(["--print-resource-dir"])
+        .output()
+        .expect("failed to clang to get resource dir");
+    std::str::from_utf8(&output.stdout)
+        .unwrap()
+        .trim()
+        .to_string()
+}
+
+/// Links the shared UBSAN runtime of clang, which `-shared-libsan` expects. The libubsan of GCC is
+/// not compatible with code which is instrumented by clang. `arch` is either the target
+/// architecture of Cargo or the first component of a target triple.
+fn link_clang_ubsan(resource_dir: &str, arch: &str) {
+    let arch = match arch {
+        "x86" | "i586" | "i686" => "i386",
+        arch => arch,
+    };
+    println!("cargo:rustc-link-search=native={}/lib/linux/", resource_dir);
+    println!(
+        "cargo:rustc-link-lib=dylib=clang_rt.ubsan_standalone-{}",
+        arch
+    );
+}
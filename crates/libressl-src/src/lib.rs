@@ -29,6 +29,8 @@ fn clone(dest: &PathBuf) -> std::io::Result<()> {
     Ok(())
 }
 
+include!("../../build-support/clang.rs");
+
 pub fn version() -> &'static str {
     env!("CARGO_PKG_VERSION")
 }
@@ -157,7 +159,24 @@ impl Build {
         );
 
         if cfg!(feature = "sancov") {
-            cc.push_str(" -fsanitize-coverage=trace-pc-guard");
+            cc.push_str(" -fsanitize-coverage=trace-pc-guard,trace-cmp");
+        }
+
+        if cfg!(feature = "coverage") {
+            cc.push_str(" -fprofile-instr-generate -fcoverage-mapping");
+        }
+
+        if cfg!(feature = "ubsan") {
+            // Errors are recoverable, such that UBSAN_OPTIONS decides whether to abort
+            cc.push_str(" -fsanitize=undefined -shared-libsan");
+            link_clang_ubsan(&clang_resource_dir(), target.split('-').next().unwrap());
+        }
+
+        if cfg!(feature = "msan") {
+            // The runtime is linked by rustc through `-Z sanitizer=memory`. Assembly is not
+            // instrumented and causes false positives.
+            cc.push_str(" -fsanitize=memory -fsanitize-memory-track-origins");
+            cfg.disable("asm", None);
         }
 
         cfg.env("CC", cc);
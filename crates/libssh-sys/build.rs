@@ -22,6 +22,8 @@ impl bindgen::callbacks::ParseCallbacks for IgnoreMacros {
     }
 }
 
+include!("../build-support/clang.rs");
+
 const REF: &str = if cfg!(feature = "vendored-libssh096") {
     "libssh-0.9.6"
 } else {
@@ -53,7 +55,13 @@ fn build(source_dir: &str) -> PathBuf {
         .define("BUILD_STATIC_LIB", "ON");
 
     if cfg!(feature = "sancov") {
-        config.cflag("-fsanitize-coverage=trace-pc-guard");
+        config.cflag("-fsanitize-coverage=trace-pc-guard,trace-cmp");
+    }
+
+    if cfg!(feature = "coverage") {
+        config
+            .cflag("-fprofile-instr-generate")
+            .cflag("-fcoverage-mapping");
     }
 
     if cfg!(feature = "asan") {
@@ -61,6 +69,22 @@ fn build(source_dir: &str) -> PathBuf {
         println!("cargo:rustc-link-lib=asan");
     }
 
+    if cfg!(feature = "ubsan") {
+        // Errors are recoverable, such that UBSAN_OPTIONS decides whether to abort
+        config.cflag("-fsanitize=undefined").cflag("-shared-libsan");
+        link_clang_ubsan(
+            &clang_resource_dir(),
+            &env::var("CARGO_CFG_TARGET_ARCH").unwrap(),
+        );
+    }
+
+    if cfg!(feature = "msan") {
+        // The runtime is linked by rustc through `-Z sanitizer=memory`
+        config
+            .cflag("-fsanitize=memory")
+            .cflag("-fsanitize-memory-track-origins");
+    }
+
     config.build()
 }
 
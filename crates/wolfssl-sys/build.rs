@@ -64,6 +64,8 @@ pub fn insert_claim_interface(additional_headers: &PathBuf) -> std::io::Result<(
     Ok(())
 }
 
+include!("../build-support/clang.rs");
+
 fn build_wolfssl(dest: &str) -> PathBuf {
     let cc = "clang".to_owned();
 
@@ -82,6 +84,9 @@ fn build_wolfssl(dest: &str) -> PathBuf {
         .enable("keygen", None) // Support for RSA certs
         .enable("certgen", None) // Support x509 decoding
         .enable("tls13", None)
+        .enable("oldtls", None) // TLS 1.1 and below
+        .enable("tlsv10", None)
+        .enable("sslv3", None)
         .enable("aesni", None)
         .enable("dtls", None)
         .enable("sp", None) // FIXME: Fixes a memory leak?
@@ -100,16 +105,24 @@ fn build_wolfssl(dest: &str) -> PathBuf {
         //.cflag("-g")// FIXME: Reenable?
         .cflag("-fPIC");
 
+    if cfg!(feature = "ech") {
+        // ECH requires HPKE and the SNI of the public name
+        config.enable("ech", None).enable("sni", None);
+    }
+
     if cfg!(feature = "sancov") {
-        config.cflag("-fsanitize-coverage=trace-pc-guard");
+        config.cflag("-fsanitize-coverage=trace-pc-guard,trace-cmp");
+    }
+
+    if cfg!(feature = "coverage") {
+        // Source-based coverage. The profiling runtime is linked by rustc through `-C instrument-coverage`
+        config
+            .cflag("-fprofile-instr-generate")
+            .cflag("-fcoverage-mapping");
     }
 
     if cfg!(feature = "asan") {
-        let output = Command::new("clang")
-            .args(["--print-resource-dir"])
-            .output()
-            .expect("failed to clang to get resource dir");
-        let clang: &str = std::str::from_utf8(&output.stdout).unwrap().trim();
+        let clang = clang_resource_dir();
 
         config
             .cflag("-fsanitize=address")
@@ -118,6 +131,29 @@ fn build_wolfssl(dest: &str) -> PathBuf {
         println!("cargo:rustc-link-lib=asan");
     }
 
+    if cfg!(feature = "ubsan") {
+        let clang = clang_resource_dir();
+
+        // Errors are recoverable, such that UBSAN_OPTIONS decides whether to abort
+        config
+            .cflag("-fsanitize=undefined")
+            .cflag("-shared-libsan")
+            .cflag(format!("-Wl,-rpath={}/lib/linux/", clang));
+        link_clang_ubsan(&clang, &env::var("CARGO_CFG_TARGET_ARCH").unwrap());
+    }
+
+    if cfg!(feature = "msan") {
+        // The runtime is linked by rustc through `-Z sanitizer=memory`. Assembly is not
+        // instrumented and causes false positives.
+        config
+            .cflag("-fsanitize=memory")
+            .cflag("-fsanitize-memory-track-origins")
+            .disable("aesni", None)
+            .disable("intelasm", None)
+            .disable("sp-asm", None)
+            .disable("asm", None);
+    }
+
     if cfg!(feature = "additional-headers") {
         let additional_headers = PathBuf::from(dest).join("additional_headers");
 
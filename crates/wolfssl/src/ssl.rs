@@ -62,6 +62,18 @@ impl SslMethod {
         unsafe { SslMethod(wolf::wolfTLSv1_2_client_method()) }
     }
 
+    pub fn tls_client_11() -> SslMethod {
+        unsafe { SslMethod(wolf::wolfTLSv1_1_client_method()) }
+    }
+
+    pub fn tls_client_10() -> SslMethod {
+        unsafe { SslMethod(wolf::wolfTLSv1_client_method()) }
+    }
+
+    pub fn ssl_client_3() -> SslMethod {
+        unsafe { SslMethod(wolf::wolfSSLv3_client_method()) }
+    }
+
     pub fn tls_server_13() -> SslMethod {
         unsafe { SslMethod(wolf::wolfTLSv1_3_server_method()) }
     }
@@ -70,6 +82,18 @@ impl SslMethod {
         unsafe { SslMethod(wolf::wolfTLSv1_2_server_method()) }
     }
 
+    pub fn tls_server_11() -> SslMethod {
+        unsafe { SslMethod(wolf::wolfTLSv1_1_server_method()) }
+    }
+
+    pub fn tls_server_10() -> SslMethod {
+        unsafe { SslMethod(wolf::wolfTLSv1_server_method()) }
+    }
+
+    pub fn ssl_server_3() -> SslMethod {
+        unsafe { SslMethod(wolf::wolfSSLv3_server_method()) }
+    }
+
     pub unsafe fn from_ptr(ptr: *mut wolf::WOLFSSL_METHOD) -> SslMethod {
         SslMethod(ptr)
     }
@@ -291,6 +315,29 @@ impl SslContextRef {
     pub fn set_num_tickets(&mut self, n: u64) -> Result<(), ErrorStack> {
         unsafe { cvt(wolf::wolfSSL_CTX_set_num_tickets(self.as_ptr(), n)).map(|_| ()) }
     }
+
+    /// Generates an Encrypted Client Hello config with a fresh HPKE key pair, which is offered
+    /// through the `retry_configs` of servers
+    #[cfg(feature = "ech")]
+    pub fn generate_ech_config(
+        &mut self,
+        public_name: &str,
+        kem_id: u16,
+        kdf_id: u16,
+        aead_id: u16,
+    ) -> Result<(), ErrorStack> {
+        let public_name = CString::new(public_name).unwrap();
+        unsafe {
+            cvt(wolf::wolfSSL_CTX_GenerateEchConfig(
+                self.as_ptr(),
+                public_name.as_ptr(),
+                kem_id,
+                kdf_id,
+                aead_id,
+            ))
+            .map(|_| ())
+        }
+    }
 }
 
 /// WolfSSL library initialization (done only once statically)
@@ -497,6 +544,15 @@ impl SslRef {
         }
     }
 
+    /// Returns the protocol version of the connection as `(major, minor)`, e.g. `(3, 3)` for
+    /// TLS 1.2. Until the version is negotiated this is the highest enabled version.
+    pub fn version(&self) -> (u8, u8) {
+        unsafe {
+            let version = (*self.as_ptr()).version;
+            (version.major, version.minor)
+        }
+    }
+
     pub fn get_accept_state(&self) -> u32 {
         unsafe { (*self.as_ptr()).options.acceptState as u32 }
     }
@@ -537,28 +593,30 @@ impl SslRef {
                 wolf::AcceptStateTls13_TLS13_TICKET_SENT => "TLS13_TICKET_SENT",
                 _ => "Unknown",
             },
-            TLSVersion::V1_2 => match state as u32 {
-                wolf::AcceptState_ACCEPT_BEGIN => "ACCEPT_BEGIN",
-                wolf::AcceptState_ACCEPT_BEGIN_RENEG => "ACCEPT_BEGIN_RENEG",
-                wolf::AcceptState_ACCEPT_CLIENT_HELLO_DONE => "ACCEPT_CLIENT_HELLO_DONE",
-                wolf::AcceptState_ACCEPT_HELLO_RETRY_REQUEST_DONE => {
-                    "ACCEPT_HELLO_RETRY_REQUEST_DONE"
+            TLSVersion::V1_2 | TLSVersion::V1_1 | TLSVersion::V1_0 | TLSVersion::SSL3 => {
+                match state as u32 {
+                    wolf::AcceptState_ACCEPT_BEGIN => "ACCEPT_BEGIN",
+                    wolf::AcceptState_ACCEPT_BEGIN_RENEG => "ACCEPT_BEGIN_RENEG",
+                    wolf::AcceptState_ACCEPT_CLIENT_HELLO_DONE => "ACCEPT_CLIENT_HELLO_DONE",
+                    wolf::AcceptState_ACCEPT_HELLO_RETRY_REQUEST_DONE => {
+                        "ACCEPT_HELLO_RETRY_REQUEST_DONE"
+                    }
+                    wolf::AcceptState_ACCEPT_FIRST_REPLY_DONE => "ACCEPT_FIRST_REPLY_DONE",
+                    wolf::AcceptState_SERVER_HELLO_SENT => "SERVER_HELLO_SENT",
+                    wolf::AcceptState_CERT_SENT => "CERT_SENT",
+                    wolf::AcceptState_CERT_VERIFY_SENT => "CERT_VERIFY_SENT",
+                    wolf::AcceptState_CERT_STATUS_SENT => "CERT_STATUS_SENT",
+                    wolf::AcceptState_KEY_EXCHANGE_SENT => "KEY_EXCHANGE_SENT",
+                    wolf::AcceptState_CERT_REQ_SENT => "CERT_REQ_SENT",
+                    wolf::AcceptState_SERVER_HELLO_DONE => "SERVER_HELLO_DONE",
+                    wolf::AcceptState_ACCEPT_SECOND_REPLY_DONE => "ACCEPT_SECOND_REPLY_DONE",
+                    wolf::AcceptState_TICKET_SENT => "TICKET_SENT",
+                    wolf::AcceptState_CHANGE_CIPHER_SENT => "CHANGE_CIPHER_SENT",
+                    wolf::AcceptState_ACCEPT_FINISHED_DONE => "ACCEPT_FINISHED_DONE",
+                    wolf::AcceptState_ACCEPT_THIRD_REPLY_DONE => "ACCEPT_THIRD_REPLY_DONE",
+                    _ => "Unknown",
                 }
-                wolf::AcceptState_ACCEPT_FIRST_REPLY_DONE => "ACCEPT_FIRST_REPLY_DONE",
-                wolf::AcceptState_SERVER_HELLO_SENT => "SERVER_HELLO_SENT",
-                wolf::AcceptState_CERT_SENT => "CERT_SENT",
-                wolf::AcceptState_CERT_VERIFY_SENT => "CERT_VERIFY_SENT",
-                wolf::AcceptState_CERT_STATUS_SENT => "CERT_STATUS_SENT",
-                wolf::AcceptState_KEY_EXCHANGE_SENT => "KEY_EXCHANGE_SENT",
-                wolf::AcceptState_CERT_REQ_SENT => "CERT_REQ_SENT",
-                wolf::AcceptState_SERVER_HELLO_DONE => "SERVER_HELLO_DONE",
-                wolf::AcceptState_ACCEPT_SECOND_REPLY_DONE => "ACCEPT_SECOND_REPLY_DONE",
-                wolf::AcceptState_TICKET_SENT => "TICKET_SENT",
-                wolf::AcceptState_CHANGE_CIPHER_SENT => "CHANGE_CIPHER_SENT",
-                wolf::AcceptState_ACCEPT_FINISHED_DONE => "ACCEPT_FINISHED_DONE",
-                wolf::AcceptState_ACCEPT_THIRD_REPLY_DONE => "ACCEPT_THIRD_REPLY_DONE",
-                _ => "Unknown",
-            },
+            }
         }
     }
 }
@@ -76,6 +76,28 @@ pub struct AgentDescriptor {
     ///
     /// Default: true
     pub server_authentication: bool,
+    /// If agent is a server:
+    ///   Accept 0-RTT data of resumed sessions.
+    /// If agent is a client:
+    ///   No effect, early data is sent by the attacker.
+    ///
+    /// Default: false
+    pub early_data: bool,
+    /// If agent is a server:
+    ///   Offer Encrypted Client Hello and decrypt the ClientHelloInner.
+    /// If agent is a client:
+    ///   No effect, ECH is sent by the attacker.
+    ///
+    /// Default: false
+    pub ech: bool,
+    /// If agent is a server:
+    ///   Offer the finite field Diffie-Hellman key exchange of TLS 1.2 and older with the ffdhe2048
+    ///   group.
+    /// If agent is a client:
+    ///   No effect, the client accepts the group which the server offers.
+    ///
+    /// Default: false
+    pub ffdhe: bool,
 }
 
 impl Default for AgentDescriptor {
@@ -87,6 +109,9 @@ impl Default for AgentDescriptor {
             try_reuse: false,
             client_authentication: false,
             server_authentication: true,
+            early_data: false,
+            ech: false,
+            ffdhe: false,
         }
     }
 }
@@ -135,6 +160,11 @@ impl AgentDescriptor {
 pub enum TLSVersion {
     V1_3,
     V1_2,
+    /// Legacy versions which are only supported by some PUTs. They share the handshake of TLS 1.2
+    /// but derive their keys with the legacy PRF.
+    V1_1,
+    V1_0,
+    SSL3,
 }
 
 /// An [`Agent`] holds a non-cloneable reference to a Stream.
@@ -2,11 +2,13 @@
 //! This also implements the serializability of terms.
 //!
 use std::{
+    any::Any,
     fmt,
     fmt::Formatter,
     hash::{Hash, Hasher},
 };
 
+use once_cell::sync::Lazy;
 use rand::random;
 use serde::{Deserialize, Serialize};
 
@@ -153,6 +155,117 @@ impl fmt::Display for Function {
     }
 }
 
+/// The concrete value of a [`Literal`]
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum LiteralValue {
+    U8(u8),
+    U16(u16),
+    U32(u32),
+    U64(u64),
+    Bytes(Vec<u8>),
+}
+
+impl LiteralValue {
+    pub fn type_shape(&self) -> &'static TypeShape {
+        static U8: Lazy<TypeShape> = Lazy::new(TypeShape::of::<u8>);
+        static U16: Lazy<TypeShape> = Lazy::new(TypeShape::of::<u16>);
+        static U32: Lazy<TypeShape> = Lazy::new(TypeShape::of::<u32>);
+        static U64: Lazy<TypeShape> = Lazy::new(TypeShape::of::<u64>);
+        static BYTES: Lazy<TypeShape> = Lazy::new(TypeShape::of::<Vec<u8>>);
+
+        match self {
+            LiteralValue::U8(_) => &U8,
+            LiteralValue::U16(_) => &U16,
+            LiteralValue::U32(_) => &U32,
+            LiteralValue::U64(_) => &U64,
+            LiteralValue::Bytes(_) => &BYTES,
+        }
+    }
+
+    pub fn boxed_any(&self) -> Box<dyn Any> {
+        match self {
+            LiteralValue::U8(value) => Box::new(*value),
+            LiteralValue::U16(value) => Box::new(*value),
+            LiteralValue::U32(value) => Box::new(*value),
+            LiteralValue::U64(value) => Box::new(*value),
+            LiteralValue::Bytes(value) => Box::new(value.clone()),
+        }
+    }
+}
+
+impl fmt::Display for LiteralValue {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            LiteralValue::U8(value) => write!(f, "{:#04x}", value),
+            LiteralValue::U16(value) => write!(f, "{:#06x}", value),
+            LiteralValue::U32(value) => write!(f, "{:#010x}", value),
+            LiteralValue::U64(value) => write!(f, "{:#018x}", value),
+            LiteralValue::Bytes(value) => {
+                write!(f, "[")?;
+                for byte in value {
+                    write!(f, "{:02x}", byte)?;
+                }
+                write!(f, "]")
+            }
+        }
+    }
+}
+
+/// A concrete value with fixed type. In contrast to [`Function`]s, literals are not part of a
+/// signature. They are introduced by mutators which learn interesting values during execution.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct Literal {
+    /// Unique ID of this literal. Uniqueness is guaranteed across all [`Term`]s ever created.
+    /// Cloning changes this ID.
+    pub unique_id: u32,
+    /// ID of this literal. This id stays the same during cloning.
+    pub resistant_id: u32,
+    pub value: LiteralValue,
+}
+
+impl Hash for Literal {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.value.hash(state);
+    }
+}
+
+impl Eq for Literal {}
+impl PartialEq for Literal {
+    fn eq(&self, other: &Self) -> bool {
+        self.value == other.value
+    }
+}
+
+impl Clone for Literal {
+    fn clone(&self) -> Self {
+        Literal {
+            unique_id: random(),
+            resistant_id: self.resistant_id,
+            value: self.value.clone(),
+        }
+    }
+}
+
+impl Literal {
+    pub fn new(value: LiteralValue) -> Self {
+        Self {
+            unique_id: random(),
+            resistant_id: random(),
+            value,
+        }
+    }
+
+    pub fn typ(&self) -> &'static TypeShape {
+        self.value.type_shape()
+    }
+}
+
+impl fmt::Display for Literal {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}/{}", self.value, remove_prefix(self.typ().name))
+    }
+}
+
 mod fn_container {
     use std::{
         fmt,
@@ -5,7 +5,7 @@ use std::{any::Any, fmt, fmt::Formatter};
 use itertools::Itertools;
 use serde::{Deserialize, Serialize};
 
-use super::atoms::{Function, Variable};
+use super::atoms::{Function, Literal, Variable};
 use crate::{
     algebra::{dynamic_function::TypeShape, error::FnError, Matcher},
     error::Error,
@@ -26,6 +26,9 @@ pub enum Term<M: Matcher> {
     /// A `Term` that is an application of an [`Function`] with arity 0 applied to 0 `Term`s can be considered a constant.
     ///
     Application(Function, Vec<Term<M>>),
+    /// A concrete value (e.g. `0x0303`). See [`Literal`] for more information.
+    ///
+    Literal(Literal),
 }
 
 impl<M: Matcher> fmt::Display for Term<M> {
@@ -39,6 +42,7 @@ impl<M: Matcher> Term<M> {
         match self {
             Term::Variable(v) => v.resistant_id,
             Term::Application(f, _) => f.resistant_id,
+            Term::Literal(l) => l.resistant_id,
         }
     }
 
@@ -48,6 +52,7 @@ impl<M: Matcher> Term<M> {
             Term::Application(_, ref subterms) => {
                 subterms.iter().map(|subterm| subterm.size()).sum::<usize>() + 1
             }
+            Term::Literal(_) => 1,
         }
     }
 
@@ -59,6 +64,9 @@ impl<M: Matcher> Term<M> {
             Term::Application(_, ref subterms) => {
                 subterms.is_empty() // constant
             }
+            Term::Literal(_) => {
+                true // literal
+            }
         }
     }
 
@@ -66,6 +74,7 @@ impl<M: Matcher> Term<M> {
         match self {
             Term::Variable(v) => &v.typ,
             Term::Application(function, _) => &function.shape().return_type,
+            Term::Literal(literal) => literal.typ(),
         }
     }
 
@@ -73,6 +82,7 @@ impl<M: Matcher> Term<M> {
         match self {
             Term::Variable(v) => v.typ.name,
             Term::Application(function, _) => function.name(),
+            Term::Literal(literal) => literal.typ().name,
         }
     }
 
@@ -100,6 +110,7 @@ impl<M: Matcher> Term<M> {
                     )
                 }
             }
+            Term::Literal(ref literal) => format!("{}{}", tabs, literal),
         }
     }
 
@@ -132,13 +143,14 @@ impl<M: Matcher> Term<M> {
                 let result: Result<Box<dyn Any>, FnError> = dynamic_fn(&dynamic_args);
                 result.map_err(Error::Fn)
             }
+            Term::Literal(literal) => Ok(literal.value.boxed_any()),
         }
     }
 }
 
 fn append<'a, M: Matcher>(term: &'a Term<M>, v: &mut Vec<&'a Term<M>>) {
     match *term {
-        Term::Variable(_) => {}
+        Term::Variable(_) | Term::Literal(_) => {}
         Term::Application(_, ref subterms) => {
             for subterm in subterms {
                 append(subterm, v);
@@ -198,7 +210,7 @@ impl<M: Matcher> Subterms<M> for Vec<Term<M>> {
 
         for (i, subterm) in self.iter().enumerate() {
             match &subterm {
-                Term::Variable(_) => {}
+                Term::Variable(_) | Term::Literal(_) => {}
                 Term::Application(_, grand_subterms) => {
                     found_grand_subterms.extend(
                         grand_subterms
@@ -2,26 +2,41 @@ use std::{
     env, fs,
     fs::File,
     io::{Read, Write},
-    path::PathBuf,
+    path::{Path, PathBuf},
     process::ExitCode,
+    time::Duration,
 };
 
-use clap::{arg, crate_authors, crate_name, crate_version, Command};
+use clap::{arg, crate_authors, crate_name, crate_version, ArgMatches, Command};
 use libafl::inputs::Input;
-use log::{error, info};
+use log::{error, info, warn};
 
 use crate::{
     algebra::set_deserialize_signature,
+    coverage::coverage,
+    diff::TraceDiff,
+    error::Error,
     experiment::*,
     fuzzer::{
-        sanitizer::asan::{asan_info, setup_asan_env},
+        cmin,
+        metrics::MetricsTarget,
+        sanitizer::{
+            asan::{asan_info, setup_asan_env},
+            lsan::setup_lsan_env,
+            msan::setup_msan_env,
+            ubsan::setup_ubsan_env,
+        },
+        stability::check_stability,
         start, FuzzerConfig,
     },
-    graphviz::write_graphviz,
+    graphviz::{write_graphviz, DataFlow},
+    inspect::inspect,
     log::create_stdout_config,
     protocol::ProtocolBehavior,
     put_registry::PutRegistry,
+    stats_plot::plot_stats,
     trace::{Trace, TraceContext},
+    triage::{triage, VIOLATION_MARKER},
 };
 
 fn create_app() -> Command<'static> {
@@ -36,12 +51,25 @@ fn create_app() -> Command<'static> {
         .arg(arg!(--minimizer "Use a minimizer"))
         .arg(arg!(--monitor "Use a monitor"))
         .arg(arg!(--"no-launcher" "Do not use the convenient launcher"))
+        .arg(arg!(--"metrics-port" [port] "Serves the statistics in the OpenMetrics format on this local port"))
+        .arg(arg!(--"metrics-file" [path] "Writes the statistics in the OpenMetrics format to this file"))
+        .arg(arg!(--"calibration-runs" [n] "Number of executions to check whether new corpus entries are stable (default: 0, disabled)"))
+        .arg(arg!(--"leak-check" "Checks for memory leaks after each execution and records leaking traces as objectives"))
         .subcommands(vec![
             Command::new("quick-experiment").about("Starts a new experiment and writes the results out"),
             Command::new("experiment").about("Starts a new experiment and writes the results out")
                 .arg(arg!(-t --title <t> "Title of the experiment"))
                          .arg(arg!(-d --description <d> "Descritpion of the experiment"))
             ,
+            Command::new("experiments")
+                .about("Works with the results of experiments")
+                .subcommand_required(true)
+                .subcommand(
+                    Command::new("compare")
+                        .about("Compares experiments and writes a Markdown and HTML report")
+                        .arg(arg!(<inputs> ... "Experiment directories, or directories of repeated runs. The first one is the baseline"))
+                        .arg(arg!(-o --output [dir] "The directory to which the report should be written (default: comparison)")),
+                ),
             Command::new("seed").about("Generates seeds to ./corpus"),
             Command::new("plot")
                 .about("Plots a trace stored in a file")
@@ -49,10 +77,41 @@ fn create_app() -> Command<'static> {
                 .arg(arg!(<format> "The format of the plot, can be svg or pdf"))
                 .arg(arg!(<output_prefix> "The file to which the trace should be written"))
                 .arg(arg!(--multiple "Whether we want to output multiple views, additionally to the combined view"))
-                .arg(arg!(--tree "Whether want to use tree mode in the combined view")),
+                .arg(arg!(--tree "Whether want to use tree mode in the combined view"))
+                .arg(arg!(--"data-flow" "Executes the trace and draws edges from variables to the outputs which satisfied them")),
+            Command::new("plot-stats")
+                .about("Plots the statistics of one or more experiments as SVG charts")
+                .arg(arg!(<output> "The directory to which the charts should be written"))
+                .arg(arg!(<inputs> ... "Experiment directories or stats.json files which are overlaid")),
             Command::new("execute")
                 .about("Executes a trace stored in a file")
                 .arg(arg!(<input> "The file which stores a trace"))
+                .arg(arg!(-r --repeat [n] "Executes the trace n times and reports whether the executions differ")),
+            Command::new("diff")
+                .about("Shows the structural differences between two traces")
+                .arg(arg!(<old> "The file which stores the original trace"))
+                .arg(arg!(<new> "The file which stores the changed trace"))
+                .arg(arg!(-f --format [format] "The output format, can be text or dot (default: text)")),
+            Command::new("inspect")
+                .about("Executes a trace and writes an HTML report of every step")
+                .arg(arg!(<input> "The file which stores a trace"))
+                .arg(arg!(<output> "The HTML file to which the report should be written")),
+            Command::new("cmin")
+                .about("Distills a corpus to a subset of traces which covers the same edges")
+                .arg(arg!(<input> "The directory which stores the corpus"))
+                .arg(arg!(<output> "The directory to which the distilled corpus should be written")),
+            Command::new("triage")
+                .about("Re-executes objectives and groups them by their root cause")
+                .arg(arg!(<input> "The directory which stores the objectives"))
+                .arg(arg!(<output> "The directory to which the summary and representatives should be written"))
+                .arg(arg!(-d --depth [n] "Number of stack frames which are used for grouping (default: 3)"))
+                .arg(arg!(-t --timeout [s] "Timeout in seconds for each execution (default: 5)")),
+            Command::new("coverage")
+                .about("Replays a corpus in a coverage build and writes an lcov and HTML report")
+                .arg(arg!(<input> "The directory which stores the corpus"))
+                .arg(arg!(<output> "The directory to which the report should be written"))
+                .arg(arg!(-i --ignore [regex] "Source files which should be excluded from the report"))
+                .arg(arg!(-t --timeout [s] "Timeout in seconds for each execution (default: 5)"))
         ])
 }
 
@@ -76,6 +135,17 @@ pub fn main<PB: ProtocolBehavior + Clone + 'static>(
     let minimizer = matches.is_present("minimizer");
     let monitor = matches.is_present("monitor");
     let no_launcher = matches.is_present("no-launcher");
+    let calibration_runs: usize = value_or(&matches, "calibration-runs", 0);
+    let leak_check = matches.is_present("leak-check");
+    let metrics = if matches.is_present("metrics-port") {
+        Some(MetricsTarget::Http(
+            matches.value_of_t_or_exit("metrics-port"),
+        ))
+    } else {
+        matches
+            .value_of("metrics-file")
+            .map(|path| MetricsTarget::File(PathBuf::from(path)))
+    };
 
     info!("Version: {}", crate::GIT_REF);
     info!("Put Versions:");
@@ -85,6 +155,11 @@ pub fn main<PB: ProtocolBehavior + Clone + 'static>(
 
     asan_info();
     setup_asan_env();
+    setup_ubsan_env();
+    setup_msan_env();
+    if leak_check {
+        setup_lsan_env();
+    }
 
     if set_deserialize_signature(PB::signature()).is_err() {
         error!("Failed to initialize deserialization");
@@ -102,19 +177,117 @@ pub fn main<PB: ProtocolBehavior + Clone + 'static>(
         let format = matches.value_of("format").unwrap();
         let is_multiple = matches.is_present("multiple");
         let is_tree = matches.is_present("tree");
-
-        if let Err(err) = plot::<PB>(input, format, output_prefix, is_multiple, is_tree) {
+        let is_data_flow = matches.is_present("data-flow");
+
+        if let Err(err) = plot::<PB>(
+            input,
+            format,
+            output_prefix,
+            is_multiple,
+            is_tree,
+            is_data_flow,
+        ) {
             error!("Failed to plot trace: {:?}", err);
             return ExitCode::FAILURE;
         }
+    } else if let Some(matches) = matches
+        .subcommand_matches("experiments")
+        .and_then(|matches| matches.subcommand_matches("compare"))
+    {
+        // Parse arguments
+        let output = matches.value_of("output").unwrap_or("comparison");
+        let inputs = matches
+            .values_of("inputs")
+            .unwrap()
+            .map(PathBuf::from)
+            .collect::<Vec<_>>();
+
+        if let Err(err) = compare_experiments(&inputs, Path::new(output)) {
+            error!("Failed to compare experiments: {:?}", err);
+            return ExitCode::FAILURE;
+        }
+    } else if let Some(matches) = matches.subcommand_matches("plot-stats") {
+        // Parse arguments
+        let output = matches.value_of("output").unwrap();
+        let inputs = matches
+            .values_of("inputs")
+            .unwrap()
+            .map(PathBuf::from)
+            .collect::<Vec<_>>();
+
+        if let Err(err) = plot_stats(&inputs, Path::new(output)) {
+            error!("Failed to plot statistics: {:?}", err);
+            return ExitCode::FAILURE;
+        }
     } else if let Some(matches) = matches.subcommand_matches("execute") {
         // Parse arguments
         let input = matches.value_of("input").unwrap();
+        let repeat: usize = value_or(matches, "repeat", 1);
 
-        if let Err(err) = execute(input, put_registry) {
+        if let Err(err) = execute(input, repeat, put_registry) {
             error!("Failed to execute trace: {:?}", err);
             return ExitCode::FAILURE;
         }
+    } else if let Some(matches) = matches.subcommand_matches("diff") {
+        // Parse arguments
+        let old = matches.value_of("old").unwrap();
+        let new = matches.value_of("new").unwrap();
+        let format = matches.value_of("format").unwrap_or("text");
+
+        if let Err(err) = diff::<PB>(old, new, format) {
+            error!("Failed to diff traces: {:?}", err);
+            return ExitCode::FAILURE;
+        }
+    } else if let Some(matches) = matches.subcommand_matches("inspect") {
+        // Parse arguments
+        let input = matches.value_of("input").unwrap();
+        let output = matches.value_of("output").unwrap();
+
+        if let Err(err) = inspect::<PB>(Path::new(input), Path::new(output)) {
+            error!("Failed to inspect trace: {:?}", err);
+            return ExitCode::FAILURE;
+        }
+    } else if let Some(matches) = matches.subcommand_matches("cmin") {
+        // Parse arguments
+        let input = matches.value_of("input").unwrap();
+        let output = matches.value_of("output").unwrap();
+
+        if let Err(err) = cmin::<PB>(Path::new(input), Path::new(output)) {
+            error!("Failed to distill corpus: {:?}", err);
+            return ExitCode::FAILURE;
+        }
+    } else if let Some(matches) = matches.subcommand_matches("triage") {
+        // Parse arguments
+        let input = matches.value_of("input").unwrap();
+        let output = matches.value_of("output").unwrap();
+        let depth: usize = value_or(matches, "depth", 3);
+        let timeout: u64 = value_or(matches, "timeout", 5);
+
+        if let Err(err) = triage(
+            Path::new(input),
+            Path::new(output),
+            depth,
+            Duration::from_secs(timeout),
+        ) {
+            error!("Failed to triage objectives: {:?}", err);
+            return ExitCode::FAILURE;
+        }
+    } else if let Some(matches) = matches.subcommand_matches("coverage") {
+        // Parse arguments
+        let input = matches.value_of("input").unwrap();
+        let output = matches.value_of("output").unwrap();
+        let ignore = matches.value_of("ignore");
+        let timeout: u64 = value_or(matches, "timeout", 5);
+
+        if let Err(err) = coverage(
+            Path::new(input),
+            Path::new(output),
+            ignore,
+            Duration::from_secs(timeout),
+        ) {
+            error!("Failed to create coverage report: {:?}", err);
+            return ExitCode::FAILURE;
+        }
     } else {
         let experiment_path = if let Some(matches) = matches.subcommand_matches("experiment") {
             let title = matches.value_of("title").unwrap();
@@ -179,6 +352,9 @@ pub fn main<PB: ProtocolBehavior + Clone + 'static>(
             mutation_config: Default::default(),
             monitor,
             no_launcher,
+            calibration_runs,
+            metrics,
+            leak_check,
         };
 
         if let Err(err) = start::<PB>(config, handle) {
@@ -196,12 +372,27 @@ pub fn main<PB: ProtocolBehavior + Clone + 'static>(
     ExitCode::SUCCESS
 }
 
+/// Parses the value of the argument `name`, or returns `default` if it is absent. Exits with a
+/// usage error if the value can not be parsed.
+fn value_or<T>(matches: &ArgMatches, name: &str, default: T) -> T
+where
+    T: std::str::FromStr,
+    <T as std::str::FromStr>::Err: std::fmt::Display,
+{
+    if matches.is_present(name) {
+        matches.value_of_t_or_exit(name)
+    } else {
+        default
+    }
+}
+
 fn plot<PB: ProtocolBehavior>(
     input: &str,
     format: &str,
     output_prefix: &str,
     is_multiple: bool,
     is_tree: bool,
+    is_data_flow: bool,
 ) -> Result<(), Box<dyn std::error::Error>> {
     let mut input_file = File::open(input)?;
 
@@ -210,11 +401,22 @@ fn plot<PB: ProtocolBehavior>(
     input_file.read_to_end(&mut buffer)?;
     let trace = postcard::from_bytes::<Trace<PB::Matcher>>(&buffer)?;
 
+    let mut data_flow = DataFlow::default();
+    if is_data_flow {
+        let mut ctx = TraceContext::new(PB::registry());
+        if let Err(err) = trace.resolve_data_flow(&mut ctx, &mut data_flow) {
+            warn!(
+                "Execution failed, data flow is only drawn up to the failing step: {}",
+                err
+            );
+        }
+    }
+
     // All-in-one tree
     write_graphviz(
         format!("{}_{}.{}", output_prefix, "all", format).as_str(),
         format,
-        trace.dot_graph(is_tree).as_str(),
+        trace.dot_graph_with_data_flow(is_tree, &data_flow).as_str(),
     )
     .expect("Failed to generate graph.");
 
@@ -246,15 +448,48 @@ fn seed<PB: ProtocolBehavior>(
     Ok(())
 }
 
-fn execute<PB: ProtocolBehavior>(
+fn diff<PB: ProtocolBehavior>(
+    old: &str,
+    new: &str,
+    format: &str,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let old = Trace::<PB::Matcher>::from_file(old)?;
+    let new = Trace::<PB::Matcher>::from_file(new)?;
+
+    let diff = TraceDiff::new(&old, &new);
+    match format {
+        "text" => print!("{}", diff.text(&old, &new)),
+        "dot" => println!("{}", diff.dot(&old, &new)),
+        _ => return Err(format!("Unknown format {}", format).into()),
+    }
+    Ok(())
+}
+
+fn execute<PB: ProtocolBehavior + 'static>(
     input: &str,
+    repeat: usize,
     put_registry: &'static PutRegistry<PB>,
 ) -> Result<(), Box<dyn std::error::Error>> {
     let trace = Trace::<PB::Matcher>::from_file(input)?;
 
     info!("Agents: {:?}", &trace.descriptors);
 
+    if repeat > 1 {
+        let report = check_stability::<PB>(&trace, repeat);
+        if !report.is_stable() {
+            return Err(format!("Executions are not stable: {}", report).into());
+        }
+        info!("Executions are stable: {}", report);
+        return Ok(());
+    }
+
     let mut ctx = TraceContext::new(put_registry);
-    trace.execute(&mut ctx)?;
+    if let Err(err) = trace.execute(&mut ctx) {
+        if let Error::SecurityClaim(msg) = &err {
+            // Parsed by the triage, therefore the line must not depend on the Debug output
+            error!("{}{}", VIOLATION_MARKER, msg);
+        }
+        return Err(err.into());
+    }
     Ok(())
 }
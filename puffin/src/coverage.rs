@@ -0,0 +1,350 @@
+//! Source-based coverage reports of a corpus. Each trace is replayed in a separate process of a
+//! binary which has been built with the `coverage` feature. Every process writes its own raw
+//! profile. The profiles are merged with `llvm-profdata` and exported with `llvm-cov` to lcov and
+//! HTML. Additionally, the report attributes each function to the trace which covered it first.
+//!
+//! The paths to the LLVM tools can be overwritten with the environment variables `LLVM_PROFDATA`
+//! and `LLVM_COV`. Their version must match the LLVM version of clang and rustc.
+
+use std::{
+    collections::BTreeMap,
+    env,
+    ffi::OsStr,
+    fs,
+    fs::File,
+    io::Write,
+    path::{Path, PathBuf},
+    process::{Command, Stdio},
+    time::Duration,
+};
+
+use log::{info, warn};
+use serde_json::Value;
+
+use crate::triage::{collect_traces, execute_isolated};
+
+/// Coverage of a single function
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct FunctionCoverage {
+    name: String,
+    file: String,
+    count: u64,
+}
+
+/// A function together with the trace which covered it first
+struct Attribution {
+    file: String,
+    count: u64,
+    first_covered_by: Option<PathBuf>,
+}
+
+fn llvm_tool(variable: &str, default: &str) -> String {
+    env::var(variable).unwrap_or_else(|_| default.to_string())
+}
+
+fn run_tool(command: &mut Command) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+    let output = command.stderr(Stdio::inherit()).output()?;
+    if !output.status.success() {
+        return Err(format!("{:?} failed with {}", command, output.status).into());
+    }
+    Ok(output.stdout)
+}
+
+fn merge_profiles(profiles: &[PathBuf], output: &Path) -> Result<(), Box<dyn std::error::Error>> {
+    run_tool(
+        Command::new(llvm_tool("LLVM_PROFDATA", "llvm-profdata"))
+            .arg("merge")
+            .arg("-sparse")
+            .args(profiles)
+            .arg("-o")
+            .arg(output),
+    )?;
+    Ok(())
+}
+
+/// Runs `llvm-cov` with the sub-command `command` on the current executable.
+fn llvm_cov(
+    command: &str,
+    profile: &Path,
+    ignore: Option<&str>,
+    args: &[&OsStr],
+) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+    let mut llvm_cov = Command::new(llvm_tool("LLVM_COV", "llvm-cov"));
+    llvm_cov
+        .arg(command)
+        .arg(env::current_exe()?)
+        .arg(format!("-instr-profile={}", profile.display()))
+        .args(args);
+
+    if let Some(ignore) = ignore {
+        llvm_cov.arg(format!("-ignore-filename-regex={}", ignore));
+    }
+
+    run_tool(&mut llvm_cov)
+}
+
+/// Extracts the functions from the JSON export of `llvm-cov`.
+fn parse_functions(export: &Value) -> Vec<FunctionCoverage> {
+    export["data"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|data| data["functions"].as_array())
+        .flatten()
+        .filter_map(|function| {
+            Some(FunctionCoverage {
+                name: function["name"].as_str()?.to_string(),
+                file: function["filenames"]
+                    .as_array()
+                    .and_then(|filenames| filenames.first())
+                    .and_then(|filename| filename.as_str())
+                    .unwrap_or_default()
+                    .to_string(),
+                count: function["count"].as_u64()?,
+            })
+        })
+        .collect()
+}
+
+fn export_functions(
+    profile: &Path,
+    ignore: Option<&str>,
+) -> Result<Vec<FunctionCoverage>, Box<dyn std::error::Error>> {
+    let export = llvm_cov(
+        "export",
+        profile,
+        ignore,
+        &[OsStr::new("-format=text"), OsStr::new("-skip-expansions")],
+    )?;
+    Ok(parse_functions(&serde_json::from_slice(&export)?))
+}
+
+/// Records for each covered function the first trace which covered it. Functions which are not
+/// covered yet are added without attribution.
+fn attribute(
+    attributions: &mut BTreeMap<String, Attribution>,
+    functions: &[FunctionCoverage],
+    trace: &Path,
+) -> usize {
+    let mut newly_covered = 0;
+
+    for function in functions {
+        let attribution = attributions
+            .entry(function.name.clone())
+            .or_insert_with(|| Attribution {
+                file: function.file.clone(),
+                count: 0,
+                first_covered_by: None,
+            });
+
+        if function.count > 0 && attribution.first_covered_by.is_none() {
+            attribution.first_covered_by = Some(trace.to_path_buf());
+            newly_covered += 1;
+        }
+        attribution.count += function.count;
+    }
+
+    newly_covered
+}
+
+fn write_attribution(
+    path: &Path,
+    attributions: &BTreeMap<String, Attribution>,
+) -> Result<(), std::io::Error> {
+    let covered = attributions
+        .values()
+        .filter(|attribution| attribution.first_covered_by.is_some())
+        .count();
+
+    let mut file = File::create(path)?;
+    writeln!(
+        file,
+        "{} of {} functions are covered.\n",
+        covered,
+        attributions.len()
+    )?;
+    writeln!(file, "| Function | File | Count | First Covered By |")?;
+    writeln!(file, "|---|---|---|---|")?;
+
+    for (name, attribution) in attributions {
+        writeln!(
+            file,
+            "| {} | {} | {} | {} |",
+            name,
+            attribution.file,
+            attribution.count,
+            attribution
+                .first_covered_by
+                .as_ref()
+                .map_or_else(|| "-".to_string(), |trace| trace.display().to_string()),
+        )?;
+    }
+
+    Ok(())
+}
+
+/// Replays every trace in `input_dir` and writes `coverage.lcov`, an HTML report in `html/` and
+/// `attribution.md` to `output_dir`. Traces are replayed in the order of their modification time,
+/// such that seeds are attributed before traces which have been found later. Source files which
+/// match the regex `ignore` are excluded from the report.
+pub fn coverage(
+    input_dir: &Path,
+    output_dir: &Path,
+    ignore: Option<&str>,
+    timeout: Duration,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut traces = Vec::new();
+    collect_traces(input_dir, &mut traces)?;
+    traces.sort_by_cached_key(|trace| {
+        (
+            fs::metadata(trace)
+                .and_then(|metadata| metadata.modified())
+                .ok(),
+            trace.clone(),
+        )
+    });
+    info!("Replaying {} traces from {:?}", traces.len(), input_dir);
+
+    let profiles_dir = output_dir.join("profiles");
+    let logs_dir = output_dir.join("logs");
+    fs::create_dir_all(&profiles_dir)?;
+    fs::create_dir_all(&logs_dir)?;
+
+    let mut profiles = Vec::new();
+    let mut attributions: BTreeMap<String, Attribution> = BTreeMap::new();
+
+    for (i, trace) in traces.iter().enumerate() {
+        let raw_profile = profiles_dir.join(format!("{}.profraw", i));
+        let log = logs_dir.join(format!("{}.log", i));
+
+        let (code, signal, timed_out) = execute_isolated(
+            trace,
+            &log,
+            timeout,
+            &[("LLVM_PROFILE_FILE", raw_profile.as_os_str())],
+        )?;
+
+        if code != Some(0) || signal.is_some() || timed_out {
+            warn!(
+                "{:?} did not exit cleanly (code: {:?}, signal: {:?}, timed out: {})",
+                trace, code, signal, timed_out
+            );
+        }
+
+        // Profiles are only written if the process exits
+        if !raw_profile.exists() {
+            warn!("{:?} did not write a profile", trace);
+            continue;
+        }
+
+        let profile = profiles_dir.join(format!("{}.profdata", i));
+        merge_profiles(&[raw_profile.clone()], &profile)?;
+        let newly_covered = attribute(
+            &mut attributions,
+            &export_functions(&profile, ignore)?,
+            trace,
+        );
+        fs::remove_file(&profile)?;
+
+        info!("{:?} covers {} new functions", trace, newly_covered);
+        profiles.push(raw_profile);
+    }
+
+    if profiles.is_empty() {
+        return Err("No trace wrote a profile. Was the binary built with coverage?".into());
+    }
+
+    let merged = output_dir.join("coverage.profdata");
+    merge_profiles(&profiles, &merged)?;
+
+    let lcov = llvm_cov("export", &merged, ignore, &[OsStr::new("-format=lcov")])?;
+    fs::write(output_dir.join("coverage.lcov"), lcov)?;
+
+    let html_dir = output_dir.join("html");
+    let output_dir_arg = format!("-output-dir={}", html_dir.display());
+    llvm_cov(
+        "show",
+        &merged,
+        ignore,
+        &[
+            OsStr::new("-format=html"),
+            OsStr::new("-show-line-counts-or-regions"),
+            OsStr::new(&output_dir_arg),
+        ],
+    )?;
+
+    write_attribution(&output_dir.join("attribution.md"), &attributions)?;
+
+    info!(
+        "Wrote coverage of {} traces to {:?}",
+        profiles.len(),
+        output_dir
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::BTreeMap, path::Path};
+
+    use serde_json::json;
+
+    use super::{attribute, parse_functions, FunctionCoverage};
+
+    fn function(name: &str, count: u64) -> FunctionCoverage {
+        FunctionCoverage {
+            name: name.to_string(),
+            file: "ssl/statem/statem_srvr.c".to_string(),
+            count,
+        }
+    }
+
+    #[test]
+    fn test_parse_functions() {
+        let export = json!({
+            "data": [{
+                "functions": [
+                    {"name": "tls_process_client_hello", "count": 3, "filenames": ["ssl/statem/statem_srvr.c"]},
+                    {"name": "tls_process_end_of_early_data", "count": 0, "filenames": ["ssl/statem/statem_srvr.c"]}
+                ]
+            }]
+        });
+
+        assert_eq!(
+            parse_functions(&export),
+            vec![
+                function("tls_process_client_hello", 3),
+                function("tls_process_end_of_early_data", 0)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_first_trace_is_attributed() {
+        let mut attributions = BTreeMap::new();
+
+        let newly_covered = attribute(
+            &mut attributions,
+            &[function("a", 1), function("b", 0)],
+            Path::new("seed.trace"),
+        );
+        assert_eq!(newly_covered, 1);
+
+        let newly_covered = attribute(
+            &mut attributions,
+            &[function("a", 2), function("b", 1)],
+            Path::new("found.trace"),
+        );
+        assert_eq!(newly_covered, 1);
+
+        assert_eq!(
+            attributions["a"].first_covered_by.as_deref(),
+            Some(Path::new("seed.trace"))
+        );
+        assert_eq!(
+            attributions["b"].first_covered_by.as_deref(),
+            Some(Path::new("found.trace"))
+        );
+        assert_eq!(attributions["a"].count, 3);
+    }
+}
@@ -0,0 +1,568 @@
+//! Structural differences between two traces, for example between a seed and the trace which
+//! remained after minimizing or mutating it. The steps of the traces are aligned such that the
+//! total size of the differences is minimal. The recipes of aligned steps are compared as trees:
+//! a changed function symbol, an inserted or removed function and a replaced subterm are reported
+//! together with their [`TermPath`]s, which can be resolved with
+//! [`find_term_mut`](crate::fuzzer::mutations::util::find_term_mut).
+
+use std::fmt::Write;
+
+use itertools::Itertools;
+
+use crate::{
+    algebra::{remove_fn_prefix, remove_prefix, Matcher, Term},
+    fuzzer::mutations::util::TermPath,
+    trace::{Action, Step, Trace},
+};
+
+/// A difference between two aligned recipes
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TermChange {
+    /// The function symbol changed, but the arguments have the same types and are compared
+    /// further
+    Symbol { old: String, new: String },
+    /// The subterm has been replaced by an unrelated one
+    Replaced { old: String, new: String },
+    /// A function has been inserted above the old subterm, which became its argument `argument`
+    Inserted { symbol: String, argument: usize },
+    /// A function has been removed and replaced by its argument `argument`
+    Removed { symbol: String, argument: usize },
+}
+
+/// A [`TermChange`] together with the paths of the affected subterms in both recipes
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LocatedChange {
+    pub change: TermChange,
+    pub old_path: TermPath,
+    pub new_path: TermPath,
+}
+
+/// How a step of the old trace relates to the new trace
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StepDiff {
+    Removed {
+        old: usize,
+    },
+    Inserted {
+        new: usize,
+    },
+    /// Steps of the same agent and kind. If the recipes are equal, then `changes` is empty.
+    Aligned {
+        old: usize,
+        new: usize,
+        changes: Vec<LocatedChange>,
+    },
+}
+
+/// The differences between two traces
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TraceDiff {
+    pub steps: Vec<StepDiff>,
+}
+
+/// Formats a term on a single line, e.g. `append(new_extensions, ec_point_formats)`.
+fn compact<M: Matcher>(term: &Term<M>) -> String {
+    match term {
+        Term::Variable(variable) => variable.to_string(),
+        Term::Application(_, args) => {
+            let name = symbol(term);
+            if args.is_empty() {
+                name
+            } else {
+                format!("{}({})", name, args.iter().map(compact).join(", "))
+            }
+        }
+        Term::Literal(literal) => literal.to_string(),
+    }
+}
+
+fn symbol<M: Matcher>(term: &Term<M>) -> String {
+    match term {
+        Term::Application(function, _) => remove_fn_prefix(&remove_prefix(function.name())),
+        _ => compact(term),
+    }
+}
+
+fn extend(path: &[usize], index: usize) -> TermPath {
+    let mut path = path.to_vec();
+    path.push(index);
+    path
+}
+
+/// Compares two recipes top-down and returns the size of the differences.
+fn diff_terms<M: Matcher>(
+    old: &Term<M>,
+    new: &Term<M>,
+    old_path: &[usize],
+    new_path: &[usize],
+    changes: &mut Vec<LocatedChange>,
+) -> usize {
+    if old == new {
+        return 0;
+    }
+
+    let located = |change| LocatedChange {
+        change,
+        old_path: old_path.to_vec(),
+        new_path: new_path.to_vec(),
+    };
+
+    if let (Term::Application(old_function, old_args), Term::Application(new_function, new_args)) =
+        (old, new)
+    {
+        if old_function.shape().argument_types == new_function.shape().argument_types {
+            let mut cost = 0;
+            if old_function != new_function {
+                changes.push(located(TermChange::Symbol {
+                    old: symbol(old),
+                    new: symbol(new),
+                }));
+                cost += 1;
+            }
+
+            for (i, (old_arg, new_arg)) in old_args.iter().zip(new_args).enumerate() {
+                cost += diff_terms(
+                    old_arg,
+                    new_arg,
+                    &extend(old_path, i),
+                    &extend(new_path, i),
+                    changes,
+                );
+            }
+            return cost;
+        }
+    }
+
+    if let Term::Application(_, new_args) = new {
+        if let Some(argument) = new_args.iter().position(|arg| arg == old) {
+            changes.push(located(TermChange::Inserted {
+                symbol: symbol(new),
+                argument,
+            }));
+            return new.size() - old.size();
+        }
+    }
+
+    if let Term::Application(_, old_args) = old {
+        if let Some(argument) = old_args.iter().position(|arg| arg == new) {
+            changes.push(located(TermChange::Removed {
+                symbol: symbol(old),
+                argument,
+            }));
+            return old.size() - new.size();
+        }
+    }
+
+    changes.push(located(TermChange::Replaced {
+        old: compact(old),
+        new: compact(new),
+    }));
+    old.size() + new.size()
+}
+
+fn step_size<M: Matcher>(step: &Step<M>) -> usize {
+    match &step.action {
+        Action::Input(input) => 1 + input.recipe.size(),
+        Action::Output(_) => 1,
+    }
+}
+
+/// Compares two steps. Steps of different agents or kinds can not be aligned.
+fn diff_steps<M: Matcher>(old: &Step<M>, new: &Step<M>) -> Option<(usize, Vec<LocatedChange>)> {
+    if old.agent != new.agent {
+        return None;
+    }
+
+    match (&old.action, &new.action) {
+        (Action::Input(old_input), Action::Input(new_input)) => {
+            let mut changes = Vec::new();
+            let cost = diff_terms(&old_input.recipe, &new_input.recipe, &[], &[], &mut changes);
+            Some((cost, changes))
+        }
+        (Action::Output(_), Action::Output(_)) => Some((0, Vec::new())),
+        _ => None,
+    }
+}
+
+impl TraceDiff {
+    /// Aligns the steps of both traces such that the size of all differences is minimal.
+    /// Removing or inserting a step costs its size, aligning two steps costs the size of the
+    /// differences of their recipes.
+    pub fn new<M: Matcher>(old: &Trace<M>, new: &Trace<M>) -> Self {
+        let n = old.steps.len();
+        let m = new.steps.len();
+
+        let aligned: Vec<Vec<Option<(usize, Vec<LocatedChange>)>>> = old
+            .steps
+            .iter()
+            .map(|old_step| {
+                new.steps
+                    .iter()
+                    .map(|new_step| diff_steps(old_step, new_step))
+                    .collect()
+            })
+            .collect();
+
+        // costs[i][j] is the cost of aligning the old steps from i and the new steps from j
+        let mut costs = vec![vec![0; m + 1]; n + 1];
+        for i in (0..n).rev() {
+            costs[i][m] = costs[i + 1][m] + step_size(&old.steps[i]);
+        }
+        for j in (0..m).rev() {
+            costs[n][j] = costs[n][j + 1] + step_size(&new.steps[j]);
+        }
+        for i in (0..n).rev() {
+            for j in (0..m).rev() {
+                let mut cost = (costs[i + 1][j] + step_size(&old.steps[i]))
+                    .min(costs[i][j + 1] + step_size(&new.steps[j]));
+                if let Some((aligned_cost, _)) = &aligned[i][j] {
+                    cost = cost.min(costs[i + 1][j + 1] + aligned_cost);
+                }
+                costs[i][j] = cost;
+            }
+        }
+
+        let mut steps = Vec::new();
+        let (mut i, mut j) = (0, 0);
+        while i < n || j < m {
+            if i < n && j < m {
+                if let Some((aligned_cost, changes)) = &aligned[i][j] {
+                    if costs[i][j] == costs[i + 1][j + 1] + aligned_cost {
+                        steps.push(StepDiff::Aligned {
+                            old: i,
+                            new: j,
+                            changes: changes.clone(),
+                        });
+                        i += 1;
+                        j += 1;
+                        continue;
+                    }
+                }
+            }
+
+            if i < n && costs[i][j] == costs[i + 1][j] + step_size(&old.steps[i]) {
+                steps.push(StepDiff::Removed { old: i });
+                i += 1;
+            } else {
+                steps.push(StepDiff::Inserted { new: j });
+                j += 1;
+            }
+        }
+
+        Self { steps }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.steps.iter().all(|step| {
+            matches!(step, StepDiff::Aligned { old, new, changes } if old == new && changes.is_empty())
+        })
+    }
+
+    /// Renders the differences as text, similar to a unified diff.
+    pub fn text<M: Matcher>(&self, old: &Trace<M>, new: &Trace<M>) -> String {
+        let describe = |step: &Step<M>| match &step.action {
+            Action::Input(input) => {
+                format!("agent {}, input {}", step.agent, compact(&input.recipe))
+            }
+            Action::Output(_) => format!("agent {}, output", step.agent),
+        };
+
+        let mut text = String::new();
+        for step in &self.steps {
+            match step {
+                StepDiff::Removed { old: i } => {
+                    writeln!(text, "- step {}: {}", i, describe(&old.steps[*i])).unwrap();
+                }
+                StepDiff::Inserted { new: j } => {
+                    writeln!(text, "+ step {}: {}", j, describe(&new.steps[*j])).unwrap();
+                }
+                StepDiff::Aligned {
+                    old: i,
+                    new: j,
+                    changes,
+                } => {
+                    let marker = if changes.is_empty() { ' ' } else { '~' };
+                    writeln!(
+                        text,
+                        "{} step {} -> {}: agent {}",
+                        marker, i, j, new.steps[*j].agent
+                    )
+                    .unwrap();
+
+                    for LocatedChange {
+                        change,
+                        old_path,
+                        new_path,
+                    } in changes
+                    {
+                        let change = match change {
+                            TermChange::Symbol { old, new } => {
+                                format!("symbol {} -> {}", old, new)
+                            }
+                            TermChange::Replaced { old, new } => {
+                                format!("replaced {} -> {}", old, new)
+                            }
+                            TermChange::Inserted { symbol, argument } => {
+                                format!(
+                                    "inserted {} with the old subterm as argument {}",
+                                    symbol, argument
+                                )
+                            }
+                            TermChange::Removed { symbol, argument } => {
+                                format!("removed {} keeping its argument {}", symbol, argument)
+                            }
+                        };
+                        writeln!(text, "    {:?} -> {:?}: {}", old_path, new_path, change).unwrap();
+                    }
+                }
+            }
+        }
+        text
+    }
+
+    /// Renders the new trace as a DOT graph. Subterms are colored by the kind of change which
+    /// affected them and removed steps are shown as notes.
+    pub fn dot<M: Matcher>(&self, old: &Trace<M>, new: &Trace<M>) -> String {
+        let mut statements = Vec::new();
+
+        for (cluster, step) in self.steps.iter().enumerate() {
+            match step {
+                StepDiff::Removed { old: i } => {
+                    let label = match &old.steps[*i].action {
+                        Action::Input(input) => compact(&input.recipe),
+                        Action::Output(_) => "output".to_string(),
+                    };
+                    statements.push(format!(
+                        "subgraph cluster{} {{ label=\"removed step {} (agent {})\"; color=\"{}\"; r{} [label=\"{}\",shape=\"note\",style=\"filled\",fillcolor=\"{}\"]; }}",
+                        cluster, i, old.steps[*i].agent, REMOVED, cluster, escape(&label), REMOVED
+                    ));
+                }
+                StepDiff::Inserted { new: j } => {
+                    statements.push(step_cluster(
+                        cluster,
+                        &new.steps[*j],
+                        &format!("inserted step {}", j),
+                        INSERTED,
+                        &[],
+                    ));
+                }
+                StepDiff::Aligned {
+                    old: i,
+                    new: j,
+                    changes,
+                } => {
+                    statements.push(step_cluster(
+                        cluster,
+                        &new.steps[*j],
+                        &format!("step {} -> {}", i, j),
+                        if changes.is_empty() { "black" } else { CHANGED },
+                        changes,
+                    ));
+                }
+            }
+        }
+
+        format!(
+            "digraph \"Diff\" {{ fontname=\"{}\"; node [fontname=\"{}\"]; {} }}",
+            FONT,
+            FONT,
+            statements.join("\n")
+        )
+    }
+}
+
+const FONT: &str = "Latin Modern Roman";
+const INSERTED: &str = "#b7e1a1";
+const REMOVED: &str = "#f4a6a6";
+const REPLACED: &str = "#ffd28a";
+const CHANGED: &str = "#fff59d";
+
+fn escape(label: &str) -> String {
+    label.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+fn node_id(cluster: usize, path: &[usize]) -> String {
+    format!("n{}_{}", cluster, path.iter().join("_"))
+}
+
+fn step_cluster<M: Matcher>(
+    cluster: usize,
+    step: &Step<M>,
+    label: &str,
+    color: &str,
+    changes: &[LocatedChange],
+) -> String {
+    let mut statements = Vec::new();
+
+    match &step.action {
+        Action::Input(input) => {
+            collect_nodes(
+                &input.recipe,
+                cluster,
+                &mut Vec::new(),
+                changes,
+                &mut statements,
+            );
+        }
+        Action::Output(_) => {
+            statements.push(format!("{} [label=\"output\"];", node_id(cluster, &[])));
+        }
+    }
+
+    // Removed functions are attached as notes to the argument which took their place
+    for (i, change) in changes.iter().enumerate() {
+        if let TermChange::Removed { symbol, .. } = &change.change {
+            statements.push(format!(
+                "r{}_{} [label=\"removed {}\",shape=\"note\",style=\"filled\",fillcolor=\"{}\"]; r{}_{} -> {} [style=\"dashed\"];",
+                cluster, i, escape(symbol), REMOVED, cluster, i, node_id(cluster, &change.new_path)
+            ));
+        }
+    }
+
+    format!(
+        "subgraph cluster{} {{ label=\"{} (agent {})\"; color=\"{}\"; {} }}",
+        cluster,
+        label,
+        step.agent,
+        color,
+        statements.join("\n")
+    )
+}
+
+fn collect_nodes<M: Matcher>(
+    term: &Term<M>,
+    cluster: usize,
+    path: &mut TermPath,
+    changes: &[LocatedChange],
+    statements: &mut Vec<String>,
+) {
+    let change = changes
+        .iter()
+        .find(|change| {
+            change.new_path == *path && !matches!(change.change, TermChange::Removed { .. })
+        })
+        .map(|change| &change.change);
+
+    let (fillcolor, tooltip) = match change {
+        Some(TermChange::Symbol { old, .. }) => (CHANGED, format!("was {}", old)),
+        Some(TermChange::Replaced { old, .. }) => (REPLACED, format!("was {}", old)),
+        Some(TermChange::Inserted { .. }) => (INSERTED, "inserted".to_string()),
+        _ => ("white", String::new()),
+    };
+
+    statements.push(format!(
+        "{} [label=\"{}\",tooltip=\"{}\",style=\"filled\",fillcolor=\"{}\"];",
+        node_id(cluster, path),
+        escape(&symbol(term)),
+        escape(&tooltip),
+        fillcolor
+    ));
+
+    if let Term::Application(_, args) = term {
+        for (i, arg) in args.iter().enumerate() {
+            path.push(i);
+            statements.push(format!(
+                "{} -> {};",
+                node_id(cluster, &path[..path.len() - 1]),
+                node_id(cluster, path)
+            ));
+            collect_nodes(arg, cluster, path, changes, statements);
+            path.pop();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{diff_terms, StepDiff, TermChange, TraceDiff};
+    use crate::{algebra::test_signature::*, term};
+
+    fn changes(old: &TestTerm, new: &TestTerm) -> Vec<(TermChange, Vec<usize>)> {
+        let mut changes = Vec::new();
+        diff_terms(old, new, &[], &[], &mut changes);
+        changes
+            .into_iter()
+            .map(|change| (change.change, change.old_path))
+            .collect()
+    }
+
+    #[test]
+    fn test_symbol_changed() {
+        let old = term! {
+            fn_client_extensions_append(fn_client_extensions_new, fn_signature_algorithm_extension)
+        };
+        let new = term! {
+            fn_client_extensions_append(fn_client_extensions_new, fn_ec_point_formats_extension)
+        };
+
+        assert_eq!(
+            changes(&old, &new),
+            vec![(
+                TermChange::Symbol {
+                    old: "signature_algorithm_extension".to_string(),
+                    new: "ec_point_formats_extension".to_string()
+                },
+                vec![1]
+            )]
+        );
+    }
+
+    #[test]
+    fn test_inserted_and_replaced() {
+        let old = term! { fn_client_extensions_new };
+        let new = term! {
+            fn_client_extensions_append(fn_client_extensions_new, fn_signature_algorithm_extension)
+        };
+        assert_eq!(
+            changes(&old, &new),
+            vec![(
+                TermChange::Inserted {
+                    symbol: "client_extensions_append".to_string(),
+                    argument: 0
+                },
+                vec![]
+            )]
+        );
+        assert!(matches!(
+            changes(&new, &old)[0].0,
+            TermChange::Removed { argument: 0, .. }
+        ));
+
+        let old = term! { fn_support_group_extension(fn_named_group_secp384r1) };
+        let new = term! { fn_signature_algorithm_extension };
+        assert!(matches!(
+            changes(&old, &new)[0].0,
+            TermChange::Replaced { .. }
+        ));
+    }
+
+    #[test]
+    fn test_align_steps() {
+        let old = setup_simple_trace();
+        let mut new = setup_simple_trace();
+        new.steps.remove(1);
+
+        let diff = TraceDiff::new(&old, &new);
+        assert_eq!(
+            diff.steps,
+            vec![
+                StepDiff::Aligned {
+                    old: 0,
+                    new: 0,
+                    changes: vec![]
+                },
+                StepDiff::Removed { old: 1 },
+                StepDiff::Aligned {
+                    old: 2,
+                    new: 1,
+                    changes: vec![]
+                },
+            ]
+        );
+        assert!(!diff.is_empty());
+        assert!(TraceDiff::new(&old, &old).is_empty());
+        assert!(diff
+            .text(&old, &new)
+            .contains("- step 1: agent 0, input client_key_exchange"));
+    }
+}
@@ -1,8 +1,25 @@
-use std::{fmt::Display, fs, fs::File, io, io::Write, path::Path};
+use std::{
+    cmp::Ordering,
+    collections::{hash_map::DefaultHasher, HashSet},
+    fmt::Display,
+    fs,
+    fs::File,
+    hash::{Hash, Hasher},
+    io,
+    io::Write,
+    path::{Path, PathBuf},
+};
 
 use chrono::Local;
+use log::info;
 
-use crate::{protocol::ProtocolBehavior, put_registry::PutRegistry, GIT_MSG, GIT_REF};
+use crate::{
+    protocol::ProtocolBehavior,
+    put_registry::PutRegistry,
+    stats_plot::{aggregate, load_stats, Sample},
+    triage::collect_traces,
+    GIT_MSG, GIT_REF,
+};
 
 pub fn format_title(title: Option<&str>, index: Option<usize>) -> String {
     let date = Local::now().format("%Y-%m-%d-%H%M%S");
@@ -43,3 +60,430 @@ pub fn write_experiment_markdown<PB: ProtocolBehavior>(
     file.write_all(full_description.as_bytes()).unwrap();
     Ok(full_description)
 }
+
+/// Results of a single run of a configuration
+struct Run {
+    name: String,
+    samples: Vec<Sample>,
+    corpus_size: usize,
+    /// Hashes of the contents of the objectives
+    objectives: HashSet<u64>,
+}
+
+impl Run {
+    fn final_coverage(&self) -> f64 {
+        self.samples
+            .last()
+            .map_or(0.0, |sample| sample.coverage as f64)
+    }
+
+    /// Seconds until the coverage reached `threshold`
+    fn time_to_coverage(&self, threshold: u64) -> Option<f64> {
+        self.samples
+            .iter()
+            .find(|sample| sample.coverage >= threshold)
+            .map(|sample| sample.time)
+    }
+}
+
+/// A configuration of the fuzzer, like a mutator change, which has been run one or more times
+struct Configuration {
+    label: String,
+    runs: Vec<Run>,
+}
+
+fn hash_objectives(dir: &Path) -> Result<HashSet<u64>, Box<dyn std::error::Error>> {
+    let mut traces = Vec::new();
+    if dir.exists() {
+        collect_traces(dir, &mut traces)?;
+    }
+
+    traces
+        .iter()
+        .map(|trace| {
+            let mut hasher = DefaultHasher::new();
+            fs::read(trace)?.hash(&mut hasher);
+            Ok(hasher.finish())
+        })
+        .collect()
+}
+
+fn load_run(dir: &Path) -> Result<Run, Box<dyn std::error::Error>> {
+    let mut corpus = Vec::new();
+    if dir.join("corpus").exists() {
+        collect_traces(&dir.join("corpus"), &mut corpus)?;
+    }
+
+    Ok(Run {
+        name: dir.file_name().map_or_else(
+            || dir.display().to_string(),
+            |name| name.to_string_lossy().to_string(),
+        ),
+        samples: aggregate(&load_stats(&dir.join("stats.json"))?),
+        corpus_size: corpus.len(),
+        objectives: hash_objectives(&dir.join("objective"))?,
+    })
+}
+
+/// Loads a configuration. `dir` is either an experiment directory, or a directory which contains
+/// repeated runs as experiment directories.
+fn load_configuration(dir: &Path) -> Result<Configuration, Box<dyn std::error::Error>> {
+    let label = dir.file_name().map_or_else(
+        || dir.display().to_string(),
+        |name| name.to_string_lossy().to_string(),
+    );
+
+    if dir.join("stats.json").exists() {
+        return Ok(Configuration {
+            label,
+            runs: vec![load_run(dir)?],
+        });
+    }
+
+    let mut run_dirs = fs::read_dir(dir)?
+        .map(|entry| entry.map(|entry| entry.path()))
+        .collect::<Result<Vec<_>, _>>()?;
+    run_dirs.retain(|run_dir| run_dir.join("stats.json").exists());
+    run_dirs.sort();
+
+    if run_dirs.is_empty() {
+        return Err(format!("{:?} does not contain any experiment", dir).into());
+    }
+
+    Ok(Configuration {
+        label,
+        runs: run_dirs
+            .iter()
+            .map(|run_dir| load_run(run_dir))
+            .collect::<Result<_, _>>()?,
+    })
+}
+
+/// Approximation of the cumulative distribution function of the standard normal distribution
+fn normal_cdf(x: f64) -> f64 {
+    // Abramowitz and Stegun 7.1.26
+    let t = 1.0 / (1.0 + 0.3275911 * x.abs() / std::f64::consts::SQRT_2);
+    let erf = 1.0
+        - t * (0.254829592
+            + t * (-0.284496736 + t * (1.421413741 + t * (-1.453152027 + t * 1.061405429))))
+            * (-(x * x) / 2.0).exp();
+    if x >= 0.0 {
+        (1.0 + erf) / 2.0
+    } else {
+        (1.0 - erf) / 2.0
+    }
+}
+
+/// Two-sided Mann-Whitney U test using the normal approximation with tie correction. Returns the
+/// p-value and the Vargha-Delaney A12 effect size, which is the probability that a value of `a` is
+/// larger than a value of `b`.
+fn mann_whitney_u(a: &[f64], b: &[f64]) -> Option<(f64, f64)> {
+    if a.is_empty() || b.is_empty() {
+        return None;
+    }
+
+    let mut values = a
+        .iter()
+        .map(|value| (*value, true))
+        .chain(b.iter().map(|value| (*value, false)))
+        .collect::<Vec<_>>();
+    values.sort_by(|x, y| x.0.partial_cmp(&y.0).unwrap_or(Ordering::Equal));
+
+    let n = values.len() as f64;
+    let (n1, n2) = (a.len() as f64, b.len() as f64);
+    let mut rank_sum_a = 0.0;
+    let mut tie_correction = 0.0;
+
+    let mut i = 0;
+    while i < values.len() {
+        let mut j = i;
+        while j + 1 < values.len() && values[j + 1].0 == values[i].0 {
+            j += 1;
+        }
+
+        // Tied values get the average of their ranks
+        let rank = (i + j) as f64 / 2.0 + 1.0;
+        let ties = (j - i + 1) as f64;
+        tie_correction += ties.powi(3) - ties;
+        rank_sum_a += rank * values[i..=j].iter().filter(|(_, is_a)| *is_a).count() as f64;
+
+        i = j + 1;
+    }
+
+    let u = rank_sum_a - n1 * (n1 + 1.0) / 2.0;
+    let a12 = u / (n1 * n2);
+
+    let mean = n1 * n2 / 2.0;
+    let variance = n1 * n2 / 12.0 * ((n + 1.0) - tie_correction / (n * (n - 1.0)));
+    if variance <= 0.0 {
+        return Some((1.0, a12));
+    }
+
+    // Continuity correction
+    let z = ((u - mean).abs() - 0.5).max(0.0) / variance.sqrt();
+    Some(((2.0 * (1.0 - normal_cdf(z))).min(1.0), a12))
+}
+
+fn mean(values: &[f64]) -> f64 {
+    if values.is_empty() {
+        return 0.0;
+    }
+    values.iter().sum::<f64>() / values.len() as f64
+}
+
+fn median(values: &[f64]) -> f64 {
+    let mut sorted = values.to_vec();
+    sorted.sort_by(|a, b| a.partial_cmp(b).unwrap_or(Ordering::Equal));
+    match sorted.len() {
+        0 => 0.0,
+        n if n % 2 == 0 => (sorted[n / 2 - 1] + sorted[n / 2]) / 2.0,
+        n => sorted[n / 2],
+    }
+}
+
+fn format_duration(seconds: Option<f64>) -> String {
+    match seconds {
+        Some(seconds) => format!(
+            "{}h {:02}min",
+            (seconds / 3600.0) as u64,
+            (seconds % 3600.0 / 60.0) as u64
+        ),
+        None => "-".to_string(),
+    }
+}
+
+/// A table which is written both as Markdown and HTML
+struct Table {
+    header: Vec<&'static str>,
+    rows: Vec<Vec<String>>,
+}
+
+impl Table {
+    fn markdown(&self) -> String {
+        let mut markdown = format!("| {} |\n", self.header.join(" | "));
+        markdown += &format!("|{}\n", "---|".repeat(self.header.len()));
+        for row in &self.rows {
+            markdown += &format!("| {} |\n", row.join(" | "));
+        }
+        markdown
+    }
+
+    fn html(&self) -> String {
+        let mut html = String::from("<table>\n<tr>");
+        for cell in &self.header {
+            html += &format!("<th>{}</th>", cell);
+        }
+        html += "</tr>\n";
+        for row in &self.rows {
+            html += "<tr>";
+            for cell in row {
+                html += &format!(
+                    "<td>{}</td>",
+                    cell.replace('&', "&amp;").replace('<', "&lt;")
+                );
+            }
+            html += "</tr>\n";
+        }
+        html + "</table>\n"
+    }
+}
+
+/// Compares the experiments in `inputs` and writes `comparison.md` and `comparison.html` to
+/// `output_dir`. Each input is either an experiment directory or a directory of repeated runs of
+/// the same configuration. The first input is the baseline for the statistical tests.
+pub fn compare_experiments(
+    inputs: &[PathBuf],
+    output_dir: &Path,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let configurations = inputs
+        .iter()
+        .map(|input| load_configuration(input))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    // The time-to-coverage is measured for the coverage which every run reached eventually
+    let threshold = configurations
+        .iter()
+        .flat_map(|configuration| configuration.runs.iter())
+        .map(|run| run.final_coverage() as u64)
+        .min()
+        .unwrap_or(0);
+
+    let mut summary = Table {
+        header: vec![
+            "Configuration",
+            "Runs",
+            "Mean Coverage",
+            "Median Coverage",
+            "Median Time to Coverage",
+            "Unique Objectives",
+            "Mean Corpus Size",
+        ],
+        rows: vec![],
+    };
+
+    let mut runs = Table {
+        header: vec![
+            "Configuration",
+            "Run",
+            "Duration",
+            "Coverage",
+            "Time to Coverage",
+            "Objectives",
+            "Corpus Size",
+        ],
+        rows: vec![],
+    };
+
+    for configuration in &configurations {
+        let coverages = configuration
+            .runs
+            .iter()
+            .map(Run::final_coverage)
+            .collect::<Vec<_>>();
+        let times = configuration
+            .runs
+            .iter()
+            .filter_map(|run| run.time_to_coverage(threshold))
+            .collect::<Vec<_>>();
+        let objectives = configuration
+            .runs
+            .iter()
+            .flat_map(|run| run.objectives.iter())
+            .collect::<HashSet<_>>();
+        let corpus_sizes = configuration
+            .runs
+            .iter()
+            .map(|run| run.corpus_size as f64)
+            .collect::<Vec<_>>();
+
+        summary.rows.push(vec![
+            configuration.label.clone(),
+            configuration.runs.len().to_string(),
+            format!("{:.1}", mean(&coverages)),
+            format!("{:.1}", median(&coverages)),
+            format_duration((!times.is_empty()).then(|| median(&times))),
+            objectives.len().to_string(),
+            format!("{:.1}", mean(&corpus_sizes)),
+        ]);
+
+        for run in &configuration.runs {
+            runs.rows.push(vec![
+                configuration.label.clone(),
+                run.name.clone(),
+                format_duration(run.samples.last().map(|sample| sample.time)),
+                run.final_coverage().to_string(),
+                format_duration(run.time_to_coverage(threshold)),
+                run.objectives.len().to_string(),
+                run.corpus_size.to_string(),
+            ]);
+        }
+    }
+
+    let mut tests = Table {
+        header: vec![
+            "Configuration",
+            "Baseline",
+            "Metric",
+            "p-value (Mann-Whitney U)",
+            "A12",
+        ],
+        rows: vec![],
+    };
+
+    if let Some((baseline, others)) = configurations.split_first() {
+        let metrics: [(&str, fn(&Run) -> f64); 2] = [
+            ("Coverage", Run::final_coverage),
+            ("Objectives", |run| run.objectives.len() as f64),
+        ];
+
+        for other in others {
+            for (metric, selector) in metrics {
+                let a = other.runs.iter().map(selector).collect::<Vec<_>>();
+                let b = baseline.runs.iter().map(selector).collect::<Vec<_>>();
+
+                if let Some((p, a12)) = mann_whitney_u(&a, &b) {
+                    tests.rows.push(vec![
+                        other.label.clone(),
+                        baseline.label.clone(),
+                        metric.to_string(),
+                        format!("{:.4}", p),
+                        format!("{:.2}", a12),
+                    ]);
+                }
+            }
+        }
+    }
+
+    fs::create_dir_all(output_dir)?;
+
+    let threshold_text = format!(
+        "Time to coverage is the time until {} edges, the lowest final coverage of all runs, are covered.",
+        threshold
+    );
+    let tests_text = "A12 is the probability that a run of the configuration has a higher value than a run of \
+                      the baseline. The p-values are only meaningful with several runs per configuration.";
+
+    fs::write(
+        output_dir.join("comparison.md"),
+        format!(
+            "# Experiment Comparison\n\n## Summary\n\n{}\n{}\n\n## Statistical Tests\n\n{}\n{}\n\n## Runs\n\n{}",
+            threshold_text,
+            summary.markdown(),
+            tests_text,
+            tests.markdown(),
+            runs.markdown()
+        ),
+    )?;
+
+    fs::write(
+        output_dir.join("comparison.html"),
+        format!(
+            "<!DOCTYPE html>\n<html>\n<head><meta charset=\"utf-8\"><title>Experiment Comparison</title>\
+             <style>table {{ border-collapse: collapse; }} td, th {{ border: 1px solid #ccc; padding: 4px 8px; }}</style></head>\n\
+             <body>\n<h1>Experiment Comparison</h1>\n<h2>Summary</h2>\n<p>{}</p>\n{}<h2>Statistical Tests</h2>\n<p>{}</p>\n{}<h2>Runs</h2>\n{}</body>\n</html>\n",
+            threshold_text,
+            summary.html(),
+            tests_text,
+            tests.html(),
+            runs.html()
+        ),
+    )?;
+
+    info!(
+        "Wrote comparison of {} configurations to {:?}",
+        configurations.len(),
+        output_dir
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{mann_whitney_u, median};
+
+    #[test]
+    fn test_mann_whitney_u() {
+        let a = [10.0, 11.0, 12.0, 13.0, 14.0, 15.0, 16.0, 17.0];
+        let b = [1.0, 2.0, 3.0, 4.0, 5.0, 6.0, 7.0, 8.0];
+
+        let (p, a12) = mann_whitney_u(&a, &b).unwrap();
+        assert!(p < 0.01);
+        assert_eq!(a12, 1.0);
+
+        let (p, a12) = mann_whitney_u(&a, &a).unwrap();
+        assert!(p > 0.9);
+        assert_eq!(a12, 0.5);
+    }
+
+    #[test]
+    fn test_mann_whitney_u_all_tied() {
+        assert_eq!(mann_whitney_u(&[1.0, 1.0], &[1.0]), Some((1.0, 0.5)));
+        assert_eq!(mann_whitney_u(&[], &[1.0]), None);
+    }
+
+    #[test]
+    fn test_median() {
+        assert_eq!(median(&[3.0, 1.0, 2.0]), 2.0);
+        assert_eq!(median(&[4.0, 1.0, 2.0, 3.0]), 2.5);
+    }
+}
@@ -0,0 +1,210 @@
+//! Corpus distillation. Replays every trace of a corpus under the edge observer and keeps a
+//! small subset which still covers every observed edge.
+
+use std::{
+    collections::HashSet,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use libafl::{
+    corpus::{ondisk::OnDiskMetadataFormat, CachedOnDiskCorpus, Corpus, Testcase},
+    inputs::Input,
+};
+use log::{info, warn};
+
+use crate::{
+    fuzzer::{harness, libafl_setup::edges_map},
+    protocol::ProtocolBehavior,
+    trace::{Action, Trace},
+};
+
+/// An edge together with the AFL-style bucket of its hit count. Hitting an edge once or a hundred
+/// times are considered distinct features, just like in the [`libafl::observers::HitcountsMapObserver`].
+type Feature = (usize, u8);
+
+/// Maps a raw hit count to its bucket.
+pub(crate) fn classify_count(count: u8) -> u8 {
+    match count {
+        0 => 0,
+        1 => 1,
+        2 => 2,
+        3 => 4,
+        4..=7 => 8,
+        8..=15 => 16,
+        16..=31 => 32,
+        32..=127 => 64,
+        128..=255 => 128,
+    }
+}
+
+/// Size of a trace which is used to break ties between traces which cover the same amount of new
+/// features. Shorter traces are preferred, then traces with smaller recipes.
+fn trace_size<M: crate::algebra::Matcher>(trace: &Trace<M>) -> (usize, usize) {
+    let term_size = trace
+        .steps
+        .iter()
+        .map(|step| match &step.action {
+            Action::Input(input) => input.recipe.size(),
+            Action::Output(_) => 0,
+        })
+        .sum();
+    (trace.steps.len(), term_size)
+}
+
+/// Greedy set cover. In each round the entry covering the most uncovered features is picked.
+/// Ties are broken by the size of an entry. Returns the indices of the chosen entries.
+fn greedy_set_cover<S: Ord + Copy>(entries: &[(HashSet<Feature>, S)]) -> Vec<usize> {
+    let mut uncovered: HashSet<Feature> = entries
+        .iter()
+        .flat_map(|(features, _)| features.iter().copied())
+        .collect();
+    let mut chosen = Vec::new();
+
+    while !uncovered.is_empty() {
+        let best = entries
+            .iter()
+            .enumerate()
+            .map(|(i, (features, size))| {
+                let gain = features.intersection(&uncovered).count();
+                (i, gain, *size)
+            })
+            .filter(|(_, gain, _)| *gain > 0)
+            .min_by(|(_, gain_a, size_a), (_, gain_b, size_b)| {
+                gain_b.cmp(gain_a).then(size_a.cmp(size_b))
+            });
+
+        match best {
+            Some((i, _, _)) => {
+                for feature in &entries[i].0 {
+                    uncovered.remove(feature);
+                }
+                chosen.push(i);
+            }
+            None => break,
+        }
+    }
+
+    chosen
+}
+
+/// Executes the trace using the fuzzing harness and collects the features from the edges map.
+fn observe_features<PB: ProtocolBehavior + 'static>(
+    trace: &Trace<PB::Matcher>,
+) -> HashSet<Feature> {
+    let map = unsafe { edges_map() };
+    map.fill(0);
+
+    harness::harness::<PB>(trace);
+
+    map.iter()
+        .enumerate()
+        .filter(|(_, count)| **count != 0)
+        .map(|(edge, count)| (edge, classify_count(*count)))
+        .collect()
+}
+
+/// Reads all traces in `input_dir`. Hidden files like the metadata written by
+/// [`CachedOnDiskCorpus`] are skipped.
+fn load_traces<PB: ProtocolBehavior>(
+    input_dir: &Path,
+) -> Result<Vec<(PathBuf, Trace<PB::Matcher>)>, Box<dyn std::error::Error>> {
+    let mut traces = Vec::new();
+
+    let mut paths = fs::read_dir(input_dir)?
+        .map(|entry| entry.map(|entry| entry.path()))
+        .collect::<Result<Vec<_>, _>>()?;
+    paths.sort();
+
+    for path in paths {
+        let is_hidden = path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .map_or(true, |name| name.starts_with('.'));
+
+        if !path.is_file() || is_hidden {
+            continue;
+        }
+
+        match Trace::<PB::Matcher>::from_file(&path) {
+            Ok(trace) => traces.push((path, trace)),
+            Err(err) => warn!("Skipping {:?} because it is not a trace: {}", path, err),
+        }
+    }
+
+    Ok(traces)
+}
+
+/// Distills the corpus in `input_dir` into `output_dir`. Every trace is replayed in-process,
+/// therefore the input corpus must not contain crashing traces.
+pub fn cmin<PB: ProtocolBehavior + 'static>(
+    input_dir: &Path,
+    output_dir: &Path,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let traces = load_traces::<PB>(input_dir)?;
+    info!("Loaded {} traces from {:?}", traces.len(), input_dir);
+
+    let entries = traces
+        .iter()
+        .map(|(path, trace)| {
+            let features = observe_features::<PB>(trace);
+            info!("{:?} covers {} features", path, features.len());
+            (features, trace_size(trace))
+        })
+        .collect::<Vec<_>>();
+
+    let chosen = greedy_set_cover(&entries);
+
+    let mut corpus = CachedOnDiskCorpus::<Trace<PB::Matcher>>::new_save_meta(
+        output_dir.to_path_buf(),
+        Some(OnDiskMetadataFormat::Json),
+        1000,
+    )?;
+
+    for i in &chosen {
+        let (path, trace) = &traces[*i];
+        info!("Keeping {:?}", path);
+        corpus.add(Testcase::new(trace.clone()))?;
+    }
+
+    info!(
+        "Distilled {} traces into {} traces in {:?}",
+        traces.len(),
+        chosen.len(),
+        output_dir
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use super::{greedy_set_cover, Feature};
+
+    fn features(edges: &[usize]) -> HashSet<Feature> {
+        edges.iter().map(|edge| (*edge, 1)).collect()
+    }
+
+    #[test]
+    fn test_greedy_set_cover() {
+        let entries = vec![
+            (features(&[0, 1]), 2),
+            (features(&[0, 1, 2, 3]), 5),
+            (features(&[2, 3]), 1),
+            (features(&[0, 1, 2, 3]), 3),
+            (features(&[4]), 1),
+            (features(&[4]), 0),
+        ];
+
+        let mut chosen = greedy_set_cover(&entries);
+        chosen.sort();
+        assert_eq!(chosen, vec![3, 5]);
+    }
+
+    #[test]
+    fn test_greedy_set_cover_empty() {
+        let entries: Vec<(HashSet<Feature>, usize)> = vec![(features(&[]), 1)];
+        assert!(greedy_set_cover(&entries).is_empty());
+    }
+}
@@ -4,13 +4,19 @@ use rand::Rng;
 
 use crate::{
     error::Error,
-    fuzzer::stats_stage::*,
+    fuzzer::{
+        objective::{report_violation, reset_violation},
+        sanitizer::lsan::{has_leaks, leak_check_enabled},
+        stability::record_observation,
+        stats_stage::*,
+    },
     protocol::ProtocolBehavior,
     trace::{Action, Trace, TraceContext},
 };
 
 pub fn harness<PB: ProtocolBehavior + 'static>(input: &Trace<PB::Matcher>) -> ExitKind {
     let mut ctx = TraceContext::new(PB::registry());
+    reset_violation();
 
     TRACE_LENGTH.update(input.steps.len());
 
@@ -23,7 +29,10 @@ pub fn harness<PB: ProtocolBehavior + 'static>(input: &Trace<PB::Matcher>) -> Ex
         }
     }
 
-    if let Err(err) = input.execute(&mut ctx) {
+    let result = input.execute(&mut ctx);
+    record_observation(&ctx, &result);
+
+    if let Err(err) = result {
         match &err {
             Error::Fn(_) => FN_ERROR.increment(),
             Error::Term(_e) => TERM.increment(),
@@ -34,13 +43,20 @@ pub fn harness<PB: ProtocolBehavior + 'static>(input: &Trace<PB::Matcher>) -> Ex
             Error::Extraction() => EXTRACTION.increment(),
             Error::SecurityClaim(msg) => {
                 warn!("{}", msg);
-                std::process::abort()
+                // Reported as objective by the SecurityViolationFeedback
+                report_violation(msg);
             }
         }
 
         trace!("{}", err);
     }
 
+    if leak_check_enabled() && has_leaks() {
+        // Aborting records the trace as objective and restarts the client. Else the same leak
+        // would be reported again after each following execution.
+        std::process::abort();
+    }
+
     ExitKind::Ok
 }
 
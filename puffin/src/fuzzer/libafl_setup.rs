@@ -1,5 +1,8 @@
 use core::time::Duration;
-use std::{fmt, path::PathBuf};
+use std::{
+    fmt,
+    path::{Path, PathBuf},
+};
 
 use libafl::{
     bolts::{
@@ -9,7 +12,7 @@ use libafl::{
         tuples::tuple_list,
         HasLen,
     },
-    corpus::{ondisk::OnDiskMetadataFormat, CachedOnDiskCorpus, Corpus, OnDiskCorpus},
+    corpus::{ondisk::OnDiskMetadataFormat, CachedOnDiskCorpus, Corpus},
     events::{
         setup_restarting_mgr_std, EventConfig, EventFirer, EventManager, EventRestarter,
         HasEventManagerId, LlmpRestartingEventManager, ProgressReporter,
@@ -26,6 +29,7 @@ use libafl::{
     mutators::MutatorsTuple,
     observers::{HitcountsMapObserver, ObserversTuple, StdMapObserver, TimeObserver},
     schedulers::{IndexesLenTimeMinimizerScheduler, QueueScheduler, Scheduler},
+    stages::StagesTuple,
     state::{HasCorpus, HasRand, StdState},
     Error, Evaluator,
 };
@@ -35,7 +39,11 @@ use log4rs::Handle;
 use super::harness;
 use crate::{
     fuzzer::{
+        metrics::{MetricsExporter, MetricsTarget},
         mutations::{trace_mutations, util::TermConstraints},
+        objective::{ObjectiveCorpus, SecurityViolationFeedback},
+        sanitizer::lsan::set_leak_check,
+        stability::CalibrationStage,
         stages::{PuffinMutationalStage, PuffinScheduledMutator},
         stats_monitor::StatsMonitor,
         stats_stage::StatsStage,
@@ -69,6 +77,13 @@ pub struct FuzzerConfig {
     pub monitor: bool,
     pub no_launcher: bool,
     pub log_file: PathBuf,
+    /// How often new corpus entries are executed to check whether they are stable
+    pub calibration_runs: usize,
+    /// Optional export of the statistics for scraping by Prometheus
+    pub metrics: Option<MetricsTarget>,
+    /// Whether to check for memory leaks after each execution. Requires a PUT which is built with
+    /// ASAN or LSAN.
+    pub leak_check: bool,
 }
 
 #[derive(Clone, Copy)]
@@ -90,7 +105,8 @@ impl Default for MutationStageConfig {
 
 #[derive(Clone, Copy)]
 pub struct MutationConfig {
-    pub fresh_zoo_after: u64,
+    /// Types which the generation of terms failed for are retried after this many mutations
+    pub retry_failed_types_after: u64,
     pub max_trace_length: usize,
     pub min_trace_length: usize,
     /// Below this term size we no longer mutate. Note that it is possible to reach
@@ -102,7 +118,7 @@ pub struct MutationConfig {
 impl Default for MutationConfig {
     fn default() -> Self {
         Self {
-            fresh_zoo_after: 100000,
+            retry_failed_types_after: 100000,
             max_trace_length: 15,
             min_trace_length: 2,
             term_constraints: TermConstraints {
@@ -113,7 +129,24 @@ impl Default for MutationConfig {
     }
 }
 
-struct RunClientBuilder<'harness, H, C, R, SC, EM, F, OF, OT, CS, MT, I>
+/// Builds and runs a single fuzzing client. The mutations, feedbacks, observers, scheduler and
+/// corpora are generic, such that embedders can replace any of them. Additional stages can be
+/// appended to the default stages with [`RunClientBuilder::with_stages`]. The default stages can
+/// be replaced or reordered with [`RunClientBuilder::replace_stages`].
+///
+/// [`start`] uses this builder with the defaults of puffin. Custom clients are usually passed to
+/// [`launch`], which takes care of the broker and the restarting event managers:
+///
+/// ```rust,ignore
+/// launch(&config, |state, event_manager, _core| {
+///     RunClientBuilder::new(config.clone(), &mut harness::<PB>, state, event_manager)
+///         .with_mutations(my_mutations())
+///         .with_stages(tuple_list!(MyStage::new()))
+///         // ...
+///         .run_client()
+/// })
+/// ```
+pub struct RunClientBuilder<'harness, H, C, R, SC, EM, F, OF, OT, CS, MT, I, ST = ()>
 where
     I: Input,
     C: Corpus<I>,
@@ -135,33 +168,22 @@ where
     objective: Option<OF>,
     initial_inputs: Option<Vec<(I, &'static str)>>,
     mutations: Option<MT>,
+    /// Whether the default stages are executed before `stages`
+    default_stages: bool,
+    /// Stages which are executed after the default stages
+    stages: ST,
 }
 
 impl<'harness, H, C, R, SC, EM, F, OF, OT, CS, MT, I>
-    RunClientBuilder<'harness, H, C, R, SC, EM, F, OF, OT, CS, MT, I>
+    RunClientBuilder<'harness, H, C, R, SC, EM, F, OF, OT, CS, MT, I, ()>
 where
     I: Input,
     C: Corpus<I>,
     R: Rand,
     SC: Corpus<I>,
-    H: FnMut(&I) -> ExitKind,
-    OF: Feedback<I, ConcreteState<C, R, SC, I>>,
-    OT: ObserversTuple<I, ConcreteState<C, R, SC, I>>
-        + serde::Serialize
-        + serde::de::DeserializeOwned,
-    F: Feedback<I, ConcreteState<C, R, SC, I>>,
-    CS: Scheduler<I, ConcreteState<C, R, SC, I>>,
-    EM: EventFirer<I>
-        + EventRestarter<ConcreteState<C, R, SC, I>>
-        + EventManager<
-            ConcreteExecutor<'harness, H, OT, ConcreteState<C, R, SC, I>, I>,
-            I,
-            ConcreteState<C, R, SC, I>,
-            StdFuzzer<CS, F, I, OF, OT, ConcreteState<C, R, SC, I>>,
-        > + ProgressReporter<I>,
     MT: MutatorsTuple<I, ConcreteState<C, R, SC, I>>,
 {
-    fn new(
+    pub fn new(
         config: FuzzerConfig,
         harness_fn: &'harness mut H,
         existing_state: Option<ConcreteState<C, R, SC, I>>,
@@ -181,75 +203,156 @@ where
             objective: None,
             initial_inputs: None,
             mutations: None,
+            default_stages: true,
+            stages: (),
         }
     }
-    fn with_rand(mut self, rand: R) -> Self {
+}
+
+impl<'harness, H, C, R, SC, EM, F, OF, OT, CS, MT, I, ST>
+    RunClientBuilder<'harness, H, C, R, SC, EM, F, OF, OT, CS, MT, I, ST>
+where
+    I: Input,
+    C: Corpus<I>,
+    R: Rand,
+    SC: Corpus<I>,
+    H: FnMut(&I) -> ExitKind,
+    OF: Feedback<I, ConcreteState<C, R, SC, I>>,
+    OT: ObserversTuple<I, ConcreteState<C, R, SC, I>>
+        + serde::Serialize
+        + serde::de::DeserializeOwned,
+    F: Feedback<I, ConcreteState<C, R, SC, I>>,
+    CS: Scheduler<I, ConcreteState<C, R, SC, I>>,
+    EM: EventFirer<I>
+        + EventRestarter<ConcreteState<C, R, SC, I>>
+        + EventManager<
+            ConcreteExecutor<'harness, H, OT, ConcreteState<C, R, SC, I>, I>,
+            I,
+            ConcreteState<C, R, SC, I>,
+            StdFuzzer<CS, F, I, OF, OT, ConcreteState<C, R, SC, I>>,
+        > + ProgressReporter<I>,
+    MT: MutatorsTuple<I, ConcreteState<C, R, SC, I>>,
+{
+    pub fn with_rand(mut self, rand: R) -> Self {
         self.rand = Some(rand);
         self
     }
 
-    fn with_corpus(mut self, corpus: C) -> Self {
+    pub fn with_corpus(mut self, corpus: C) -> Self {
         self.corpus = Some(corpus);
         self
     }
 
-    fn with_objective_corpus(mut self, objective_corpus: SC) -> Self {
+    pub fn with_objective_corpus(mut self, objective_corpus: SC) -> Self {
         self.objective_corpus = Some(objective_corpus);
         self
     }
 
-    fn with_scheduler(mut self, scheduler: CS) -> Self {
+    pub fn with_scheduler(mut self, scheduler: CS) -> Self {
         self.scheduler = Some(scheduler);
         self
     }
 
-    fn with_feedback(mut self, feedback: F) -> Self {
+    pub fn with_feedback(mut self, feedback: F) -> Self {
         self.feedback = Some(feedback);
         self
     }
 
-    fn with_objective(mut self, objective: OF) -> Self {
+    pub fn with_objective(mut self, objective: OF) -> Self {
         self.objective = Some(objective);
         self
     }
 
-    fn with_observers(mut self, observers: OT) -> Self {
+    pub fn with_observers(mut self, observers: OT) -> Self {
         self.observers = Some(observers);
         self
     }
 
-    fn with_initial_inputs(mut self, initial_inputs: Vec<(I, &'static str)>) -> Self {
+    pub fn with_initial_inputs(mut self, initial_inputs: Vec<(I, &'static str)>) -> Self {
         self.initial_inputs = Some(initial_inputs);
         self
     }
 
-    fn with_mutations(mut self, mutations: MT) -> Self {
+    pub fn with_mutations(mut self, mutations: MT) -> Self {
         self.mutations = Some(mutations);
         self
     }
 
-    fn run_client(mut self) -> Result<(), Error> {
+    /// Appends `stages` to the default stages. They are executed after the statistics have been
+    /// updated.
+    pub fn with_stages<ST2>(
+        self,
+        stages: ST2,
+    ) -> RunClientBuilder<'harness, H, C, R, SC, EM, F, OF, OT, CS, MT, I, ST2> {
+        RunClientBuilder {
+            config: self.config,
+            harness_fn: self.harness_fn,
+            existing_state: self.existing_state,
+            rand: self.rand,
+            objective_corpus: self.objective_corpus,
+            corpus: self.corpus,
+            scheduler: self.scheduler,
+            event_manager: self.event_manager,
+            observers: self.observers,
+            feedback: self.feedback,
+            objective: self.objective,
+            initial_inputs: self.initial_inputs,
+            mutations: self.mutations,
+            default_stages: self.default_stages,
+            stages,
+        }
+    }
+
+    /// Executes only `stages` instead of the default stages. The default stages can be reordered
+    /// by passing them in a different order, for example
+    /// `tuple_list!(PuffinMutationalStage::new(..), CalibrationStage::new(..), StatsStage::new())`.
+    /// The mutations of the builder are only used by the default stages.
+    pub fn replace_stages<ST2>(
+        self,
+        stages: ST2,
+    ) -> RunClientBuilder<'harness, H, C, R, SC, EM, F, OF, OT, CS, MT, I, ST2> {
+        let mut builder = self.with_stages(stages);
+        builder.default_stages = false;
+        builder
+    }
+
+    /// Creates the state, fuzzer and executor and runs the fuzzing loop. The feedback, objective,
+    /// observers and scheduler must have been set before, as well as the mutations if the default
+    /// stages are used. The random number generator and the corpora are only required if no
+    /// existing state is passed. Missing components are reported as
+    /// [`Error::IllegalArgument`].
+    pub fn run_client(mut self) -> Result<(), Error>
+    where
+        ST: StagesTuple<
+            ConcreteExecutor<'harness, H, OT, ConcreteState<C, R, SC, I>, I>,
+            EM,
+            ConcreteState<C, R, SC, I>,
+            StdFuzzer<CS, F, I, OF, OT, ConcreteState<C, R, SC, I>>,
+        >,
+    {
         let event_manager_id = self.event_manager.mgr_id().id as u64;
         info!("Event manager ID is {}", event_manager_id);
 
-        let mut feedback = self.feedback.unwrap();
-        let mut objective = self.objective.unwrap();
+        let mut feedback = required(self.feedback, "feedback")?;
+        let mut objective = required(self.objective, "objective")?;
 
         // If not restarting, create a State from scratch
-        let mut state = self.existing_state.unwrap_or_else(|| {
-            StdState::new(
-                self.rand.unwrap(),
-                self.corpus.unwrap(),
-                self.objective_corpus.unwrap(),
+        let mut state = match self.existing_state {
+            Some(state) => state,
+            None => StdState::new(
+                required(self.rand, "rand")?,
+                required(self.corpus, "corpus")?,
+                required(self.objective_corpus, "objective corpus")?,
                 &mut feedback,
                 &mut objective,
-            )
-            .unwrap()
-        });
+            )?,
+        };
 
         let FuzzerConfig {
             initial_corpus_dir,
             max_iters,
+            calibration_runs,
+            leak_check,
             mutation_stage_config:
                 MutationStageConfig {
                     max_iterations_per_stage,
@@ -258,21 +361,16 @@ where
             ..
         } = self.config;
 
-        let mutator =
-            PuffinScheduledMutator::new(self.mutations.unwrap(), max_mutations_per_iteration);
-        let mut stages = tuple_list!(
-            PuffinMutationalStage::new(mutator, max_iterations_per_stage),
-            StatsStage::new()
-        );
+        set_leak_check(leak_check);
 
         let mut fuzzer: StdFuzzer<CS, F, I, OF, OT, _> =
-            StdFuzzer::new(self.scheduler.unwrap(), feedback, objective);
+            StdFuzzer::new(required(self.scheduler, "scheduler")?, feedback, objective);
 
         let mut executor: ConcreteExecutor<'harness, H, OT, _, I> = TimeoutExecutor::new(
             InProcessExecutor::new(
                 self.harness_fn,
                 // hint: edges_observer is expensive to serialize (only noticeable if we add all inputs to the corpus)
-                self.observers.unwrap(),
+                required(self.observers, "observers")?,
                 &mut fuzzer,
                 &mut state,
                 &mut self.event_manager,
@@ -300,7 +398,7 @@ where
             } else {
                 warn!("Initial seed corpus not found. Using embedded seeds.");
 
-                for (seed, name) in self.initial_inputs.unwrap() {
+                for (seed, name) in self.initial_inputs.unwrap_or_default() {
                     info!("Using seed {}", name);
                     fuzzer
                         .add_input(&mut state, &mut executor, &mut self.event_manager, seed)
@@ -309,26 +407,89 @@ where
             }
         }
 
-        if let Some(max_iters) = max_iters {
-            fuzzer.fuzz_loop_for(
-                &mut stages,
+        if !self.default_stages {
+            return Self::fuzz(
+                &mut fuzzer,
+                &mut self.stages,
                 &mut executor,
                 &mut state,
                 &mut self.event_manager,
                 max_iters,
-            )?;
+            );
+        }
+
+        let mutator = PuffinScheduledMutator::new(
+            required(self.mutations, "mutations")?,
+            max_mutations_per_iteration,
+        );
+        let calibration = CalibrationStage::new(calibration_runs);
+        // The stages of the builder are the tail of the stage tuple
+        #[cfg(all(feature = "cmplog", feature = "sancov_libafl", not(test)))]
+        let mut stages = (
+            calibration,
+            (
+                crate::fuzzer::stages::CmpLogStage::new(),
+                (
+                    PuffinMutationalStage::new(mutator, max_iterations_per_stage),
+                    (StatsStage::new(), self.stages),
+                ),
+            ),
+        );
+        #[cfg(not(all(feature = "cmplog", feature = "sancov_libafl", not(test))))]
+        let mut stages = (
+            calibration,
+            (
+                PuffinMutationalStage::new(mutator, max_iterations_per_stage),
+                (StatsStage::new(), self.stages),
+            ),
+        );
+
+        Self::fuzz(
+            &mut fuzzer,
+            &mut stages,
+            &mut executor,
+            &mut state,
+            &mut self.event_manager,
+            max_iters,
+        )
+    }
+
+    /// Runs the fuzzing loop with `stages` for `max_iters` iterations, or forever.
+    fn fuzz<ST2>(
+        fuzzer: &mut StdFuzzer<CS, F, I, OF, OT, ConcreteState<C, R, SC, I>>,
+        stages: &mut ST2,
+        executor: &mut ConcreteExecutor<'harness, H, OT, ConcreteState<C, R, SC, I>, I>,
+        state: &mut ConcreteState<C, R, SC, I>,
+        event_manager: &mut EM,
+        max_iters: Option<u64>,
+    ) -> Result<(), Error>
+    where
+        ST2: StagesTuple<
+            ConcreteExecutor<'harness, H, OT, ConcreteState<C, R, SC, I>, I>,
+            EM,
+            ConcreteState<C, R, SC, I>,
+            StdFuzzer<CS, F, I, OF, OT, ConcreteState<C, R, SC, I>>,
+        >,
+    {
+        if let Some(max_iters) = max_iters {
+            fuzzer.fuzz_loop_for(stages, executor, state, event_manager, max_iters)?;
         } else {
-            fuzzer.fuzz_loop(
-                &mut stages,
-                &mut executor,
-                &mut state,
-                &mut self.event_manager,
-            )?;
+            fuzzer.fuzz_loop(stages, executor, state, event_manager)?;
         }
         Ok(())
     }
 }
 
+/// Takes a component of the [`RunClientBuilder`] which must have been set.
+fn required<T>(component: Option<T>, name: &str) -> Result<T, Error> {
+    component.ok_or_else(|| {
+        Error::illegal_argument(format!(
+            "The {} of the client must be set before running it",
+            name
+        ))
+    })
+}
+
 type ConcreteMinimizer<C, R, SC, I> =
     IndexesLenTimeMinimizerScheduler<QueueScheduler, I, ConcreteState<C, R, SC, I>>;
 
@@ -352,7 +513,7 @@ type ConcreteFeedback<'a, C, R, SC, I> = CombinedFeedback<
     ConcreteState<C, R, SC, I>,
 >;
 
-impl<'harness, 'a, H, SC, C, R, EM, OF, MT, I>
+impl<'harness, 'a, H, SC, C, R, EM, OF, MT, I, ST>
     RunClientBuilder<
         'harness,
         H,
@@ -366,6 +527,7 @@ impl<'harness, 'a, H, SC, C, R, EM, OF, MT, I>
         ConcreteMinimizer<C, R, SC, I>,
         MT,
         I,
+        ST,
     >
 where
     I: Input + HasLen,
@@ -391,21 +553,10 @@ where
         > + ProgressReporter<I>,
     MT: MutatorsTuple<I, ConcreteState<C, R, SC, I>>,
 {
-    fn install_minimizer(self) -> Self {
-        #[cfg(not(test))]
-        let map = unsafe {
-            pub use libafl_targets::{EDGES_MAP, MAX_EDGES_NUM};
-            &mut EDGES_MAP[0..MAX_EDGES_NUM]
-        };
-
-        #[cfg(test)]
-        let map = unsafe {
-            // When testing we should not import libafl_targets, else it conflicts with sancov_dummy
-            pub const EDGES_MAP_SIZE: usize = 65536;
-            pub static mut EDGES_MAP: [u8; EDGES_MAP_SIZE] = [0; EDGES_MAP_SIZE];
-            pub static mut MAX_EDGES_NUM: usize = 0;
-            &mut EDGES_MAP[0..MAX_EDGES_NUM]
-        };
+    /// Installs the default feedback, observers and scheduler, which minimize the corpus based on
+    /// the edge coverage, the length and the execution time of the traces.
+    pub fn install_minimizer(self) -> Self {
+        let map = unsafe { edges_map() };
 
         let map_feedback = MaxMapFeedback::with_names_tracking(
             MAP_FEEDBACK_NAME,
@@ -435,6 +586,54 @@ where
     }
 }
 
+/// Returns the part of the edges map which is used by the instrumented PUT.
+///
+/// # Safety
+///
+/// The returned slice aliases a global static which is written by the sancov callbacks.
+/// Callers must not hold on to it across concurrent executions.
+pub(crate) unsafe fn edges_map() -> &'static mut [u8] {
+    #[cfg(not(test))]
+    let map = {
+        pub use libafl_targets::{EDGES_MAP, MAX_EDGES_NUM};
+        &mut EDGES_MAP[0..MAX_EDGES_NUM]
+    };
+
+    #[cfg(test)]
+    let map = {
+        // When testing we should not import libafl_targets, else it conflicts with sancov_dummy
+        pub const EDGES_MAP_SIZE: usize = 65536;
+        pub static mut EDGES_MAP: [u8; EDGES_MAP_SIZE] = [0; EDGES_MAP_SIZE];
+        pub static mut MAX_EDGES_NUM: usize = 0;
+        &mut EDGES_MAP[0..MAX_EDGES_NUM]
+    };
+
+    map
+}
+
+fn stats_monitor(
+    monitor_file: &Path,
+    metrics: &Option<MetricsTarget>,
+) -> Result<StatsMonitor<impl FnMut(String) + Clone>, Error> {
+    let mut monitor = StatsMonitor::new(
+        |s| {
+            info!("{}", s);
+        },
+        monitor_file.to_path_buf(),
+    )?;
+
+    if let Some(metrics) = metrics {
+        // Without the launcher, restarted clients also create a monitor. The port is already
+        // taken by the broker in that case.
+        match MetricsExporter::new(metrics) {
+            Ok(exporter) => monitor = monitor.with_metrics(exporter),
+            Err(err) => warn!("Not exporting metrics: {}", err),
+        }
+    }
+
+    Ok(monitor)
+}
+
 /// Starts the fuzzing loop
 pub fn start<PB: ProtocolBehavior + Clone + 'static>(
     config: FuzzerConfig,
@@ -446,13 +645,9 @@ pub fn start<PB: ProtocolBehavior + Clone + 'static>(
         objective_dir,
         static_seed,
         log_file,
-        monitor_file,
-        broker_port,
-        monitor,
-        no_launcher,
         mutation_config:
             MutationConfig {
-                fresh_zoo_after,
+                retry_failed_types_after,
                 max_trace_length,
                 min_trace_length,
                 term_constraints,
@@ -462,7 +657,7 @@ pub fn start<PB: ProtocolBehavior + Clone + 'static>(
 
     info!("Running on cores: {}", &core_definition);
 
-    let mut run_client =
+    let run_client =
         |state: Option<StdState<_, Trace<PB::Matcher>, _, _>>,
          event_manager: LlmpRestartingEventManager<Trace<PB::Matcher>, _, _, StdShMemProvider>,
          _unknown: usize|
@@ -478,7 +673,7 @@ pub fn start<PB: ProtocolBehavior + Clone + 'static>(
                     *min_trace_length,
                     *max_trace_length,
                     *term_constraints,
-                    *fresh_zoo_after,
+                    *retry_failed_types_after,
                     PB::signature(),
                 ))
                 .with_initial_inputs(PB::create_corpus())
@@ -492,13 +687,17 @@ pub fn start<PB: ProtocolBehavior + Clone + 'static>(
                     .unwrap(),
                 )
                 .with_objective_corpus(
-                    OnDiskCorpus::new_save_meta(
+                    ObjectiveCorpus::new_save_meta(
                         objective_dir.clone(),
                         Some(OnDiskMetadataFormat::JsonPretty),
                     )
                     .unwrap(),
                 )
-                .with_objective(feedback_or!(CrashFeedback::new(), TimeoutFeedback::new()));
+                .with_objective(feedback_or!(
+                    CrashFeedback::new(),
+                    TimeoutFeedback::new(),
+                    SecurityViolationFeedback::new()
+                ));
 
             #[cfg(feature = "sancov_libafl")]
             {
@@ -519,22 +718,44 @@ pub fn start<PB: ProtocolBehavior + Clone + 'static>(
             builder.run_client()
         };
 
+    launch(&config, run_client)
+}
+
+/// Starts the broker and the clients according to `config`. The clients are restarted by
+/// [`LlmpRestartingEventManager`]s and run `run_client`, which usually uses a
+/// [`RunClientBuilder`]. `run_client` is called with the state of the previous run of the client,
+/// the event manager and the core of the client.
+pub fn launch<I, OT, S, CF>(config: &FuzzerConfig, mut run_client: CF) -> Result<(), Error>
+where
+    I: Input,
+    OT: ObserversTuple<I, S> + serde::de::DeserializeOwned,
+    S: serde::de::DeserializeOwned,
+    CF: FnMut(
+        Option<S>,
+        LlmpRestartingEventManager<I, OT, S, StdShMemProvider>,
+        usize,
+    ) -> Result<(), Error>,
+{
+    let FuzzerConfig {
+        core_definition,
+        monitor_file,
+        broker_port,
+        monitor,
+        no_launcher,
+        metrics,
+        ..
+    } = config;
+
     if *no_launcher {
         let (state, restarting_mgr) = setup_restarting_mgr_std(
-            StatsMonitor::new(
-                |s| {
-                    info!("{}", s);
-                },
-                monitor_file.clone(),
-            )
-            .unwrap(),
+            stats_monitor(monitor_file, metrics)?,
             *broker_port,
             EventConfig::AlwaysUnique,
         )?;
 
         run_client(state, restarting_mgr, 0)
     } else {
-        let cores = Cores::from_cmdline(config.core_definition.as_str()).unwrap();
+        let cores = Cores::from_cmdline(core_definition.as_str()).unwrap();
         let configuration: EventConfig = "launcher default".into();
         let sh_mem_provider = StdShMemProvider::new().expect("Failed to init shared memory");
 
@@ -553,15 +774,7 @@ pub fn start<PB: ProtocolBehavior + Clone + 'static>(
             libafl::bolts::launcher::Launcher::builder()
                 .shmem_provider(sh_mem_provider)
                 .configuration(configuration)
-                .monitor(
-                    StatsMonitor::new(
-                        |s| {
-                            info!("{}", s);
-                        },
-                        monitor_file.clone(),
-                    )
-                    .unwrap(),
-                )
+                .monitor(stats_monitor(monitor_file, metrics)?)
                 .run_client(&mut run_client)
                 .cores(&cores)
                 .broker_port(*broker_port)
@@ -574,3 +787,114 @@ pub fn start<PB: ProtocolBehavior + Clone + 'static>(
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::{path::PathBuf, sync::Mutex};
+
+    use libafl::{
+        bolts::{rands::StdRand, tuples::tuple_list},
+        corpus::InMemoryCorpus,
+        events::SimpleEventManager,
+        executors::ExitKind,
+        inputs::BytesInput,
+        monitors::SimpleMonitor,
+        mutators::BitFlipMutator,
+        schedulers::QueueScheduler,
+    };
+
+    use super::{FuzzerConfig, MutationConfig, MutationStageConfig, RunClientBuilder};
+    use crate::fuzzer::stats_stage::StatsStage;
+
+    /// The in-process executor installs global signal handlers, therefore only one client may run
+    /// at a time
+    static CLIENT: Mutex<()> = Mutex::new(());
+
+    fn config() -> FuzzerConfig {
+        FuzzerConfig {
+            initial_corpus_dir: PathBuf::from("/nonexistent/corpus"),
+            static_seed: Some(0),
+            max_iters: Some(3),
+            core_definition: "0".to_string(),
+            monitor_file: PathBuf::from("/nonexistent/stats.json"),
+            corpus_dir: PathBuf::from("/nonexistent/corpus"),
+            objective_dir: PathBuf::from("/nonexistent/objective"),
+            broker_port: 1337,
+            minimizer: false,
+            mutation_stage_config: MutationStageConfig {
+                max_iterations_per_stage: 4,
+                max_mutations_per_iteration: 2,
+            },
+            mutation_config: MutationConfig::default(),
+            monitor: false,
+            no_launcher: true,
+            log_file: PathBuf::from("/nonexistent/log.json"),
+            calibration_runs: 2,
+            metrics: None,
+            leak_check: false,
+        }
+    }
+
+    #[test]
+    fn test_run_client_with_default_stages() {
+        let _client = CLIENT.lock().unwrap();
+        let mut executions = 0;
+        let mut harness = |_input: &BytesInput| {
+            executions += 1;
+            ExitKind::Ok
+        };
+
+        RunClientBuilder::new(
+            config(),
+            &mut harness,
+            None,
+            SimpleEventManager::new(SimpleMonitor::new(|_| {})),
+        )
+        .with_rand(StdRand::with_seed(0))
+        .with_corpus(InMemoryCorpus::new())
+        .with_objective_corpus(InMemoryCorpus::new())
+        .with_feedback(())
+        .with_objective(())
+        .with_observers(())
+        .with_scheduler(QueueScheduler::new())
+        .with_mutations(tuple_list!(BitFlipMutator::new()))
+        .with_initial_inputs(vec![(BytesInput::new(vec![0; 8]), "zeros")])
+        .run_client()
+        .unwrap();
+
+        // The seed, its calibration and at least one mutated input per iteration
+        assert!(executions >= 1 + 2 + 3);
+    }
+
+    #[test]
+    fn test_run_client_with_replaced_stages() {
+        let _client = CLIENT.lock().unwrap();
+        let mut executions = 0;
+        let mut harness = |_input: &BytesInput| {
+            executions += 1;
+            ExitKind::Ok
+        };
+
+        RunClientBuilder::new(
+            config(),
+            &mut harness,
+            None,
+            SimpleEventManager::new(SimpleMonitor::new(|_| {})),
+        )
+        .with_rand(StdRand::with_seed(0))
+        .with_corpus(InMemoryCorpus::new())
+        .with_objective_corpus(InMemoryCorpus::new())
+        .with_feedback(())
+        .with_objective(())
+        .with_observers(())
+        .with_scheduler(QueueScheduler::new())
+        .with_mutations(tuple_list!(BitFlipMutator::new()))
+        .with_initial_inputs(vec![(BytesInput::new(vec![0; 8]), "zeros")])
+        .replace_stages(tuple_list!(StatsStage::new()))
+        .run_client()
+        .unwrap();
+
+        // Only the seed is executed, because no stage executes inputs
+        assert_eq!(executions, 1);
+    }
+}
@@ -0,0 +1,226 @@
+//! Export of the client statistics in the OpenMetrics text format, which can be scraped by
+//! Prometheus. The statistics which the `StatsMonitor` writes to the JSON log are flattened into
+//! metrics, such that for example `errors.fn_error` of a client becomes
+//! `puffin_errors_fn_error_total{client="1"}`. The metrics are either served on a local HTTP port
+//! or written to a file which is replaced after each update.
+
+use std::{
+    collections::BTreeMap,
+    fmt::Write as FmtWrite,
+    fs, io,
+    io::{Read, Write},
+    net::{Ipv4Addr, SocketAddr, TcpListener},
+    path::PathBuf,
+    sync::{Arc, Mutex},
+    thread,
+};
+
+use log::{error, info};
+use serde::Serialize;
+use serde_json::Value;
+
+const PREFIX: &str = "puffin";
+
+/// Statistics which are monotonically increasing
+const COUNTERS: &[&str] = &["total_execs", "errors_", "stability_"];
+
+/// Where the metrics are exported to
+#[derive(Clone, Debug)]
+pub enum MetricsTarget {
+    /// Serve the metrics on this port of the loopback interface
+    Http(u16),
+    /// Replace this file with the current metrics after each update
+    File(PathBuf),
+}
+
+#[derive(Clone)]
+enum Sink {
+    Http(Arc<Mutex<String>>),
+    File(PathBuf),
+}
+
+/// Keeps the latest statistics of each client and publishes them after each update
+#[derive(Clone)]
+pub struct MetricsExporter {
+    sink: Sink,
+    clients: BTreeMap<u32, Vec<(String, f64)>>,
+}
+
+impl MetricsExporter {
+    pub fn new(target: &MetricsTarget) -> Result<Self, io::Error> {
+        let sink = match target {
+            MetricsTarget::Http(port) => {
+                let body = Arc::new(Mutex::new(String::new()));
+                serve(
+                    TcpListener::bind(SocketAddr::from((Ipv4Addr::LOCALHOST, *port)))?,
+                    body.clone(),
+                );
+                info!("Serving metrics on http://127.0.0.1:{}/metrics", port);
+                Sink::Http(body)
+            }
+            MetricsTarget::File(path) => Sink::File(path.clone()),
+        };
+
+        Ok(Self {
+            sink,
+            clients: BTreeMap::new(),
+        })
+    }
+
+    /// Replaces the metrics of the client `id` with the numbers in `statistics`.
+    pub fn update<T: Serialize>(&mut self, id: u32, statistics: &T) -> Result<(), io::Error> {
+        let value = serde_json::to_value(statistics)?;
+        let mut metrics = Vec::new();
+        flatten(&value, PREFIX, &mut metrics);
+        self.clients.insert(id, metrics);
+
+        let text = self.render();
+        match &self.sink {
+            Sink::Http(body) => {
+                *body.lock().unwrap() = text;
+            }
+            Sink::File(path) => {
+                // Write to a temporary file first, such that readers never see a partial file
+                let temporary = path.with_extension("tmp");
+                fs::write(&temporary, text)?;
+                fs::rename(&temporary, path)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn render(&self) -> String {
+        let mut families: BTreeMap<&str, Vec<(u32, f64)>> = BTreeMap::new();
+        for (client, metrics) in &self.clients {
+            for (name, value) in metrics {
+                families
+                    .entry(name.as_str())
+                    .or_default()
+                    .push((*client, *value));
+            }
+        }
+
+        let mut text = String::new();
+        for (name, samples) in families {
+            let is_counter = COUNTERS
+                .iter()
+                .any(|counter| name[PREFIX.len() + 1..].starts_with(counter));
+            // In OpenMetrics only the samples of a counter carry the suffix, not the family
+            let (kind, suffix) = if is_counter {
+                ("counter", "_total")
+            } else {
+                ("gauge", "")
+            };
+
+            writeln!(text, "# TYPE {} {}", name, kind).unwrap();
+            for (client, value) in samples {
+                writeln!(
+                    text,
+                    "{}{}{{client=\"{}\"}} {}",
+                    name, suffix, client, value
+                )
+                .unwrap();
+            }
+        }
+        text.push_str("# EOF\n");
+        text
+    }
+}
+
+/// Collects all numbers in `value`. The keys of nested objects are joined with underscores.
+/// Strings, like the time of the statistics, and missing values are skipped.
+fn flatten(value: &Value, name: &str, metrics: &mut Vec<(String, f64)>) {
+    match value {
+        Value::Object(map) => {
+            for (key, value) in map {
+                if key == "id" || key == "time" {
+                    continue;
+                }
+                flatten(value, &format!("{}_{}", name, key), metrics);
+            }
+        }
+        Value::Number(number) => {
+            if let Some(number) = number.as_f64() {
+                metrics.push((name.to_string(), number));
+            }
+        }
+        Value::Bool(value) => metrics.push((name.to_string(), if *value { 1.0 } else { 0.0 })),
+        _ => {}
+    }
+}
+
+/// Answers every HTTP request with the current metrics. The request itself is not inspected.
+fn serve(listener: TcpListener, body: Arc<Mutex<String>>) {
+    thread::spawn(move || {
+        for stream in listener.incoming() {
+            let result = stream.and_then(|mut stream| {
+                let mut request = [0u8; 1024];
+                let _ = stream.read(&mut request)?;
+
+                let body = body.lock().unwrap().clone();
+                write!(
+                    stream,
+                    "HTTP/1.1 200 OK\r\nContent-Type: application/openmetrics-text; version=1.0.0; charset=utf-8\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                    body.len(),
+                    body
+                )
+            });
+
+            if let Err(err) = result {
+                error!("Failed to serve metrics: {}", err);
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::{flatten, MetricsExporter, Sink, PREFIX};
+
+    #[test]
+    fn test_flatten() {
+        let mut metrics = Vec::new();
+        flatten(
+            &json!({"id": 1, "time": {"secs_since_epoch": 1}, "errors": {"fn_error": 3}, "coverage": null, "exec_per_sec": 10}),
+            PREFIX,
+            &mut metrics,
+        );
+        metrics.sort_by(|a, b| a.0.cmp(&b.0));
+
+        assert_eq!(
+            metrics,
+            vec![
+                ("puffin_errors_fn_error".to_string(), 3.0),
+                ("puffin_exec_per_sec".to_string(), 10.0)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_render() {
+        // Unique per process, such that concurrent test runs do not share the file
+        let path =
+            std::env::temp_dir().join(format!("puffin-metrics-test-{}.prom", std::process::id()));
+        let mut exporter = MetricsExporter {
+            sink: Sink::File(path.clone()),
+            clients: Default::default(),
+        };
+        exporter
+            .update(0, &json!({"total_execs": 5, "corpus_size": 2}))
+            .unwrap();
+        exporter
+            .update(1, &json!({"total_execs": 7, "corpus_size": 3}))
+            .unwrap();
+
+        let text = exporter.render();
+        assert!(text.contains("# TYPE puffin_total_execs counter\n"));
+        assert!(text.contains("puffin_total_execs_total{client=\"1\"} 7\n"));
+        assert!(text.contains("# TYPE puffin_corpus_size gauge\n"));
+        assert!(text.contains("puffin_corpus_size{client=\"0\"} 2\n"));
+        assert!(text.ends_with("# EOF\n"));
+
+        std::fs::remove_file(path).unwrap();
+    }
+}
@@ -5,9 +5,13 @@ use libafl::{bolts::HasLen, inputs::Input};
 
 use crate::trace::Trace;
 
+mod cmin;
 mod harness;
 mod libafl_setup;
+pub mod metrics;
+pub mod objective;
 pub mod sanitizer;
+pub mod stability;
 mod stages;
 mod stats_monitor;
 mod stats_stage;
@@ -15,7 +19,13 @@ pub mod term_zoo;
 // Public for benchmarks
 pub mod mutations;
 
-pub use libafl_setup::{start, FuzzerConfig};
+pub use cmin::cmin;
+pub use harness::harness;
+pub use libafl_setup::{
+    launch, start, FuzzerConfig, MutationConfig, MutationStageConfig, RunClientBuilder,
+};
+pub use stages::{PuffinMutationalStage, PuffinScheduledMutator};
+pub use stats_stage::StatsStage;
 
 use crate::algebra::Matcher;
 
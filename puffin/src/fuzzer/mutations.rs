@@ -4,22 +4,27 @@ use libafl::{
         tuples::{tuple_list, tuple_list_type},
     },
     mutators::MutationResult,
+    observers::{CmpValues, CmpValuesMetadata},
     state::{HasCorpus, HasMaxSize, HasMetadata, HasRand},
     Error,
 };
 use util::{Choosable, *};
 
 use crate::{
-    algebra::{atoms::Function, signature::Signature, Matcher, Subterms, Term},
-    fuzzer::term_zoo::TermZoo,
-    trace::Trace,
+    algebra::{
+        atoms::{Function, Literal, LiteralValue},
+        signature::Signature,
+        Matcher, Subterms, Term,
+    },
+    fuzzer::term_zoo::{GeneratorConfig, TermGenerator},
+    trace::{Action, Trace},
 };
 
 pub fn trace_mutations<S, M: Matcher>(
     min_trace_length: usize,
     max_trace_length: usize,
     constraints: TermConstraints,
-    fresh_zoo_after: u64,
+    retry_failed_types_after: u64,
     signature: &'static Signature,
 ) -> tuple_list_type!(
        RepeatMutator<S>,
@@ -28,7 +33,8 @@ pub fn trace_mutations<S, M: Matcher>(
        ReplaceMatchMutator<S>,
        RemoveAndLiftMutator<S>,
        GenerateMutator<S, M>,
-       SwapMutator<S>
+       SwapMutator<S>,
+       CmpOperandMutator<S>
    )
 where
     S: HasCorpus<Trace<M>> + HasMetadata + HasMaxSize + HasRand,
@@ -39,8 +45,15 @@ where
         ReplaceReuseMutator::new(constraints),
         ReplaceMatchMutator::new(constraints, signature),
         RemoveAndLiftMutator::new(constraints),
-        GenerateMutator::new(0, fresh_zoo_after, constraints, None, signature), // Refresh zoo after 100000M mutations
-        SwapMutator::new(constraints)
+        GenerateMutator::new(
+            0,
+            retry_failed_types_after,
+            constraints,
+            GeneratorConfig::default(),
+            signature
+        ),
+        SwapMutator::new(constraints),
+        CmpOperandMutator::new(constraints)
     )
 }
 
@@ -145,10 +158,10 @@ where
     ) -> Result<MutationResult, Error> {
         let rand = state.rand_mut();
         let filter = |term: &Term<M>| match term {
-            Term::Variable(_) => false,
+            Term::Variable(_) | Term::Literal(_) => false,
             Term::Application(_, subterms) => subterms
                 .find_subterm(|subterm| match subterm {
-                    Term::Variable(_) => false,
+                    Term::Variable(_) | Term::Literal(_) => false,
                     Term::Application(_, grand_subterms) => {
                         grand_subterms.find_subterm_same_shape(subterm).is_some()
                     }
@@ -158,7 +171,7 @@ where
         if let Some(mut to_mutate) = choose_term_filtered_mut(trace, filter, self.constraints, rand)
         {
             match &mut to_mutate {
-                Term::Variable(_) => Ok(MutationResult::Skipped),
+                Term::Variable(_) | Term::Literal(_) => Ok(MutationResult::Skipped),
                 Term::Application(_, ref mut subterms) => {
                     if let Some(((subterm_index, _), grand_subterm)) = choose_iter(
                         subterms.filter_grand_subterms(|subterm, grand_subterm| {
@@ -195,7 +208,7 @@ where
 
 /// fn_add with fn_sub.
 
-/// It can also replace any variable with a constant.
+/// It can also replace any variable or literal with a constant.
 pub struct ReplaceMatchMutator<S>
 where
     S: HasRand,
@@ -232,9 +245,10 @@ where
         let rand = state.rand_mut();
         if let Some(mut to_mutate) = choose_term_mut(trace, self.constraints, rand) {
             match &mut to_mutate {
-                Term::Variable(variable) => {
+                Term::Variable(_) | Term::Literal(_) => {
+                    let typ = *to_mutate.get_type_shape();
                     if let Some((shape, dynamic_fn)) = self.signature.functions.choose_filtered(
-                        |(shape, _)| variable.typ == shape.return_type && shape.is_constant(),
+                        |(shape, _)| typ == shape.return_type && shape.is_constant(),
                         rand,
                     ) {
                         to_mutate.mutate(Term::Application(
@@ -442,36 +456,37 @@ where
     }
 }
 
-/// GENERATE: Generates a previously-unseen term using a term zoo
+/// GENERATE: Generates a previously-unseen term of the same type as a chosen subterm. Variables
+/// which are already used in the trace may be reused, as well as knowledge of the same type from
+/// the outputs of the agents.
 pub struct GenerateMutator<S, M: Matcher>
 where
     S: HasRand,
 {
     mutation_counter: u64,
-    refresh_zoo_after: u64,
+    retry_failed_types_after: u64,
     constraints: TermConstraints,
-    zoo: Option<TermZoo<M>>,
-    signature: &'static Signature,
-    phantom_s: std::marker::PhantomData<S>,
+    generator: TermGenerator,
+    phantom_s: std::marker::PhantomData<(S, M)>,
 }
 impl<S, M: Matcher> GenerateMutator<S, M>
 where
     S: HasRand,
 {
+    /// Types which could not be generated are retried after `retry_failed_types_after` mutations.
     #[must_use]
     pub fn new(
         mutation_counter: u64,
-        refresh_zoo_after: u64,
+        retry_failed_types_after: u64,
         constraints: TermConstraints,
-        zoo: Option<TermZoo<M>>,
+        config: GeneratorConfig,
         signature: &'static Signature,
     ) -> Self {
         Self {
             mutation_counter,
-            refresh_zoo_after,
+            retry_failed_types_after,
             constraints,
-            zoo,
-            signature,
+            generator: TermGenerator::new(signature, config),
             phantom_s: std::marker::PhantomData,
         }
     }
@@ -487,19 +502,40 @@ where
         _stage_idx: i32,
     ) -> Result<MutationResult, Error> {
         let rand = state.rand_mut();
+
+        let mut variables = trace
+            .steps
+            .iter()
+            .filter_map(|step| match &step.action {
+                Action::Input(input) => Some(&input.recipe),
+                Action::Output(_) => None,
+            })
+            .flat_map(|recipe| recipe.into_iter())
+            .filter(|term| matches!(term, Term::Variable(_)))
+            .cloned()
+            .collect::<Vec<_>>();
+        let agents = trace
+            .descriptors
+            .iter()
+            .map(|descriptor| descriptor.name)
+            .collect::<Vec<_>>();
+
         if let Some(to_mutate) = choose_term_mut(trace, self.constraints, rand) {
             self.mutation_counter += 1;
-            let zoo = if self.mutation_counter % self.refresh_zoo_after == 0 {
-                self.zoo.insert(TermZoo::generate(self.signature, rand))
-            } else {
-                self.zoo
-                    .get_or_insert_with(|| TermZoo::generate(self.signature, rand))
-            };
-            if let Some(term) = zoo.choose_filtered(
-                |term| to_mutate.get_type_shape() == term.get_type_shape(),
-                rand,
-            ) {
-                to_mutate.mutate(term.clone());
+            if self.mutation_counter % self.retry_failed_types_after == 0 {
+                self.generator.clear_failed();
+            }
+
+            // Knowledge of the requested type which the agents output, even if no recipe uses it yet
+            let typ = *to_mutate.get_type_shape();
+            variables.extend(
+                agents
+                    .iter()
+                    .map(|agent| Term::Variable(Signature::new_var(typ, *agent, None, 0))),
+            );
+
+            if let Some(term) = self.generator.generate(typ, &variables, rand) {
+                to_mutate.mutate(term);
                 Ok(MutationResult::Mutated)
             } else {
                 Ok(MutationResult::Skipped)
@@ -518,6 +554,129 @@ where
     }
 }
 
+/// Returns pairs of operands of a logged comparison. Integers are additionally interpreted as
+/// big-endian byte vectors, because that is how they are encoded on the wire.
+fn cmp_operands(cmp: &CmpValues) -> Vec<(LiteralValue, LiteralValue)> {
+    match cmp {
+        CmpValues::U8((a, b)) => vec![
+            (LiteralValue::U8(*a), LiteralValue::U8(*b)),
+            (LiteralValue::Bytes(vec![*a]), LiteralValue::Bytes(vec![*b])),
+        ],
+        CmpValues::U16((a, b)) => vec![
+            (LiteralValue::U16(*a), LiteralValue::U16(*b)),
+            (
+                LiteralValue::Bytes(a.to_be_bytes().to_vec()),
+                LiteralValue::Bytes(b.to_be_bytes().to_vec()),
+            ),
+        ],
+        CmpValues::U32((a, b)) => vec![
+            (LiteralValue::U32(*a), LiteralValue::U32(*b)),
+            (
+                LiteralValue::Bytes(a.to_be_bytes().to_vec()),
+                LiteralValue::Bytes(b.to_be_bytes().to_vec()),
+            ),
+        ],
+        CmpValues::U64((a, b)) => vec![
+            (LiteralValue::U64(*a), LiteralValue::U64(*b)),
+            (
+                LiteralValue::Bytes(a.to_be_bytes().to_vec()),
+                LiteralValue::Bytes(b.to_be_bytes().to_vec()),
+            ),
+        ],
+        CmpValues::Bytes((a, b)) => vec![(
+            LiteralValue::Bytes(a.clone()),
+            LiteralValue::Bytes(b.clone()),
+        )],
+    }
+}
+
+/// CMP-OPERAND: Replaces a leaf of an integer or byte vector type with an operand of a comparison
+
+/// which the PUT executed. If the leaf is a literal which equals one operand, then it is replaced by
+/// the other one.
+pub struct CmpOperandMutator<S>
+where
+    S: HasRand,
+{
+    constraints: TermConstraints,
+    phantom_s: std::marker::PhantomData<S>,
+}
+
+impl<S> CmpOperandMutator<S>
+where
+    S: HasRand,
+{
+    #[must_use]
+    pub fn new(constraints: TermConstraints) -> Self {
+        Self {
+            constraints,
+            phantom_s: std::marker::PhantomData,
+        }
+    }
+}
+
+impl<S, M: Matcher> libafl::mutators::Mutator<Trace<M>, S> for CmpOperandMutator<S>
+where
+    S: HasRand + HasMetadata,
+{
+    fn mutate(
+        &mut self,
+        state: &mut S,
+        trace: &mut Trace<M>,
+        _stage_idx: i32,
+    ) -> Result<MutationResult, Error> {
+        let length = match state.metadata().get::<CmpValuesMetadata>() {
+            Some(metadata) if !metadata.list.is_empty() => metadata.list.len(),
+            _ => return Ok(MutationResult::Skipped),
+        };
+        let index = state.rand_mut().below(length as u64) as usize;
+        let cmp = state.metadata().get::<CmpValuesMetadata>().unwrap().list[index].clone();
+
+        let pairs = cmp_operands(&cmp);
+        let types = pairs
+            .iter()
+            .map(|(a, _)| *a.type_shape())
+            .collect::<Vec<_>>();
+
+        let rand = state.rand_mut();
+        if let Some(to_mutate) = choose_term_filtered_mut(
+            trace,
+            |term: &Term<M>| term.is_leaf() && types.contains(term.get_type_shape()),
+            self.constraints,
+            rand,
+        ) {
+            if let Some((a, b)) = pairs
+                .into_iter()
+                .find(|(a, _)| a.type_shape() == to_mutate.get_type_shape())
+            {
+                let value = match &*to_mutate {
+                    Term::Literal(literal) if literal.value == a => b,
+                    Term::Literal(literal) if literal.value == b => a,
+                    _ => {
+                        if rand.below(2) == 0 {
+                            a
+                        } else {
+                            b
+                        }
+                    }
+                };
+                to_mutate.mutate(Term::Literal(Literal::new(value)));
+                return Ok(MutationResult::Mutated);
+            }
+        }
+        Ok(MutationResult::Skipped)
+    }
+}
+
+impl<S> libafl::bolts::tuples::Named for CmpOperandMutator<S>
+where
+    S: HasRand,
+{
+    fn name(&self) -> &str {
+        std::any::type_name::<CmpOperandMutator<S>>()
+    }
+}
+
 pub mod util {
     use libafl::bolts::rands::Rand;
 
@@ -627,7 +786,7 @@ pub mod util {
                     while let Some((term, path)) = stack.pop() {
                         // push next terms onto stack
                         match term {
-                            Term::Variable(_) => {
+                            Term::Variable(_) | Term::Literal(_) => {
                                 // reached leaf
                             }
                             Term::Application(_, subterms) => {
@@ -678,7 +837,7 @@ pub mod util {
         let subterm_index = term_path.remove(0);
 
         match term {
-            Term::Variable(_) => None,
+            Term::Variable(_) | Term::Literal(_) => None,
             Term::Application(_, subterms) => {
                 if let Some(subterm) = subterms.get_mut(subterm_index) {
                     find_term_by_term_path_mut(subterm, term_path)
@@ -844,7 +1003,7 @@ mod tests {
             if let Some(last) = trace.steps.iter().last() {
                 match &last.action {
                     Action::Input(input) => match &input.recipe {
-                        Term::Variable(_) => {}
+                        Term::Variable(_) | Term::Literal(_) => {}
                         Term::Application(_, subterms) => {
                             if let Some(last_subterm) = subterms.iter().last() {
                                 if last_subterm.name() == fn_seq_1.name() {
@@ -971,6 +1130,64 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_cmp_operand_mutator() {
+        let mut state = create_state();
+        let mut metadata = CmpValuesMetadata::new();
+        metadata.list.push(CmpValues::U32((0, 0x1337)));
+        state.add_metadata(metadata);
+
+        let mut mutator = CmpOperandMutator::new(TermConstraints::default());
+
+        fn has_literal(trace: &TestTrace, value: &LiteralValue) -> bool {
+            trace.steps.iter().any(|step| match &step.action {
+                Action::Input(input) => input.recipe.into_iter().any(|term| match term {
+                    Term::Literal(literal) => &literal.value == value,
+                    _ => false,
+                }),
+                Action::Output(_) => false,
+            })
+        }
+
+        loop {
+            let mut trace = setup_simple_trace();
+            mutator.mutate(&mut state, &mut trace, 0).unwrap();
+
+            if has_literal(&trace, &LiteralValue::U32(0x1337)) {
+                break;
+            }
+        }
+    }
+
+    #[test]
+    fn test_generate_mutator_reuses_outputs() {
+        let mut state = create_state();
+        let server = AgentName::first();
+
+        let mut mutator: GenerateMutator<_, AnyMatcher> = GenerateMutator::new(
+            0,
+            100,
+            TermConstraints::default(),
+            GeneratorConfig {
+                variable_percentage: 100,
+                ..GeneratorConfig::default()
+            },
+            &TEST_SIGNATURE,
+        );
+
+        // The simple trace uses no variables, therefore only knowledge from outputs can be reused
+        let mut trace = setup_simple_trace();
+        while mutator.mutate(&mut state, &mut trace, 0).unwrap() == MutationResult::Skipped {}
+
+        assert!(trace.steps.iter().any(|step| match &step.action {
+            Action::Input(input) => input.recipe.into_iter().any(|term| match term {
+                Term::Variable(variable) => variable.query.agent_name == server,
+                _ => false,
+            }),
+            Action::Output(_) => false,
+        }));
+    }
+
     #[test]
     fn test_find_term() {
         let mut rand = StdRand::with_seed(45);
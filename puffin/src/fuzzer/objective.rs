@@ -0,0 +1,334 @@
+//! Objectives which are found because of a security violation instead of a crash. The harness
+//! reports violations through [`report_violation`] instead of aborting the process. The
+//! [`SecurityViolationFeedback`] turns them into objectives and attaches the message of the
+//! violation as [`SecurityViolationMetadata`]. The [`ObjectiveCorpus`] stores them separately from
+//! crashes and timeouts.
+
+use core::cell::RefCell;
+use std::{path::PathBuf, sync::Mutex};
+
+use libafl::{
+    bolts::tuples::Named,
+    corpus::{ondisk::OnDiskMetadataFormat, Corpus, OnDiskCorpus, Testcase},
+    events::EventFirer,
+    executors::ExitKind,
+    feedbacks::Feedback,
+    impl_serdeany,
+    inputs::Input,
+    observers::ObserversTuple,
+    state::{HasClientPerfMonitor, HasMetadata},
+    Error,
+};
+use serde::{Deserialize, Serialize};
+
+/// Name of the subdirectory of the objective directory in which violations are stored
+pub const VIOLATIONS_DIR: &str = "violations";
+
+/// Violation which occurred during the last execution of the harness
+static LAST_VIOLATION: Mutex<Option<&'static str>> = Mutex::new(None);
+
+/// Records that the current execution violated the security policy.
+pub fn report_violation(msg: &'static str) {
+    *LAST_VIOLATION.lock().unwrap() = Some(msg);
+}
+
+/// Forgets any violation of a previous execution.
+pub fn reset_violation() {
+    *LAST_VIOLATION.lock().unwrap() = None;
+}
+
+fn take_violation() -> Option<&'static str> {
+    LAST_VIOLATION.lock().unwrap().take()
+}
+
+/// Message of the violation which caused a testcase to become an objective
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SecurityViolationMetadata {
+    pub message: String,
+}
+
+impl_serdeany!(SecurityViolationMetadata);
+
+/// Feedback which is interesting if the harness reported a security violation
+#[derive(Debug, Default)]
+pub struct SecurityViolationFeedback {
+    violation: Option<&'static str>,
+}
+
+impl SecurityViolationFeedback {
+    #[must_use]
+    pub fn new() -> Self {
+        Self { violation: None }
+    }
+}
+
+impl Named for SecurityViolationFeedback {
+    fn name(&self) -> &str {
+        "SecurityViolationFeedback"
+    }
+}
+
+impl<I, S> Feedback<I, S> for SecurityViolationFeedback
+where
+    I: Input,
+    S: HasClientPerfMonitor,
+{
+    fn is_interesting<EM, OT>(
+        &mut self,
+        _state: &mut S,
+        _manager: &mut EM,
+        _input: &I,
+        _observers: &OT,
+        _exit_kind: &ExitKind,
+    ) -> Result<bool, Error>
+    where
+        EM: EventFirer<I>,
+        OT: ObserversTuple<I, S>,
+    {
+        self.violation = take_violation();
+        Ok(self.violation.is_some())
+    }
+
+    fn append_metadata(&mut self, _state: &mut S, testcase: &mut Testcase<I>) -> Result<(), Error> {
+        if let Some(message) = self.violation.take() {
+            testcase.add_metadata(SecurityViolationMetadata {
+                message: message.to_string(),
+            });
+        }
+        Ok(())
+    }
+
+    fn discard_metadata(&mut self, _state: &mut S, _input: &I) -> Result<(), Error> {
+        self.violation = None;
+        Ok(())
+    }
+}
+
+/// Objective corpus which stores testcases with [`SecurityViolationMetadata`] in the subdirectory
+/// [`VIOLATIONS_DIR`] and all other objectives in the objective directory itself.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(bound = "I: serde::de::DeserializeOwned")]
+pub struct ObjectiveCorpus<I>
+where
+    I: Input,
+{
+    crashes: OnDiskCorpus<I>,
+    violations: OnDiskCorpus<I>,
+    /// For each global index whether it is a violation and its index in the corresponding corpus
+    entries: Vec<(bool, usize)>,
+    current: Option<usize>,
+}
+
+impl<I> ObjectiveCorpus<I>
+where
+    I: Input,
+{
+    pub fn new_save_meta(
+        dir_path: PathBuf,
+        meta_format: Option<OnDiskMetadataFormat>,
+    ) -> Result<Self, Error> {
+        Ok(Self {
+            violations: OnDiskCorpus::new_save_meta(dir_path.join(VIOLATIONS_DIR), meta_format)?,
+            crashes: OnDiskCorpus::new_save_meta(dir_path, meta_format)?,
+            entries: vec![],
+            current: None,
+        })
+    }
+
+    fn inner(&self, is_violation: bool) -> &OnDiskCorpus<I> {
+        if is_violation {
+            &self.violations
+        } else {
+            &self.crashes
+        }
+    }
+
+    fn inner_mut(&mut self, is_violation: bool) -> &mut OnDiskCorpus<I> {
+        if is_violation {
+            &mut self.violations
+        } else {
+            &mut self.crashes
+        }
+    }
+
+    fn entry(&self, idx: usize) -> Result<(bool, usize), Error> {
+        self.entries
+            .get(idx)
+            .copied()
+            .ok_or_else(|| Error::key_not_found(format!("Index {} out of bounds", idx)))
+    }
+}
+
+impl<I> Corpus<I> for ObjectiveCorpus<I>
+where
+    I: Input,
+{
+    fn count(&self) -> usize {
+        self.entries.len()
+    }
+
+    fn add(&mut self, testcase: Testcase<I>) -> Result<usize, Error> {
+        let is_violation = testcase.has_metadata::<SecurityViolationMetadata>();
+        let inner_idx = self.inner_mut(is_violation).add(testcase)?;
+        self.entries.push((is_violation, inner_idx));
+        Ok(self.entries.len() - 1)
+    }
+
+    fn replace(&mut self, idx: usize, testcase: Testcase<I>) -> Result<Testcase<I>, Error> {
+        let (is_violation, inner_idx) = self.entry(idx)?;
+        self.inner_mut(is_violation).replace(inner_idx, testcase)
+    }
+
+    fn remove(&mut self, idx: usize) -> Result<Option<Testcase<I>>, Error> {
+        if idx >= self.entries.len() {
+            return Ok(None);
+        }
+
+        let (is_violation, inner_idx) = self.entries.remove(idx);
+        let removed = self.inner_mut(is_violation).remove(inner_idx)?;
+
+        // Indices of the inner corpus shift after a removal
+        for (entry_is_violation, entry_idx) in &mut self.entries {
+            if *entry_is_violation == is_violation && *entry_idx > inner_idx {
+                *entry_idx -= 1;
+            }
+        }
+
+        Ok(removed)
+    }
+
+    fn get(&self, idx: usize) -> Result<&RefCell<Testcase<I>>, Error> {
+        let (is_violation, inner_idx) = self.entry(idx)?;
+        self.inner(is_violation).get(inner_idx)
+    }
+
+    fn current(&self) -> &Option<usize> {
+        &self.current
+    }
+
+    fn current_mut(&mut self) -> &mut Option<usize> {
+        &mut self.current
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::{Path, PathBuf};
+
+    use libafl::{
+        bolts::rands::StdRand,
+        corpus::{Corpus, InMemoryCorpus, Testcase},
+        events::SimpleEventManager,
+        executors::ExitKind,
+        feedbacks::Feedback,
+        inputs::{BytesInput, HasBytesVec},
+        monitors::SimpleMonitor,
+        state::{HasMetadata, StdState},
+    };
+
+    use super::{
+        report_violation, reset_violation, ObjectiveCorpus, SecurityViolationFeedback,
+        SecurityViolationMetadata, VIOLATIONS_DIR,
+    };
+
+    fn objective_dir(name: &str) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("puffin-objective-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        dir
+    }
+
+    fn testcase(byte: u8, is_violation: bool) -> Testcase<BytesInput> {
+        let mut testcase = Testcase::new(BytesInput::new(vec![byte; 4]));
+        if is_violation {
+            testcase.add_metadata(SecurityViolationMetadata {
+                message: "violation".to_string(),
+            });
+        }
+        testcase
+    }
+
+    fn input_of(corpus: &ObjectiveCorpus<BytesInput>, idx: usize) -> Vec<u8> {
+        let testcase = corpus.get(idx).unwrap().borrow();
+        testcase.input().as_ref().unwrap().bytes().to_vec()
+    }
+
+    fn parent_of(corpus: &ObjectiveCorpus<BytesInput>, idx: usize) -> PathBuf {
+        let testcase = corpus.get(idx).unwrap().borrow();
+        let filename = testcase.filename().as_ref().unwrap();
+        assert!(Path::new(filename).exists());
+        Path::new(filename).parent().unwrap().to_path_buf()
+    }
+
+    #[test]
+    fn test_remove_remaps_indices() {
+        let dir = objective_dir("remove");
+        let mut corpus = ObjectiveCorpus::new_save_meta(dir.clone(), None).unwrap();
+
+        corpus.add(testcase(0, false)).unwrap();
+        corpus.add(testcase(1, true)).unwrap();
+        corpus.add(testcase(2, false)).unwrap();
+        corpus.add(testcase(3, true)).unwrap();
+        corpus.add(testcase(4, false)).unwrap();
+
+        let removed = corpus.remove(2).unwrap().unwrap();
+        assert_eq!(removed.input().as_ref().unwrap().bytes(), &[2; 4]);
+        assert_eq!(corpus.count(), 4);
+
+        let expected = [(0, false), (1, true), (3, true), (4, false)];
+        for (idx, (byte, is_violation)) in expected.into_iter().enumerate() {
+            assert_eq!(input_of(&corpus, idx), vec![byte; 4]);
+            let expected_dir = if is_violation {
+                dir.join(VIOLATIONS_DIR)
+            } else {
+                dir.clone()
+            };
+            assert_eq!(parent_of(&corpus, idx), expected_dir);
+        }
+
+        assert!(corpus.remove(4).unwrap().is_none());
+        assert!(corpus.get(4).is_err());
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_feedback_only_flags_violations() {
+        let mut state = StdState::new(
+            StdRand::with_seed(0),
+            InMemoryCorpus::<BytesInput>::new(),
+            InMemoryCorpus::new(),
+            &mut (),
+            &mut (),
+        )
+        .unwrap();
+        let mut manager = SimpleEventManager::new(SimpleMonitor::new(|_| {}));
+        let input = BytesInput::new(vec![0; 4]);
+        let mut feedback = SecurityViolationFeedback::new();
+
+        reset_violation();
+        assert!(!feedback
+            .is_interesting(&mut state, &mut manager, &input, &(), &ExitKind::Ok)
+            .unwrap());
+
+        report_violation("Mismatching versions");
+        assert!(feedback
+            .is_interesting(&mut state, &mut manager, &input, &(), &ExitKind::Ok)
+            .unwrap());
+        let mut testcase = Testcase::new(input.clone());
+        feedback.append_metadata(&mut state, &mut testcase).unwrap();
+        assert_eq!(
+            testcase
+                .metadata()
+                .get::<SecurityViolationMetadata>()
+                .unwrap()
+                .message,
+            "Mismatching versions"
+        );
+
+        // The violation is consumed by the execution which reported it
+        assert!(!feedback
+            .is_interesting(&mut state, &mut manager, &input, &(), &ExitKind::Ok)
+            .unwrap());
+    }
+}
@@ -0,0 +1,71 @@
+//! Helpers for LeakSanitizer. Leaks are checked after each execution of the harness if enabled
+//! for the campaign. The check is available if the PUT is built with ASAN or LSAN.
+
+use std::{
+    env,
+    ffi::CStr,
+    sync::atomic::{AtomicBool, Ordering},
+};
+
+use log::{info, warn};
+
+/// Options which enable the leak checks during the campaign, but not when the process exits
+const LEAK_CHECK_OPTIONS: &str = "detect_leaks=1:leak_check_at_exit=0";
+
+static LEAK_CHECK: AtomicBool = AtomicBool::new(false);
+
+type LeakCheck = unsafe extern "C" fn() -> libc::c_int;
+
+/// Enables or disables the leak check after each execution of the harness.
+pub fn set_leak_check(enabled: bool) {
+    LEAK_CHECK.store(enabled, Ordering::Relaxed);
+}
+
+pub fn leak_check_enabled() -> bool {
+    LEAK_CHECK.load(Ordering::Relaxed)
+}
+
+/// Appends the options for leak checking to the environment of ASAN and LSAN. The options are
+/// read by the sanitizer runtime when a process starts, so they only apply to the clients which
+/// are spawned afterwards.
+pub fn setup_lsan_env() {
+    info!("Enabling leak checks..");
+    for variable in ["ASAN_OPTIONS", "LSAN_OPTIONS"] {
+        env::set_var(
+            variable,
+            format!(
+                "{}:{}",
+                env::var(variable).unwrap_or_default(),
+                LEAK_CHECK_OPTIONS,
+            ),
+        );
+    }
+}
+
+/// Looks up the leak check of the sanitizer runtime. It is resolved at runtime, such that
+/// binaries without a sanitizer still link.
+fn recoverable_leak_check() -> Option<LeakCheck> {
+    let name = CStr::from_bytes_with_nul(b"__lsan_do_recoverable_leak_check\0").unwrap();
+    let symbol = unsafe { libc::dlsym(libc::RTLD_DEFAULT, name.as_ptr()) };
+
+    if symbol.is_null() {
+        None
+    } else {
+        Some(unsafe { std::mem::transmute::<*mut libc::c_void, LeakCheck>(symbol) })
+    }
+}
+
+/// Checks whether memory leaked since the process started. The sanitizer prints a report for each
+/// leak. Returns false if no sanitizer runtime with leak detection is linked.
+pub fn has_leaks() -> bool {
+    match recoverable_leak_check() {
+        Some(check) => unsafe { check() != 0 },
+        None => {
+            warn!(
+                "Leak checks are enabled, but no sanitizer runtime with leak detection is linked"
+            );
+            set_leak_check(false);
+            false
+        }
+    }
+}
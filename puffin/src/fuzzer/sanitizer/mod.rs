@@ -1,4 +1,7 @@
 pub mod asan;
+pub mod lsan;
+pub mod msan;
+pub mod ubsan;
 
 #[cfg(all(feature = "sancov_pcguard_log", feature = "sancov_libafl"))]
 compile_error!("`sancov_pcguard_log` and `sancov_libafl` features are mutually exclusive.");
@@ -0,0 +1,27 @@
+//! Helpers for MSAN
+
+use std::env;
+
+use log::info;
+
+/// MSAN exits with a non-zero code after a report by default. Aborting is required, else the
+/// fuzzer workers fail to restart and the objective is not recorded.
+const DEFAULT_OPTIONS: &str = "abort_on_error=1";
+
+/// Prepends the default options to the environment variable for MSAN. Options which are set by
+/// the user take precedence.
+pub fn setup_msan_env() {
+    info!(
+        "MSAN env options: {}",
+        env::var("MSAN_OPTIONS").unwrap_or_default()
+    );
+
+    env::set_var(
+        "MSAN_OPTIONS",
+        format!(
+            "{}:{}",
+            DEFAULT_OPTIONS,
+            env::var("MSAN_OPTIONS").unwrap_or_default(),
+        ),
+    );
+}
@@ -11,3 +11,24 @@ pub unsafe extern "C" fn __sanitizer_cov_trace_pc_guard(guard: *mut u32) {
 pub unsafe extern "C" fn __sanitizer_cov_trace_pc_guard_init(mut start: *mut u32, stop: *mut u32) {
     trace!("__sanitizer_cov_trace_pc_guard_init");
 }
+
+// Comparisons are not logged, but the PUT may be compiled with -fsanitize-coverage=trace-cmp
+
+#[no_mangle]
+pub unsafe extern "C" fn __sanitizer_cov_trace_cmp1(_arg1: u8, _arg2: u8) {}
+#[no_mangle]
+pub unsafe extern "C" fn __sanitizer_cov_trace_cmp2(_arg1: u16, _arg2: u16) {}
+#[no_mangle]
+pub unsafe extern "C" fn __sanitizer_cov_trace_cmp4(_arg1: u32, _arg2: u32) {}
+#[no_mangle]
+pub unsafe extern "C" fn __sanitizer_cov_trace_cmp8(_arg1: u64, _arg2: u64) {}
+#[no_mangle]
+pub unsafe extern "C" fn __sanitizer_cov_trace_const_cmp1(_arg1: u8, _arg2: u8) {}
+#[no_mangle]
+pub unsafe extern "C" fn __sanitizer_cov_trace_const_cmp2(_arg1: u16, _arg2: u16) {}
+#[no_mangle]
+pub unsafe extern "C" fn __sanitizer_cov_trace_const_cmp4(_arg1: u32, _arg2: u32) {}
+#[no_mangle]
+pub unsafe extern "C" fn __sanitizer_cov_trace_const_cmp8(_arg1: u64, _arg2: u64) {}
+#[no_mangle]
+pub unsafe extern "C" fn __sanitizer_cov_trace_switch(_val: u64, _cases: *mut u64) {}
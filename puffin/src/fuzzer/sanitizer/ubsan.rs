@@ -0,0 +1,27 @@
+//! Helpers for UBSAN
+
+use std::env;
+
+use log::info;
+
+/// UBSAN only prints a report by default. Aborting turns the report into a crash, which is
+/// recorded as objective.
+const DEFAULT_OPTIONS: &str = "halt_on_error=1:abort_on_error=1:print_stacktrace=1";
+
+/// Prepends the default options to the environment variable for UBSAN. Options which are set by
+/// the user take precedence.
+pub fn setup_ubsan_env() {
+    info!(
+        "UBSAN env options: {}",
+        env::var("UBSAN_OPTIONS").unwrap_or_default()
+    );
+
+    env::set_var(
+        "UBSAN_OPTIONS",
+        format!(
+            "{}:{}",
+            DEFAULT_OPTIONS,
+            env::var("UBSAN_OPTIONS").unwrap_or_default(),
+        ),
+    );
+}
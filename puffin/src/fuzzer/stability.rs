@@ -0,0 +1,415 @@
+//! Detection of nondeterministic executions. A trace is executed several times and the outputs of
+//! the agents, the claims, the result of the execution and the edge coverage are compared. Edges
+//! whose hit count bucket differs between executions are considered unstable, like in AFL's
+//! stability metric.
+
+use std::{
+    collections::{hash_map::DefaultHasher, BTreeMap, BTreeSet, HashSet},
+    fmt,
+    hash::{Hash, Hasher},
+    marker::PhantomData,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Mutex,
+    },
+};
+
+use libafl::{
+    executors::Executor,
+    impl_serdeany,
+    inputs::Input,
+    stages::Stage,
+    state::{HasCorpus, HasMetadata},
+    Error,
+};
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    error,
+    fuzzer::{
+        cmin::classify_count,
+        libafl_setup::edges_map,
+        stats_stage::{CALIBRATED_TRACES, UNSTABLE_EDGES, UNSTABLE_TRACES},
+    },
+    protocol::ProtocolBehavior,
+    trace::{Trace, TraceContext},
+};
+
+/// What is observed during a single execution of a trace
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Observation {
+    /// Hash of the knowledge which has been extracted from the outputs of the agents
+    outputs: u64,
+    /// Hash of the claims which have been made by the agents
+    claims: u64,
+    /// Whether the execution succeeded, or the error which stopped it
+    result: String,
+    /// Hit count buckets of all edges which have been hit
+    edges: BTreeMap<usize, u8>,
+}
+
+fn hash_debug<T: fmt::Debug>(items: impl Iterator<Item = T>) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    for item in items {
+        format!("{:?}", item).hash(&mut hasher);
+    }
+    hasher.finish()
+}
+
+impl Observation {
+    /// Observes the state of `ctx` after an execution which ended with `result`. The edges are
+    /// observed separately, see [`observe_edges`].
+    fn new<PB: ProtocolBehavior + 'static>(
+        ctx: &TraceContext<PB>,
+        result: &Result<(), error::Error>,
+    ) -> Self {
+        let result = match result {
+            Ok(()) => "ok".to_string(),
+            Err(err) => err.to_string(),
+        };
+
+        let outputs = hash_debug(
+            ctx.knowledge()
+                .iter()
+                .map(|knowledge| (knowledge.agent_name, &knowledge.matcher, &knowledge.data)),
+        );
+        let claims = hash_debug(ctx.claims().deref_borrow().iter());
+
+        Observation {
+            outputs,
+            claims,
+            result,
+            edges: BTreeMap::new(),
+        }
+    }
+}
+
+/// Hit count buckets of all edges which have been hit since the edges map has been cleared
+fn observe_edges() -> BTreeMap<usize, u8> {
+    let map = unsafe { edges_map() };
+    map.iter()
+        .enumerate()
+        .filter(|(_, count)| **count != 0)
+        .map(|(edge, count)| (edge, classify_count(*count)))
+        .collect()
+}
+
+fn clear_edges() {
+    unsafe { edges_map() }.fill(0);
+}
+
+/// Whether the harness currently records an [`Observation`] of each execution
+static RECORDING: AtomicBool = AtomicBool::new(false);
+
+/// Observation of the last execution of the harness while recording
+static LAST_OBSERVATION: Mutex<Option<Observation>> = Mutex::new(None);
+
+/// Records the outputs, claims and result of an execution of the harness if the
+/// [`CalibrationStage`] currently calibrates a trace. Recording is skipped otherwise, because
+/// hashing the knowledge is expensive.
+pub fn record_observation<PB: ProtocolBehavior + 'static>(
+    ctx: &TraceContext<PB>,
+    result: &Result<(), error::Error>,
+) {
+    if RECORDING.load(Ordering::Relaxed) {
+        *LAST_OBSERVATION.lock().unwrap() = Some(Observation::new(ctx, result));
+    }
+}
+
+fn take_observation() -> Option<Observation> {
+    LAST_OBSERVATION.lock().unwrap().take()
+}
+
+/// Executes the trace once and records the observations.
+pub fn observe<PB: ProtocolBehavior + 'static>(trace: &Trace<PB::Matcher>) -> Observation {
+    clear_edges();
+
+    let mut ctx = TraceContext::new(PB::registry());
+    let result = trace.execute(&mut ctx);
+
+    Observation {
+        edges: observe_edges(),
+        ..Observation::new(&ctx, &result)
+    }
+}
+
+/// Result of comparing several executions of the same trace
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StabilityReport {
+    pub runs: usize,
+    pub unstable_outputs: bool,
+    pub unstable_claims: bool,
+    pub unstable_result: bool,
+    /// Number of edges which have been hit in any execution
+    pub edges: usize,
+    pub unstable_edges: BTreeSet<usize>,
+}
+
+impl StabilityReport {
+    pub fn from_observations(observations: &[Observation]) -> Self {
+        let first = match observations.first() {
+            Some(first) => first,
+            None => return Self::default(),
+        };
+
+        let differs = |f: &dyn Fn(&Observation) -> bool| observations.iter().any(f);
+
+        let all_edges: BTreeSet<usize> = observations
+            .iter()
+            .flat_map(|observation| observation.edges.keys().copied())
+            .collect();
+
+        let unstable_edges = all_edges
+            .iter()
+            .copied()
+            .filter(|edge| {
+                let bucket = first.edges.get(edge);
+                observations
+                    .iter()
+                    .any(|observation| observation.edges.get(edge) != bucket)
+            })
+            .collect();
+
+        Self {
+            runs: observations.len(),
+            unstable_outputs: differs(&|observation| observation.outputs != first.outputs),
+            unstable_claims: differs(&|observation| observation.claims != first.claims),
+            unstable_result: differs(&|observation| observation.result != first.result),
+            edges: all_edges.len(),
+            unstable_edges,
+        }
+    }
+
+    pub fn is_stable(&self) -> bool {
+        !self.unstable_outputs
+            && !self.unstable_claims
+            && !self.unstable_result
+            && self.unstable_edges.is_empty()
+    }
+
+    /// Ratio of edges which are hit the same way in all executions
+    pub fn stability(&self) -> f64 {
+        if self.edges == 0 {
+            return 1.0;
+        }
+        (self.edges - self.unstable_edges.len()) as f64 / self.edges as f64
+    }
+}
+
+impl fmt::Display for StabilityReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} runs, stability {:.2}% ({} of {} edges unstable)",
+            self.runs,
+            self.stability() * 100.0,
+            self.unstable_edges.len(),
+            self.edges
+        )?;
+
+        let unstable = [
+            (self.unstable_outputs, "outputs"),
+            (self.unstable_claims, "claims"),
+            (self.unstable_result, "result"),
+        ]
+        .iter()
+        .filter(|(unstable, _)| *unstable)
+        .map(|(_, name)| *name)
+        .collect::<Vec<_>>();
+
+        if !unstable.is_empty() {
+            write!(f, ", differing {}", unstable.join(", "))?;
+        }
+        Ok(())
+    }
+}
+
+/// Executes the trace `runs` times and compares the executions.
+pub fn check_stability<PB: ProtocolBehavior + 'static>(
+    trace: &Trace<PB::Matcher>,
+    runs: usize,
+) -> StabilityReport {
+    let observations = (0..runs).map(|_| observe::<PB>(trace)).collect::<Vec<_>>();
+    StabilityReport::from_observations(&observations)
+}
+
+/// The stability of a corpus entry as determined by the [`CalibrationStage`]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StabilityMetadata {
+    pub report: StabilityReport,
+}
+
+impl_serdeany!(StabilityMetadata);
+
+/// All edges which have been unstable for any corpus entry
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct UnstableEdgesMetadata {
+    pub edges: HashSet<usize>,
+}
+
+impl_serdeany!(UnstableEdgesMetadata);
+
+/// A stage which executes each corpus entry several times through the executor the first time it
+/// is scheduled. The harness records what it observes through [`record_observation`]. Harnesses
+/// which do not record are only compared by their edges and [`ExitKind`](libafl::executors::ExitKind).
+/// The resulting [`StabilityReport`] is stored as [`StabilityMetadata`] in the testcase and
+/// unstable edges are collected in the [`UnstableEdgesMetadata`] of the state.
+pub struct CalibrationStage<E, EM, I, S, Z>
+where
+    I: Input,
+{
+    runs: usize,
+    #[allow(clippy::type_complexity)]
+    phantom: PhantomData<(E, EM, I, S, Z)>,
+}
+
+impl<E, EM, I, S, Z> Stage<E, EM, S, Z> for CalibrationStage<E, EM, I, S, Z>
+where
+    E: Executor<EM, I, S, Z>,
+    I: Input,
+    S: HasCorpus<I> + HasMetadata,
+{
+    fn perform(
+        &mut self,
+        fuzzer: &mut Z,
+        executor: &mut E,
+        state: &mut S,
+        manager: &mut EM,
+        corpus_idx: usize,
+    ) -> Result<(), Error> {
+        if self.runs < 2 {
+            return Ok(());
+        }
+
+        let input = {
+            let mut testcase = state.corpus().get(corpus_idx)?.borrow_mut();
+            if testcase.has_metadata::<StabilityMetadata>() {
+                return Ok(());
+            }
+            testcase.load_input()?.clone()
+        };
+
+        RECORDING.store(true, Ordering::Relaxed);
+        let observations = (0..self.runs)
+            .map(|_| {
+                clear_edges();
+                take_observation();
+
+                let exit_kind = executor.run_target(fuzzer, state, manager, &input)?;
+
+                let observation = take_observation().unwrap_or_else(|| Observation {
+                    outputs: 0,
+                    claims: 0,
+                    result: format!("{:?}", exit_kind),
+                    edges: BTreeMap::new(),
+                });
+                Ok(Observation {
+                    edges: observe_edges(),
+                    ..observation
+                })
+            })
+            .collect::<Result<Vec<_>, Error>>();
+        RECORDING.store(false, Ordering::Relaxed);
+
+        let report = StabilityReport::from_observations(&observations?);
+        CALIBRATED_TRACES.increment();
+
+        if !report.is_stable() {
+            UNSTABLE_TRACES.increment();
+            warn!("Corpus entry {} is unstable: {}", corpus_idx, report);
+
+            if !state.has_metadata::<UnstableEdgesMetadata>() {
+                state.add_metadata(UnstableEdgesMetadata::default());
+            }
+            let known = state
+                .metadata_mut()
+                .get_mut::<UnstableEdgesMetadata>()
+                .unwrap();
+            for edge in &report.unstable_edges {
+                if known.edges.insert(*edge) {
+                    UNSTABLE_EDGES.increment();
+                }
+            }
+        } else {
+            info!("Corpus entry {} is stable: {}", corpus_idx, report);
+        }
+
+        state
+            .corpus()
+            .get(corpus_idx)?
+            .borrow_mut()
+            .add_metadata(StabilityMetadata { report });
+
+        Ok(())
+    }
+}
+
+impl<E, EM, I, S, Z> CalibrationStage<E, EM, I, S, Z>
+where
+    I: Input,
+{
+    /// Creates a stage which executes every new corpus entry `runs` times. Less than two runs
+    /// disable the calibration.
+    pub fn new(runs: usize) -> Self {
+        Self {
+            runs,
+            phantom: PhantomData,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use super::{Observation, StabilityReport};
+
+    fn observation(result: &str, edges: &[(usize, u8)]) -> Observation {
+        Observation {
+            outputs: 1,
+            claims: 2,
+            result: result.to_string(),
+            edges: edges.iter().copied().collect::<BTreeMap<_, _>>(),
+        }
+    }
+
+    #[test]
+    fn test_stable_executions() {
+        let report = StabilityReport::from_observations(&[
+            observation("ok", &[(1, 1), (2, 4)]),
+            observation("ok", &[(1, 1), (2, 4)]),
+        ]);
+
+        assert!(report.is_stable());
+        assert_eq!(report.stability(), 1.0);
+    }
+
+    #[test]
+    fn test_unstable_edges() {
+        let report = StabilityReport::from_observations(&[
+            observation("ok", &[(1, 1), (2, 4), (3, 1)]),
+            observation("ok", &[(1, 1), (2, 8)]),
+            observation("ok", &[(1, 1), (2, 4)]),
+        ]);
+
+        assert!(!report.is_stable());
+        assert!(!report.unstable_result);
+        assert_eq!(report.edges, 3);
+        assert_eq!(
+            report.unstable_edges.into_iter().collect::<Vec<_>>(),
+            vec![2, 3]
+        );
+    }
+
+    #[test]
+    fn test_unstable_result() {
+        let report = StabilityReport::from_observations(&[
+            observation("ok", &[(1, 1)]),
+            observation("error in the stream", &[(1, 1)]),
+        ]);
+
+        assert!(report.unstable_result);
+        assert!(!report.is_stable());
+    }
+}
@@ -192,3 +192,75 @@ where
         }
     }
 }
+
+//-----------------------------
+
+/// A stage which executes the current corpus entry once with comparison logging enabled. The
+/// operands of the comparisons are stored in the [`libafl::observers::CmpValuesMetadata`] of the
+/// state, where the [`CmpOperandMutator`](crate::fuzzer::mutations::CmpOperandMutator) picks them up.
+#[cfg(all(feature = "cmplog", feature = "sancov_libafl", not(test)))]
+#[derive(Clone, Debug)]
+pub struct CmpLogStage<E, EM, I, S, Z>
+where
+    I: Input,
+{
+    #[allow(clippy::type_complexity)]
+    phantom: PhantomData<(E, EM, I, S, Z)>,
+}
+
+#[cfg(all(feature = "cmplog", feature = "sancov_libafl", not(test)))]
+impl<E, EM, I, S, Z> Stage<E, EM, S, Z> for CmpLogStage<E, EM, I, S, Z>
+where
+    E: libafl::executors::Executor<EM, I, S, Z>,
+    I: Input,
+    S: HasClientPerfMonitor + HasCorpus<I> + libafl::state::HasMetadata,
+{
+    fn perform(
+        &mut self,
+        fuzzer: &mut Z,
+        executor: &mut E,
+        state: &mut S,
+        manager: &mut EM,
+        corpus_idx: usize,
+    ) -> Result<(), Error> {
+        use libafl::observers::Observer;
+        use libafl_targets::{CmpLogObserver, CMPLOG_MAP};
+
+        let input = state
+            .corpus()
+            .get(corpus_idx)?
+            .borrow_mut()
+            .load_input()?
+            .clone();
+
+        let mut observer = unsafe { CmpLogObserver::new("cmplog", &mut CMPLOG_MAP, true) };
+
+        observer.pre_exec(state, &input)?;
+        let exit_kind = executor.run_target(fuzzer, state, manager, &input)?;
+        observer.post_exec(state, &input, &exit_kind)?;
+
+        Ok(())
+    }
+}
+
+#[cfg(all(feature = "cmplog", feature = "sancov_libafl", not(test)))]
+impl<E, EM, I, S, Z> CmpLogStage<E, EM, I, S, Z>
+where
+    I: Input,
+{
+    pub fn new() -> Self {
+        Self {
+            phantom: PhantomData,
+        }
+    }
+}
+
+#[cfg(all(feature = "cmplog", feature = "sancov_libafl", not(test)))]
+impl<E, EM, I, S, Z> Default for CmpLogStage<E, EM, I, S, Z>
+where
+    I: Input,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -13,11 +13,13 @@ use libafl::{
     bolts::current_time,
     monitors::{ClientStats, Monitor, PerfFeature, UserStats},
 };
+use log::error;
 use serde::Serialize;
 use serde_json::Serializer as JSONSerializer;
 
 use crate::fuzzer::{
     libafl_setup::MAP_FEEDBACK_NAME,
+    metrics::MetricsExporter,
     stats_stage::{RuntimeStats, STATS},
 };
 
@@ -32,6 +34,7 @@ where
     log_count: u64,
     stats_file: PathBuf,
     json_writer: JSONSerializer<BufWriter<File>>,
+    metrics: Option<MetricsExporter>,
 }
 
 impl<F> Clone for StatsMonitor<F>
@@ -51,6 +54,7 @@ where
                     .open(&self.stats_file)
                     .unwrap(),
             )),
+            metrics: self.metrics.clone(),
         }
     }
 }
@@ -112,6 +116,8 @@ where
 
         error_counter.count(client);
 
+        let stability = StabilityStatistics::new(client);
+
         let corpus_size = client.corpus_size;
         let objective_size = client.objective_size;
         let mut fmt = format!(
@@ -137,11 +143,12 @@ where
 
         (self.print_fn)(fmt);
 
-        ClientStatistics {
+        let statistics = ClientStatistics {
             id: sender_id,
             time: SystemTime::now(),
             trace,
             errors: error_counter,
+            stability,
             #[cfg(feature = "introspection")]
             intro: introspect_feature,
             coverage,
@@ -149,9 +156,15 @@ where
             objective_size,
             total_execs,
             exec_per_sec: exec_sec,
+        };
+
+        statistics.serialize(&mut self.json_writer).unwrap();
+
+        if let Some(metrics) = &mut self.metrics {
+            if let Err(err) = metrics.update(sender_id, &statistics) {
+                error!("Failed to export metrics: {}", err);
+            }
         }
-        .serialize(&mut self.json_writer)
-        .unwrap();
     }
 
     fn global(&mut self, event_msg: &String) {
@@ -176,6 +189,7 @@ struct ClientStatistics {
     id: u32,
     time: SystemTime,
     errors: ErrorStatistics,
+    stability: StabilityStatistics,
     trace: TraceStatistics,
     #[cfg(feature = "introspection")]
     intro: IntrospectStatistics,
@@ -230,6 +244,13 @@ struct ErrorStatistics {
     ext_error: u64,
 }
 
+#[derive(Serialize)]
+struct StabilityStatistics {
+    calibrated_traces: u64,
+    unstable_traces: u64,
+    unstable_edges: u64,
+}
+
 #[derive(Serialize)]
 struct TraceStatistics {
     min_trace_length: Option<u64>,
@@ -339,6 +360,33 @@ fn get_number(user_stats: &ClientStats, name: &str) -> u64 {
     }
 }
 
+impl StabilityStatistics {
+    pub fn new(client_stats: &ClientStats) -> Self {
+        let mut stability = Self {
+            calibrated_traces: 0,
+            unstable_traces: 0,
+            unstable_edges: 0,
+        };
+
+        for stat_definition in &STATS {
+            match stat_definition {
+                RuntimeStats::CalibratedTraces(c) => {
+                    stability.calibrated_traces = get_number(client_stats, c.name)
+                }
+                RuntimeStats::UnstableTraces(c) => {
+                    stability.unstable_traces = get_number(client_stats, c.name)
+                }
+                RuntimeStats::UnstableEdges(c) => {
+                    stability.unstable_edges = get_number(client_stats, c.name)
+                }
+                _ => {}
+            }
+        }
+
+        stability
+    }
+}
+
 impl TraceStatistics {
     pub fn new(user_stats: &ClientStats) -> TraceStatistics {
         let mut trace_stats = Self {
@@ -426,6 +474,13 @@ where
             log_count: 0,
             stats_file,
             json_writer,
+            metrics: None,
         })
     }
+
+    /// Additionally exports the statistics of the clients in the OpenMetrics text format
+    pub fn with_metrics(mut self, metrics: MetricsExporter) -> Self {
+        self.metrics = Some(metrics);
+        self
+    }
 }
@@ -22,6 +22,9 @@ pub enum RuntimeStats {
     ExtractionError(&'static Counter),
     TraceLength(&'static MinMaxMean),
     TermSize(&'static MinMaxMean),
+    CalibratedTraces(&'static Counter),
+    UnstableTraces(&'static Counter),
+    UnstableEdges(&'static Counter),
 }
 
 impl RuntimeStats {
@@ -39,6 +42,9 @@ impl RuntimeStats {
             RuntimeStats::ExtractionError(inner) => inner.fire(consume),
             RuntimeStats::TraceLength(inner) => inner.fire(consume),
             RuntimeStats::TermSize(inner) => inner.fire(consume),
+            RuntimeStats::CalibratedTraces(inner) => inner.fire(consume),
+            RuntimeStats::UnstableTraces(inner) => inner.fire(consume),
+            RuntimeStats::UnstableEdges(inner) => inner.fire(consume),
         }
     }
 }
@@ -62,7 +68,12 @@ pub static TRACE_LENGTH: MinMaxMean = MinMaxMean::new("trace-length");
 
 pub static TERM_SIZE: MinMaxMean = MinMaxMean::new("term-size");
 
-pub static STATS: [RuntimeStats; 9] = [
+// Stability of the corpus, see crate::fuzzer::stability
+pub static CALIBRATED_TRACES: Counter = Counter::new("calibrated");
+pub static UNSTABLE_TRACES: Counter = Counter::new("unstable-traces");
+pub static UNSTABLE_EDGES: Counter = Counter::new("unstable-edges");
+
+pub static STATS: [RuntimeStats; 12] = [
     RuntimeStats::FnError(&FN_ERROR),
     RuntimeStats::TermError(&TERM),
     RuntimeStats::PutError(&PUT),
@@ -72,6 +83,9 @@ pub static STATS: [RuntimeStats; 9] = [
     RuntimeStats::ExtractionError(&EXTRACTION),
     RuntimeStats::TraceLength(&TRACE_LENGTH),
     RuntimeStats::TermSize(&TERM_SIZE),
+    RuntimeStats::CalibratedTraces(&CALIBRATED_TRACES),
+    RuntimeStats::UnstableTraces(&UNSTABLE_TRACES),
+    RuntimeStats::UnstableEdges(&UNSTABLE_EDGES),
 ];
 
 pub trait Fire: Sync {
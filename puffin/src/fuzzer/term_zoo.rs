@@ -1,11 +1,16 @@
-//! Generates a zoo of terms form a [`Signature`]. For each function symbol in the signature
-//! a closed term is generated and added to the zoo.
+//! Generates terms from a [`Signature`]. The [`TermGenerator`] synthesizes a closed term for a
+//! requested type on demand. A zoo contains for each function symbol in the signature a term
+//! which applies the symbol to generated arguments.
+
+use std::collections::{HashMap, HashSet};
 
 use libafl::bolts::rands::Rand;
 
 use crate::{
     algebra::{
         atoms::Function,
+        dynamic_function::TypeShape,
+        remove_prefix,
         signature::{FunctionDefinition, Signature},
         Matcher, Term,
     },
@@ -15,69 +20,260 @@ use crate::{
 const MAX_DEPTH: u16 = 8; // how deep terms we allow max
 const MAX_TRIES: u16 = 100; // How often we want to try to generate before stopping
 
-pub struct TermZoo<M: Matcher> {
-    terms: Vec<Term<M>>,
+/// Budgets and weights of the [`TermGenerator`]
+#[derive(Clone, Debug)]
+pub struct GeneratorConfig {
+    /// Maximum depth of generated terms. A constant has depth 1.
+    pub max_depth: u16,
+    /// Maximum number of symbols in generated terms
+    pub max_size: usize,
+    /// How often the generation of a type is attempted before the type is considered failed
+    pub tries: u16,
+    /// Chance in percent of using a variable of the trace instead of generating a term, if a
+    /// variable of the requested type is available
+    pub variable_percentage: u64,
+    /// Relative weights of function symbols by name without module path, like `fn_seq_0`.
+    /// Symbols which are not listed have weight 1, symbols with weight 0 are never chosen.
+    pub weights: HashMap<&'static str, u64>,
 }
 
-impl<M: Matcher> TermZoo<M> {
-    pub fn generate<R: Rand>(signature: &Signature, rand: &mut R) -> Self {
-        let terms = signature
-            .functions
-            .iter()
-            .filter_map(|def| {
-                let mut counter = MAX_TRIES;
+impl Default for GeneratorConfig {
+    fn default() -> Self {
+        Self {
+            max_depth: MAX_DEPTH,
+            max_size: 64,
+            tries: 10,
+            variable_percentage: 25,
+            weights: HashMap::new(),
+        }
+    }
+}
 
-                loop {
-                    if counter == 0 {
-                        break None;
-                    }
+/// Smallest depth and size of closed terms of a type
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct Bounds {
+    depth: u16,
+    size: usize,
+}
 
-                    counter -= 1;
+/// Synthesizes closed terms of a requested type. Functions are only chosen if their arguments can
+/// still be generated within the remaining depth and size budgets. Types which could not be
+/// generated are remembered and skipped until [`TermGenerator::clear_failed`] is called.
+pub struct TermGenerator {
+    signature: &'static Signature,
+    config: GeneratorConfig,
+    bounds: HashMap<TypeShape, Bounds>,
+    failed: HashSet<TypeShape>,
+}
 
-                    if let Some(term) = Self::generate_term(signature, def, MAX_DEPTH, rand) {
-                        break Some(term);
+impl TermGenerator {
+    pub fn new(signature: &'static Signature, config: GeneratorConfig) -> Self {
+        Self {
+            signature,
+            bounds: Self::compute_bounds(signature),
+            config,
+            failed: HashSet::new(),
+        }
+    }
+
+    /// Computes for each type the smallest depth and size of closed terms by iterating to a fixed
+    /// point. Types without closed terms are missing in the result.
+    fn compute_bounds(signature: &Signature) -> HashMap<TypeShape, Bounds> {
+        let mut bounds: HashMap<TypeShape, Bounds> = HashMap::new();
+
+        let mut changed = true;
+        while changed {
+            changed = false;
+
+            for (shape, _) in &signature.functions {
+                let arguments = shape
+                    .argument_types
+                    .iter()
+                    .map(|typ| bounds.get(typ).copied())
+                    .collect::<Option<Vec<_>>>();
+
+                if let Some(arguments) = arguments {
+                    let candidate = Bounds {
+                        depth: 1 + arguments.iter().map(|b| b.depth).max().unwrap_or(0),
+                        size: 1 + arguments.iter().map(|b| b.size).sum::<usize>(),
+                    };
+
+                    match bounds.get_mut(&shape.return_type) {
+                        Some(entry) => {
+                            if candidate.depth < entry.depth || candidate.size < entry.size {
+                                entry.depth = entry.depth.min(candidate.depth);
+                                entry.size = entry.size.min(candidate.size);
+                                changed = true;
+                            }
+                        }
+                        None => {
+                            bounds.insert(shape.return_type, candidate);
+                            changed = true;
+                        }
                     }
                 }
-            })
+            }
+        }
+
+        bounds
+    }
+
+    pub fn is_failed(&self, typ: &TypeShape) -> bool {
+        self.failed.contains(typ)
+    }
+
+    /// Forgets the types which could not be generated
+    pub fn clear_failed(&mut self) {
+        self.failed.clear();
+    }
+
+    /// Generates a term of type `typ`. Variables of the trace with the same type are used
+    /// occasionally, or if no term can be generated.
+    pub fn generate<M: Matcher, R: Rand>(
+        &mut self,
+        typ: TypeShape,
+        variables: &[Term<M>],
+        rand: &mut R,
+    ) -> Option<Term<M>> {
+        let variables = variables
+            .iter()
+            .filter(|variable| *variable.get_type_shape() == typ)
             .collect::<Vec<_>>();
 
-        Self { terms }
+        if !variables.is_empty() && rand.below(100) < self.config.variable_percentage {
+            return variables.choose(rand).map(|variable| (*variable).clone());
+        }
+
+        if !self.failed.contains(&typ) {
+            for _ in 0..self.config.tries {
+                if let Some(term) =
+                    self.generate_type(typ, self.config.max_depth, self.config.max_size, rand)
+                {
+                    return Some(term);
+                }
+            }
+            self.failed.insert(typ);
+        }
+
+        variables.choose(rand).map(|variable| (*variable).clone())
     }
 
-    fn generate_term<R: Rand>(
-        signature: &Signature,
-        (shape, dynamic_fn): &FunctionDefinition,
+    /// Generates a term which applies `definition` to generated arguments
+    pub fn generate_application<M: Matcher, R: Rand>(
+        &mut self,
+        definition: &FunctionDefinition,
+        rand: &mut R,
+    ) -> Option<Term<M>> {
+        for _ in 0..self.config.tries {
+            if let Some(term) = self.apply(
+                definition,
+                self.config.max_depth,
+                self.config.max_size,
+                rand,
+            ) {
+                return Some(term);
+            }
+        }
+        None
+    }
+
+    fn fits(&self, typ: &TypeShape, depth: u16, size: usize) -> bool {
+        self.bounds
+            .get(typ)
+            .map_or(false, |bounds| bounds.depth <= depth && bounds.size <= size)
+    }
+
+    fn weight(&self, (shape, _): &FunctionDefinition) -> u64 {
+        if self.config.weights.is_empty() {
+            return 1;
+        }
+
+        self.config
+            .weights
+            .get(remove_prefix(shape.name).as_str())
+            .copied()
+            .unwrap_or(1)
+    }
+
+    fn generate_type<M: Matcher, R: Rand>(
+        &self,
+        typ: TypeShape,
         depth: u16,
+        size: usize,
         rand: &mut R,
     ) -> Option<Term<M>> {
-        if depth == 0 {
-            // Reached max depth
+        if depth == 0 || size == 0 {
             return None;
         }
 
-        let required_types = &shape.argument_types;
+        // Only consider functions whose arguments fit into the remaining budgets
+        let candidates = self
+            .signature
+            .functions_by_typ
+            .get(&typ)?
+            .iter()
+            .filter(|(shape, _)| {
+                shape
+                    .argument_types
+                    .iter()
+                    .all(|argument| self.fits(argument, depth - 1, size - 1))
+                    && 1 + shape
+                        .argument_types
+                        .iter()
+                        .map(|argument| self.bounds[argument].size)
+                        .sum::<usize>()
+                        <= size
+            })
+            .map(|definition| (definition, self.weight(definition)))
+            .filter(|(_, weight)| *weight > 0)
+            .collect::<Vec<_>>();
 
-        let mut subterms = Vec::with_capacity(required_types.len());
+        let total = candidates.iter().map(|(_, weight)| weight).sum::<u64>();
+        if total == 0 {
+            return None;
+        }
 
-        for typ in required_types {
-            if let Some(possibilities) = signature.functions_by_typ.get(typ) {
-                if let Some(possibility) = possibilities.choose(rand) {
-                    if let Some(subterm) =
-                        Self::generate_term(signature, possibility, depth - 1, rand)
-                    {
-                        subterms.push(subterm)
-                    } else {
-                        // Max depth reached
-                        return None;
-                    }
-                } else {
-                    // No possibilities available
-                    return None;
-                }
+        let mut choice = rand.below(total);
+        let (definition, _) = candidates.into_iter().find(|(_, weight)| {
+            if choice < *weight {
+                true
             } else {
-                // Type not available
-                return None;
+                choice -= weight;
+                false
             }
+        })?;
+
+        self.apply(definition, depth, size, rand)
+    }
+
+    fn apply<M: Matcher, R: Rand>(
+        &self,
+        (shape, dynamic_fn): &FunctionDefinition,
+        depth: u16,
+        size: usize,
+        rand: &mut R,
+    ) -> Option<Term<M>> {
+        if depth == 0 || size == 0 {
+            return None;
+        }
+
+        let minimal_sizes = shape
+            .argument_types
+            .iter()
+            .map(|argument| self.bounds.get(argument).map(|bounds| bounds.size))
+            .collect::<Option<Vec<_>>>()?;
+
+        let mut remaining = size - 1;
+        let mut subterms = Vec::with_capacity(shape.argument_types.len());
+
+        for (i, argument) in shape.argument_types.iter().enumerate() {
+            // Reserve the minimal size of the following arguments
+            let reserved = minimal_sizes[i + 1..].iter().sum::<usize>();
+            let budget = remaining.checked_sub(reserved)?;
+
+            let subterm = self.generate_type(*argument, depth - 1, budget, rand)?;
+            remaining -= subterm.size();
+            subterms.push(subterm);
         }
 
         Some(Term::Application(
@@ -85,6 +281,30 @@ impl<M: Matcher> TermZoo<M> {
             subterms,
         ))
     }
+}
+
+pub struct TermZoo<M: Matcher> {
+    terms: Vec<Term<M>>,
+}
+
+impl<M: Matcher> TermZoo<M> {
+    pub fn generate<R: Rand>(signature: &'static Signature, rand: &mut R) -> Self {
+        let mut generator = TermGenerator::new(
+            signature,
+            GeneratorConfig {
+                tries: MAX_TRIES,
+                ..GeneratorConfig::default()
+            },
+        );
+
+        let terms = signature
+            .functions
+            .iter()
+            .filter_map(|definition| generator.generate_application(definition, rand))
+            .collect::<Vec<_>>();
+
+        Self { terms }
+    }
 
     pub fn choose_filtered<P, R: Rand>(&self, filter: P, rand: &mut R) -> Option<&Term<M>>
     where
@@ -97,3 +317,110 @@ impl<M: Matcher> TermZoo<M> {
         &self.terms
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use libafl::bolts::rands::StdRand;
+
+    use super::{GeneratorConfig, TermGenerator};
+    use crate::{
+        agent::AgentName,
+        algebra::{
+            dynamic_function::TypeShape,
+            signature::Signature,
+            test_signature::{ClientExtensions, HandshakeMessage, TestTerm, TEST_SIGNATURE},
+            Term,
+        },
+    };
+
+    fn depth(term: &TestTerm) -> u16 {
+        match term {
+            Term::Application(_, subterms) => 1 + subterms.iter().map(depth).max().unwrap_or(0),
+            _ => 1,
+        }
+    }
+
+    #[test]
+    fn test_generate_respects_budgets() {
+        let mut rand = StdRand::with_seed(101);
+        let mut generator = TermGenerator::new(
+            &TEST_SIGNATURE,
+            GeneratorConfig {
+                max_depth: 3,
+                max_size: 6,
+                ..GeneratorConfig::default()
+            },
+        );
+
+        for _ in 0..100 {
+            let term: TestTerm = generator
+                .generate(TypeShape::of::<ClientExtensions>(), &[], &mut rand)
+                .unwrap();
+            assert_eq!(*term.get_type_shape(), TypeShape::of::<ClientExtensions>());
+            assert!(depth(&term) <= 3);
+            assert!(term.size() <= 6);
+        }
+    }
+
+    #[test]
+    fn test_generate_constants_only() {
+        let mut rand = StdRand::with_seed(102);
+        let mut generator = TermGenerator::new(
+            &TEST_SIGNATURE,
+            GeneratorConfig {
+                max_depth: 1,
+                ..GeneratorConfig::default()
+            },
+        );
+
+        for _ in 0..100 {
+            let term: TestTerm = generator
+                .generate(TypeShape::of::<HandshakeMessage>(), &[], &mut rand)
+                .unwrap();
+            assert_eq!(term.size(), 1);
+        }
+    }
+
+    #[test]
+    fn test_generate_failed_type() {
+        let mut rand = StdRand::with_seed(103);
+        let mut generator = TermGenerator::new(&TEST_SIGNATURE, GeneratorConfig::default());
+        let typ = TypeShape::of::<u8>();
+
+        let term: Option<TestTerm> = generator.generate(typ, &[], &mut rand);
+        assert!(term.is_none());
+        assert!(generator.is_failed(&typ));
+
+        let variable: TestTerm = Term::Variable(Signature::new_var_with_type::<u8, _>(
+            AgentName::first(),
+            None,
+            0,
+        ));
+        let term = generator.generate(typ, &[variable.clone()], &mut rand);
+        assert_eq!(term, Some(variable));
+
+        generator.clear_failed();
+        assert!(!generator.is_failed(&typ));
+    }
+
+    #[test]
+    fn test_generate_weights() {
+        let mut rand = StdRand::with_seed(104);
+        let mut generator = TermGenerator::new(
+            &TEST_SIGNATURE,
+            GeneratorConfig {
+                weights: HashMap::from([("fn_seq_0", 0)]),
+                ..GeneratorConfig::default()
+            },
+        );
+
+        for _ in 0..100 {
+            let term: TestTerm = generator
+                .generate(TypeShape::of::<u32>(), &[], &mut rand)
+                .unwrap();
+            assert!(term.name().ends_with("fn_seq_1"));
+        }
+    }
+}
@@ -10,8 +10,11 @@ use std::{
 use itertools::Itertools;
 
 use crate::{
+    agent::AgentName,
     algebra::{remove_fn_prefix, remove_prefix, Matcher, Term},
-    trace::{Action, Trace},
+    error::Error,
+    protocol::ProtocolBehavior,
+    trace::{Action, Step, StepHook, Trace, TraceContext},
 };
 
 // Colorful theme
@@ -58,17 +61,143 @@ pub fn write_graphviz(output: &str, format: &str, dot_script: &str) -> Result<()
     Ok(())
 }
 
+/// A data-flow edge from a [`Variable`](crate::algebra::atoms::Variable) in the recipe of a step
+/// to the step whose output satisfied the query of the variable
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DataFlowEdge {
+    /// Step of the recipe which contains the variable
+    pub step: usize,
+    pub variable_unique_id: u32,
+    pub variable_resistant_id: u32,
+    /// Step whose output contained the knowledge
+    pub source_step: usize,
+    /// Agent which produced the knowledge
+    pub source_agent: AgentName,
+}
+
+/// Data-flow edges of a trace. They depend on the outputs of the agents and are therefore
+/// resolved by executing the trace, see [`Trace::resolve_data_flow`].
+#[derive(Debug, Clone, Default)]
+pub struct DataFlow {
+    pub edges: Vec<DataFlowEdge>,
+}
+
+impl DataFlow {
+    fn statements(&self, tree_mode: bool) -> Vec<String> {
+        let mut statements = Vec::new();
+
+        for (source_step, source_agent) in self
+            .edges
+            .iter()
+            .map(|edge| (edge.source_step, edge.source_agent))
+            .unique()
+        {
+            statements.push(format!(
+                "o_{} [label=\"Output of agent {} (step {})\",shape=\"box\",style=\"dashed\",fontname=\"{}\"];",
+                source_step, source_agent, source_step, FONT
+            ));
+        }
+
+        for edge in &self.edges {
+            let variable = if tree_mode {
+                format!("v_{}_{}", edge.step, edge.variable_unique_id)
+            } else {
+                format!("v_{}", edge.variable_resistant_id)
+            };
+            statements.push(format!(
+                "{} -> o_{} [style=\"dashed\",color=\"blue\",constraint=false];",
+                variable, edge.source_step
+            ));
+        }
+
+        statements
+    }
+}
+
+/// Records the data-flow edges of each input step while the trace is executed
+struct DataFlowResolver<'a> {
+    data_flow: &'a mut DataFlow,
+    /// The step which produced each knowledge, or `None` if it stems from a prior trace
+    origins: Vec<Option<(usize, AgentName)>>,
+}
+
+impl<PB: ProtocolBehavior> StepHook<PB> for DataFlowResolver<'_> {
+    fn before_step(&mut self, i: usize, step: &Step<PB::Matcher>, ctx: &TraceContext<PB>) {
+        self.origins.resize(ctx.knowledge().len(), None);
+
+        if let Action::Input(input) = &step.action {
+            for term in &input.recipe {
+                if let Term::Variable(variable) = term {
+                    let origin = ctx
+                        .find_knowledge(variable.typ, &variable.query)
+                        .and_then(|(index, _)| self.origins[index]);
+
+                    if let Some((source_step, source_agent)) = origin {
+                        self.data_flow.edges.push(DataFlowEdge {
+                            step: i,
+                            variable_unique_id: variable.unique_id,
+                            variable_resistant_id: variable.resistant_id,
+                            source_step,
+                            source_agent,
+                        });
+                    }
+                }
+            }
+        }
+    }
+
+    fn after_step(
+        &mut self,
+        i: usize,
+        step: &Step<PB::Matcher>,
+        ctx: &TraceContext<PB>,
+        _result: &Result<(), Error>,
+    ) {
+        self.origins
+            .resize(ctx.knowledge().len(), Some((i, step.agent)));
+    }
+}
+
 impl<M: Matcher> Trace<M> {
     pub fn dot_graph(&self, tree_mode: bool) -> String {
+        self.dot_graph_with_data_flow(tree_mode, &DataFlow::default())
+    }
+
+    /// Like [`Trace::dot_graph`], but additionally draws the edges of `data_flow` from the
+    /// variables to the outputs which satisfied them.
+    pub fn dot_graph_with_data_flow(&self, tree_mode: bool, data_flow: &DataFlow) -> String {
         format!(
             "strict digraph \"Trace\" \
             {{ \
                 splines=false;\
                 fontname=\"{}\";\
                 {} \
+                {} \
             }}",
             FONT,
-            self.dot_subgraphs(tree_mode).join("\n")
+            self.dot_subgraphs(tree_mode).join("\n"),
+            data_flow.statements(tree_mode).join("\n")
+        )
+    }
+
+    /// Executes the trace and records for each variable in the recipes which step produced the
+    /// knowledge the variable evaluates to. Variables which are satisfied by claims or by the
+    /// outputs of prior traces have no edge. If the execution fails, then the edges up to the
+    /// failing step are kept in `data_flow` and the error is returned.
+    pub fn resolve_data_flow<PB>(
+        &self,
+        ctx: &mut TraceContext<PB>,
+        data_flow: &mut DataFlow,
+    ) -> Result<(), Error>
+    where
+        PB: ProtocolBehavior<Matcher = M>,
+    {
+        self.execute_with(
+            ctx,
+            &mut DataFlowResolver {
+                data_flow,
+                origins: Vec::new(),
+            },
         )
     }
 
@@ -123,6 +252,13 @@ impl<M: Matcher> Term<M> {
                     format!("f_{}", func.resistant_id)
                 }
             }
+            Term::Literal(literal) => {
+                if tree_mode {
+                    format!("l_{}_{}", cluster_id, literal.unique_id)
+                } else {
+                    format!("l_{}", literal.resistant_id)
+                }
+            }
         }
     }
 
@@ -180,6 +316,14 @@ impl<M: Matcher> Term<M> {
                     Self::collect_statements(subterm, tree_mode, cluster_id, statements);
                 }
             }
+            Term::Literal(literal) => {
+                statements.push(format!(
+                    "{} {} [fontname=\"{}\"];",
+                    term.unique_id(tree_mode, cluster_id),
+                    Self::node_attributes(&literal.value, COLOR_LEAVES, SHAPE_LEAVES),
+                    FONT
+                ));
+            }
         }
     }
 
@@ -207,7 +351,8 @@ impl<M: Matcher> Term<M> {
 
 #[cfg(test)]
 mod tests {
-    use crate::algebra::test_signature::setup_simple_trace;
+    use super::{DataFlow, DataFlowEdge};
+    use crate::{agent::AgentName, algebra::test_signature::setup_simple_trace};
 
     #[test]
     fn test_dot_graph() {
@@ -215,4 +360,33 @@ mod tests {
         let _string = trace.dot_graph(true);
         //println!("{}", string);
     }
+
+    #[test]
+    fn test_data_flow_statements() {
+        let edge = DataFlowEdge {
+            step: 2,
+            variable_unique_id: 10,
+            variable_resistant_id: 20,
+            source_step: 1,
+            source_agent: AgentName::first(),
+        };
+        let data_flow = DataFlow {
+            edges: vec![edge.clone(), DataFlowEdge { step: 3, ..edge }],
+        };
+
+        let statements = data_flow.statements(true);
+        // One node for the output of step 1 and an edge from each variable
+        assert_eq!(statements.len(), 3);
+        assert!(statements[0].starts_with("o_1 [label=\"Output of agent 0 (step 1)\""));
+        assert!(statements[1].starts_with("v_2_10 -> o_1 "));
+        assert!(statements[2].starts_with("v_3_10 -> o_1 "));
+
+        let statements = data_flow.statements(false);
+        assert!(statements[1].starts_with("v_20 -> o_1 "));
+
+        let trace = setup_simple_trace();
+        assert!(trace
+            .dot_graph_with_data_flow(true, &data_flow)
+            .contains("v_2_10 -> o_1 "));
+    }
 }
@@ -0,0 +1,439 @@
+//! Inspection of a single trace execution. The trace is executed step by step and a
+//! self-contained HTML page is written which shows for each step the recipe as a collapsible tree
+//! together with the evaluated value of every subterm, the knowledge which the following output
+//! added and the claims which the agents emitted.
+//!
+//! This is mostly useful to find out why a seed stopped working, for example after upgrading a
+//! PUT: the first subterm which fails to evaluate or the first output which lacks the expected
+//! knowledge is highlighted.
+
+use std::{any::Any, fmt::Write, fs, path::Path};
+
+use libafl::inputs::Input;
+use log::info;
+
+use crate::{
+    algebra::{remove_prefix, Matcher, Term},
+    claims::Claim,
+    error::Error,
+    protocol::ProtocolBehavior,
+    trace::{Action, Step, StepHook, Trace, TraceContext},
+    variable_data::VariableData,
+};
+
+const STYLE: &str = "
+body { font-family: sans-serif; margin: 2em; }
+h1 { font-size: 1.4em; }
+section.step { border: 1px solid #ccc; border-radius: 4px; margin: 1em 0; padding: 0.5em 1em; }
+section.failed { border-color: #c00; }
+details { margin-left: 1.2em; }
+summary { cursor: pointer; }
+.leaf { margin-left: 1.2em; }
+.symbol { font-family: monospace; font-weight: bold; }
+.type { color: #666; font-family: monospace; }
+.ok { color: #080; }
+.error { color: #c00; font-weight: bold; }
+pre { background: #f6f6f6; margin: 0.2em 0 0.2em 1.2em; padding: 0.3em; overflow-x: auto; max-height: 20em; }
+table { border-collapse: collapse; }
+td, th { border: 1px solid #ccc; padding: 0.2em 0.5em; text-align: left; vertical-align: top; }
+";
+
+/// Escapes text such that it can be embedded in HTML.
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '&' => escaped.push_str("&amp;"),
+            '"' => escaped.push_str("&quot;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Formats an evaluated value. Values are type-erased, therefore only the messages of the
+/// protocol and common primitive types can be shown. For all other types `None` is returned.
+fn format_value<PB: ProtocolBehavior>(value: &dyn Any) -> Option<String> {
+    macro_rules! debug_value {
+        ($($typ:ty),*) => {
+            $(
+                if let Some(value) = value.downcast_ref::<$typ>() {
+                    return Some(format!("{:#?}", value));
+                }
+            )*
+        };
+    }
+
+    if let Some(bytes) = value.downcast_ref::<Vec<u8>>() {
+        return Some(format!("{} bytes: {}", bytes.len(), hex(bytes)));
+    }
+
+    debug_value!(
+        PB::ProtocolMessage,
+        PB::OpaqueProtocolMessage,
+        bool,
+        u8,
+        u16,
+        u32,
+        u64,
+        Option<Vec<u8>>,
+        Vec<Vec<u8>>
+    );
+
+    None
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+/// Evaluates `term` bottom-up like [`Term::evaluate`] and appends it as a tree to `html`. Subterms
+/// are evaluated even if a sibling failed, such that all failing subterms are visible. Returns the
+/// value of `term`.
+fn render_term<M: Matcher>(
+    term: &Term<M>,
+    evaluate_variable: &dyn Fn(&Term<M>) -> Result<Box<dyn Any>, Error>,
+    format_value: &dyn Fn(&dyn Any) -> Option<String>,
+    html: &mut String,
+) -> Result<Box<dyn Any>, String> {
+    let type_name = escape(&remove_prefix(term.get_type_shape().name));
+
+    let (label, result, children) = match term {
+        Term::Variable(variable) => (
+            format!(
+                "<span class=\"symbol\">{}</span>",
+                escape(&variable.to_string())
+            ),
+            evaluate_variable(term).map_err(|err| err.to_string()),
+            String::new(),
+        ),
+        Term::Literal(literal) => (
+            format!(
+                "<span class=\"symbol\">{}</span>",
+                escape(&literal.value.to_string())
+            ),
+            Ok(literal.value.boxed_any()),
+            String::new(),
+        ),
+        Term::Application(function, args) => {
+            let mut children = String::new();
+            let mut dynamic_args = Vec::new();
+            let mut failed = false;
+
+            for arg in args {
+                match render_term(arg, evaluate_variable, format_value, &mut children) {
+                    Ok(value) => dynamic_args.push(value),
+                    Err(_) => failed = true,
+                }
+            }
+
+            let result = if failed {
+                Err("not evaluated because an argument failed".to_string())
+            } else {
+                (function.dynamic_fn())(&dynamic_args).map_err(|err| err.to_string())
+            };
+
+            (
+                format!(
+                    "<span class=\"symbol\">{}</span>",
+                    escape(&remove_prefix(function.name()))
+                ),
+                result,
+                children,
+            )
+        }
+    };
+
+    let value = match &result {
+        Ok(value) => match format_value(value.as_ref()) {
+            Some(formatted) => format!("<pre>{}</pre>", escape(&formatted)),
+            None => "<pre>(value can not be displayed)</pre>".to_string(),
+        },
+        Err(err) => format!("<pre class=\"error\">{}</pre>", escape(err)),
+    };
+    let status = if result.is_ok() {
+        "<span class=\"ok\">&#10003;</span>"
+    } else {
+        "<span class=\"error\">&#10007;</span>"
+    };
+
+    if term.is_leaf() {
+        write!(
+            html,
+            "<div class=\"leaf\">{} {} : <span class=\"type\">{}</span>{}</div>",
+            status, label, type_name, value
+        )
+        .unwrap();
+    } else {
+        // Failing subtrees are expanded, such that the cause of the failure is visible immediately
+        write!(
+            html,
+            "<details{}><summary>{} {} : <span class=\"type\">{}</span></summary>{}{}</details>",
+            if result.is_err() { " open" } else { "" },
+            status,
+            label,
+            type_name,
+            value,
+            children
+        )
+        .unwrap();
+    }
+
+    result
+}
+
+/// Appends a table of `knowledge` to `html`.
+fn render_knowledge<'a, M: Matcher>(
+    knowledge: impl Iterator<Item = (Option<&'a M>, &'a dyn VariableData)>,
+    html: &mut String,
+) {
+    let mut rows = String::new();
+    for (i, (matcher, data)) in knowledge.enumerate() {
+        write!(
+            rows,
+            "<tr><td>{}</td><td class=\"type\">{}</td><td>{}</td><td><pre>{}</pre></td></tr>",
+            i,
+            escape(&remove_prefix(data.type_name())),
+            escape(&format!("{:?}", matcher)),
+            escape(&format!("{:#?}", data))
+        )
+        .unwrap();
+    }
+
+    if rows.is_empty() {
+        html.push_str("<p>No knowledge was added.</p>");
+    } else {
+        write!(
+            html,
+            "<details><summary>Knowledge added</summary><table><tr><th>#</th><th>Type</th><th>Matcher</th><th>Value</th></tr>{}</table></details>",
+            rows
+        )
+        .unwrap();
+    }
+}
+
+fn render_claims<C: Claim>(claims: &[C], html: &mut String) {
+    if claims.is_empty() {
+        return;
+    }
+
+    html.push_str("<details><summary>Claims emitted</summary>");
+    for claim in claims {
+        write!(
+            html,
+            "<pre>Agent {}: {}</pre>",
+            claim.agent_name(),
+            escape(&format!("{:#?}", claim))
+        )
+        .unwrap();
+    }
+    html.push_str("</details>");
+}
+
+/// Appends the report of each step to `html` while the trace is executed. The recipe of each
+/// input step is evaluated once by [`render_term`] and the rendered value is sent to the agent.
+struct Inspector<'a> {
+    html: &'a mut String,
+    body: String,
+    knowledge_before: usize,
+    claims_before: usize,
+}
+
+impl<PB: ProtocolBehavior> StepHook<PB> for Inspector<'_> {
+    fn before_step(&mut self, _i: usize, _step: &Step<PB::Matcher>, ctx: &TraceContext<PB>) {
+        self.body.clear();
+        self.knowledge_before = ctx.knowledge().len();
+        self.claims_before = ctx.claims().deref_borrow().slice().len();
+    }
+
+    fn evaluate(
+        &mut self,
+        _i: usize,
+        recipe: &Term<PB::Matcher>,
+        ctx: &TraceContext<PB>,
+    ) -> Result<Box<dyn Any>, Error> {
+        self.body.push_str("<h3>Recipe</h3>");
+        let evaluate_variable = |term: &Term<PB::Matcher>| term.evaluate(ctx);
+        render_term(
+            recipe,
+            &evaluate_variable,
+            &format_value::<PB>,
+            &mut self.body,
+        )
+        .map_err(Error::Term)
+    }
+
+    fn after_step(
+        &mut self,
+        i: usize,
+        step: &Step<PB::Matcher>,
+        ctx: &TraceContext<PB>,
+        result: &Result<(), Error>,
+    ) {
+        let body = &mut self.body;
+
+        body.push_str("<h3>Output</h3>");
+        render_knowledge(
+            ctx.knowledge()[self.knowledge_before..]
+                .iter()
+                .map(|knowledge| (knowledge.matcher.as_ref(), knowledge.data.as_ref())),
+            body,
+        );
+        render_claims(
+            &ctx.claims().deref_borrow().slice()[self.claims_before..],
+            body,
+        );
+
+        if let Err(err) = result {
+            write!(
+                body,
+                "<p class=\"error\">Step failed: {}</p>",
+                escape(&err.to_string())
+            )
+            .unwrap();
+        }
+
+        write!(
+            self.html,
+            "<section class=\"step{}\"><h2>Step {}: {} of agent {}</h2>{}</section>",
+            if result.is_err() { " failed" } else { "" },
+            i,
+            match step.action {
+                Action::Input(_) => "Input",
+                Action::Output(_) => "Output",
+            },
+            step.agent,
+            body
+        )
+        .unwrap();
+    }
+}
+
+/// Executes `trace` while appending the report of each step to `html`. The prior traces are
+/// reported first, each in its own section. Stops at the first step which fails.
+fn inspect_trace<PB: ProtocolBehavior + 'static>(
+    trace: &Trace<PB::Matcher>,
+    ctx: &mut TraceContext<PB>,
+    html: &mut String,
+) -> Result<(), Error> {
+    for (i, prior) in trace.prior_traces.iter().enumerate() {
+        write!(html, "<section><h2>Prior trace {}</h2>", i).unwrap();
+        prior.spawn_agents(ctx)?;
+        let result = inspect_trace(prior, ctx, html);
+        html.push_str("</section>");
+        result?;
+        ctx.reset_agents()?;
+    }
+
+    trace.execute_steps_with(
+        ctx,
+        &mut Inspector {
+            html,
+            body: String::new(),
+            knowledge_before: 0,
+            claims_before: 0,
+        },
+    )
+}
+
+/// Executes the trace stored in `input` and writes an HTML report of the execution to `output`.
+pub fn inspect<PB: ProtocolBehavior + 'static>(
+    input: &Path,
+    output: &Path,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let trace = Trace::<PB::Matcher>::from_file(input)?;
+
+    let mut steps = String::new();
+    let mut ctx = TraceContext::new(PB::registry());
+    let result = inspect_trace(&trace, &mut ctx, &mut steps);
+
+    let mut agents = String::new();
+    for descriptor in &trace.descriptors {
+        write!(agents, "<li>{}</li>", escape(&format!("{:?}", descriptor))).unwrap();
+    }
+
+    let summary = match &result {
+        Ok(()) => "<p class=\"ok\">The trace executed successfully.</p>".to_string(),
+        Err(err) => format!(
+            "<p class=\"error\">The execution failed: {}</p>",
+            escape(&err.to_string())
+        ),
+    };
+
+    let title = escape(&input.display().to_string());
+    let page = format!(
+        "<!DOCTYPE html><html><head><meta charset=\"utf-8\"><title>{}</title><style>{}</style></head><body><h1>{}</h1>{}<h2>Agents</h2><ul>{}</ul>{}</body></html>\n",
+        title, STYLE, title, summary, agents, steps
+    );
+    fs::write(output, page)?;
+
+    info!("Wrote inspection of {:?} to {:?}", input, output);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::any::Any;
+
+    use super::{escape, render_term};
+    use crate::{
+        agent::AgentName,
+        algebra::{
+            atoms::{Literal, LiteralValue},
+            error::FnError,
+            signature::Signature,
+            test_signature::example_op_c,
+            AnyMatcher, Term,
+        },
+        error::Error,
+    };
+
+    fn format_u16(value: &dyn Any) -> Option<String> {
+        value.downcast_ref::<u16>().map(|value| value.to_string())
+    }
+
+    fn missing_variable(_term: &Term<AnyMatcher>) -> Result<Box<dyn Any>, Error> {
+        Err(Error::Fn(FnError::Unknown("missing".to_string())))
+    }
+
+    #[test]
+    fn test_escape() {
+        assert_eq!(
+            escape("<a href=\"x\">&</a>"),
+            "&lt;a href=&quot;x&quot;&gt;&amp;&lt;/a&gt;"
+        );
+    }
+
+    #[test]
+    fn test_render_evaluated_term() {
+        let term: Term<AnyMatcher> = Term::Application(
+            Signature::new_function(&example_op_c),
+            vec![Term::Literal(Literal::new(LiteralValue::U8(1)))],
+        );
+
+        let mut html = String::new();
+        let value = render_term(&term, &missing_variable, &format_u16, &mut html).unwrap();
+
+        assert_eq!(value.downcast_ref::<u16>(), Some(&2));
+        assert!(html.contains("example_op_c"));
+        assert!(html.contains("<pre>2</pre>"));
+        assert!(html.contains("0x01"));
+    }
+
+    #[test]
+    fn test_render_failing_variable() {
+        let variable: Term<AnyMatcher> = Term::Variable(Signature::new_var_with_type::<u8, _>(
+            AgentName::first(),
+            None,
+            0,
+        ));
+        let term = Term::Application(Signature::new_function(&example_op_c), vec![variable]);
+
+        let mut html = String::new();
+        assert!(render_term(&term, &missing_variable, &format_u16, &mut html).is_err());
+        assert!(html.starts_with("<details open>"));
+        assert!(html.contains("not evaluated because an argument failed"));
+    }
+}
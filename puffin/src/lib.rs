@@ -8,16 +8,21 @@ pub mod algebra;
 pub mod claims;
 pub mod cli;
 pub mod codec;
+pub mod coverage;
+pub mod diff;
 pub mod error;
 pub mod experiment;
 pub mod fuzzer;
 pub mod graphviz;
+pub mod inspect;
 pub mod log;
 pub mod protocol;
 pub mod put;
 pub mod put_registry;
+pub mod stats_plot;
 pub mod stream;
 pub mod trace;
+pub mod triage;
 pub mod variable_data;
 
 pub use libafl;
@@ -101,7 +101,11 @@ pub trait Put<PB: ProtocolBehavior>:
     /// checks whether a agent is reusable with the descriptor
     fn is_reusable_with(&self, other: &AgentDescriptor) -> bool {
         let agent_descriptor = self.descriptor();
-        agent_descriptor.typ == other.typ && agent_descriptor.tls_version == other.tls_version
+        agent_descriptor.typ == other.typ
+            && agent_descriptor.tls_version == other.tls_version
+            && agent_descriptor.early_data == other.early_data
+            && agent_descriptor.ech == other.ech
+            && agent_descriptor.ffdhe == other.ffdhe
     }
 
     /// Shutdown the PUT by consuming it and returning a string which summarizes the execution.
@@ -0,0 +1,487 @@
+//! Plots of the statistics which the `StatsMonitor` writes to `stats.json` during a campaign. The
+//! file contains one JSON object per report of a client. The reports of all clients are
+//! aggregated into a single time series per experiment, such that several experiments can be
+//! overlaid in the same chart.
+
+use std::{
+    collections::BTreeMap,
+    fmt::Write as FmtWrite,
+    fs,
+    fs::File,
+    io::BufReader,
+    path::{Path, PathBuf},
+};
+
+use log::{info, warn};
+use serde_json::Value;
+
+// https://colorbrewer2.org/#type=qualitative&scheme=Set1&n=8
+const COLORS: &[&str] = &[
+    "#e41a1c", "#377eb8", "#4daf4a", "#984ea3", "#ff7f00", "#a65628", "#f781bf", "#999999",
+];
+
+const WIDTH: f64 = 800.0;
+const HEIGHT: f64 = 450.0;
+const MARGIN_LEFT: f64 = 80.0;
+const MARGIN_RIGHT: f64 = 180.0;
+const MARGIN_TOP: f64 = 40.0;
+const MARGIN_BOTTOM: f64 = 50.0;
+
+/// Error counters of the `errors` object in `stats.json`
+const ERROR_KINDS: &[&str] = &[
+    "fn_error",
+    "term_error",
+    "ssl_error",
+    "io_error",
+    "ag_error",
+    "str_error",
+    "ext_error",
+];
+
+/// The state of the campaign summed over all clients after a report of one of them
+#[derive(Debug, Clone, Default, PartialEq)]
+pub(crate) struct Sample {
+    /// Seconds since the first report
+    pub time: f64,
+    /// Highest number of discovered edges of any client
+    pub coverage: u64,
+    pub corpus_size: u64,
+    pub objective_size: u64,
+    pub total_execs: u64,
+    pub exec_per_sec: u64,
+    pub errors: BTreeMap<&'static str, u64>,
+}
+
+/// Reads all reports from `stats.json`. The file is written while the campaign is running,
+/// therefore a truncated last report is ignored.
+pub(crate) fn load_stats(path: &Path) -> Result<Vec<Value>, Box<dyn std::error::Error>> {
+    let reader = BufReader::new(File::open(path)?);
+    let mut reports = Vec::new();
+
+    for report in serde_json::Deserializer::from_reader(reader).into_iter::<Value>() {
+        match report {
+            Ok(report) => reports.push(report),
+            Err(err) if err.is_eof() => {
+                warn!("Ignoring truncated report at the end of {:?}", path);
+                break;
+            }
+            Err(err) => return Err(err.into()),
+        }
+    }
+
+    Ok(reports)
+}
+
+fn number(report: &Value, pointer: &str) -> u64 {
+    report.pointer(pointer).and_then(Value::as_u64).unwrap_or(0)
+}
+
+fn seconds(report: &Value) -> f64 {
+    number(report, "/time/secs_since_epoch") as f64
+        + number(report, "/time/nanos_since_epoch") as f64 / 1e9
+}
+
+/// Aggregates the reports of all clients. After each report the latest report of each client is
+/// combined into a [`Sample`].
+pub(crate) fn aggregate(reports: &[Value]) -> Vec<Sample> {
+    let start = match reports.first() {
+        Some(first) => seconds(first),
+        None => return vec![],
+    };
+
+    let mut latest: BTreeMap<u64, &Value> = BTreeMap::new();
+    let mut samples = Vec::with_capacity(reports.len());
+
+    for report in reports {
+        latest.insert(number(report, "/id"), report);
+
+        let mut sample = Sample {
+            time: seconds(report) - start,
+            ..Sample::default()
+        };
+
+        for client in latest.values() {
+            sample.coverage = sample.coverage.max(number(client, "/coverage/discovered"));
+            sample.corpus_size += number(client, "/corpus_size");
+            sample.objective_size += number(client, "/objective_size");
+            sample.total_execs += number(client, "/total_execs");
+            sample.exec_per_sec += number(client, "/exec_per_sec");
+
+            for kind in ERROR_KINDS {
+                *sample.errors.entry(*kind).or_default() +=
+                    number(client, &format!("/errors/{}", kind));
+            }
+        }
+
+        samples.push(sample);
+    }
+
+    samples
+}
+
+/// A line in a chart
+struct Series<'a> {
+    label: &'a str,
+    points: Vec<(f64, f64)>,
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+fn format_tick(value: f64) -> String {
+    if value >= 1e6 {
+        format!("{:.1}M", value / 1e6)
+    } else if value >= 1e3 {
+        format!("{:.1}k", value / 1e3)
+    } else if value.fract() == 0.0 {
+        format!("{}", value)
+    } else {
+        format!("{:.2}", value)
+    }
+}
+
+fn svg_header(svg: &mut String, title: &str) {
+    writeln!(
+        svg,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{w}" height="{h}" viewBox="0 0 {w} {h}" font-family="sans-serif" font-size="12">"#,
+        w = WIDTH,
+        h = HEIGHT
+    )
+    .unwrap();
+    writeln!(
+        svg,
+        r#"<rect width="100%" height="100%" fill="white"/><text x="{}" y="24" text-anchor="middle" font-size="16">{}</text>"#,
+        (MARGIN_LEFT + WIDTH - MARGIN_RIGHT) / 2.0,
+        escape(title)
+    )
+    .unwrap();
+}
+
+fn svg_legend(svg: &mut String, labels: &[&str]) {
+    for (i, label) in labels.iter().enumerate() {
+        let y = MARGIN_TOP + 20.0 * i as f64;
+        writeln!(
+            svg,
+            r#"<rect x="{x}" y="{y}" width="12" height="12" fill="{color}"/><text x="{tx}" y="{ty}">{label}</text>"#,
+            x = WIDTH - MARGIN_RIGHT + 15.0,
+            y = y,
+            color = COLORS[i % COLORS.len()],
+            tx = WIDTH - MARGIN_RIGHT + 32.0,
+            ty = y + 11.0,
+            label = escape(label)
+        )
+        .unwrap();
+    }
+}
+
+/// Draws the axes and the ticks of the y-axis. Returns a function which maps a value to the
+/// y-coordinate.
+fn svg_y_axis(svg: &mut String, y_label: &str, max_y: f64) -> impl Fn(f64) -> f64 {
+    let max_y = if max_y > 0.0 { max_y } else { 1.0 };
+    let plot_height = HEIGHT - MARGIN_TOP - MARGIN_BOTTOM;
+    let to_y = move |y: f64| HEIGHT - MARGIN_BOTTOM - y / max_y * plot_height;
+
+    writeln!(
+        svg,
+        r#"<path d="M{l},{t} V{b} H{r}" stroke="black" fill="none"/>"#,
+        l = MARGIN_LEFT,
+        t = MARGIN_TOP,
+        b = HEIGHT - MARGIN_BOTTOM,
+        r = WIDTH - MARGIN_RIGHT
+    )
+    .unwrap();
+
+    for i in 0..=5 {
+        let value = max_y * i as f64 / 5.0;
+        writeln!(
+            svg,
+            r##"<line x1="{l}" x2="{r}" y1="{y}" y2="{y}" stroke="#dddddd"/><text x="{tx}" y="{ty}" text-anchor="end">{v}</text>"##,
+            l = MARGIN_LEFT,
+            r = WIDTH - MARGIN_RIGHT,
+            y = to_y(value),
+            tx = MARGIN_LEFT - 6.0,
+            ty = to_y(value) + 4.0,
+            v = format_tick(value)
+        )
+        .unwrap();
+    }
+
+    writeln!(
+        svg,
+        r#"<text transform="translate(18,{y}) rotate(-90)" text-anchor="middle">{label}</text>"#,
+        y = (MARGIN_TOP + HEIGHT - MARGIN_BOTTOM) / 2.0,
+        label = escape(y_label)
+    )
+    .unwrap();
+
+    to_y
+}
+
+/// Renders a line chart with the time in hours on the x-axis.
+fn line_chart(title: &str, y_label: &str, series: &[Series]) -> String {
+    let max_x = series
+        .iter()
+        .flat_map(|series| series.points.iter().map(|(x, _)| *x))
+        .fold(0.0, f64::max);
+    let max_y = series
+        .iter()
+        .flat_map(|series| series.points.iter().map(|(_, y)| *y))
+        .fold(0.0, f64::max);
+    let max_x = if max_x > 0.0 { max_x } else { 1.0 };
+
+    let plot_width = WIDTH - MARGIN_LEFT - MARGIN_RIGHT;
+    let to_x = |x: f64| MARGIN_LEFT + x / max_x * plot_width;
+
+    let mut svg = String::new();
+    svg_header(&mut svg, title);
+    let to_y = svg_y_axis(&mut svg, y_label, max_y);
+
+    for i in 0..=5 {
+        let value = max_x * i as f64 / 5.0;
+        writeln!(
+            svg,
+            r#"<text x="{x}" y="{y}" text-anchor="middle">{v}h</text>"#,
+            x = to_x(value),
+            y = HEIGHT - MARGIN_BOTTOM + 18.0,
+            v = format_tick((value / 3600.0 * 100.0).round() / 100.0)
+        )
+        .unwrap();
+    }
+
+    for (i, series) in series.iter().enumerate() {
+        let points = series
+            .points
+            .iter()
+            .map(|(x, y)| format!("{:.1},{:.1}", to_x(*x), to_y(*y)))
+            .collect::<Vec<_>>()
+            .join(" ");
+        writeln!(
+            svg,
+            r#"<polyline points="{}" fill="none" stroke="{}" stroke-width="1.5"/>"#,
+            points,
+            COLORS[i % COLORS.len()]
+        )
+        .unwrap();
+    }
+
+    svg_legend(
+        &mut svg,
+        &series.iter().map(|series| series.label).collect::<Vec<_>>(),
+    );
+    svg.push_str("</svg>\n");
+    svg
+}
+
+/// Renders a grouped bar chart. Each group has one bar per experiment.
+fn bar_chart(title: &str, y_label: &str, groups: &[&str], bars: &[(&str, Vec<f64>)]) -> String {
+    let max_y = bars
+        .iter()
+        .flat_map(|(_, values)| values.iter().copied())
+        .fold(0.0, f64::max);
+
+    let mut svg = String::new();
+    svg_header(&mut svg, title);
+    let to_y = svg_y_axis(&mut svg, y_label, max_y);
+
+    let group_width = (WIDTH - MARGIN_LEFT - MARGIN_RIGHT) / groups.len().max(1) as f64;
+    let bar_width = group_width * 0.8 / bars.len().max(1) as f64;
+
+    for (g, group) in groups.iter().enumerate() {
+        let group_x = MARGIN_LEFT + g as f64 * group_width;
+        writeln!(
+            svg,
+            r#"<text x="{x}" y="{y}" text-anchor="middle">{label}</text>"#,
+            x = group_x + group_width / 2.0,
+            y = HEIGHT - MARGIN_BOTTOM + 18.0,
+            label = escape(group)
+        )
+        .unwrap();
+
+        for (b, (_, values)) in bars.iter().enumerate() {
+            let value = values.get(g).copied().unwrap_or(0.0);
+            writeln!(
+                svg,
+                r#"<rect x="{x:.1}" y="{y:.1}" width="{w:.1}" height="{h:.1}" fill="{color}"/>"#,
+                x = group_x + group_width * 0.1 + b as f64 * bar_width,
+                y = to_y(value),
+                w = bar_width,
+                h = HEIGHT - MARGIN_BOTTOM - to_y(value),
+                color = COLORS[b % COLORS.len()]
+            )
+            .unwrap();
+        }
+    }
+
+    svg_legend(
+        &mut svg,
+        &bars.iter().map(|(label, _)| *label).collect::<Vec<_>>(),
+    );
+    svg.push_str("</svg>\n");
+    svg
+}
+
+/// Resolves an experiment directory to its `stats.json` and derives a label from the path.
+pub(crate) fn stats_file(input: &Path) -> (PathBuf, String) {
+    let (file, dir) = if input.is_dir() {
+        (input.join("stats.json"), input)
+    } else {
+        (input.to_path_buf(), input.parent().unwrap_or(input))
+    };
+
+    let label = dir
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_else(|| input.display().to_string());
+
+    (file, label)
+}
+
+/// Plots the statistics of each experiment in `inputs` into SVG files in `output_dir`. An input is
+/// either an experiment directory or a `stats.json` file.
+pub fn plot_stats(inputs: &[PathBuf], output_dir: &Path) -> Result<(), Box<dyn std::error::Error>> {
+    let mut experiments = Vec::new();
+    for input in inputs {
+        let (file, label) = stats_file(input);
+        let samples = aggregate(&load_stats(&file)?);
+        info!("Loaded {} reports of {}", samples.len(), label);
+        experiments.push((label, samples));
+    }
+
+    fs::create_dir_all(output_dir)?;
+
+    let charts: [(&str, &str, &str, fn(&Sample) -> f64); 4] = [
+        ("coverage", "Coverage", "Edges", |s| s.coverage as f64),
+        ("execs", "Executions per Second", "exec/s", |s| {
+            s.exec_per_sec as f64
+        }),
+        ("corpus", "Corpus Size", "Traces", |s| s.corpus_size as f64),
+        ("objectives", "Objectives", "Traces", |s| {
+            s.objective_size as f64
+        }),
+    ];
+
+    for (name, title, y_label, selector) in charts {
+        let series = experiments
+            .iter()
+            .map(|(label, samples)| Series {
+                label,
+                points: samples
+                    .iter()
+                    .map(|sample| (sample.time, selector(sample)))
+                    .collect(),
+            })
+            .collect::<Vec<_>>();
+
+        let path = output_dir.join(format!("{}.svg", name));
+        fs::write(&path, line_chart(title, y_label, &series))?;
+        info!("Wrote {:?}", path);
+    }
+
+    // Share of executions which failed with each kind of error at the end of the campaign
+    let bars = experiments
+        .iter()
+        .map(|(label, samples)| {
+            let last = samples.last().cloned().unwrap_or_default();
+            let total = last.total_execs.max(1) as f64;
+            let values = ERROR_KINDS
+                .iter()
+                .map(|kind| last.errors.get(kind).copied().unwrap_or(0) as f64 / total * 100.0)
+                .collect();
+            (label.as_str(), values)
+        })
+        .collect::<Vec<_>>();
+
+    let groups = ERROR_KINDS
+        .iter()
+        .map(|kind| kind.trim_end_matches("_error"))
+        .collect::<Vec<_>>();
+
+    let path = output_dir.join("errors.svg");
+    fs::write(
+        &path,
+        bar_chart("Errors", "% of Executions", &groups, &bars),
+    )?;
+    info!("Wrote {:?}", path);
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use serde_json::json;
+
+    use super::{aggregate, line_chart, load_stats, Series};
+
+    fn report(id: u64, secs: u64, corpus_size: u64, discovered: u64) -> serde_json::Value {
+        json!({
+            "id": id,
+            "time": {"secs_since_epoch": secs, "nanos_since_epoch": 0},
+            "errors": {"fn_error": 1, "term_error": 0},
+            "coverage": {"discovered": discovered, "max": 100},
+            "corpus_size": corpus_size,
+            "objective_size": 0,
+            "total_execs": 10,
+            "exec_per_sec": 5
+        })
+    }
+
+    #[test]
+    fn test_load_truncated_stats() {
+        let path = std::env::temp_dir().join("puffin-test-stats.json");
+        let mut file = std::fs::File::create(&path).unwrap();
+        write!(
+            file,
+            "{}{}{{\"id\": 0, \"ti",
+            report(0, 1, 1, 1),
+            report(1, 2, 1, 1)
+        )
+        .unwrap();
+        drop(file);
+
+        assert_eq!(load_stats(&path).unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_aggregate_clients() {
+        let samples = aggregate(&[
+            report(0, 100, 3, 10),
+            report(1, 160, 4, 12),
+            report(0, 220, 5, 11),
+        ]);
+
+        assert_eq!(samples.len(), 3);
+        assert_eq!(samples[2].time, 120.0);
+        assert_eq!(samples[1].corpus_size, 7);
+        assert_eq!(samples[2].corpus_size, 9);
+        assert_eq!(samples[2].coverage, 12);
+        assert_eq!(samples[2].total_execs, 20);
+        assert_eq!(samples[2].errors["fn_error"], 2);
+    }
+
+    #[test]
+    fn test_line_chart() {
+        let svg = line_chart(
+            "Coverage",
+            "Edges",
+            &[
+                Series {
+                    label: "a",
+                    points: vec![(0.0, 1.0), (10.0, 2.0)],
+                },
+                Series {
+                    label: "b",
+                    points: vec![(0.0, 1.0), (5.0, 3.0), (20.0, 4.0)],
+                },
+            ],
+        );
+
+        assert!(svg.starts_with("<svg"));
+        assert_eq!(svg.matches("<polyline").count(), 2);
+        assert!(svg.contains(">b</text>"));
+    }
+}
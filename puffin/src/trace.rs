@@ -142,6 +142,11 @@ impl<PB: ProtocolBehavior> TraceContext<PB> {
         Ok(())
     }
 
+    /// The knowledge gathered so far, in the order in which it was extracted
+    pub fn knowledge(&self) -> &[Knowledge<PB::Matcher>] {
+        &self.knowledge
+    }
+
     pub fn add_knowledge(&mut self, knowledge: Knowledge<PB::Matcher>) {
         self.knowledge.push(knowledge)
     }
@@ -181,26 +186,35 @@ impl<PB: ProtocolBehavior> TraceContext<PB> {
         query_type_shape: TypeShape,
         query: &Query<PB::Matcher>,
     ) -> Option<&(dyn VariableData)> {
+        self.find_knowledge(query_type_shape, query)
+            .map(|(_, knowledge)| knowledge.data.as_ref())
+    }
+
+    /// Like [`TraceContext::find_variable`], but returns the whole [`Knowledge`] together with its
+    /// position in [`TraceContext::knowledge`].
+    pub fn find_knowledge(
+        &self,
+        query_type_shape: TypeShape,
+        query: &Query<PB::Matcher>,
+    ) -> Option<(usize, &Knowledge<PB::Matcher>)> {
         let query_type_id: TypeId = query_type_shape.into();
 
-        let mut possibilities: Vec<&Knowledge<PB::Matcher>> = Vec::new();
+        let mut possibilities: Vec<(usize, &Knowledge<PB::Matcher>)> = Vec::new();
 
-        for knowledge in &self.knowledge {
+        for (i, knowledge) in self.knowledge.iter().enumerate() {
             let data: &dyn VariableData = knowledge.data.as_ref();
 
             if query_type_id == data.type_id()
                 && query.agent_name == knowledge.agent_name
                 && knowledge.matcher.matches(&query.matcher)
             {
-                possibilities.push(knowledge);
+                possibilities.push((i, knowledge));
             }
         }
 
-        possibilities.sort_by_key(|a| a.specificity());
+        possibilities.sort_by_key(|(_, a)| a.specificity());
 
-        possibilities
-            .get(query.counter as usize)
-            .map(|possibility| possibility.data.as_ref())
+        possibilities.get(query.counter as usize).copied()
     }
 
     /// Adds data to the inbound [`Channel`] of the [`Agent`] referenced by the parameter "agent".
@@ -309,12 +323,68 @@ pub struct Trace<M: Matcher> {
     pub prior_traces: Vec<Trace<M>>,
 }
 
+/// Callbacks of [`Trace::execute_with`], which are called around the execution of each step
+pub trait StepHook<PB: ProtocolBehavior> {
+    /// Called before step `i` is executed.
+    fn before_step(&mut self, _i: usize, _step: &Step<PB::Matcher>, _ctx: &TraceContext<PB>) {}
+
+    /// Evaluates the recipe of the input step `i`. The returned value is sent to the agent.
+    fn evaluate(
+        &mut self,
+        _i: usize,
+        recipe: &Term<PB::Matcher>,
+        ctx: &TraceContext<PB>,
+    ) -> Result<Box<dyn Any>, Error> {
+        recipe.evaluate(ctx)
+    }
+
+    /// Called after step `i`, including the implicit output of an input step, has been executed
+    /// with `result`.
+    fn after_step(
+        &mut self,
+        _i: usize,
+        _step: &Step<PB::Matcher>,
+        _ctx: &TraceContext<PB>,
+        _result: &Result<(), Error>,
+    ) {
+    }
+}
+
+impl<PB: ProtocolBehavior> StepHook<PB> for () {}
+
+/// Executes step `i` followed by an [`OutputAction`] if it is an input step.
+fn execute_step<PB, H>(
+    i: usize,
+    step: &Step<PB::Matcher>,
+    ctx: &mut TraceContext<PB>,
+    hook: &mut H,
+) -> Result<(), Error>
+where
+    PB: ProtocolBehavior,
+    H: StepHook<PB>,
+{
+    match &step.action {
+        Action::Input(input) => {
+            let evaluated = hook.evaluate(i, &input.recipe, ctx)?;
+            input.input_evaluated(step, ctx, evaluated)?;
+
+            // Output after each InputAction step
+            let output_step = &OutputAction::<PB::Matcher>::new_step(step.agent);
+            output_step.action.execute(output_step, ctx)
+        }
+        Action::Output(_) => step.action.execute(step, ctx),
+    }
+}
+
 /// A [`Trace`] consists of several [`Step`]s. Each has either a [`OutputAction`] or an [`InputAction`].
 /// Each [`Step`]s references an [`Agent`] by name. Furthermore, a trace also has a list of
 /// *AgentDescriptors* which act like a blueprint to spawn [`Agent`]s with a corresponding server
 /// or client role and a specific TLs version. Essentially they are an [`Agent`] without a stream.
 impl<M: Matcher> Trace<M> {
-    fn spawn_agents<PB: ProtocolBehavior>(&self, ctx: &mut TraceContext<PB>) -> Result<(), Error> {
+    pub(crate) fn spawn_agents<PB: ProtocolBehavior>(
+        &self,
+        ctx: &mut TraceContext<PB>,
+    ) -> Result<(), Error> {
         for descriptor in &self.descriptors {
             let name = if let Some(reusable) = ctx
                 .agents
@@ -344,28 +414,46 @@ impl<M: Matcher> Trace<M> {
     pub fn execute<PB>(&self, ctx: &mut TraceContext<PB>) -> Result<(), Error>
     where
         PB: ProtocolBehavior<Matcher = M>,
+    {
+        self.execute_with(ctx, &mut ())
+    }
+
+    /// Executes the trace like [`Trace::execute`] and calls `hook` around each step of this
+    /// trace. The steps of the prior traces are not passed to `hook`. If a step fails, then
+    /// [`StepHook::after_step`] is still called before the error is returned.
+    pub fn execute_with<PB, H>(&self, ctx: &mut TraceContext<PB>, hook: &mut H) -> Result<(), Error>
+    where
+        PB: ProtocolBehavior<Matcher = M>,
+        H: StepHook<PB>,
     {
         for trace in &self.prior_traces {
             trace.spawn_agents(ctx)?;
             trace.execute(ctx)?;
             ctx.reset_agents()?;
         }
+        self.execute_steps_with(ctx, hook)
+    }
+
+    /// Executes only the steps of this trace and calls `hook` around each of them. The prior
+    /// traces must have been executed already.
+    pub(crate) fn execute_steps_with<PB, H>(
+        &self,
+        ctx: &mut TraceContext<PB>,
+        hook: &mut H,
+    ) -> Result<(), Error>
+    where
+        PB: ProtocolBehavior<Matcher = M>,
+        H: StepHook<PB>,
+    {
         self.spawn_agents(ctx)?;
         let steps = &self.steps;
         for (i, step) in steps.iter().enumerate() {
             debug!("Executing step #{}", i);
 
-            step.action.execute(step, ctx)?;
-
-            // Output after each InputAction step
-            match step.action {
-                Action::Input(_) => {
-                    let output_step = &OutputAction::<M>::new_step(step.agent);
-
-                    output_step.action.execute(output_step, ctx)?;
-                }
-                Action::Output(_) => {}
-            }
+            hook.before_step(i, step, ctx);
+            let result = execute_step(i, step, ctx, hook);
+            hook.after_step(i, step, ctx, &result);
+            result?;
 
             ctx.claims.deref_borrow().log();
         }
@@ -453,7 +541,11 @@ pub enum Action<M: Matcher> {
 }
 
 impl<M: Matcher> Action<M> {
-    fn execute<PB>(&self, step: &Step<M>, ctx: &mut TraceContext<PB>) -> Result<(), Error>
+    pub(crate) fn execute<PB>(
+        &self,
+        step: &Step<M>,
+        ctx: &mut TraceContext<PB>,
+    ) -> Result<(), Error>
     where
         PB: ProtocolBehavior<Matcher = M>,
     {
@@ -553,8 +645,9 @@ pub struct InputAction<M: Matcher> {
     pub recipe: Term<M>,
 }
 
-/// Processes messages in the inbound channel. Uses the recipe field to evaluate to a rustls Message
-/// or a MultiMessage.
+/// Processes messages in the inbound channel. Uses the recipe field to evaluate to a rustls Message,
+/// an OpaqueMessage or a sequence of OpaqueMessages. A sequence is added record by record to the
+/// inbound channel before the state machine is driven forward.
 impl<M: Matcher> InputAction<M> {
     pub fn new_step(agent: AgentName, recipe: Term<M>) -> Step<M> {
         Step {
@@ -573,7 +666,19 @@ impl<M: Matcher> InputAction<M> {
     {
         // message controlled by the attacker
         let evaluated = self.recipe.evaluate(ctx)?;
+        self.input_evaluated(step, ctx, evaluated)
+    }
 
+    /// Sends the value `evaluated` of the recipe to the agent of the step.
+    fn input_evaluated<PB: ProtocolBehavior>(
+        &self,
+        step: &Step<M>,
+        ctx: &mut TraceContext<PB>,
+        evaluated: Box<dyn Any>,
+    ) -> Result<(), Error>
+    where
+        PB: ProtocolBehavior<Matcher = M>,
+    {
         if let Some(msg) = evaluated.as_ref().downcast_ref::<PB::ProtocolMessage>() {
             msg.debug("Input message");
 
@@ -584,9 +689,17 @@ impl<M: Matcher> InputAction<M> {
         {
             opaque_message.debug("Input opaque message");
             ctx.add_to_inbound(step.agent, opaque_message)?;
+        } else if let Some(opaque_messages) = evaluated
+            .as_ref()
+            .downcast_ref::<Vec<PB::OpaqueProtocolMessage>>()
+        {
+            for opaque_message in opaque_messages {
+                opaque_message.debug("Input opaque message");
+                ctx.add_to_inbound(step.agent, opaque_message)?;
+            }
         } else {
             return Err(FnError::Unknown(String::from(
-                "Recipe is not a `Message`, `OpaqueMessage` or `MultiMessage`!",
+                "Recipe is not a `Message`, `OpaqueMessage` or a sequence of `OpaqueMessage`s!",
             ))
             .into());
         }
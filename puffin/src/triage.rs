@@ -0,0 +1,429 @@
+//! Triage of objectives. Each objective is re-executed in a separate process using the `execute`
+//! sub-command. The output of the process is used to classify the objective and to group it into
+//! a bucket, such that objectives with the same root cause end up in the same bucket.
+
+use std::{
+    collections::{hash_map::DefaultHasher, BTreeMap},
+    env,
+    ffi::OsStr,
+    fmt, fs,
+    fs::File,
+    hash::{Hash, Hasher},
+    io::Write,
+    os::unix::process::ExitStatusExt,
+    path::{Path, PathBuf},
+    process::{Command, Stdio},
+    thread,
+    time::{Duration, Instant},
+};
+
+use log::{info, warn};
+
+/// Frames which belong to the sanitizer runtime or libc and do not tell anything about the bug.
+const IGNORED_FRAMES: &[&str] = &[
+    "__asan",
+    "__interceptor_",
+    "__sanitizer",
+    "__ubsan",
+    "__msan",
+    "__lsan",
+    "abort",
+    "raise",
+    "gsignal",
+    "__libc_start",
+    "_start",
+];
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum ObjectiveKind {
+    /// A sanitizer printed a report
+    Sanitizer,
+    /// The trace violated the security policy
+    SecurityViolation,
+    /// The execution did not finish within the timeout
+    Timeout,
+    /// The process died because of a signal without a sanitizer report
+    Crash,
+    /// The objective no longer triggers when executed on its own
+    NotReproducible,
+}
+
+impl fmt::Display for ObjectiveKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            ObjectiveKind::Sanitizer => "sanitizer",
+            ObjectiveKind::SecurityViolation => "violation",
+            ObjectiveKind::Timeout => "timeout",
+            ObjectiveKind::Crash => "crash",
+            ObjectiveKind::NotReproducible => "not-reproducible",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// Classification of a single execution of an objective
+#[derive(Debug, Clone)]
+pub struct Classification {
+    pub kind: ObjectiveKind,
+    /// Human readable summary, like the first line of a sanitizer report
+    pub description: String,
+    /// The top frames of the stack which are used to compute the bucket
+    pub frames: Vec<String>,
+}
+
+impl Classification {
+    /// Hash of the kind, the description and the top stack frames. For sanitizer reports only the
+    /// frames are hashed, because the description contains addresses.
+    pub fn bucket(&self) -> String {
+        let mut hasher = DefaultHasher::new();
+        self.kind.hash(&mut hasher);
+        if self.kind == ObjectiveKind::Sanitizer && !self.frames.is_empty() {
+            self.frames.hash(&mut hasher);
+        } else {
+            self.description.hash(&mut hasher);
+        }
+        format!("{}-{:016x}", self.kind, hasher.finish())
+    }
+}
+
+/// Prefix of the line which the `execute` sub-command logs if the trace violates the security
+/// policy. The message of the violation follows the prefix.
+pub const VIOLATION_MARKER: &str = "Security violation: ";
+
+/// Extracts the message of a security violation from the output of the `execute` sub-command.
+fn parse_violation(output: &str) -> Option<String> {
+    output.lines().find_map(|line| {
+        let start = line.find(VIOLATION_MARKER)? + VIOLATION_MARKER.len();
+        Some(line[start..].trim_end().to_string())
+    })
+}
+
+const UBSAN_MARKER: &str = "runtime error: ";
+
+/// Extracts the description and the top `depth` function names of the first stack in a sanitizer
+/// report. Function names are used instead of addresses, because they are stable across builds and
+/// ASLR.
+fn parse_sanitizer_report(output: &str, depth: usize) -> Option<(String, Vec<String>)> {
+    let mut lines = output.lines();
+
+    // ASAN and LSAN report an ERROR, MSAN a WARNING and UBSAN a "runtime error" prefixed by the
+    // source location
+    let error_line = lines.find(|line| {
+        (line.contains("Sanitizer:") && (line.contains("ERROR") || line.contains("WARNING")))
+            || line.contains(UBSAN_MARKER)
+    })?;
+    let description = error_line
+        .split_once("Sanitizer: ")
+        .or_else(|| error_line.split_once(UBSAN_MARKER))
+        .map_or(error_line, |(_, description)| description)
+        .trim()
+        .to_string();
+
+    let mut frames = Vec::new();
+    let mut in_stack = false;
+
+    for line in lines {
+        let line = line.trim();
+        if !line.starts_with('#') {
+            if in_stack {
+                // Only the first stack is relevant. Further stacks describe allocations.
+                break;
+            }
+            continue;
+        }
+
+        in_stack = true;
+
+        // Frames look like "#0 0x55d0f1 in function_name /path/to/file.c:12:3"
+        let function = line
+            .split_once(" in ")
+            .and_then(|(_, rest)| rest.split_whitespace().next());
+
+        if let Some(function) = function {
+            if IGNORED_FRAMES
+                .iter()
+                .any(|ignored| function.starts_with(ignored))
+            {
+                continue;
+            }
+
+            frames.push(function.to_string());
+            if frames.len() >= depth {
+                break;
+            }
+        }
+    }
+
+    Some((description, frames))
+}
+
+/// Runs the `execute` sub-command of the current executable on the `trace`. The output of the
+/// process is written to `log`. Additional environment variables for the process can be passed in
+/// `envs`. Returns the exit code, the signal which terminated the process and whether the process
+/// timed out.
+pub(crate) fn execute_isolated(
+    trace: &Path,
+    log: &Path,
+    timeout: Duration,
+    envs: &[(&str, &OsStr)],
+) -> Result<(Option<i32>, Option<i32>, bool), Box<dyn std::error::Error>> {
+    let log_file = File::create(log)?;
+
+    let mut child = Command::new(env::current_exe()?)
+        .arg("execute")
+        .arg(trace)
+        .envs(envs.iter().copied())
+        .env(
+            "ASAN_OPTIONS",
+            format!(
+                "{}:detect_leaks=0",
+                env::var("ASAN_OPTIONS").unwrap_or_default()
+            ),
+        )
+        .stdin(Stdio::null())
+        .stdout(log_file.try_clone()?)
+        .stderr(log_file)
+        .spawn()?;
+
+    let start = Instant::now();
+    loop {
+        if let Some(status) = child.try_wait()? {
+            return Ok((status.code(), status.signal(), false));
+        }
+
+        if start.elapsed() > timeout {
+            child.kill()?;
+            child.wait()?;
+            return Ok((None, None, true));
+        }
+
+        thread::sleep(Duration::from_millis(10));
+    }
+}
+
+fn classify(
+    output: &str,
+    code: Option<i32>,
+    signal: Option<i32>,
+    timed_out: bool,
+    depth: usize,
+) -> Classification {
+    if let Some((description, frames)) = parse_sanitizer_report(output, depth) {
+        return Classification {
+            kind: ObjectiveKind::Sanitizer,
+            description,
+            frames,
+        };
+    }
+
+    if let Some(message) = parse_violation(output) {
+        return Classification {
+            kind: ObjectiveKind::SecurityViolation,
+            description: message,
+            frames: vec![],
+        };
+    }
+
+    if timed_out {
+        return Classification {
+            kind: ObjectiveKind::Timeout,
+            description: "execution timed out".to_string(),
+            frames: vec![],
+        };
+    }
+
+    match signal {
+        Some(signal) => Classification {
+            kind: ObjectiveKind::Crash,
+            description: format!("killed by signal {}", signal),
+            frames: vec![],
+        },
+        None => Classification {
+            kind: ObjectiveKind::NotReproducible,
+            description: format!("exited with code {}", code.unwrap_or(-1)),
+            frames: vec![],
+        },
+    }
+}
+
+/// Collects all traces in `dir` and its subdirectories. Objectives of different kinds may be
+/// stored in separate subdirectories.
+pub(crate) fn collect_traces(dir: &Path, traces: &mut Vec<PathBuf>) -> Result<(), std::io::Error> {
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            collect_traces(&path, traces)?;
+        } else if path
+            .extension()
+            .map_or(false, |extension| extension == "trace")
+        {
+            traces.push(path);
+        }
+    }
+    Ok(())
+}
+
+struct Bucket {
+    classification: Classification,
+    representative: PathBuf,
+    representative_size: u64,
+    members: Vec<PathBuf>,
+}
+
+/// Re-executes every objective in `input_dir`, groups them by bucket and writes a summary together
+/// with a representative trace for each bucket to `output_dir`.
+pub fn triage(
+    input_dir: &Path,
+    output_dir: &Path,
+    depth: usize,
+    timeout: Duration,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut traces = Vec::new();
+    collect_traces(input_dir, &mut traces)?;
+    traces.sort();
+    info!("Triaging {} objectives from {:?}", traces.len(), input_dir);
+
+    let logs_dir = output_dir.join("logs");
+    fs::create_dir_all(&logs_dir)?;
+
+    let mut buckets: BTreeMap<String, Bucket> = BTreeMap::new();
+
+    for (i, trace) in traces.iter().enumerate() {
+        let log = logs_dir.join(format!("{}.log", i));
+        let (code, signal, timed_out) = match execute_isolated(trace, &log, timeout, &[]) {
+            Ok(result) => result,
+            Err(err) => {
+                warn!("Failed to execute {:?}: {}", trace, err);
+                continue;
+            }
+        };
+
+        let output = String::from_utf8_lossy(&fs::read(&log)?).to_string();
+        let classification = classify(&output, code, signal, timed_out, depth);
+        let size = fs::metadata(trace)?.len();
+        let id = classification.bucket();
+
+        info!("{:?}: {} ({})", trace, id, classification.description);
+
+        let bucket = buckets.entry(id).or_insert_with(|| Bucket {
+            classification,
+            representative: trace.clone(),
+            representative_size: size,
+            members: vec![],
+        });
+
+        // The smallest trace represents the bucket
+        if size < bucket.representative_size {
+            bucket.representative = trace.clone();
+            bucket.representative_size = size;
+        }
+        bucket.members.push(trace.clone());
+    }
+
+    let mut summary = File::create(output_dir.join("summary.md"))?;
+    writeln!(
+        summary,
+        "| Bucket | Kind | Count | Description | Top Frames | Representative |"
+    )?;
+    writeln!(summary, "|---|---|---|---|---|---|")?;
+
+    for (id, bucket) in &buckets {
+        let representative = format!("{}.trace", id);
+        fs::copy(&bucket.representative, output_dir.join(&representative))?;
+
+        writeln!(
+            summary,
+            "| {} | {} | {} | {} | {} | [{}](./{}) |",
+            id,
+            bucket.classification.kind,
+            bucket.members.len(),
+            bucket.classification.description.replace('|', "\\|"),
+            bucket.classification.frames.join(" < "),
+            bucket.representative.display(),
+            representative,
+        )?;
+    }
+
+    info!(
+        "Sorted {} objectives into {} buckets. Summary written to {:?}",
+        traces.len(),
+        buckets.len(),
+        output_dir.join("summary.md")
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        classify, parse_sanitizer_report, parse_violation, ObjectiveKind, VIOLATION_MARKER,
+    };
+
+    const REPORT: &str = "=================================================================
+==1234==ERROR: AddressSanitizer: heap-buffer-overflow on address 0x602000000011 at pc 0x55d0f1
+READ of size 1 at 0x602000000011 thread T0
+    #0 0x55d0f1 in __asan_memcpy (/tlspuffin+0x10)
+    #1 0x55d0f2 in tls_parse_ctos_key_share /openssl/ssl/statem/extensions_srvr.c:644:9
+    #2 0x55d0f3 in tls_parse_all_extensions /openssl/ssl/statem/extensions.c:752:14
+    #3 0x55d0f4 in tls_process_client_hello /openssl/ssl/statem/statem_srvr.c:1600:5
+
+0x602000000011 is located 0 bytes to the right of 1-byte region
+allocated by thread T0 here:
+    #0 0x55d0f5 in malloc (/tlspuffin+0x11)
+";
+
+    #[test]
+    fn test_parse_sanitizer_report() {
+        let (description, frames) = parse_sanitizer_report(REPORT, 2).unwrap();
+        assert!(description.starts_with("heap-buffer-overflow"));
+        assert_eq!(
+            frames,
+            vec!["tls_parse_ctos_key_share", "tls_parse_all_extensions"]
+        );
+    }
+
+    #[test]
+    fn test_parse_ubsan_and_msan_report() {
+        let ubsan = "/openssl/crypto/bn/bn_lib.c:12:3: runtime error: signed integer overflow: 2147483647 + 1 cannot be represented in type 'int'
+    #0 0x55d0f1 in BN_num_bits /openssl/crypto/bn/bn_lib.c:12:3
+    #1 0x55d0f2 in tls_construct_server_key_exchange /openssl/ssl/statem/statem_srvr.c:2500:9
+";
+        let (description, frames) = parse_sanitizer_report(ubsan, 1).unwrap();
+        assert!(description.starts_with("signed integer overflow"));
+        assert_eq!(frames, vec!["BN_num_bits"]);
+
+        let msan = "==1234==WARNING: MemorySanitizer: use-of-uninitialized-value
+    #0 0x55d0f1 in __msan_warning (/tlspuffin+0x10)
+    #1 0x55d0f2 in ssl3_get_record /openssl/ssl/record/ssl3_record.c:300:9
+";
+        let (description, frames) = parse_sanitizer_report(msan, 1).unwrap();
+        assert_eq!(description, "use-of-uninitialized-value");
+        assert_eq!(frames, vec!["ssl3_get_record"]);
+    }
+
+    #[test]
+    fn test_same_stack_same_bucket() {
+        let other = REPORT.replace("0x602000000011", "0x602000000042");
+        let a = classify(REPORT, None, Some(6), false, 3);
+        let b = classify(&other, None, Some(6), false, 3);
+        assert_eq!(a.kind, ObjectiveKind::Sanitizer);
+        assert_eq!(a.bucket(), b.bucket());
+    }
+
+    #[test]
+    fn test_parse_violation() {
+        let output = format!(
+            "2022-07-28T12:00:00Z\t{}Mismatching master secrets\n\
+            2022-07-28T12:00:00Z\tFailed to execute trace: SecurityClaim(\"Mismatching master secrets\")",
+            VIOLATION_MARKER
+        );
+        assert_eq!(
+            parse_violation(&output),
+            Some("Mismatching master secrets".to_string())
+        );
+        assert_eq!(
+            classify(&output, Some(1), None, false, 3).kind,
+            ObjectiveKind::SecurityViolation
+        );
+    }
+}
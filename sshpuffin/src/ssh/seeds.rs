@@ -18,6 +18,9 @@ pub fn seed_successful(client: AgentName, server: AgentName) -> Trace<AnyMatcher
                 try_reuse: false,             // FIXME: Remove?
                 client_authentication: false, // FIXME: Remove?
                 server_authentication: false, // FIXME: Remove?
+                early_data: false,            // FIXME: Remove?
+                ech: false,                   // FIXME: Remove?
+                ffdhe: false,                 // FIXME: Remove?
             },
             AgentDescriptor {
                 name: server,
@@ -26,6 +29,9 @@ pub fn seed_successful(client: AgentName, server: AgentName) -> Trace<AnyMatcher
                 try_reuse: false,             // FIXME: Remove?
                 client_authentication: false, // FIXME: Remove?
                 server_authentication: false, // FIXME: Remove?
+                early_data: false,            // FIXME: Remove?
+                ech: false,                   // FIXME: Remove?
+                ffdhe: false,                 // FIXME: Remove?
             },
         ],
         steps: vec![
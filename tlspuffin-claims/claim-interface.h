@@ -9,6 +9,9 @@ typedef enum ClaimTLSVersion {
     CLAIM_TLS_VERSION_UNDEFINED = 0,
     CLAIM_TLS_VERSION_V1_2 = 1,
     CLAIM_TLS_VERSION_V1_3 = 2,
+    CLAIM_TLS_VERSION_V1_1 = 3,
+    CLAIM_TLS_VERSION_V1_0 = 4,
+    CLAIM_TLS_VERSION_SSL3 = 5,
 } TLSVersion;
 
 typedef enum ClaimType {
@@ -152,6 +155,16 @@ typedef struct Claim {
 
     // Transcript
     ClaimTranscript transcript;
+
+    // The fields below are appended, such that the layout of the fields above stays compatible
+    // with forks which do not set them yet.
+
+    // Whether the master secret is an extended master secret (RFC 7627)
+    int extended_master_secret;
+    // Whether the handshake renegotiates an established connection
+    int renegotiation;
+    // Whether the renegotiation_info extension (RFC 5746) has been negotiated
+    int secure_renegotiation;
 } Claim;
 
 typedef void (*claim_t)(Claim claim, void *ctx);
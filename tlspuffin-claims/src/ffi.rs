@@ -47,6 +47,9 @@ impl fmt::Display for Claim {
             available_ciphers: {},\
             chosen_cipher: {},\
             transcript: {},\
+            extended_master_secret: {},\
+            renegotiation: {},\
+            secure_renegotiation: {},\
             ",
             self.typ,
             self.write,
@@ -79,6 +82,9 @@ impl fmt::Display for Claim {
             self.available_ciphers,
             self.chosen_cipher,
             self.transcript,
+            self.extended_master_secret,
+            self.renegotiation,
+            self.secure_renegotiation,
         )
     }
 }
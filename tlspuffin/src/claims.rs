@@ -79,6 +79,10 @@ pub struct Finished {
     pub client_random: SmallVec<[u8; 32]>,
     pub server_random: SmallVec<[u8; 32]>,
     pub session_id: SmallVec<[u8; 32]>,
+    /// The version which has been negotiated with the peer. This can be lower than the version of
+    /// the [`AgentDescriptor`](puffin::agent::AgentDescriptor), which is the highest version an
+    /// agent supports.
+    pub negotiated_version: Option<TLSVersion>,
 
     pub authenticate_peer: bool,
     /// DER encoded certificate. DER works, because:
@@ -87,6 +91,15 @@ pub struct Finished {
     pub peer_certificate: SmallVec<[u8; 32]>,
 
     pub master_secret: SmallVec<[u8; 32]>,
+    /// Whether the TLS 1.2 master secret is derived from the session hash
+    /// (<https://datatracker.ietf.org/doc/html/rfc7627>). `None` if the PUT does not report it.
+    pub extended_master_secret: Option<bool>,
+    /// Whether the handshake renegotiates an established TLS 1.2 connection
+    pub renegotiation: bool,
+    /// Whether the connection is bound to the previous handshake through the `renegotiation_info`
+    /// extension (<https://datatracker.ietf.org/doc/html/rfc5746>). `None` if the PUT does not
+    /// report it.
+    pub secure_renegotiation: Option<bool>,
 
     pub chosen_cipher: u16,
     pub available_ciphers: SmallVec<[u16; 20]>,
@@ -119,6 +132,15 @@ pub struct Finished {
     */
 }
 
+/// Early data which was accepted by a server
+#[derive(Debug, Clone)]
+pub struct EarlyData {
+    pub outbound: bool,
+
+    pub client_random: SmallVec<[u8; 32]>,
+    pub early_secret: SmallVec<[u8; 32]>,
+}
+
 #[derive(Debug, Clone)]
 pub enum ClaimDataTranscript {
     ClientHello(TranscriptClientHello),
@@ -136,6 +158,7 @@ pub enum ClaimDataMessage {
     Certificate(Certificate),
     CertificateVerify(CertificateVerify),
     Finished(Finished),
+    EarlyData(EarlyData),
 }
 
 #[derive(Debug, Clone)]
@@ -168,6 +191,7 @@ impl Claim for TlsClaim {
                 Message::Certificate(_) => Type::of::<Certificate>(),
                 Message::CertificateVerify(_) => Type::of::<CertificateVerify>(),
                 Message::Finished(_) => Type::of::<Finished>(),
+                Message::EarlyData(_) => Type::of::<EarlyData>(),
             },
             ClaimData::Transcript(transcript) => match transcript {
                 Transcript::ClientHello(_) => Type::of::<TranscriptClientHello>(),
@@ -191,6 +215,7 @@ impl Claim for TlsClaim {
                 Message::Certificate(claim) => claim.boxed_any(),
                 Message::CertificateVerify(claim) => claim.boxed_any(),
                 Message::Finished(claim) => claim.boxed_any(),
+                Message::EarlyData(claim) => claim.boxed_any(),
             },
             ClaimData::Transcript(transcript) => match transcript {
                 Transcript::ClientHello(claim) => claim.boxed_any(),
@@ -102,6 +102,21 @@
 //! ```
 //!
 
+// The openssl-src fork does not instrument OpenSSL with UBSAN, MSAN or coverage, only LibreSSL
+// through libressl-src
+#[cfg(all(
+    feature = "coverage",
+    feature = "openssl-src",
+    not(feature = "libressl-src")
+))]
+compile_error!("The coverage feature requires a LibreSSL or wolfSSL PUT.");
+#[cfg(all(
+    any(feature = "ubsan", feature = "msan"),
+    feature = "openssl-src",
+    not(feature = "libressl-src")
+))]
+compile_error!("The ubsan and msan features require a LibreSSL or wolfSSL PUT.");
+
 pub mod claims;
 pub mod debug;
 #[cfg(feature = "openssl-binding")]
@@ -8,6 +8,7 @@ use std::{
 };
 
 use openssl::{
+    dh::Dh,
     error::ErrorStack,
     pkey::{PKeyRef, Private},
     ssl::{Ssl, SslContext, SslMethod, SslOptions, SslStream, SslVerifyMode},
@@ -30,15 +31,18 @@ use smallvec::SmallVec;
 
 use crate::{
     claims::{
-        ClaimData, ClaimDataMessage, ClaimDataTranscript, ClientHello, Finished, TlsClaim,
-        TlsTranscript, TranscriptCertificate, TranscriptClientFinished, TranscriptClientHello,
-        TranscriptPartialClientHello, TranscriptServerFinished, TranscriptServerHello,
+        ClaimData, ClaimDataMessage, ClaimDataTranscript, ClientHello, EarlyData, Finished,
+        TlsClaim, TlsTranscript, TranscriptCertificate, TranscriptClientFinished,
+        TranscriptClientHello, TranscriptPartialClientHello, TranscriptServerFinished,
+        TranscriptServerHello,
     },
     openssl::util::{set_max_protocol_version, static_rsa_cert},
     protocol::TLSProtocolBehavior,
     put::TlsPutConfig,
     put_registry::OPENSSL111_PUT,
-    static_certs::{ALICE_CERT, ALICE_PRIVATE_KEY, BOB_CERT, BOB_PRIVATE_KEY, EVE_CERT},
+    static_certs::{
+        ALICE_CERT, ALICE_PRIVATE_KEY, BOB_CERT, BOB_PRIVATE_KEY, EVE_CERT, FFDHE2048_PARAMS,
+    },
     tls::rustls::msgs::{
         deframer::MessageDeframer,
         message::{Message, OpaqueMessage},
@@ -95,6 +99,9 @@ pub fn new_openssl_factory() -> Box<dyn Factory<TLSProtocolBehavior>> {
 pub struct OpenSSL {
     stream: SslStream<MemoryStream<MessageDeframer>>,
     config: TlsPutConfig,
+    /// Whether the server still expects early data before the handshake continues
+    #[cfg(feature = "openssl111-binding")]
+    reading_early_data: bool,
 }
 
 impl Drop for OpenSSL {
@@ -122,6 +129,17 @@ impl Stream<Message, OpaqueMessage> for OpenSSL {
     }
 }
 
+fn to_tls_version(version: security_claims::ClaimTLSVersion) -> Option<TLSVersion> {
+    match version {
+        security_claims::ClaimTLSVersion::CLAIM_TLS_VERSION_UNDEFINED => None,
+        security_claims::ClaimTLSVersion::CLAIM_TLS_VERSION_V1_3 => Some(TLSVersion::V1_3),
+        security_claims::ClaimTLSVersion::CLAIM_TLS_VERSION_V1_2 => Some(TLSVersion::V1_2),
+        security_claims::ClaimTLSVersion::CLAIM_TLS_VERSION_V1_1 => Some(TLSVersion::V1_1),
+        security_claims::ClaimTLSVersion::CLAIM_TLS_VERSION_V1_0 => Some(TLSVersion::V1_0),
+        security_claims::ClaimTLSVersion::CLAIM_TLS_VERSION_SSL3 => Some(TLSVersion::SSL3),
+    }
+}
+
 fn to_claim_data(protocol_version: TLSVersion, claim: security_claims::Claim) -> Option<ClaimData> {
     match claim.typ {
         // Transcripts
@@ -172,12 +190,38 @@ fn to_claim_data(protocol_version: TLSVersion, claim: security_claims::Claim) ->
                 session_id: SmallVec::from_slice(
                     &claim.session_id.data[..claim.session_id.length as usize],
                 ),
+                negotiated_version: to_tls_version(claim.version.data),
                 authenticate_peer: false,             // FIXME
                 peer_certificate: Default::default(), // FIXME
                 master_secret: match protocol_version {
                     TLSVersion::V1_3 => SmallVec::from_slice(&claim.master_secret.secret),
-                    TLSVersion::V1_2 => SmallVec::from_slice(&claim.master_secret_12.secret),
+                    TLSVersion::V1_2 | TLSVersion::V1_1 | TLSVersion::V1_0 | TLSVersion::SSL3 => {
+                        SmallVec::from_slice(&claim.master_secret_12.secret)
+                    }
+                },
+                #[cfg(feature = "claims-extended-master-secret")]
+                extended_master_secret: match protocol_version {
+                    TLSVersion::V1_3 => None,
+                    TLSVersion::V1_2 | TLSVersion::V1_1 | TLSVersion::V1_0 | TLSVersion::SSL3 => {
+                        Some(claim.extended_master_secret > 0)
+                    }
+                },
+                // Forks which do not set the field pass claims which end before it
+                #[cfg(not(feature = "claims-extended-master-secret"))]
+                extended_master_secret: None,
+                #[cfg(feature = "claims-renegotiation")]
+                renegotiation: claim.renegotiation > 0,
+                #[cfg(feature = "claims-renegotiation")]
+                secure_renegotiation: match protocol_version {
+                    TLSVersion::V1_3 => None,
+                    TLSVersion::V1_2 | TLSVersion::V1_1 | TLSVersion::V1_0 | TLSVersion::SSL3 => {
+                        Some(claim.secure_renegotiation > 0)
+                    }
                 },
+                #[cfg(not(feature = "claims-renegotiation"))]
+                renegotiation: false,
+                #[cfg(not(feature = "claims-renegotiation"))]
+                secure_renegotiation: None,
                 chosen_cipher: claim.chosen_cipher.data,
                 available_ciphers: SmallVec::from_iter(
                     claim.available_ciphers.ciphers[..claim.available_ciphers.length as usize]
@@ -213,7 +257,13 @@ fn to_claim_data(protocol_version: TLSVersion, claim: security_claims::Claim) ->
         security_claims::ClaimType::CLAIM_SERVER_DONE => None,
         security_claims::ClaimType::CLAIM_SESSION_TICKET => None,
         security_claims::ClaimType::CLAIM_CERTIFICATE_STATUS => None,
-        security_claims::ClaimType::CLAIM_EARLY_DATA => None,
+        security_claims::ClaimType::CLAIM_EARLY_DATA => {
+            Some(ClaimData::Message(ClaimDataMessage::EarlyData(EarlyData {
+                outbound: claim.write > 0,
+                client_random: SmallVec::from(claim.client_random.data),
+                early_secret: SmallVec::from_slice(&claim.early_secret.secret),
+            })))
+        }
         security_claims::ClaimType::CLAIM_ENCRYPTED_EXTENSIONS => None,
         _ => None,
     }
@@ -221,6 +271,25 @@ fn to_claim_data(protocol_version: TLSVersion, claim: security_claims::Claim) ->
 
 impl Put<TLSProtocolBehavior> for OpenSSL {
     fn progress(&mut self, _agent_name: &AgentName) -> Result<(), Error> {
+        #[cfg(feature = "openssl111-binding")]
+        if self.reading_early_data {
+            // OpenSSL only accepts early data if it is read before the handshake continues
+            let mut vec: Vec<u8> = Vec::from([1; 128]);
+            loop {
+                match self.stream.read_early_data(&mut vec) {
+                    Ok(0) => {
+                        self.reading_early_data = false;
+                        break;
+                    }
+                    Ok(_) => {}
+                    Err(err) => {
+                        let maybe_error: MaybeError = Err::<usize, _>(err).into();
+                        return maybe_error.into();
+                    }
+                }
+            }
+        }
+
         let result = if self.is_state_successful() {
             // Trigger another read
             let mut vec: Vec<u8> = Vec::from([1; 128]);
@@ -236,6 +305,10 @@ impl Put<TLSProtocolBehavior> for OpenSSL {
 
     fn reset(&mut self, agent_name: AgentName) -> Result<(), Error> {
         bindings::clear(self.stream.ssl()); // FIXME: Add non-clear method like in wolfssl
+        #[cfg(feature = "openssl111-binding")]
+        {
+            self.reading_early_data = Self::reads_early_data(&self.config.descriptor);
+        }
 
         Ok(())
     }
@@ -337,7 +410,12 @@ impl OpenSSL {
         #[cfg(feature = "claims")]
         let agent_name = agent_descriptor.name;
 
-        let mut openssl = OpenSSL { config, stream };
+        let mut openssl = OpenSSL {
+            #[cfg(feature = "openssl111-binding")]
+            reading_early_data: Self::reads_early_data(&config.descriptor),
+            config,
+            stream,
+        };
 
         #[cfg(feature = "claims")]
         openssl.register_claimer(agent_name);
@@ -345,6 +423,11 @@ impl OpenSSL {
         Ok(openssl)
     }
 
+    #[cfg(feature = "openssl111-binding")]
+    fn reads_early_data(descriptor: &AgentDescriptor) -> bool {
+        descriptor.typ == AgentType::Server && descriptor.early_data
+    }
+
     fn create_server(descriptor: &AgentDescriptor) -> Result<Ssl, ErrorStack> {
         let mut ctx_builder = SslContext::builder(SslMethod::tls())?;
 
@@ -370,6 +453,11 @@ impl OpenSSL {
         #[cfg(feature = "openssl111-binding")]
         bindings::set_allow_no_dhe_kex(&mut ctx_builder);
 
+        #[cfg(feature = "openssl111-binding")]
+        if descriptor.early_data {
+            ctx_builder.set_max_early_data(16384)?;
+        }
+
         set_max_protocol_version(&mut ctx_builder, descriptor.tls_version)?;
 
         #[cfg(any(feature = "openssl101-binding", feature = "openssl102-binding"))]
@@ -381,6 +469,10 @@ impl OpenSSL {
             bindings::set_tmp_rsa(&ctx_builder, &openssl::rsa::Rsa::generate(512)?)?;
         }
 
+        if descriptor.ffdhe {
+            ctx_builder.set_tmp_dh(&Dh::params_from_pem(FFDHE2048_PARAMS.0.as_bytes())?)?;
+        }
+
         // Allow EXPORT in server
         ctx_builder.set_cipher_list("ALL:EXPORT:!LOW:!aNULL:!eNULL:!SSLv2")?;
 
@@ -83,7 +83,26 @@ pub fn set_max_protocol_version(
             Ok(())
         }
         TLSVersion::V1_2 => ctx_builder.set_max_proto_version(Some(SslVersion::TLS1_2)),
+        TLSVersion::V1_1 => ctx_builder.set_max_proto_version(Some(SslVersion::TLS1_1)),
+        TLSVersion::V1_0 => ctx_builder.set_max_proto_version(Some(SslVersion::TLS1)),
+        // SSL 3.0 is only available if OpenSSL has been configured with `enable-ssl3`
+        TLSVersion::SSL3 => ctx_builder.set_max_proto_version(Some(SslVersion::SSL3)),
     }?;
 
+    // Old OpenSSL versions negotiate the highest version which is not disabled
+    #[cfg(not(any(feature = "openssl111-binding", feature = "libressl")))]
+    {
+        use openssl::ssl::SslOptions;
+
+        ctx_builder.set_options(match tls_version {
+            TLSVersion::V1_3 | TLSVersion::V1_2 => SslOptions::empty(),
+            TLSVersion::V1_1 => SslOptions::NO_TLSV1_2,
+            TLSVersion::V1_0 => SslOptions::NO_TLSV1_2 | SslOptions::NO_TLSV1_1,
+            TLSVersion::SSL3 => {
+                SslOptions::NO_TLSV1_2 | SslOptions::NO_TLSV1_1 | SslOptions::NO_TLSV1
+            }
+        });
+    }
+
     Ok(())
 }
@@ -577,6 +577,18 @@ pub mod tcp_puts {
                 args.push("-v");
                 args.push("3");
             }
+            TLSVersion::V1_1 => {
+                args.push("-v");
+                args.push("2");
+            }
+            TLSVersion::V1_0 => {
+                args.push("-v");
+                args.push("1");
+            }
+            TLSVersion::SSL3 => {
+                args.push("-v");
+                args.push("0");
+            }
         }
 
         let warmups = warmups.map(|warmups| warmups.to_string());
@@ -612,6 +624,18 @@ pub mod tcp_puts {
                 args.push("-v");
                 args.push("3");
             }
+            TLSVersion::V1_1 => {
+                args.push("-v");
+                args.push("2");
+            }
+            TLSVersion::V1_0 => {
+                args.push("-v");
+                args.push("1");
+            }
+            TLSVersion::SSL3 => {
+                args.push("-v");
+                args.push("0");
+            }
         }
 
         ParametersGuard {
@@ -646,6 +670,15 @@ pub mod tcp_puts {
             TLSVersion::V1_2 => {
                 args.push("-tls1_2");
             }
+            TLSVersion::V1_1 => {
+                args.push("-tls1_1");
+            }
+            TLSVersion::V1_0 => {
+                args.push("-tls1");
+            }
+            TLSVersion::SSL3 => {
+                args.push("-ssl3");
+            }
         }
 
         ParametersGuard {
@@ -668,6 +701,15 @@ pub mod tcp_puts {
             TLSVersion::V1_2 => {
                 args.push("-tls1_2");
             }
+            TLSVersion::V1_1 => {
+                args.push("-tls1_1");
+            }
+            TLSVersion::V1_0 => {
+                args.push("-tls1");
+            }
+            TLSVersion::SSL3 => {
+                args.push("-ssl3");
+            }
         }
 
         ParametersGuard {
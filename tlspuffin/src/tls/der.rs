@@ -0,0 +1,116 @@
+//! Minimal reading and writing of DER encoded ASN.1 elements
+//! (<https://www.itu.int/rec/T-REC-X.690>).
+//!
+//! This covers what the attacker needs for certificates and keys and is not a general ASN.1
+//! implementation.
+
+pub const DER_BOOLEAN: u8 = 0x01;
+pub const DER_INTEGER: u8 = 0x02;
+pub const DER_BIT_STRING: u8 = 0x03;
+pub const DER_OCTET_STRING: u8 = 0x04;
+pub const DER_NULL: u8 = 0x05;
+pub const DER_OID: u8 = 0x06;
+pub const DER_UTF8_STRING: u8 = 0x0c;
+pub const DER_PRINTABLE_STRING: u8 = 0x13;
+pub const DER_UTC_TIME: u8 = 0x17;
+pub const DER_GENERALIZED_TIME: u8 = 0x18;
+pub const DER_SEQUENCE: u8 = 0x30;
+pub const DER_SET: u8 = 0x31;
+pub const DER_CONTEXT_0: u8 = 0xa0;
+pub const DER_CONTEXT_3: u8 = 0xa3;
+
+/// Reads a single DER element and returns its tag, its contents and the remaining input.
+pub fn der_read(input: &[u8]) -> Option<(u8, &[u8], &[u8])> {
+    let (&tag, rest) = input.split_first()?;
+    let (&first, rest) = rest.split_first()?;
+
+    let (len, rest) = if first & 0x80 == 0 {
+        (first as usize, rest)
+    } else {
+        let len_len = (first & 0x7f) as usize;
+        if len_len == 0 || len_len > 4 || rest.len() < len_len {
+            return None;
+        }
+        let (len_bytes, rest) = rest.split_at(len_len);
+        let len = len_bytes
+            .iter()
+            .fold(0usize, |len, byte| (len << 8) | *byte as usize);
+        (len, rest)
+    };
+
+    if rest.len() < len {
+        return None;
+    }
+    let (contents, rest) = rest.split_at(len);
+    Some((tag, contents, rest))
+}
+
+/// Reads a single DER element with the `expected` tag and returns its contents and the remaining
+/// input.
+pub fn der_expect(input: &[u8], expected: u8) -> Option<(&[u8], &[u8])> {
+    let (tag, contents, rest) = der_read(input)?;
+    if tag != expected {
+        return None;
+    }
+    Some((contents, rest))
+}
+
+/// Reads a single DER element and returns it including tag and length
+pub fn der_element(input: &[u8]) -> Option<(&[u8], &[u8])> {
+    let (_, _, rest) = der_read(input)?;
+    Some((&input[..input.len() - rest.len()], rest))
+}
+
+pub fn der_encode(tag: u8, contents: &[u8]) -> Vec<u8> {
+    let mut encoded = vec![tag];
+
+    let len = contents.len();
+    if len < 0x80 {
+        encoded.push(len as u8);
+    } else {
+        let len_bytes = len.to_be_bytes();
+        let skip = len_bytes.iter().take_while(|byte| **byte == 0).count();
+        encoded.push(0x80 | (len_bytes.len() - skip) as u8);
+        encoded.extend_from_slice(&len_bytes[skip..]);
+    }
+
+    encoded.extend_from_slice(contents);
+    encoded
+}
+
+/// Minimal encoding of a positive INTEGER
+pub fn encode_unsigned(value: u64) -> Vec<u8> {
+    let bytes = value.to_be_bytes();
+    let skip = bytes.iter().take_while(|byte| **byte == 0).count().min(7);
+    let mut encoded = bytes[skip..].to_vec();
+    if encoded[0] & 0x80 != 0 {
+        encoded.insert(0, 0);
+    }
+    encoded
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_long_length() {
+        let contents = vec![0x42; 300];
+        let encoded = der_encode(DER_OCTET_STRING, &contents);
+        assert_eq!(encoded[..4], [DER_OCTET_STRING, 0x82, 0x01, 0x2c]);
+
+        let (read, rest) = der_expect(&encoded, DER_OCTET_STRING).unwrap();
+        assert_eq!(read, contents.as_slice());
+        assert!(rest.is_empty());
+        assert_eq!(der_expect(&encoded, DER_SEQUENCE), None);
+        assert_eq!(der_read(&encoded[..encoded.len() - 1]), None);
+    }
+
+    #[test]
+    fn test_encode_unsigned() {
+        assert_eq!(encode_unsigned(0), vec![0x00]);
+        assert_eq!(encode_unsigned(0x7f), vec![0x7f]);
+        assert_eq!(encode_unsigned(0x80), vec![0x00, 0x80]);
+        assert_eq!(encode_unsigned(0x0102), vec![0x01, 0x02]);
+    }
+}
@@ -12,7 +12,7 @@ use crate::{
         key::{Certificate, PrivateKey},
         msgs::{
             enums::SignatureScheme,
-            handshake::{CertificateEntry, CertificateExtensions, HandshakePayload},
+            handshake::{CertificateEntry, CertificateExtensions, HandshakePayload, Random},
             message::{Message, MessagePayload},
         },
         sign::{EcdsaSigningKey, RsaSigner, Signer, SigningKey},
@@ -20,6 +20,10 @@ use crate::{
             construct_tls13_client_verify_message_raw, construct_tls13_server_verify_message_raw,
         },
     },
+    x509::{
+        parse_certificate, X509Extension, X509Extensions, X509Name, X509PublicKey,
+        X509TbsCertificate, X509Validity, OID_CLIENT_AUTH, OID_SERVER_AUTH, OID_UNKNOWN,
+    },
 };
 
 pub fn fn_bob_cert() -> Result<Vec<u8>, FnError> {
@@ -46,6 +50,10 @@ pub fn fn_random_ec_cert() -> Result<Vec<u8>, FnError> {
     Ok(RANDOM_EC_CERT.1.into())
 }
 
+pub fn fn_certificate_from_der(cert: &Vec<u8>) -> Result<Certificate, FnError> {
+    Ok(Certificate(cert.clone()))
+}
+
 pub fn fn_certificate_entry(cert: &Vec<u8>) -> Result<CertificateEntry, FnError> {
     Ok(CertificateEntry {
         cert: Certificate(cert.clone()),
@@ -119,6 +127,23 @@ pub fn fn_rsa_sign_server(
     )
 }
 
+/// Signs the ServerDHParams of a TLS 1.2 ServerKeyExchange
+/// (<https://datatracker.ietf.org/doc/html/rfc5246#section-7.4.3>)
+pub fn fn_rsa_sign_server_params12(
+    client_random: &Random,
+    server_random: &Random,
+    params: &Vec<u8>,
+    private_key: &Vec<u8>,
+    scheme: &SignatureScheme,
+) -> Result<Vec<u8>, FnError> {
+    let mut message = Vec::new();
+    message.extend_from_slice(&client_random.0);
+    message.extend_from_slice(&server_random.0);
+    message.extend_from_slice(params);
+
+    _fn_rsa_sign(&message, private_key, scheme)
+}
+
 fn _fn_rsa_sign(
     message: &[u8],
     private_key: &Vec<u8>,
@@ -186,6 +211,199 @@ fn _fn_ecdsa_sign(message: &[u8], private_key: &Vec<u8>) -> Result<Vec<u8>, FnEr
         .map_err(|_err| FnError::Crypto("Failed to sign using ECDHE key".to_string()))
 }
 
+// ----
+// Structural X.509 certificates
+// ----
+
+pub fn fn_x509_name_empty() -> Result<X509Name, FnError> {
+    Ok(X509Name::empty())
+}
+
+pub fn fn_x509_name_append_common_name(
+    name: &X509Name,
+    value: &Vec<u8>,
+) -> Result<X509Name, FnError> {
+    name.append_common_name(value)
+}
+
+pub fn fn_x509_name_append_organization(
+    name: &X509Name,
+    value: &Vec<u8>,
+) -> Result<X509Name, FnError> {
+    name.append_organization(value)
+}
+
+pub fn fn_x509_name_append_country(name: &X509Name, value: &Vec<u8>) -> Result<X509Name, FnError> {
+    name.append_country(value)
+}
+
+pub fn fn_x509_common_name() -> Result<Vec<u8>, FnError> {
+    Ok(b"tlspuffin".to_vec())
+}
+
+fn _parse_certificate(cert: &Vec<u8>) -> Result<(X509Name, X509Name, X509PublicKey), FnError> {
+    parse_certificate(cert)
+        .ok_or_else(|| FnError::Unknown("Failed to parse X.509 certificate".to_string()))
+}
+
+/// Subject of a DER encoded certificate. The subject of [`fn_alice_cert`] is the issuer of
+/// certificates which are signed with [`fn_alice_key`].
+pub fn fn_x509_subject(cert: &Vec<u8>) -> Result<X509Name, FnError> {
+    Ok(_parse_certificate(cert)?.1)
+}
+
+pub fn fn_x509_issuer(cert: &Vec<u8>) -> Result<X509Name, FnError> {
+    Ok(_parse_certificate(cert)?.0)
+}
+
+pub fn fn_x509_public_key(cert: &Vec<u8>) -> Result<X509PublicKey, FnError> {
+    Ok(_parse_certificate(cert)?.2)
+}
+
+/// Validity between `not_before` and `not_after`, which are seconds since the UNIX epoch
+pub fn fn_x509_validity(not_before: &u64, not_after: &u64) -> Result<X509Validity, FnError> {
+    Ok(X509Validity::new(*not_before, *not_after))
+}
+
+/// Valid from 2022-07-05 10:18:12, like the static certificates, until the end of 2049
+pub fn fn_x509_validity_default() -> Result<X509Validity, FnError> {
+    Ok(X509Validity::new(1657016292, 2524607999))
+}
+
+/// Valid during the year 2000
+pub fn fn_x509_validity_expired() -> Result<X509Validity, FnError> {
+    Ok(X509Validity::new(946684800, 978307199))
+}
+
+/// Valid from 2090 on. The times are encoded as GeneralizedTime.
+pub fn fn_x509_validity_not_yet_valid() -> Result<X509Validity, FnError> {
+    Ok(X509Validity::new(3786825600, 4102444799))
+}
+
+pub fn fn_x509_extensions_new() -> Result<X509Extensions, FnError> {
+    Ok(X509Extensions(vec![]))
+}
+
+pub fn fn_x509_extensions_append(
+    extensions: &X509Extensions,
+    extension: &X509Extension,
+) -> Result<X509Extensions, FnError> {
+    let mut new_extensions = extensions.clone();
+    new_extensions.0.push(extension.clone());
+    Ok(new_extensions)
+}
+
+/// Arbitrary extension with the encoded object identifier `oid` and the DER encoded `value`
+pub fn fn_x509_extension(
+    oid: &Vec<u8>,
+    critical: &bool,
+    value: &Vec<u8>,
+) -> Result<X509Extension, FnError> {
+    Ok(X509Extension::new(oid, *critical, value.clone()))
+}
+
+/// Critical extension which is unknown to every implementation and therefore must be rejected
+pub fn fn_x509_unknown_critical_extension() -> Result<X509Extension, FnError> {
+    Ok(X509Extension::new(OID_UNKNOWN, true, vec![0x05, 0x00]))
+}
+
+pub fn fn_x509_san_dns_extension(dns_name: &Vec<u8>) -> Result<X509Extension, FnError> {
+    Ok(X509Extension::subject_alt_name(0x82, dns_name))
+}
+
+pub fn fn_x509_san_ip_extension(ip_address: &Vec<u8>) -> Result<X509Extension, FnError> {
+    Ok(X509Extension::subject_alt_name(0x87, ip_address))
+}
+
+/// KeyUsage where bit `i` of `usage` is the `i`-th named bit of RFC 5280
+pub fn fn_x509_key_usage_extension(usage: &u64) -> Result<X509Extension, FnError> {
+    Ok(X509Extension::key_usage(*usage))
+}
+
+/// digitalSignature and keyEncipherment
+pub fn fn_x509_key_usage_leaf() -> Result<u64, FnError> {
+    Ok(0b101)
+}
+
+/// keyCertSign and cRLSign
+pub fn fn_x509_key_usage_ca() -> Result<u64, FnError> {
+    Ok(0b110_0000)
+}
+
+pub fn fn_x509_basic_constraints_extension(ca: &bool) -> Result<X509Extension, FnError> {
+    Ok(X509Extension::basic_constraints(*ca, None))
+}
+
+pub fn fn_x509_basic_constraints_path_len_extension(
+    ca: &bool,
+    path_len: &u64,
+) -> Result<X509Extension, FnError> {
+    Ok(X509Extension::basic_constraints(*ca, Some(*path_len)))
+}
+
+pub fn fn_x509_server_auth_extension() -> Result<X509Extension, FnError> {
+    Ok(X509Extension::extended_key_usage(OID_SERVER_AUTH))
+}
+
+pub fn fn_x509_client_auth_extension() -> Result<X509Extension, FnError> {
+    Ok(X509Extension::extended_key_usage(OID_CLIENT_AUTH))
+}
+
+pub fn fn_x509_tbs_certificate(
+    serial: &u64,
+    scheme: &SignatureScheme,
+    issuer: &X509Name,
+    validity: &X509Validity,
+    subject: &X509Name,
+    public_key: &X509PublicKey,
+    extensions: &X509Extensions,
+) -> Result<X509TbsCertificate, FnError> {
+    Ok(X509TbsCertificate {
+        serial: *serial,
+        signature_scheme: *scheme,
+        issuer: issuer.clone(),
+        validity: validity.clone(),
+        subject: subject.clone(),
+        public_key: public_key.clone(),
+        extensions: extensions.clone(),
+    })
+}
+
+/// Signs `tbs` with `private_key`, e.g. [`fn_alice_key`] or [`fn_bob_key`], using its signature
+/// scheme and returns the DER encoded certificate.
+pub fn fn_x509_sign(tbs: &X509TbsCertificate, private_key: &Vec<u8>) -> Result<Vec<u8>, FnError> {
+    let signature = _fn_x509_signature(tbs, private_key)?;
+    tbs.encode_signed(&signature)
+}
+
+/// Like [`fn_x509_sign`], but the last byte of the signature is flipped
+pub fn fn_x509_sign_broken(
+    tbs: &X509TbsCertificate,
+    private_key: &Vec<u8>,
+) -> Result<Vec<u8>, FnError> {
+    let mut signature = _fn_x509_signature(tbs, private_key)?;
+    if let Some(last) = signature.last_mut() {
+        *last ^= 0xff;
+    }
+    tbs.encode_signed(&signature)
+}
+
+/// Encodes `tbs` with an arbitrary `signature`
+pub fn fn_x509_with_signature(
+    tbs: &X509TbsCertificate,
+    signature: &Vec<u8>,
+) -> Result<Vec<u8>, FnError> {
+    tbs.encode_signed(signature)
+}
+
+fn _fn_x509_signature(tbs: &X509TbsCertificate, private_key: &Vec<u8>) -> Result<Vec<u8>, FnError> {
+    let message = tbs.encode()?;
+    match tbs.signature_scheme {
+        SignatureScheme::ECDSA_NISTP256_SHA256 => _fn_ecdsa_sign(&message, private_key),
+        scheme => _fn_rsa_sign(&message, private_key, &scheme),
+    }
+}
+
 pub fn fn_rsa_pss_signature_algorithm() -> Result<SignatureScheme, FnError> {
     Ok(SignatureScheme::RSA_PSS_SHA256)
 }
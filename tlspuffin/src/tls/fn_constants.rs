@@ -67,6 +67,16 @@ pub fn fn_large_length() -> Result<u64, FnError> {
     Ok(32702) // chosen by experimenting
 }
 
+/// PKCS#1 v1.5 block type of private key operations
+pub fn fn_pkcs1_block_type_1() -> Result<u8, FnError> {
+    Ok(1)
+}
+
+/// PKCS#1 v1.5 block type of public key encryptions
+pub fn fn_pkcs1_block_type_2() -> Result<u8, FnError> {
+    Ok(2)
+}
+
 pub fn fn_empty_bytes_vec() -> Result<Vec<u8>, FnError> {
     Ok(vec![])
 }
@@ -0,0 +1,262 @@
+#![allow(clippy::ptr_arg)]
+#![allow(dead_code)]
+
+//! Encrypted Client Hello (ECH) according to draft-ietf-tls-esni.
+//!
+//! The attacker builds the ClientHelloInner and ClientHelloOuter from the usual ClientHello
+//! symbols. The functions in this module encrypt the inner into the outer ClientHello. The inner
+//! and outer ClientHellos are not checked for consistency, such that the fuzzer can explore how
+//! servers reconstruct and validate them.
+
+use puffin::{algebra::error::FnError, codec::Codec};
+
+use crate::tls::{
+    hpke::HpkeSenderContext,
+    rustls::msgs::{
+        base::PayloadU16,
+        enums::{ExtensionType, HandshakeType, HpkeAead, HpkeKdf},
+        handshake::{
+            ClientExtension, ClientHelloPayload, EchClientHello, EchClientHelloOuter, EchConfig,
+            EchConfigList, EchOuterExtensions, HandshakeMessagePayload, HandshakePayload,
+            HpkeSymmetricCipherSuite, ServerExtension,
+        },
+        message::{Message, MessagePayload},
+    },
+};
+
+/// Prefix of the HPKE info, which is followed by the `ECHConfig`
+const ECH_INFO_PREFIX: &[u8] = b"tls ech\0";
+/// All AEADs of HPKE have tags of 16 bytes
+const AEAD_TAG_LEN: usize = 16;
+
+// ----
+// ECH configurations
+// ----
+
+/// Extracts the `retry_configs` from the decrypted EncryptedExtensions of a server, which
+/// rejected ECH
+pub fn fn_ech_retry_configs(encrypted_extensions: &Message) -> Result<EchConfigList, FnError> {
+    match &encrypted_extensions.payload {
+        MessagePayload::Handshake(HandshakeMessagePayload {
+            payload: HandshakePayload::EncryptedExtensions(extensions),
+            ..
+        }) => extensions.0.iter().find_map(|extension| match extension {
+            ServerExtension::EncryptedClientHello(configs) => Some(configs.clone()),
+            _ => None,
+        }),
+        _ => None,
+    }
+    .ok_or_else(|| FnError::Unknown("Could not find ECH retry configs in message".to_owned()))
+}
+
+/// First configuration of `configs` with a supported version
+pub fn fn_ech_config(configs: &EchConfigList) -> Result<EchConfig, FnError> {
+    configs
+        .0
+        .iter()
+        .find(|config| matches!(config, EchConfig::Current(_)))
+        .cloned()
+        .ok_or_else(|| FnError::Unknown("No supported ECH config".to_owned()))
+}
+
+/// Replaces the HPKE public key of `config`, such that the server is unable to decrypt
+pub fn fn_ech_config_with_public_key(
+    config: &EchConfig,
+    public_key: &Vec<u8>,
+) -> Result<EchConfig, FnError> {
+    let mut config = config.clone();
+    match config {
+        EchConfig::Current(ref mut contents) => {
+            contents.key_config.public_key = PayloadU16::new(public_key.clone());
+            Ok(config)
+        }
+        EchConfig::Unknown { .. } => Err(FnError::Unknown("Unsupported ECH config".to_owned())),
+    }
+}
+
+// ----
+// ECH extensions
+// ----
+
+/// Extension of the ClientHelloInner
+pub fn fn_ech_inner_extension() -> Result<ClientExtension, FnError> {
+    Ok(ClientExtension::EncryptedClientHello(EchClientHello::Inner))
+}
+
+/// Outer extension of a client which does not have an ECH config
+/// (<https://datatracker.ietf.org/doc/html/draft-ietf-tls-esni#section-6.2>)
+pub fn fn_ech_grease_extension() -> Result<ClientExtension, FnError> {
+    Ok(ClientExtension::EncryptedClientHello(
+        EchClientHello::Outer(EchClientHelloOuter {
+            cipher_suite: HpkeSymmetricCipherSuite {
+                kdf_id: HpkeKdf::HKDF_SHA256,
+                aead_id: HpkeAead::AES_128_GCM,
+            },
+            config_id: 42,
+            enc: PayloadU16::new(vec![42; 32]),
+            payload: PayloadU16::new(vec![42; 144]),
+        }),
+    ))
+}
+
+/// Extension of the ClientHelloInner which references the `types` of extensions which are copied
+/// from the ClientHelloOuter
+pub fn fn_ech_outer_extensions_extension(
+    types: &Vec<ExtensionType>,
+) -> Result<ClientExtension, FnError> {
+    Ok(ClientExtension::EchOuterExtensions(EchOuterExtensions(
+        types.clone(),
+    )))
+}
+
+pub fn fn_new_extension_types() -> Result<Vec<ExtensionType>, FnError> {
+    Ok(vec![])
+}
+
+pub fn fn_append_extension_type(
+    types: &Vec<ExtensionType>,
+    typ: &ExtensionType,
+) -> Result<Vec<ExtensionType>, FnError> {
+    let mut new = types.clone();
+    new.push(*typ);
+    Ok(new)
+}
+
+pub fn fn_key_share_extension_type() -> Result<ExtensionType, FnError> {
+    Ok(ExtensionType::KeyShare)
+}
+
+pub fn fn_supported_versions_extension_type() -> Result<ExtensionType, FnError> {
+    Ok(ExtensionType::SupportedVersions)
+}
+
+/// Must not be referenced from `ech_outer_extensions`
+pub fn fn_ech_extension_type() -> Result<ExtensionType, FnError> {
+    Ok(ExtensionType::EncryptedClientHello)
+}
+
+// ----
+// ClientHelloOuter
+// ----
+
+/// Encrypts the ClientHelloInner `inner` for `config` and appends the resulting ECH extension to
+/// the ClientHelloOuter `outer`. The `inner` is encrypted as given, so the attacker is
+/// responsible for emptying its session id and compressing extensions.
+pub fn fn_ech_client_hello_outer(
+    outer: &Message,
+    inner: &Message,
+    config: &EchConfig,
+) -> Result<Message, FnError> {
+    ech_client_hello_outer(outer, inner, &[], config, true)
+}
+
+/// Like [`fn_ech_client_hello_outer`], but appends `padding` to the encoded ClientHelloInner.
+/// Servers are supposed to reject padding which is not zero.
+pub fn fn_ech_client_hello_outer_padded(
+    outer: &Message,
+    inner: &Message,
+    padding: &Vec<u8>,
+    config: &EchConfig,
+) -> Result<Message, FnError> {
+    ech_client_hello_outer(outer, inner, padding, config, true)
+}
+
+/// Like [`fn_ech_client_hello_outer`], but the additional data of the encryption lacks the ECH
+/// extension. Servers fail to decrypt the ClientHelloInner and are supposed to reject ECH.
+pub fn fn_ech_client_hello_outer_mismatching_aad(
+    outer: &Message,
+    inner: &Message,
+    config: &EchConfig,
+) -> Result<Message, FnError> {
+    ech_client_hello_outer(outer, inner, &[], config, false)
+}
+
+pub fn fn_ech_zero_padding(length: &u64) -> Result<Vec<u8>, FnError> {
+    let length =
+        usize::try_from(*length).map_err(|_| FnError::Unknown("Padding too long".to_owned()))?;
+    Ok(vec![0; length])
+}
+
+fn client_hello_payload(message: &Message) -> Result<ClientHelloPayload, FnError> {
+    match &message.payload {
+        MessagePayload::Handshake(HandshakeMessagePayload {
+            payload: HandshakePayload::ClientHello(payload),
+            ..
+        }) => Ok(payload.clone()),
+        _ => Err(FnError::Unknown("Message is not a ClientHello".to_owned())),
+    }
+}
+
+fn ech_client_hello_outer(
+    outer: &Message,
+    inner: &Message,
+    padding: &[u8],
+    config: &EchConfig,
+    with_ech_extension_aad: bool,
+) -> Result<Message, FnError> {
+    let contents = match config {
+        EchConfig::Current(contents) => contents,
+        EchConfig::Unknown { .. } => {
+            return Err(FnError::Unknown("Unsupported ECH config".to_owned()))
+        }
+    };
+    let key_config = &contents.key_config;
+    let cipher_suite = key_config
+        .symmetric_cipher_suites
+        .0
+        .first()
+        .cloned()
+        .ok_or_else(|| FnError::Unknown("ECH config without cipher suites".to_owned()))?;
+
+    let mut info = ECH_INFO_PREFIX.to_vec();
+    config.encode(&mut info);
+    let (enc, mut context) = HpkeSenderContext::new(
+        key_config.kem_id,
+        cipher_suite.kdf_id,
+        cipher_suite.aead_id,
+        &key_config.public_key.0,
+        &info,
+    )?;
+
+    let mut encoded_inner = client_hello_payload(inner)?.get_encoding();
+    encoded_inner.extend_from_slice(padding);
+
+    let mut outer_payload = client_hello_payload(outer)?;
+    let aad = if with_ech_extension_aad {
+        // The ClientHelloOuterAAD is the ClientHelloOuter with a zeroed ECH payload
+        let mut aad_payload = outer_payload.clone();
+        aad_payload
+            .extensions
+            .push(ClientExtension::EncryptedClientHello(
+                EchClientHello::Outer(EchClientHelloOuter {
+                    cipher_suite: cipher_suite.clone(),
+                    config_id: key_config.config_id,
+                    enc: PayloadU16::new(enc.clone()),
+                    payload: PayloadU16::new(vec![0; encoded_inner.len() + AEAD_TAG_LEN]),
+                }),
+            ));
+        aad_payload.get_encoding()
+    } else {
+        outer_payload.get_encoding()
+    };
+
+    let payload = context.seal(&aad, &encoded_inner)?;
+    outer_payload
+        .extensions
+        .push(ClientExtension::EncryptedClientHello(
+            EchClientHello::Outer(EchClientHelloOuter {
+                cipher_suite,
+                config_id: key_config.config_id,
+                enc: PayloadU16::new(enc),
+                payload: PayloadU16::new(payload),
+            }),
+        ));
+
+    Ok(Message {
+        version: outer.version,
+        payload: MessagePayload::Handshake(HandshakeMessagePayload {
+            typ: HandshakeType::ClientHello,
+            payload: HandshakePayload::ClientHello(outer_payload),
+        }),
+    })
+}
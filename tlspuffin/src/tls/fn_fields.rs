@@ -7,7 +7,10 @@ use puffin::{
 };
 
 use crate::tls::{
-    key_exchange::tls12_new_secrets,
+    key_exchange::{
+        tls12_new_ems_secrets, tls12_new_ems_secrets_from_premaster, tls12_new_secrets,
+        tls12_new_secrets_from_premaster, tls_legacy_new_secrets_from_premaster,
+    },
     key_schedule::dhe_key_schedule,
     rustls::{
         hash_hs::HandshakeHash,
@@ -27,6 +30,18 @@ pub fn fn_protocol_version12() -> Result<ProtocolVersion, FnError> {
     Ok(ProtocolVersion::TLSv1_2)
 }
 
+pub fn fn_protocol_version11() -> Result<ProtocolVersion, FnError> {
+    Ok(ProtocolVersion::TLSv1_1)
+}
+
+pub fn fn_protocol_version10() -> Result<ProtocolVersion, FnError> {
+    Ok(ProtocolVersion::TLSv1_0)
+}
+
+pub fn fn_protocol_version_ssl3() -> Result<ProtocolVersion, FnError> {
+    Ok(ProtocolVersion::SSLv3)
+}
+
 pub fn fn_new_session_id() -> Result<SessionID, FnError> {
     let mut id: Vec<u8> = Vec::from([3u8; 32]);
     id.insert(0, 32);
@@ -182,6 +197,132 @@ pub fn fn_sign_transcript(
     Ok(secrets.client_verify_data(&vh))
 }
 
+/// Client verify data of a TLS 1.2 handshake with the RSA or finite field Diffie-Hellman key
+/// exchange
+pub fn fn_sign_transcript_premaster(
+    client_random: &Random,
+    server_random: &Random,
+    premaster_secret: &Vec<u8>,
+    transcript: &HandshakeHash,
+) -> Result<Vec<u8>, FnError> {
+    let secrets = tls12_new_secrets_from_premaster(client_random, server_random, premaster_secret)?;
+
+    let vh = transcript.get_current_hash();
+    Ok(secrets.client_verify_data(&vh))
+}
+
+/// Server verify data of a TLS 1.2 handshake with the RSA or finite field Diffie-Hellman key
+/// exchange
+pub fn fn_sign_transcript_server_premaster(
+    client_random: &Random,
+    server_random: &Random,
+    premaster_secret: &Vec<u8>,
+    transcript: &HandshakeHash,
+) -> Result<Vec<u8>, FnError> {
+    let secrets = tls12_new_secrets_from_premaster(client_random, server_random, premaster_secret)?;
+
+    let vh = transcript.get_current_hash();
+    Ok(secrets.server_verify_data(&vh))
+}
+
+/// Like [`fn_sign_transcript`], but with the extended master secret of the `session_hash`. The
+/// session hash covers the handshake up to and including the ClientKeyExchange.
+pub fn fn_sign_transcript_ems(
+    server_random: &Random,
+    server_ecdh_pubkey: &Vec<u8>,
+    session_hash: &HandshakeHash,
+    transcript: &HandshakeHash,
+    group: &NamedGroup,
+) -> Result<Vec<u8>, FnError> {
+    let secrets = tls12_new_ems_secrets(server_random, server_ecdh_pubkey, group, session_hash)?;
+
+    let vh = transcript.get_current_hash();
+    Ok(secrets.client_verify_data(&vh))
+}
+
+/// Like [`fn_sign_transcript_premaster`], but with the extended master secret of the
+/// `session_hash`
+pub fn fn_sign_transcript_premaster_ems(
+    client_random: &Random,
+    server_random: &Random,
+    premaster_secret: &Vec<u8>,
+    session_hash: &HandshakeHash,
+    transcript: &HandshakeHash,
+) -> Result<Vec<u8>, FnError> {
+    let secrets = tls12_new_ems_secrets_from_premaster(
+        client_random,
+        server_random,
+        premaster_secret,
+        session_hash,
+    )?;
+
+    let vh = transcript.get_current_hash();
+    Ok(secrets.client_verify_data(&vh))
+}
+
+/// Like [`fn_sign_transcript_server_premaster`], but with the extended master secret of the
+/// `session_hash`
+pub fn fn_sign_transcript_server_premaster_ems(
+    client_random: &Random,
+    server_random: &Random,
+    premaster_secret: &Vec<u8>,
+    session_hash: &HandshakeHash,
+    transcript: &HandshakeHash,
+) -> Result<Vec<u8>, FnError> {
+    let secrets = tls12_new_ems_secrets_from_premaster(
+        client_random,
+        server_random,
+        premaster_secret,
+        session_hash,
+    )?;
+
+    let vh = transcript.get_current_hash();
+    Ok(secrets.server_verify_data(&vh))
+}
+
+/// Client verify data of a handshake of `version`, which is TLS 1.1 or older. The `transcript`
+/// must be created by [`fn_new_transcript_legacy`](crate::tls::fn_utils::fn_new_transcript_legacy).
+pub fn fn_sign_transcript_legacy(
+    client_random: &Random,
+    server_random: &Random,
+    premaster_secret: &Vec<u8>,
+    version: &ProtocolVersion,
+    transcript: &HandshakeHash,
+) -> Result<Vec<u8>, FnError> {
+    let secrets = tls_legacy_new_secrets_from_premaster(
+        client_random,
+        server_random,
+        premaster_secret,
+        version,
+    )?;
+
+    Ok(secrets.client_verify_data(legacy_messages(transcript)?))
+}
+
+/// Server verify data of a handshake of TLS 1.1 or older, see [`fn_sign_transcript_legacy`]
+pub fn fn_sign_transcript_server_legacy(
+    client_random: &Random,
+    server_random: &Random,
+    premaster_secret: &Vec<u8>,
+    version: &ProtocolVersion,
+    transcript: &HandshakeHash,
+) -> Result<Vec<u8>, FnError> {
+    let secrets = tls_legacy_new_secrets_from_premaster(
+        client_random,
+        server_random,
+        premaster_secret,
+        version,
+    )?;
+
+    Ok(secrets.server_verify_data(legacy_messages(transcript)?))
+}
+
+fn legacy_messages(transcript: &HandshakeHash) -> Result<&[u8], FnError> {
+    transcript
+        .get_legacy_buffer()
+        .ok_or_else(|| FnError::Unknown("Transcript does not buffer messages".to_string()))
+}
+
 // ----
 // Cipher Suites
 // ----
@@ -211,6 +352,34 @@ pub fn fn_cipher_suite12() -> Result<CipherSuite, FnError> {
     )
 }
 
+pub fn fn_cipher_suite12_chacha20_poly1305_sha256() -> Result<CipherSuite, FnError> {
+    Ok(CipherSuite::TLS_ECDHE_RSA_WITH_CHACHA20_POLY1305_SHA256)
+}
+
+pub fn fn_cipher_suite12_rsa_aes_128_gcm_sha256() -> Result<CipherSuite, FnError> {
+    Ok(CipherSuite::TLS_RSA_WITH_AES_128_GCM_SHA256)
+}
+
+pub fn fn_cipher_suite12_rsa_aes_128_cbc_sha() -> Result<CipherSuite, FnError> {
+    Ok(CipherSuite::TLS_RSA_WITH_AES_128_CBC_SHA)
+}
+
+pub fn fn_cipher_suite12_dhe_rsa_aes_128_gcm_sha256() -> Result<CipherSuite, FnError> {
+    Ok(CipherSuite::TLS_DHE_RSA_WITH_AES_128_GCM_SHA256)
+}
+
+pub fn fn_cipher_suite12_aes_128_cbc_sha() -> Result<CipherSuite, FnError> {
+    Ok(CipherSuite::TLS_ECDHE_RSA_WITH_AES_128_CBC_SHA)
+}
+
+pub fn fn_cipher_suite12_aes_256_cbc_sha() -> Result<CipherSuite, FnError> {
+    Ok(CipherSuite::TLS_ECDHE_RSA_WITH_AES_256_CBC_SHA)
+}
+
+pub fn fn_cipher_suite12_aes_128_cbc_sha256() -> Result<CipherSuite, FnError> {
+    Ok(CipherSuite::TLS_ECDHE_RSA_WITH_AES_128_CBC_SHA256)
+}
+
 pub fn fn_cipher_suite13_aes_128_gcm_sha256() -> Result<CipherSuite, FnError> {
     Ok(CipherSuite::TLS13_AES_128_GCM_SHA256)
 }
@@ -8,7 +8,9 @@
 //! Return type is `Message`
 //!
 
-use puffin::algebra::error::FnError;
+use std::collections::VecDeque;
+
+use puffin::{algebra::error::FnError, codec::Codec};
 
 use crate::{
     nyi_fn,
@@ -19,9 +21,10 @@ use crate::{
             base::{Payload, PayloadU16, PayloadU24, PayloadU8},
             ccs::ChangeCipherSpecPayload,
             enums::*,
+            fragmenter::MessageFragmenter,
             handshake::{CertificateEntry, CertificateStatus, HelloRetryExtension, *},
             heartbeat::HeartbeatPayload,
-            message::{Message, MessagePayload, OpaqueMessage},
+            message::{Message, MessagePayload, OpaqueMessage, PlainMessage},
         },
     },
 };
@@ -38,6 +41,153 @@ pub fn fn_empty_handshake_message() -> Result<OpaqueMessage, FnError> {
     })
 }
 
+// ----
+// Record layer
+// Every `Message` is sent as a single record. The following functions allow splitting a message
+// into several records, joining several messages into one record and sending arbitrary sequences
+// of records within a single input.
+// ----
+
+/// Sequence of records which is added record by record to the inbound channel of an agent
+pub fn fn_new_records() -> Result<Vec<OpaqueMessage>, FnError> {
+    Ok(vec![])
+}
+
+pub fn fn_append_record(
+    records: &Vec<OpaqueMessage>,
+    record: &OpaqueMessage,
+) -> Result<Vec<OpaqueMessage>, FnError> {
+    let mut new = records.clone();
+    new.push(record.clone());
+    Ok(new)
+}
+
+pub fn fn_append_records(
+    records: &Vec<OpaqueMessage>,
+    other: &Vec<OpaqueMessage>,
+) -> Result<Vec<OpaqueMessage>, FnError> {
+    let mut new = records.clone();
+    new.extend_from_slice(other);
+    Ok(new)
+}
+
+/// The single plaintext record which carries `message`
+pub fn fn_message_record(message: &Message) -> Result<OpaqueMessage, FnError> {
+    Ok(PlainMessage::from(message.clone()).into_unencrypted_opaque())
+}
+
+/// Like `message`, but sent in a record of `version`. Once TLS 1.1 or older is negotiated, the
+/// records have to carry the negotiated version instead of TLS 1.2.
+pub fn fn_with_record_version(
+    message: &Message,
+    version: &ProtocolVersion,
+) -> Result<Message, FnError> {
+    let mut message = message.clone();
+    message.version = *version;
+    Ok(message)
+}
+
+pub fn fn_new_fragment_sizes() -> Result<Vec<u64>, FnError> {
+    Ok(vec![])
+}
+
+pub fn fn_append_fragment_size(sizes: &Vec<u64>, size: &u64) -> Result<Vec<u64>, FnError> {
+    let mut new = sizes.clone();
+    new.push(*size);
+    Ok(new)
+}
+
+/// Splits the plaintext record of `message` into records with payloads of the given `sizes`. Sizes
+/// of zero yield empty records. Bytes which are not covered by `sizes` are sent in a last record.
+pub fn fn_fragment(message: &Message, sizes: &Vec<u64>) -> Result<Vec<OpaqueMessage>, FnError> {
+    let plain = PlainMessage::from(message.clone());
+    let mut remaining: &[u8] = &plain.payload.0;
+    let mut records = Vec::with_capacity(sizes.len() + 1);
+
+    for size in sizes {
+        let size = usize::try_from(*size)
+            .map_err(|_| FnError::Unknown("Fragment size too large".to_string()))?;
+        if size > remaining.len() {
+            return Err(FnError::Unknown(format!(
+                "Fragment of {} bytes exceeds the remaining {} bytes of the message",
+                size,
+                remaining.len()
+            )));
+        }
+
+        let (fragment, rest) = remaining.split_at(size);
+        records.push(OpaqueMessage {
+            typ: plain.typ,
+            version: plain.version,
+            payload: Payload::new(fragment),
+        });
+        remaining = rest;
+    }
+
+    if !remaining.is_empty() {
+        records.push(OpaqueMessage {
+            typ: plain.typ,
+            version: plain.version,
+            payload: Payload::new(remaining),
+        });
+    }
+
+    Ok(records)
+}
+
+/// Splits the plaintext record of `message` into records with payloads of at most
+/// `max_fragment_len` bytes like an honest implementation does.
+pub fn fn_fragment_max(
+    message: &Message,
+    max_fragment_len: &u64,
+) -> Result<Vec<OpaqueMessage>, FnError> {
+    let max_fragment_len = usize::try_from(*max_fragment_len)
+        .map_err(|_| FnError::Unknown("Fragment length too large".to_string()))?;
+    let fragmenter = MessageFragmenter::with_max_fragment_len(max_fragment_len)
+        .map_err(|err| FnError::Unknown(err.to_string()))?;
+
+    let mut records = VecDeque::new();
+    fragmenter.fragment(PlainMessage::from(message.clone()), &mut records);
+
+    Ok(records
+        .into_iter()
+        .map(PlainMessage::into_unencrypted_opaque)
+        .collect())
+}
+
+pub fn fn_new_messages() -> Result<Vec<Message>, FnError> {
+    Ok(vec![])
+}
+
+pub fn fn_append_message(
+    messages: &Vec<Message>,
+    message: &Message,
+) -> Result<Vec<Message>, FnError> {
+    let mut new = messages.clone();
+    new.push(message.clone());
+    Ok(new)
+}
+
+/// Joins the payloads of `messages` into a single record. The content type and version of the
+/// record are taken from the first message.
+pub fn fn_coalesce(messages: &Vec<Message>) -> Result<OpaqueMessage, FnError> {
+    let mut plain_messages = messages.iter().cloned().map(PlainMessage::from);
+    let first = plain_messages
+        .next()
+        .ok_or_else(|| FnError::Unknown("Can not coalesce zero messages".to_string()))?;
+
+    let mut payload = first.payload.0;
+    for plain in plain_messages {
+        payload.extend_from_slice(&plain.payload.0);
+    }
+
+    Ok(OpaqueMessage {
+        typ: first.typ,
+        version: first.version,
+        payload: Payload::new(payload),
+    })
+}
+
 // ----
 // Alert Message constructors
 // https://www.iana.org/assignments/tls-parameters/tls-parameters.xhtml#tls-parameters-6
@@ -227,7 +377,15 @@ pub fn fn_new_session_ticket13(
     })
 }
 /// EndOfEarlyData => 0x05,
-nyi_fn!();
+pub fn fn_end_of_early_data() -> Result<Message, FnError> {
+    Ok(Message {
+        version: ProtocolVersion::TLSv1_2,
+        payload: MessagePayload::Handshake(HandshakeMessagePayload {
+            typ: HandshakeType::EndOfEarlyData,
+            payload: HandshakePayload::EndOfEarlyData,
+        }),
+    })
+}
 /// HelloRetryRequest => 0x06,
 pub fn fn_hello_retry_request(
     legacy_version: &ProtocolVersion,
@@ -306,6 +464,17 @@ pub fn fn_server_key_exchange(data: &Vec<u8>) -> Result<Message, FnError> {
         }),
     })
 }
+/// ServerKeyExchange of the finite field Diffie-Hellman key exchange. The encoded `params` are
+/// not parsed, such that the attacker can send malformed parameters.
+pub fn fn_server_key_exchange_dhe(
+    params: &Vec<u8>,
+    scheme: &SignatureScheme,
+    signature: &Vec<u8>,
+) -> Result<Message, FnError> {
+    let mut data = params.clone();
+    DigitallySignedStruct::new(*scheme, signature.clone()).encode(&mut data);
+    fn_server_key_exchange(&data)
+}
 /// CertificateRequest => 0x0d,
 pub fn fn_certificate_request() -> Result<Message, FnError> {
     // todo unclear where the arguments come from here, needs manual trace implementation
@@ -9,22 +9,34 @@ use puffin::{
 };
 
 use crate::tls::{
-    key_exchange::{tls12_key_exchange, tls12_new_secrets},
+    key_exchange::{
+        ffdhe2048_parameters, pkcs1_decode, pkcs1_encode, rsa_decrypt_raw, rsa_encrypt_raw,
+        rsa_modulus_len, tls12_dh_public_key, tls12_dh_shared_secret, tls12_key_exchange,
+        tls12_new_ems_secrets, tls12_new_ems_secrets_from_premaster, tls12_new_secrets,
+        tls12_new_secrets_from_premaster, tls12_new_secrets_with_suite,
+        tls_legacy_new_secrets_with_suite,
+    },
     key_schedule::*,
     rustls::{
         conn::Side,
         hash_hs::HandshakeHash,
         key::Certificate,
         msgs::{
-            base::PayloadU8,
-            enums::{HandshakeType, NamedGroup},
+            base::{PayloadU16, PayloadU8},
+            enums::{CipherSuite, HandshakeType, NamedGroup, ProtocolVersion},
             handshake::{
-                CertificateEntry, CertificateExtension, CertificateExtensions,
-                HandshakeMessagePayload, HandshakePayload, Random, ServerECDHParams,
+                CertificateEntry, CertificateExtension, CertificateExtensions, ClientDHParams,
+                HandshakeMessagePayload, HandshakePayload, Random, ServerDHParams,
+                ServerECDHParams, ServerKeyExchangePayload,
             },
             message::{Message, MessagePayload, OpaqueMessage, PlainMessage},
         },
+        suites::{SupportedCipherSuite, ALL_CIPHER_SUITES},
         tls12,
+        tls12::{
+            cbc::{lookup_cbc_suite, Tls12CbcCipherSuite},
+            Tls12CipherSuite,
+        },
         tls13::key_schedule::KeyScheduleEarly,
     },
 };
@@ -142,6 +154,39 @@ pub fn fn_encrypt_handshake(
     Ok(application_data)
 }
 
+/// Like [`fn_encrypt_handshake`], but encrypts plaintext `records` as produced by `fn_fragment`
+/// or `fn_coalesce`. The records are protected with consecutive sequence numbers, starting at
+/// `sequence`. This allows to fragment and coalesce encrypted handshake messages of TLS 1.3.
+pub fn fn_encrypt_handshake_records(
+    records: &Vec<OpaqueMessage>,
+    server_hello: &HandshakeHash,
+    server_key_share: &Option<Vec<u8>>,
+    psk: &Option<Vec<u8>>,
+    group: &NamedGroup,
+    client: &bool,
+    sequence: &u64,
+) -> Result<Vec<OpaqueMessage>, FnError> {
+    let (suite, key, _) =
+        tls13_handshake_traffic_secret(server_hello, server_key_share, psk, *client, group)?;
+    let encrypter = suite
+        .tls13()
+        .ok_or_else(|| FnError::Crypto("No tls 1.3 suite".to_owned()))?
+        .derive_encrypter(&key);
+
+    records
+        .iter()
+        .enumerate()
+        .map(|(i, record)| {
+            let plain = record.clone().into_plain_message();
+            encrypter
+                .encrypt(plain.borrow(), *sequence + i as u64)
+                .map_err(|_err| {
+                    FnError::Crypto("Failed to encrypt it fn_encrypt_handshake_records".to_string())
+                })
+        })
+        .collect()
+}
+
 pub fn fn_encrypt_application(
     some_message: &Message,
     server_hello_transcript: &HandshakeHash,
@@ -171,6 +216,100 @@ pub fn fn_encrypt_application(
     Ok(application_data)
 }
 
+/// Decrypts application data with the application traffic secret of `generation`. The
+/// generation of the sender increases with each KeyUpdate it sends.
+pub fn fn_decrypt_application_updated(
+    application_data: &Message,
+    server_hello_transcript: &HandshakeHash,
+    server_finished_transcript: &HandshakeHash,
+    server_key_share: &Option<Vec<u8>>,
+    psk: &Option<Vec<u8>>,
+    group: &NamedGroup,
+    client: &bool,
+    generation: &u64,
+    sequence: &u64,
+) -> Result<Message, FnError> {
+    let (suite, key) = tls13_application_traffic_secret_generation(
+        server_hello_transcript,
+        server_finished_transcript,
+        server_key_share,
+        psk,
+        group,
+        !*client,
+        *generation,
+    )?;
+    let decrypter = suite
+        .tls13()
+        .ok_or_else(|| FnError::Crypto("No tls 1.3 suite".to_owned()))?
+        .derive_decrypter(&key);
+    let message = decrypter
+        .decrypt(
+            PlainMessage::from(application_data.clone()).into_unencrypted_opaque(),
+            *sequence,
+        )
+        .map_err(|_err| {
+            FnError::Crypto("Failed to decrypt it fn_decrypt_application_updated".to_string())
+        })?;
+    Message::try_from(message)
+        .map_err(|_err| FnError::Crypto("Failed to create Message from decrypted data".to_string()))
+}
+
+/// Encrypts a message of the client, or of the server if `client` is false, with the application
+/// traffic secret of `generation`. The sequence number starts at 0 again after each KeyUpdate.
+pub fn fn_encrypt_application_updated(
+    some_message: &Message,
+    server_hello_transcript: &HandshakeHash,
+    server_finished_transcript: &HandshakeHash,
+    server_key_share: &Option<Vec<u8>>,
+    psk: &Option<Vec<u8>>,
+    group: &NamedGroup,
+    client: &bool,
+    generation: &u64,
+    sequence: &u64,
+) -> Result<OpaqueMessage, FnError> {
+    let (suite, key) = tls13_application_traffic_secret_generation(
+        server_hello_transcript,
+        server_finished_transcript,
+        server_key_share,
+        psk,
+        group,
+        *client,
+        *generation,
+    )?;
+    let encrypter = suite
+        .tls13()
+        .ok_or_else(|| FnError::Crypto("No tls 1.3 suite".to_owned()))?
+        .derive_encrypter(&key);
+    let application_data = encrypter
+        .encrypt(PlainMessage::from(some_message.clone()).borrow(), *sequence)
+        .map_err(|_err| {
+            FnError::Crypto("Failed to encrypt it fn_encrypt_application_updated".to_string())
+        })?;
+    Ok(application_data)
+}
+
+/// Encrypts 0-RTT data or the EndOfEarlyData message of the client with the
+/// client_early_traffic_secret. `client_hello_transcript` includes the ClientHello with the
+/// filled binder.
+pub fn fn_encrypt_early_data(
+    some_message: &Message,
+    client_hello_transcript: &HandshakeHash,
+    psk: &Vec<u8>,
+    sequence: &u64,
+) -> Result<OpaqueMessage, FnError> {
+    let (suite, key) = tls13_client_early_traffic_secret(client_hello_transcript, psk)?;
+    let encrypter = suite
+        .tls13()
+        .ok_or_else(|| FnError::Crypto("No tls 1.3 suite".to_owned()))?
+        .derive_encrypter(&key);
+    let early_data = encrypter
+        .encrypt(PlainMessage::from(some_message.clone()).borrow(), *sequence)
+        .map_err(|_err| {
+            FnError::Crypto("Failed to encrypt it fn_encrypt_early_data".to_string())
+        })?;
+    Ok(early_data)
+}
+
 pub fn fn_derive_psk(
     server_hello: &HandshakeHash,
     server_finished: &HandshakeHash,
@@ -288,6 +427,12 @@ pub fn fn_new_transcript12() -> Result<HandshakeHash, FnError> {
     Ok(transcript)
 }
 
+/// Transcript of TLS 1.0 and TLS 1.1 which buffers the messages for the MD5 and SHA-1 hash
+pub fn fn_new_transcript_legacy() -> Result<HandshakeHash, FnError> {
+    let transcript = HandshakeHash::new_buffered(&ring::digest::SHA1_FOR_LEGACY_USE_ONLY);
+    Ok(transcript)
+}
+
 pub fn fn_decode_ecdh_pubkey(data: &Vec<u8>) -> Result<Vec<u8>, FnError> {
     let mut rd = Reader::init(data.as_slice());
     let params = ServerECDHParams::read(&mut rd)
@@ -328,6 +473,524 @@ pub fn fn_encrypt12(
     Ok(encrypted)
 }
 
+fn lookup_aead_suite12(cipher_suite: &CipherSuite) -> Option<&'static Tls12CipherSuite> {
+    ALL_CIPHER_SUITES.iter().find_map(|suite| match suite {
+        SupportedCipherSuite::Tls12(suite) if suite.common.suite == *cipher_suite => Some(*suite),
+        _ => None,
+    })
+}
+
+/// The CBC suites all use the SHA-256 based PRF of TLS 1.2, so their secrets are the ones which
+/// [`tls12_new_secrets`] derives.
+fn lookup_cbc_suite12(cipher_suite: &CipherSuite) -> Result<&'static Tls12CbcCipherSuite, FnError> {
+    lookup_cbc_suite(*cipher_suite)
+        .ok_or_else(|| FnError::Crypto(format!("{:?} is not a TLS 1.2 CBC suite", cipher_suite)))
+}
+
+fn tls12_side(client: bool) -> Side {
+    match client {
+        true => Side::Client,
+        false => Side::Server,
+    }
+}
+
+/// Like [`fn_encrypt12`], but protects the record with `cipher_suite`. Both AEAD and CBC suites
+/// are supported.
+pub fn fn_encrypt12_with_suite(
+    message: &Message,
+    server_random: &Random,
+    server_ecdh_pubkey: &Vec<u8>,
+    group: &NamedGroup,
+    cipher_suite: &CipherSuite,
+    client: &bool,
+    sequence: &u64,
+) -> Result<OpaqueMessage, FnError> {
+    let plain = PlainMessage::from(message.clone());
+    let encrypted = if let Some(cbc_suite) = lookup_cbc_suite(*cipher_suite) {
+        let secrets = tls12_new_secrets(server_random, server_ecdh_pubkey, group)?;
+        let (_decrypter, encrypter) = secrets
+            .make_cbc_cipher_pair(cbc_suite, tls12_side(*client))
+            .map_err(|err| FnError::Crypto(err.to_string()))?;
+        encrypter.encrypt(plain.borrow(), *sequence)
+    } else {
+        let suite = lookup_aead_suite12(cipher_suite)
+            .ok_or_else(|| FnError::Crypto(format!("{:?} is not a TLS 1.2 suite", cipher_suite)))?;
+        let secrets =
+            tls12_new_secrets_with_suite(server_random, server_ecdh_pubkey, group, suite)?;
+        let (_decrypter, encrypter) = secrets.make_cipher_pair(tls12_side(*client));
+        encrypter.encrypt(plain.borrow(), *sequence)
+    };
+
+    encrypted
+        .map_err(|_err| FnError::Crypto("Failed to encrypt it fn_encrypt12_with_suite".to_string()))
+}
+
+/// Decrypts a TLS 1.2 record which is protected with `cipher_suite`. `client` is the side which
+/// decrypts the record.
+pub fn fn_decrypt12_with_suite(
+    application_data: &Message,
+    server_random: &Random,
+    server_ecdh_pubkey: &Vec<u8>,
+    group: &NamedGroup,
+    cipher_suite: &CipherSuite,
+    client: &bool,
+    sequence: &u64,
+) -> Result<Message, FnError> {
+    let opaque = PlainMessage::from(application_data.clone()).into_unencrypted_opaque();
+    let message = if let Some(cbc_suite) = lookup_cbc_suite(*cipher_suite) {
+        let secrets = tls12_new_secrets(server_random, server_ecdh_pubkey, group)?;
+        let (decrypter, _encrypter) = secrets
+            .make_cbc_cipher_pair(cbc_suite, tls12_side(*client))
+            .map_err(|err| FnError::Crypto(err.to_string()))?;
+        decrypter.decrypt(opaque, *sequence)
+    } else {
+        let suite = lookup_aead_suite12(cipher_suite)
+            .ok_or_else(|| FnError::Crypto(format!("{:?} is not a TLS 1.2 suite", cipher_suite)))?;
+        let secrets =
+            tls12_new_secrets_with_suite(server_random, server_ecdh_pubkey, group, suite)?;
+        let (decrypter, _encrypter) = secrets.make_cipher_pair(tls12_side(*client));
+        decrypter.decrypt(opaque, *sequence)
+    }
+    .map_err(|_err| FnError::Crypto("Failed to decrypt it fn_decrypt12_with_suite".to_string()))?;
+
+    Message::try_from(message)
+        .map_err(|_err| FnError::Crypto("Failed to create Message from decrypted data".to_string()))
+}
+
+/// Encrypts `message` with the CBC suite `cipher_suite`. The `padding` is appended as is and
+/// has to include the padding length byte. If `valid_mac` is false, the MAC is corrupted.
+pub fn fn_encrypt12_cbc(
+    message: &Message,
+    server_random: &Random,
+    server_ecdh_pubkey: &Vec<u8>,
+    group: &NamedGroup,
+    cipher_suite: &CipherSuite,
+    client: &bool,
+    sequence: &u64,
+    padding: &Vec<u8>,
+    valid_mac: &bool,
+) -> Result<OpaqueMessage, FnError> {
+    let cbc_suite = lookup_cbc_suite12(cipher_suite)?;
+    let secrets = tls12_new_secrets(server_random, server_ecdh_pubkey, group)?;
+    let (_decrypter, encrypter) = secrets
+        .make_cbc_cipher_pair(cbc_suite, tls12_side(*client))
+        .map_err(|err| FnError::Crypto(err.to_string()))?;
+
+    encrypter
+        .encrypt_with(
+            PlainMessage::from(message.clone()).borrow(),
+            *sequence,
+            padding,
+            *valid_mac,
+        )
+        .map_err(|err| FnError::Crypto(format!("Failed to encrypt it fn_encrypt12_cbc: {}", err)))
+}
+
+/// Decrypts a CBC record. If `verify` is false, the padding and the MAC are not checked.
+pub fn fn_decrypt12_cbc(
+    application_data: &Message,
+    server_random: &Random,
+    server_ecdh_pubkey: &Vec<u8>,
+    group: &NamedGroup,
+    cipher_suite: &CipherSuite,
+    client: &bool,
+    sequence: &u64,
+    verify: &bool,
+) -> Result<Message, FnError> {
+    let cbc_suite = lookup_cbc_suite12(cipher_suite)?;
+    let secrets = tls12_new_secrets(server_random, server_ecdh_pubkey, group)?;
+    let (decrypter, _encrypter) = secrets
+        .make_cbc_cipher_pair(cbc_suite, tls12_side(*client))
+        .map_err(|err| FnError::Crypto(err.to_string()))?;
+
+    let message = decrypter
+        .decrypt_with(
+            PlainMessage::from(application_data.clone()).into_unencrypted_opaque(),
+            *sequence,
+            *verify,
+        )
+        .map_err(|_err| FnError::Crypto("Failed to decrypt it fn_decrypt12_cbc".to_string()))?;
+
+    Message::try_from(message)
+        .map_err(|_err| FnError::Crypto("Failed to create Message from decrypted data".to_string()))
+}
+
+/// Well-formed padding for `message` under the CBC suite `cipher_suite`, extended by
+/// `extra_blocks` blocks
+pub fn fn_cbc_padding12(
+    message: &Message,
+    cipher_suite: &CipherSuite,
+    extra_blocks: &u64,
+) -> Result<Vec<u8>, FnError> {
+    let cbc_suite = lookup_cbc_suite12(cipher_suite)?;
+    let fragment_len = PlainMessage::from(message.clone()).payload.0.len();
+    Ok(cbc_suite.padding(fragment_len, *extra_blocks as usize))
+}
+
+// ----
+// TLS 1.2 key exchange with RSA and finite field Diffie-Hellman
+// ----
+
+pub fn fn_encrypt12_premaster(
+    message: &Message,
+    client_random: &Random,
+    server_random: &Random,
+    premaster_secret: &Vec<u8>,
+    client: &bool,
+    sequence: &u64,
+) -> Result<OpaqueMessage, FnError> {
+    let secrets = tls12_new_secrets_from_premaster(client_random, server_random, premaster_secret)?;
+
+    let (_decrypter, encrypter) = secrets.make_cipher_pair(tls12_side(*client));
+    let encrypted = encrypter
+        .encrypt(PlainMessage::from(message.clone()).borrow(), *sequence)
+        .map_err(|_err| {
+            FnError::Crypto("Failed to encrypt it fn_encrypt12_premaster".to_string())
+        })?;
+    Ok(encrypted)
+}
+
+/// Decrypts a record which is protected with the secrets of `premaster_secret`. `client` is the
+/// side which decrypts the record.
+pub fn fn_decrypt12_premaster(
+    application_data: &Message,
+    client_random: &Random,
+    server_random: &Random,
+    premaster_secret: &Vec<u8>,
+    client: &bool,
+    sequence: &u64,
+) -> Result<Message, FnError> {
+    let secrets = tls12_new_secrets_from_premaster(client_random, server_random, premaster_secret)?;
+
+    let (decrypter, _encrypter) = secrets.make_cipher_pair(tls12_side(*client));
+    let message = decrypter
+        .decrypt(
+            PlainMessage::from(application_data.clone()).into_unencrypted_opaque(),
+            *sequence,
+        )
+        .map_err(|_err| {
+            FnError::Crypto("Failed to decrypt it fn_decrypt12_premaster".to_string())
+        })?;
+    Message::try_from(message)
+        .map_err(|_err| FnError::Crypto("Failed to create Message from decrypted data".to_string()))
+}
+
+// ----
+// TLS 1.1 and older
+// ----
+
+/// Encrypts `message` for a connection of `version` with the secrets of `premaster_secret` and the
+/// CBC suite `cipher_suite`. Only TLS 1.1 is supported, as TLS 1.0 and SSL 3.0 chain the IV across
+/// records.
+pub fn fn_encrypt_legacy(
+    message: &Message,
+    client_random: &Random,
+    server_random: &Random,
+    premaster_secret: &Vec<u8>,
+    version: &ProtocolVersion,
+    cipher_suite: &CipherSuite,
+    client: &bool,
+    sequence: &u64,
+) -> Result<OpaqueMessage, FnError> {
+    let cbc_suite = lookup_cbc_suite12(cipher_suite)?;
+    let secrets = tls_legacy_new_secrets_with_suite(
+        client_random,
+        server_random,
+        premaster_secret,
+        version,
+        cbc_suite,
+    )?;
+    let (_decrypter, encrypter) = secrets
+        .make_cipher_pair(tls12_side(*client))
+        .map_err(|err| FnError::Crypto(err.to_string()))?;
+
+    encrypter
+        .encrypt(PlainMessage::from(message.clone()).borrow(), *sequence)
+        .map_err(|_err| FnError::Crypto("Failed to encrypt it fn_encrypt_legacy".to_string()))
+}
+
+// ----
+// TLS 1.2 extended master secret (RFC 7627)
+// ----
+
+/// Like [`fn_encrypt12`], but with the extended master secret of the `session_hash`
+pub fn fn_encrypt12_ems(
+    message: &Message,
+    server_random: &Random,
+    server_ecdh_pubkey: &Vec<u8>,
+    group: &NamedGroup,
+    session_hash: &HandshakeHash,
+    client: &bool,
+    sequence: &u64,
+) -> Result<OpaqueMessage, FnError> {
+    let secrets = tls12_new_ems_secrets(server_random, server_ecdh_pubkey, group, session_hash)?;
+
+    let (_decrypter, encrypter) = secrets.make_cipher_pair(tls12_side(*client));
+    encrypter
+        .encrypt(PlainMessage::from(message.clone()).borrow(), *sequence)
+        .map_err(|_err| FnError::Crypto("Failed to encrypt it fn_encrypt12_ems".to_string()))
+}
+
+/// Like [`fn_encrypt12_premaster`], but with the extended master secret of the `session_hash`
+pub fn fn_encrypt12_premaster_ems(
+    message: &Message,
+    client_random: &Random,
+    server_random: &Random,
+    premaster_secret: &Vec<u8>,
+    session_hash: &HandshakeHash,
+    client: &bool,
+    sequence: &u64,
+) -> Result<OpaqueMessage, FnError> {
+    let secrets = tls12_new_ems_secrets_from_premaster(
+        client_random,
+        server_random,
+        premaster_secret,
+        session_hash,
+    )?;
+
+    let (_decrypter, encrypter) = secrets.make_cipher_pair(tls12_side(*client));
+    encrypter
+        .encrypt(PlainMessage::from(message.clone()).borrow(), *sequence)
+        .map_err(|_err| {
+            FnError::Crypto("Failed to encrypt it fn_encrypt12_premaster_ems".to_string())
+        })
+}
+
+/// Like [`fn_decrypt12_premaster`], but with the extended master secret of the `session_hash`
+pub fn fn_decrypt12_premaster_ems(
+    application_data: &Message,
+    client_random: &Random,
+    server_random: &Random,
+    premaster_secret: &Vec<u8>,
+    session_hash: &HandshakeHash,
+    client: &bool,
+    sequence: &u64,
+) -> Result<Message, FnError> {
+    let secrets = tls12_new_ems_secrets_from_premaster(
+        client_random,
+        server_random,
+        premaster_secret,
+        session_hash,
+    )?;
+
+    let (decrypter, _encrypter) = secrets.make_cipher_pair(tls12_side(*client));
+    let message = decrypter
+        .decrypt(
+            PlainMessage::from(application_data.clone()).into_unencrypted_opaque(),
+            *sequence,
+        )
+        .map_err(|_err| {
+            FnError::Crypto("Failed to decrypt it fn_decrypt12_premaster_ems".to_string())
+        })?;
+    Message::try_from(message)
+        .map_err(|_err| FnError::Crypto("Failed to create Message from decrypted data".to_string()))
+}
+
+/// Pre-master secret of the RSA key exchange, which starts with the offered `version`
+pub fn fn_rsa_premaster_secret12(version: &ProtocolVersion) -> Result<Vec<u8>, FnError> {
+    let mut premaster_secret = Vec::with_capacity(48);
+    version.encode(&mut premaster_secret);
+    premaster_secret.extend_from_slice(&[42; 46]);
+    Ok(premaster_secret)
+}
+
+/// Well-formed PKCS#1 encoding of `premaster_secret` for the RSA key of `certificate`
+pub fn fn_rsa_pkcs1_encode12(
+    premaster_secret: &Vec<u8>,
+    certificate: &Vec<u8>,
+) -> Result<Vec<u8>, FnError> {
+    pkcs1_encode(premaster_secret, rsa_modulus_len(certificate)?, 2, true)
+}
+
+/// PKCS#1 encoding of `premaster_secret` with an arbitrary block type and an optional separator
+/// after the padding string. This allows Bleichenbacher-style queries.
+pub fn fn_rsa_pkcs1_encode12_malformed(
+    premaster_secret: &Vec<u8>,
+    certificate: &Vec<u8>,
+    block_type: &u8,
+    separator: &bool,
+) -> Result<Vec<u8>, FnError> {
+    pkcs1_encode(
+        premaster_secret,
+        rsa_modulus_len(certificate)?,
+        *block_type,
+        *separator,
+    )
+}
+
+/// Encrypts a PKCS#1 encoded block with the RSA key of `certificate`
+pub fn fn_rsa_encrypt12(encoded: &Vec<u8>, certificate: &Vec<u8>) -> Result<Vec<u8>, FnError> {
+    rsa_encrypt_raw(encoded, certificate)
+}
+
+pub fn fn_encode_rsa_premaster12(encrypted: &Vec<u8>) -> Result<Vec<u8>, FnError> {
+    let mut buf = Vec::new();
+    PayloadU16::new(encrypted.clone()).encode(&mut buf);
+    Ok(buf)
+}
+
+/// Decrypts the pre-master secret of a ClientKeyExchange of the RSA key exchange
+pub fn fn_rsa_decrypt_premaster12(
+    client_key_exchange: &Vec<u8>,
+    private_key: &Vec<u8>,
+) -> Result<Vec<u8>, FnError> {
+    let mut rd = Reader::init(client_key_exchange.as_slice());
+    let encrypted = PayloadU16::read(&mut rd)
+        .ok_or_else(|| FnError::Unknown("Failed to parse encrypted premaster".to_string()))?;
+    pkcs1_decode(&rsa_decrypt_raw(&encrypted.0, private_key)?)
+}
+
+pub fn fn_ffdhe2048_prime() -> Result<Vec<u8>, FnError> {
+    Ok(ffdhe2048_parameters()?.0)
+}
+
+pub fn fn_ffdhe2048_generator() -> Result<Vec<u8>, FnError> {
+    Ok(ffdhe2048_parameters()?.1)
+}
+
+/// The prime 2^64 - 59, which is far too small for a secure key exchange
+pub fn fn_dh_weak_prime() -> Result<Vec<u8>, FnError> {
+    Ok(vec![0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xc5])
+}
+
+/// Degenerated generator which generates only the trivial subgroup
+pub fn fn_dh_generator_one() -> Result<Vec<u8>, FnError> {
+    Ok(vec![1])
+}
+
+pub fn fn_new_dh_pubkey12(prime: &Vec<u8>, generator: &Vec<u8>) -> Result<Vec<u8>, FnError> {
+    tls12_dh_public_key(prime, generator)
+}
+
+pub fn fn_encode_dh_pubkey12(pubkey: &Vec<u8>) -> Result<Vec<u8>, FnError> {
+    let mut buf = Vec::new();
+    ClientDHParams {
+        public: PayloadU16::new(pubkey.clone()),
+    }
+    .encode(&mut buf);
+    Ok(buf)
+}
+
+/// Encodes the ServerDHParams of a DHE ServerKeyExchange
+pub fn fn_dh_server_params12(
+    prime: &Vec<u8>,
+    generator: &Vec<u8>,
+    pubkey: &Vec<u8>,
+) -> Result<Vec<u8>, FnError> {
+    let mut buf = Vec::new();
+    ServerDHParams::new(prime, generator, pubkey).encode(&mut buf);
+    Ok(buf)
+}
+
+fn decode_server_dh_params(data: &Vec<u8>) -> Result<ServerDHParams, FnError> {
+    let mut rd = Reader::init(data.as_slice());
+    ServerDHParams::read(&mut rd)
+        .ok_or_else(|| FnError::Unknown("Failed to parse dh parameters".to_string()))
+}
+
+pub fn fn_decode_dh_prime12(data: &Vec<u8>) -> Result<Vec<u8>, FnError> {
+    Ok(decode_server_dh_params(data)?.dh_p.0)
+}
+
+pub fn fn_decode_dh_generator12(data: &Vec<u8>) -> Result<Vec<u8>, FnError> {
+    Ok(decode_server_dh_params(data)?.dh_g.0)
+}
+
+pub fn fn_decode_dh_pubkey12(data: &Vec<u8>) -> Result<Vec<u8>, FnError> {
+    Ok(decode_server_dh_params(data)?.dh_ys.0)
+}
+
+pub fn fn_decode_client_dh_pubkey12(data: &Vec<u8>) -> Result<Vec<u8>, FnError> {
+    let mut rd = Reader::init(data.as_slice());
+    let params = ClientDHParams::read(&mut rd)
+        .ok_or_else(|| FnError::Unknown("Failed to parse dh public key".to_string()))?;
+    Ok(params.public.0)
+}
+
+pub fn fn_dh_premaster_secret12(
+    prime: &Vec<u8>,
+    peer_pubkey: &Vec<u8>,
+) -> Result<Vec<u8>, FnError> {
+    tls12_dh_shared_secret(prime, peer_pubkey)
+}
+
+// ----
+// TLS 1.2 renegotiation (RFC 5746)
+// ----
+// The messages of a renegotiation are encrypted and therefore not part of the knowledge. These
+// functions extract the values from the decrypted messages, such that the encryption functions
+// can continue on the keys of the renegotiated handshake.
+
+pub fn fn_get_client_random(client_hello: &Message) -> Result<Random, FnError> {
+    match client_hello.payload.clone() {
+        MessagePayload::Handshake(payload) => match payload.payload {
+            HandshakePayload::ClientHello(payload) => Some(payload.random),
+            _ => None,
+        },
+        _ => None,
+    }
+    .ok_or_else(|| FnError::Unknown("Could not find client random in message".to_owned()))
+}
+
+pub fn fn_get_server_random(server_hello: &Message) -> Result<Random, FnError> {
+    match server_hello.payload.clone() {
+        MessagePayload::Handshake(payload) => match payload.payload {
+            HandshakePayload::ServerHello(payload) => Some(payload.random),
+            _ => None,
+        },
+        _ => None,
+    }
+    .ok_or_else(|| FnError::Unknown("Could not find server random in message".to_owned()))
+}
+
+/// Encoded parameters of a ServerKeyExchange, like the ServerECDHParams
+pub fn fn_get_server_key_exchange(server_key_exchange: &Message) -> Result<Vec<u8>, FnError> {
+    match server_key_exchange.payload.clone() {
+        MessagePayload::Handshake(payload) => match payload.payload {
+            HandshakePayload::ServerKeyExchange(ServerKeyExchangePayload::Unknown(payload)) => {
+                Some(payload.0)
+            }
+            _ => None,
+        },
+        _ => None,
+    }
+    .ok_or_else(|| FnError::Unknown("Could not find server key exchange in message".to_owned()))
+}
+
+/// Encoded exchange keys of a ClientKeyExchange, like the RSA encrypted pre-master secret
+pub fn fn_get_client_key_exchange(client_key_exchange: &Message) -> Result<Vec<u8>, FnError> {
+    match client_key_exchange.payload.clone() {
+        MessagePayload::Handshake(payload) => match payload.payload {
+            HandshakePayload::ClientKeyExchange(payload) => Some(payload.0),
+            _ => None,
+        },
+        _ => None,
+    }
+    .ok_or_else(|| FnError::Unknown("Could not find client key exchange in message".to_owned()))
+}
+
+pub fn fn_get_verify_data(finished: &Message) -> Result<Vec<u8>, FnError> {
+    match finished.payload.clone() {
+        MessagePayload::Handshake(payload) => match payload.payload {
+            HandshakePayload::Finished(payload) => Some(payload.0),
+            _ => None,
+        },
+        _ => None,
+    }
+    .ok_or_else(|| FnError::Unknown("Could not find verify data in message".to_owned()))
+}
+
+/// Value of the `renegotiation_info` extension of a renegotiating ServerHello. A renegotiating
+/// client sends only the verify data of its previous Finished.
+pub fn fn_server_renegotiation_info(
+    client_verify_data: &Vec<u8>,
+    server_verify_data: &Vec<u8>,
+) -> Result<Vec<u8>, FnError> {
+    let mut renegotiated_connection = client_verify_data.clone();
+    renegotiated_connection.extend_from_slice(server_verify_data);
+    Ok(renegotiated_connection)
+}
+
 pub fn fn_new_certificate() -> Result<Certificate, FnError> {
     let der_cert = hex::decode(
         "308203473082022fa003020102021406f7fb1d20\
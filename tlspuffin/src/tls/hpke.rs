@@ -0,0 +1,329 @@
+//! Hybrid Public Key Encryption (<https://datatracker.ietf.org/doc/html/rfc9180>) in the base mode.
+//!
+//! Only the sender is implemented, because the attacker encrypts to public keys of the PUT, for
+//! example when sending an Encrypted Client Hello. Like in [`crate::tls::key_exchange`], the
+//! ephemeral key of the attacker is fixed, such that traces are reproducible.
+
+use puffin::algebra::error::FnError;
+use ring::{aead, agreement, hmac, test::rand::FixedByteRandom};
+
+use crate::tls::rustls::msgs::enums::{HpkeAead, HpkeKdf, HpkeKem};
+
+const HPKE_VERSION: &[u8] = b"HPKE-v1";
+const MODE_BASE: u8 = 0x00;
+
+/// Sender context of HPKE which encrypts messages with increasing sequence numbers
+pub struct HpkeSenderContext {
+    aead: &'static aead::Algorithm,
+    key: Vec<u8>,
+    base_nonce: Vec<u8>,
+    seq: u64,
+}
+
+impl HpkeSenderContext {
+    /// Sets up a context for the recipient `public_key` and returns it together with the
+    /// encapsulated key, which is required by the recipient to set up its context.
+    pub fn new(
+        kem: HpkeKem,
+        kdf: HpkeKdf,
+        aead: HpkeAead,
+        public_key: &[u8],
+        info: &[u8],
+    ) -> Result<(Vec<u8>, Self), FnError> {
+        let (shared_secret, enc) = encap(kem, public_key)?;
+        let context = key_schedule(kem, kdf, aead, &shared_secret, info)?;
+        Ok((enc, context))
+    }
+
+    pub fn seal(&mut self, aad: &[u8], plaintext: &[u8]) -> Result<Vec<u8>, FnError> {
+        let key = aead::UnboundKey::new(self.aead, &self.key)
+            .map_err(|_| FnError::Crypto("Invalid HPKE key".to_string()))?;
+        let nonce = aead::Nonce::try_assume_unique_for_key(&self.nonce())
+            .map_err(|_| FnError::Crypto("Invalid HPKE nonce".to_string()))?;
+
+        let mut in_out = plaintext.to_vec();
+        aead::LessSafeKey::new(key)
+            .seal_in_place_append_tag(nonce, aead::Aad::from(aad), &mut in_out)
+            .map_err(|_| FnError::Crypto("Failed to seal HPKE message".to_string()))?;
+
+        self.seq += 1;
+        Ok(in_out)
+    }
+
+    /// XOR of the base nonce and the sequence number
+    fn nonce(&self) -> Vec<u8> {
+        let seq = self.seq.to_be_bytes();
+        let offset = self.base_nonce.len() - seq.len();
+
+        let mut nonce = self.base_nonce.clone();
+        for (byte, seq_byte) in nonce[offset..].iter_mut().zip(seq) {
+            *byte ^= seq_byte;
+        }
+        nonce
+    }
+}
+
+fn kem_algorithm(kem: HpkeKem) -> Result<&'static agreement::Algorithm, FnError> {
+    match kem {
+        HpkeKem::DHKEM_X25519_HKDF_SHA256 => Ok(&agreement::X25519),
+        HpkeKem::DHKEM_P256_HKDF_SHA256 => Ok(&agreement::ECDH_P256),
+        _ => Err(FnError::Crypto(format!("Unsupported HPKE KEM {:?}", kem))),
+    }
+}
+
+fn kdf_algorithm(kdf: HpkeKdf) -> Result<hmac::Algorithm, FnError> {
+    match kdf {
+        HpkeKdf::HKDF_SHA256 => Ok(hmac::HMAC_SHA256),
+        HpkeKdf::HKDF_SHA384 => Ok(hmac::HMAC_SHA384),
+        HpkeKdf::HKDF_SHA512 => Ok(hmac::HMAC_SHA512),
+        _ => Err(FnError::Crypto(format!("Unsupported HPKE KDF {:?}", kdf))),
+    }
+}
+
+fn aead_algorithm(aead: HpkeAead) -> Result<&'static aead::Algorithm, FnError> {
+    match aead {
+        HpkeAead::AES_128_GCM => Ok(&aead::AES_128_GCM),
+        HpkeAead::AES_256_GCM => Ok(&aead::AES_256_GCM),
+        HpkeAead::CHACHA20_POLY1305 => Ok(&aead::CHACHA20_POLY1305),
+        _ => Err(FnError::Crypto(format!("Unsupported HPKE AEAD {:?}", aead))),
+    }
+}
+
+/// Both supported KEMs use HKDF-SHA256 and produce shared secrets of 32 bytes
+const KEM_SECRET_LEN: usize = 32;
+
+fn kem_suite_id(kem: HpkeKem) -> Vec<u8> {
+    let mut suite_id = b"KEM".to_vec();
+    suite_id.extend_from_slice(&kem.get_u16().to_be_bytes());
+    suite_id
+}
+
+fn hpke_suite_id(kem: HpkeKem, kdf: HpkeKdf, aead: HpkeAead) -> Vec<u8> {
+    let mut suite_id = b"HPKE".to_vec();
+    suite_id.extend_from_slice(&kem.get_u16().to_be_bytes());
+    suite_id.extend_from_slice(&kdf.get_u16().to_be_bytes());
+    suite_id.extend_from_slice(&aead.get_u16().to_be_bytes());
+    suite_id
+}
+
+/// DHKEM with the fixed ephemeral key of the attacker. Returns the shared secret and the
+/// encapsulated key.
+fn encap(kem: HpkeKem, public_key: &[u8]) -> Result<(Vec<u8>, Vec<u8>), FnError> {
+    let algorithm = kem_algorithm(kem)?;
+    let random = FixedByteRandom { byte: 42 };
+    let ephemeral = agreement::EphemeralPrivateKey::generate(algorithm, &random)
+        .map_err(|_| FnError::Crypto("Failed to generate ephemeral key".to_string()))?;
+    let enc = ephemeral
+        .compute_public_key()
+        .map_err(|_| FnError::Crypto("Failed to compute public key".to_string()))?
+        .as_ref()
+        .to_vec();
+
+    let dh = agreement::agree_ephemeral(
+        ephemeral,
+        &agreement::UnparsedPublicKey::new(algorithm, public_key),
+        FnError::Crypto("Failed to compute HPKE shared secret".to_string()),
+        |secret| Ok(secret.to_vec()),
+    )?;
+
+    let mut kem_context = enc.clone();
+    kem_context.extend_from_slice(public_key);
+
+    let suite_id = kem_suite_id(kem);
+    let eae_prk = labeled_extract(hmac::HMAC_SHA256, &suite_id, &[], b"eae_prk", &dh);
+    let shared_secret = labeled_expand(
+        hmac::HMAC_SHA256,
+        &suite_id,
+        &eae_prk,
+        b"shared_secret",
+        &kem_context,
+        KEM_SECRET_LEN,
+    )?;
+
+    Ok((shared_secret, enc))
+}
+
+fn key_schedule(
+    kem: HpkeKem,
+    kdf: HpkeKdf,
+    aead: HpkeAead,
+    shared_secret: &[u8],
+    info: &[u8],
+) -> Result<HpkeSenderContext, FnError> {
+    let suite_id = hpke_suite_id(kem, kdf, aead);
+    let hash = kdf_algorithm(kdf)?;
+    let aead = aead_algorithm(aead)?;
+
+    // The base mode uses neither a PSK nor a PSK identity
+    let psk_id_hash = labeled_extract(hash, &suite_id, &[], b"psk_id_hash", &[]);
+    let info_hash = labeled_extract(hash, &suite_id, &[], b"info_hash", info);
+
+    let mut context = vec![MODE_BASE];
+    context.extend_from_slice(&psk_id_hash);
+    context.extend_from_slice(&info_hash);
+
+    let secret = labeled_extract(hash, &suite_id, shared_secret, b"secret", &[]);
+    let key = labeled_expand(hash, &suite_id, &secret, b"key", &context, aead.key_len())?;
+    let base_nonce = labeled_expand(
+        hash,
+        &suite_id,
+        &secret,
+        b"base_nonce",
+        &context,
+        aead.nonce_len(),
+    )?;
+
+    Ok(HpkeSenderContext {
+        aead,
+        key,
+        base_nonce,
+        seq: 0,
+    })
+}
+
+fn labeled_extract(
+    hash: hmac::Algorithm,
+    suite_id: &[u8],
+    salt: &[u8],
+    label: &[u8],
+    ikm: &[u8],
+) -> Vec<u8> {
+    let labeled_ikm = [HPKE_VERSION, suite_id, label, ikm].concat();
+    extract(hash, salt, &labeled_ikm)
+}
+
+fn labeled_expand(
+    hash: hmac::Algorithm,
+    suite_id: &[u8],
+    prk: &[u8],
+    label: &[u8],
+    info: &[u8],
+    len: usize,
+) -> Result<Vec<u8>, FnError> {
+    let len_bytes = u16::try_from(len)
+        .map_err(|_| FnError::Crypto("HPKE output too long".to_string()))?
+        .to_be_bytes();
+    let labeled_info = [&len_bytes[..], HPKE_VERSION, suite_id, label, info].concat();
+    expand(hash, prk, &labeled_info, len)
+}
+
+/// HKDF-Extract. An empty salt is equivalent to a salt of zeros, because HMAC pads its key with
+/// zeros.
+fn extract(hash: hmac::Algorithm, salt: &[u8], ikm: &[u8]) -> Vec<u8> {
+    hmac::sign(&hmac::Key::new(hash, salt), ikm)
+        .as_ref()
+        .to_vec()
+}
+
+/// HKDF-Expand
+fn expand(hash: hmac::Algorithm, prk: &[u8], info: &[u8], len: usize) -> Result<Vec<u8>, FnError> {
+    if len > 255 * hash.digest_algorithm().output_len {
+        return Err(FnError::Crypto("HKDF output too long".to_string()));
+    }
+
+    let key = hmac::Key::new(hash, prk);
+    let mut output = Vec::with_capacity(len);
+    let mut block: Vec<u8> = Vec::new();
+    let mut counter = 1u8;
+
+    while output.len() < len {
+        let mut context = hmac::Context::with_key(&key);
+        context.update(&block);
+        context.update(info);
+        context.update(&[counter]);
+        block = context.sign().as_ref().to_vec();
+
+        output.extend_from_slice(&block);
+        counter = counter.wrapping_add(1);
+    }
+
+    output.truncate(len);
+    Ok(output)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Test vector A.1.1 of RFC 9180
+    const INFO: &str = "4f6465206f6e2061204772656369616e2055726e";
+    const RECIPIENT_PUBLIC_KEY: &str =
+        "3948cfe0ad1ddb695d780e59077195da6c56506b027329794ab02bca80815c4d";
+    const PLAINTEXT: &str = "4265617574792069732074727574682c20747275746820626561757479";
+
+    #[test]
+    fn test_key_schedule() {
+        let shared_secret =
+            hex::decode("fe0e18c9f024ce43799ae393c7e8fe8fce9d218875e8227b0187c04e7d2ea1fc")
+                .unwrap();
+        let context = key_schedule(
+            HpkeKem::DHKEM_X25519_HKDF_SHA256,
+            HpkeKdf::HKDF_SHA256,
+            HpkeAead::AES_128_GCM,
+            &shared_secret,
+            &hex::decode(INFO).unwrap(),
+        )
+        .unwrap();
+
+        assert_eq!(hex::encode(context.key), "4531685d41d65f03dc48f6b8302c05b0");
+        assert_eq!(hex::encode(context.base_nonce), "56d890e5accaaf011cff4b7d");
+    }
+
+    #[test]
+    fn test_seal() {
+        let (enc, mut context) = HpkeSenderContext::new(
+            HpkeKem::DHKEM_X25519_HKDF_SHA256,
+            HpkeKdf::HKDF_SHA256,
+            HpkeAead::AES_128_GCM,
+            &hex::decode(RECIPIENT_PUBLIC_KEY).unwrap(),
+            &hex::decode(INFO).unwrap(),
+        )
+        .unwrap();
+
+        // Public key of the fixed ephemeral key
+        assert_eq!(
+            hex::encode(enc),
+            "07aaff3e9fc167275544f4c3a6a17cd837f2ec6e78cd8a57b1e3dfb3cc035a76"
+        );
+
+        let plaintext = hex::decode(PLAINTEXT).unwrap();
+        assert_eq!(
+            hex::encode(context.seal(b"Count-0", &plaintext).unwrap()),
+            "70d1bb55f620d3d8f9777e94eeb51c52350e1206056809131b864fe0010468f0e3f9b3a9ebf37be140e0250930"
+        );
+        assert_eq!(
+            hex::encode(context.seal(b"Count-1", &plaintext).unwrap()),
+            "3322c66e67217b792326b0fd7fcf6342f3664b9245c250c2a3aa633da43051d5ecfa539b74852777ecef429456"
+        );
+    }
+
+    #[test]
+    fn test_seal_chacha20_poly1305() {
+        let (_, mut context) = HpkeSenderContext::new(
+            HpkeKem::DHKEM_X25519_HKDF_SHA256,
+            HpkeKdf::HKDF_SHA256,
+            HpkeAead::CHACHA20_POLY1305,
+            &hex::decode(RECIPIENT_PUBLIC_KEY).unwrap(),
+            &hex::decode(INFO).unwrap(),
+        )
+        .unwrap();
+
+        let plaintext = hex::decode(PLAINTEXT).unwrap();
+        assert_eq!(
+            hex::encode(context.seal(b"Count-0", &plaintext).unwrap()),
+            "832aa3afbc31706e3632e46d31ab33cd6e582ce9a6471e31fc83ac14f77e35c9ae0343c20882804f5e3f5670d9"
+        );
+    }
+
+    #[test]
+    fn test_unsupported_kem() {
+        assert!(HpkeSenderContext::new(
+            HpkeKem::DHKEM_X448_HKDF_SHA512,
+            HpkeKdf::HKDF_SHA256,
+            HpkeAead::AES_128_GCM,
+            &[0; 56],
+            &[],
+        )
+        .is_err());
+    }
+}
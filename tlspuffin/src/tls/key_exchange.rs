@@ -1,14 +1,26 @@
-use std::convert::TryInto;
-
+use num_bigint::BigUint;
 use puffin::algebra::error::FnError;
-use ring::test::rand::FixedByteRandom;
-
-use crate::tls::rustls::{
-    conn::ConnectionRandoms,
-    kx::{KeyExchange, SupportedKxGroup, ALL_KX_GROUPS},
-    msgs::{enums::NamedGroup, handshake::Random},
-    tls12,
-    tls12::ConnectionSecrets,
+use ring::{digest::Digest, test::rand::FixedByteRandom};
+
+use crate::{
+    static_certs::FFDHE2048_PARAMS,
+    tls::{
+        der::{der_expect, der_read, DER_BIT_STRING, DER_CONTEXT_0, DER_INTEGER, DER_SEQUENCE},
+        rustls::{
+            conn::ConnectionRandoms,
+            hash_hs::HandshakeHash,
+            kx::{KeyExchange, SupportedKxGroup, ALL_KX_GROUPS},
+            msgs::{
+                enums::{NamedGroup, ProtocolVersion},
+                handshake::Random,
+            },
+            tls12,
+            tls12::{
+                cbc::{Tls12CbcCipherSuite, TLS_RSA_WITH_AES_128_CBC_SHA},
+                ConnectionSecrets, LegacyConnectionSecrets, Tls12CipherSuite,
+            },
+        },
+    },
 };
 
 fn deterministic_key_exchange(skxg: &'static SupportedKxGroup) -> Result<KeyExchange, FnError> {
@@ -67,34 +79,375 @@ pub fn tls12_new_secrets(
     group: &NamedGroup,
 ) -> Result<ConnectionSecrets, FnError> {
     let suite = &tls12::TLS_ECDHE_RSA_WITH_AES_128_GCM_SHA256; // todo https://github.com/tlspuffin/tlspuffin/issues/129
+    let suite = suite
+        .tls12()
+        .ok_or_else(|| FnError::Unknown("VersionNotCompatibleError".to_string()))?;
+    tls12_new_secrets_with_suite(server_random, server_ecdh_pubkey, group, suite)
+}
 
-    let mut server_random_bytes = vec![0; 32];
+/// Like [`tls12_new_secrets`], but the master secret is derived with the PRF of `suite`
+pub fn tls12_new_secrets_with_suite(
+    server_random: &Random,
+    server_ecdh_pubkey: &Vec<u8>,
+    group: &NamedGroup,
+    suite: &'static Tls12CipherSuite,
+) -> Result<ConnectionSecrets, FnError> {
+    // master_secret is: 01 40 26 dd 53 3c 0a...
+    tls12_secrets_from_key_exchange(server_random, server_ecdh_pubkey, group, suite, None)
+}
 
-    server_random.write_slice(&mut server_random_bytes);
+/// Like [`tls12_new_secrets`], but derives the extended master secret from the `session_hash`,
+/// which covers the handshake up to and including the ClientKeyExchange
+/// (<https://datatracker.ietf.org/doc/html/rfc7627#section-4>).
+pub fn tls12_new_ems_secrets(
+    server_random: &Random,
+    server_ecdh_pubkey: &Vec<u8>,
+    group: &NamedGroup,
+    session_hash: &HandshakeHash,
+) -> Result<ConnectionSecrets, FnError> {
+    let suite = &tls12::TLS_ECDHE_RSA_WITH_AES_128_GCM_SHA256; // todo https://github.com/tlspuffin/tlspuffin/issues/129
+    let suite = suite
+        .tls12()
+        .ok_or_else(|| FnError::Unknown("VersionNotCompatibleError".to_string()))?;
+    tls12_secrets_from_key_exchange(
+        server_random,
+        server_ecdh_pubkey,
+        group,
+        suite,
+        Some(session_hash.get_current_hash()),
+    )
+}
 
-    let server_random = server_random_bytes
-        .try_into()
-        .map_err(|_| FnError::Unknown("Server random did not have length of 32".to_string()))?;
+fn tls12_secrets_from_key_exchange(
+    server_random: &Random,
+    server_ecdh_pubkey: &Vec<u8>,
+    group: &NamedGroup,
+    suite: &'static Tls12CipherSuite,
+    ems_seed: Option<Digest>,
+) -> Result<ConnectionSecrets, FnError> {
     let randoms = ConnectionRandoms {
         client: [1; 32], // todo https://github.com/tlspuffin/tlspuffin/issues/129
-        server: server_random,
+        server: server_random.0,
     };
     let kx = tls12_key_exchange(group)?;
+    ConnectionSecrets::from_key_exchange(kx, server_ecdh_pubkey, ems_seed, randoms, suite)
+        .map_err(|_err| FnError::Crypto("Failed to shared secrets for TLS 1.2".to_string()))
+}
+
+/// Derives the TLS 1.2 secrets from a pre-master secret of the RSA or finite field
+/// Diffie-Hellman key exchange. The secrets protect records like
+/// TLS_ECDHE_RSA_WITH_AES_128_GCM_SHA256, which is the same as for TLS_RSA_WITH_AES_128_GCM_SHA256
+/// and TLS_DHE_RSA_WITH_AES_128_GCM_SHA256.
+pub fn tls12_new_secrets_from_premaster(
+    client_random: &Random,
+    server_random: &Random,
+    premaster_secret: &[u8],
+) -> Result<ConnectionSecrets, FnError> {
+    let suite = &tls12::TLS_ECDHE_RSA_WITH_AES_128_GCM_SHA256; // todo https://github.com/tlspuffin/tlspuffin/issues/129
     let suite = suite
         .tls12()
         .ok_or_else(|| FnError::Unknown("VersionNotCompatibleError".to_string()))?;
-    let secrets =
-        ConnectionSecrets::from_key_exchange(kx, server_ecdh_pubkey, None, randoms, suite)
-            .map_err(|_err| FnError::Crypto("Failed to shared secrets for TLS 1.2".to_string()))?;
-    // master_secret is: 01 40 26 dd 53 3c 0a...
-    Ok(secrets)
+    let randoms = ConnectionRandoms {
+        client: client_random.0,
+        server: server_random.0,
+    };
+
+    Ok(ConnectionSecrets::from_premaster_secret(
+        premaster_secret,
+        None,
+        randoms,
+        suite,
+    ))
+}
+
+/// Like [`tls12_new_secrets_from_premaster`], but derives the extended master secret from the
+/// `session_hash`
+pub fn tls12_new_ems_secrets_from_premaster(
+    client_random: &Random,
+    server_random: &Random,
+    premaster_secret: &[u8],
+    session_hash: &HandshakeHash,
+) -> Result<ConnectionSecrets, FnError> {
+    let suite = &tls12::TLS_ECDHE_RSA_WITH_AES_128_GCM_SHA256; // todo https://github.com/tlspuffin/tlspuffin/issues/129
+    let suite = suite
+        .tls12()
+        .ok_or_else(|| FnError::Unknown("VersionNotCompatibleError".to_string()))?;
+    let randoms = ConnectionRandoms {
+        client: client_random.0,
+        server: server_random.0,
+    };
+
+    Ok(ConnectionSecrets::from_premaster_secret(
+        premaster_secret,
+        Some(session_hash.get_current_hash()),
+        randoms,
+        suite,
+    ))
+}
+
+/// Like [`tls12_new_secrets_from_premaster`], but derives the secrets of `version`, which is TLS 1.1
+/// or older. These versions have no AEAD suites, so the records are protected like
+/// TLS_RSA_WITH_AES_128_CBC_SHA, which all of them support.
+pub fn tls_legacy_new_secrets_from_premaster(
+    client_random: &Random,
+    server_random: &Random,
+    premaster_secret: &[u8],
+    version: &ProtocolVersion,
+) -> Result<LegacyConnectionSecrets, FnError> {
+    tls_legacy_new_secrets_with_suite(
+        client_random,
+        server_random,
+        premaster_secret,
+        version,
+        &TLS_RSA_WITH_AES_128_CBC_SHA,
+    )
+}
+
+/// Like [`tls_legacy_new_secrets_from_premaster`], but the records are protected with `suite`
+pub fn tls_legacy_new_secrets_with_suite(
+    client_random: &Random,
+    server_random: &Random,
+    premaster_secret: &[u8],
+    version: &ProtocolVersion,
+    suite: &'static Tls12CbcCipherSuite,
+) -> Result<LegacyConnectionSecrets, FnError> {
+    match version {
+        ProtocolVersion::TLSv1_1 | ProtocolVersion::TLSv1_0 | ProtocolVersion::SSLv3 => {}
+        _ => {
+            return Err(FnError::Crypto(format!(
+                "{:?} is not TLS 1.1 or older",
+                version
+            )))
+        }
+    }
+
+    let randoms = ConnectionRandoms {
+        client: client_random.0,
+        server: server_random.0,
+    };
+
+    Ok(LegacyConnectionSecrets::from_premaster_secret(
+        premaster_secret,
+        randoms,
+        *version,
+        suite,
+    ))
+}
+
+// ----
+// Finite field Diffie-Hellman
+// ----
+
+/// Private exponent of the attacker. Like [`deterministic_key_exchange`] it is fixed, such that
+/// traces are reproducible.
+const DETERMINISTIC_DH_PRIVATE_EXPONENT: [u8; 32] = [42; 32];
+
+/// Raises `base` to the private exponent of the attacker modulo `prime`
+fn dh_exponentiate(prime: &[u8], base: &[u8]) -> Result<Vec<u8>, FnError> {
+    let prime = BigUint::from_bytes_be(prime);
+    if prime < BigUint::from(3u8) {
+        return Err(FnError::Crypto("DH prime is too small".to_string()));
+    }
+
+    let private = BigUint::from_bytes_be(&DETERMINISTIC_DH_PRIVATE_EXPONENT);
+    Ok(BigUint::from_bytes_be(base)
+        .modpow(&private, &prime)
+        .to_bytes_be())
+}
+
+/// Computes the public value of the attacker for `prime` and `generator`. The parameters are not
+/// validated, such that composite moduli or degenerated generators are possible.
+pub fn tls12_dh_public_key(prime: &[u8], generator: &[u8]) -> Result<Vec<u8>, FnError> {
+    dh_exponentiate(prime, generator)
+}
+
+/// Computes the pre-master secret of the attacker and the peer's public value. Leading zero bytes
+/// are stripped (<https://datatracker.ietf.org/doc/html/rfc5246#section-8.1.2>).
+pub fn tls12_dh_shared_secret(prime: &[u8], peer_public: &[u8]) -> Result<Vec<u8>, FnError> {
+    dh_exponentiate(prime, peer_public)
+}
+
+/// Returns the prime and generator of the group ffdhe2048
+pub fn ffdhe2048_parameters() -> Result<(Vec<u8>, Vec<u8>), FnError> {
+    let invalid = || FnError::Unknown("Failed to parse DH parameters".to_string());
+    let (params, _) = der_expect(FFDHE2048_PARAMS.1, DER_SEQUENCE).ok_or_else(invalid)?;
+    let (prime, rest) = der_expect(params, DER_INTEGER).ok_or_else(invalid)?;
+    let (generator, _) = der_expect(rest, DER_INTEGER).ok_or_else(invalid)?;
+
+    Ok((
+        BigUint::from_bytes_be(prime).to_bytes_be(),
+        BigUint::from_bytes_be(generator).to_bytes_be(),
+    ))
+}
+
+// ----
+// RSA
+// ----
+
+/// Modulus and exponent of an RSA key
+struct RsaKey {
+    modulus: BigUint,
+    exponent: BigUint,
+}
+
+impl RsaKey {
+    /// Reads the public key of an X.509 certificate in DER
+    fn public_from_certificate(certificate: &[u8]) -> Option<Self> {
+        let (certificate, _) = der_expect(certificate, DER_SEQUENCE)?;
+        let (tbs_certificate, _) = der_expect(certificate, DER_SEQUENCE)?;
+
+        let mut rest = tbs_certificate;
+        if rest.first() == Some(&DER_CONTEXT_0) {
+            // version
+            rest = der_read(rest)?.2;
+        }
+        // serialNumber, signature, issuer, validity, subject
+        for _ in 0..5 {
+            rest = der_read(rest)?.2;
+        }
+
+        let (subject_public_key_info, _) = der_expect(rest, DER_SEQUENCE)?;
+        let (_algorithm, rest) = der_expect(subject_public_key_info, DER_SEQUENCE)?;
+        let (public_key, _) = der_expect(rest, DER_BIT_STRING)?;
+        let (&unused_bits, public_key) = public_key.split_first()?;
+        if unused_bits != 0 {
+            return None;
+        }
+
+        let (public_key, _) = der_expect(public_key, DER_SEQUENCE)?;
+        let (modulus, rest) = der_expect(public_key, DER_INTEGER)?;
+        let (exponent, _) = der_expect(rest, DER_INTEGER)?;
+
+        Some(Self {
+            modulus: BigUint::from_bytes_be(modulus),
+            exponent: BigUint::from_bytes_be(exponent),
+        })
+    }
+
+    /// Reads the private key of an RSAPrivateKey (PKCS#1) in DER
+    fn private_from_pkcs1(private_key: &[u8]) -> Option<Self> {
+        let (private_key, _) = der_expect(private_key, DER_SEQUENCE)?;
+        let (_version, rest) = der_expect(private_key, DER_INTEGER)?;
+        let (modulus, rest) = der_expect(rest, DER_INTEGER)?;
+        let (_public_exponent, rest) = der_expect(rest, DER_INTEGER)?;
+        let (private_exponent, _) = der_expect(rest, DER_INTEGER)?;
+
+        Some(Self {
+            modulus: BigUint::from_bytes_be(modulus),
+            exponent: BigUint::from_bytes_be(private_exponent),
+        })
+    }
+
+    /// Length of the modulus in bytes
+    fn len(&self) -> usize {
+        ((self.modulus.bits() + 7) / 8) as usize
+    }
+
+    /// Raw RSA operation. The result is left-padded with zeros to the length of the modulus.
+    fn apply(&self, input: &[u8]) -> Result<Vec<u8>, FnError> {
+        let input = BigUint::from_bytes_be(input);
+        if input >= self.modulus {
+            return Err(FnError::Crypto(
+                "RSA input is not smaller than the modulus".to_string(),
+            ));
+        }
+
+        let output = input.modpow(&self.exponent, &self.modulus).to_bytes_be();
+        let mut padded = vec![0u8; self.len().saturating_sub(output.len())];
+        padded.extend_from_slice(&output);
+        Ok(padded)
+    }
+}
+
+fn rsa_public_key(certificate: &[u8]) -> Result<RsaKey, FnError> {
+    RsaKey::public_from_certificate(certificate)
+        .ok_or_else(|| FnError::Crypto("Failed to read RSA public key of certificate".to_string()))
+}
+
+/// Length of the modulus of the RSA key in `certificate`
+pub fn rsa_modulus_len(certificate: &[u8]) -> Result<usize, FnError> {
+    Ok(rsa_public_key(certificate)?.len())
+}
+
+/// Textbook RSA encryption of `encoded_message` with the public key of `certificate`. The
+/// message is not padded, which gives the attacker full control over the PKCS#1 encoding.
+pub fn rsa_encrypt_raw(encoded_message: &[u8], certificate: &[u8]) -> Result<Vec<u8>, FnError> {
+    rsa_public_key(certificate)?.apply(encoded_message)
+}
+
+/// Textbook RSA decryption of `ciphertext` with a PKCS#1 encoded private key
+pub fn rsa_decrypt_raw(ciphertext: &[u8], private_key: &[u8]) -> Result<Vec<u8>, FnError> {
+    RsaKey::private_from_pkcs1(private_key)
+        .ok_or_else(|| FnError::Crypto("Failed to read RSA private key".to_string()))?
+        .apply(ciphertext)
+}
+
+/// Padding byte of the PKCS#1 encryption block. It is fixed and non-zero, such that traces are
+/// reproducible.
+const DETERMINISTIC_PKCS1_PADDING_BYTE: u8 = 42;
+
+/// Encodes `message` as `00 || block_type || PS || 00 || message` of length `len`
+/// (<https://datatracker.ietf.org/doc/html/rfc8017#section-7.2.1>). Well-formed blocks for
+/// encryption have the block type 2. If `separator` is false, the zero byte which terminates the
+/// padding string is left out.
+pub fn pkcs1_encode(
+    message: &[u8],
+    len: usize,
+    block_type: u8,
+    separator: bool,
+) -> Result<Vec<u8>, FnError> {
+    let padding_len = len
+        .checked_sub(2 + usize::from(separator) + message.len())
+        .ok_or_else(|| FnError::Crypto("Message too long for PKCS#1 encoding".to_string()))?;
+
+    let mut encoded = Vec::with_capacity(len);
+    encoded.push(0x00);
+    encoded.push(block_type);
+    encoded.extend(std::iter::repeat(DETERMINISTIC_PKCS1_PADDING_BYTE).take(padding_len));
+    if separator {
+        encoded.push(0x00);
+    }
+    encoded.extend_from_slice(message);
+    Ok(encoded)
+}
+
+/// Minimal length of the padding string of PKCS#1 encryption blocks
+const PKCS1_MIN_PADDING_LEN: usize = 8;
+
+/// Decodes a PKCS#1 encryption block of block type 2
+pub fn pkcs1_decode(encoded: &[u8]) -> Result<Vec<u8>, FnError> {
+    let invalid = || FnError::Crypto("Invalid PKCS#1 encoding".to_string());
+
+    match encoded {
+        [0x00, 0x02, rest @ ..] => {
+            let separator = rest
+                .iter()
+                .position(|byte| *byte == 0)
+                .ok_or_else(invalid)?;
+            if separator < PKCS1_MIN_PADDING_LEN {
+                return Err(invalid());
+            }
+            Ok(rest[separator + 1..].to_vec())
+        }
+        _ => Err(invalid()),
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use test_log::test;
 
-    use crate::tls::{key_exchange::deterministic_key_exchange, rustls::kx::SECP384R1};
+    use crate::{
+        static_certs::{ALICE_CERT, ALICE_PRIVATE_KEY},
+        tls::{
+            key_exchange::{
+                deterministic_key_exchange, ffdhe2048_parameters, pkcs1_decode, pkcs1_encode,
+                rsa_decrypt_raw, rsa_encrypt_raw, rsa_modulus_len,
+                tls12_new_ems_secrets_from_premaster, tls12_new_secrets_from_premaster,
+            },
+            rustls::{hash_hs::HandshakeHash, kx::SECP384R1, msgs::handshake::Random},
+        },
+    };
 
     #[test]
     fn test_deterministic_key() {
@@ -103,4 +456,58 @@ mod tests {
 
         assert_eq!(a.pubkey.as_ref(), b.pubkey.as_ref())
     }
+
+    #[test]
+    fn test_ffdhe2048_parameters() {
+        let (prime, generator) = ffdhe2048_parameters().unwrap();
+        assert_eq!(prime.len(), 256);
+        assert_eq!(generator, vec![2]);
+    }
+
+    #[test]
+    fn test_rsa_premaster_roundtrip() {
+        let premaster = vec![3u8; 48];
+        let len = rsa_modulus_len(ALICE_CERT.1).unwrap();
+        assert_eq!(len, 256);
+
+        let encoded = pkcs1_encode(&premaster, len, 2, true).unwrap();
+        let encrypted = rsa_encrypt_raw(&encoded, ALICE_CERT.1).unwrap();
+        let decrypted = rsa_decrypt_raw(&encrypted, ALICE_PRIVATE_KEY.1).unwrap();
+
+        assert_eq!(decrypted, encoded);
+        assert_eq!(pkcs1_decode(&decrypted).unwrap(), premaster);
+
+        let malformed = pkcs1_encode(&premaster, len, 1, true).unwrap();
+        assert!(pkcs1_decode(&malformed).is_err());
+    }
+
+    #[test]
+    fn test_ems_master_secret() {
+        let client_random = Random([1; 32]);
+        let server_random = Random([2; 32]);
+        let premaster = [3u8; 48];
+        let session_hash = HandshakeHash::new(&ring::digest::SHA256);
+
+        let classic =
+            tls12_new_secrets_from_premaster(&client_random, &server_random, &premaster).unwrap();
+        let ems = tls12_new_ems_secrets_from_premaster(
+            &client_random,
+            &server_random,
+            &premaster,
+            &session_hash,
+        )
+        .unwrap();
+        assert_ne!(classic.get_master_secret(), ems.get_master_secret());
+
+        // The extended master secret only depends on the session hash and not on the randoms
+        let other_client_random = Random([4; 32]);
+        let other = tls12_new_ems_secrets_from_premaster(
+            &other_client_random,
+            &server_random,
+            &premaster,
+            &session_hash,
+        )
+        .unwrap();
+        assert_eq!(ems.get_master_secret(), other.get_master_secret());
+    }
 }
@@ -71,6 +71,55 @@ pub fn tls13_application_traffic_secret(
     ))
 }
 
+/// Upper bound for the number of KeyUpdates, such that mutated traces do not derive secrets forever
+const MAX_KEY_UPDATE_GENERATION: u64 = 1024;
+
+/// Derives the application traffic secret after `generation` KeyUpdates of the client or the
+/// server. Generation 0 is the secret which is derived from the handshake.
+pub fn tls13_application_traffic_secret_generation(
+    server_hello: &HandshakeHash,
+    server_finished: &HandshakeHash,
+    server_key_share: &Option<Vec<u8>>,
+    psk: &Option<Vec<u8>>,
+    group: &NamedGroup,
+    client: bool,
+    generation: u64,
+) -> Result<(&'static SupportedCipherSuite, Prk), FnError> {
+    if generation > MAX_KEY_UPDATE_GENERATION {
+        return Err(FnError::Unknown(format!(
+            "Too many key updates: {}",
+            generation
+        )));
+    }
+
+    let (suite, secret, pending) = tls13_application_traffic_secret(
+        server_hello,
+        server_finished,
+        server_key_share,
+        psk,
+        group,
+        client,
+    )?;
+
+    if generation == 0 {
+        return Ok((suite, secret));
+    }
+
+    let (mut traffic, _tag, _client_secret) =
+        pending.sign_client_finish_raw(&server_finished.get_current_hash_raw());
+
+    let mut secret = secret;
+    for _ in 0..generation {
+        secret = if client {
+            traffic.next_client_application_traffic_secret()
+        } else {
+            traffic.next_server_application_traffic_secret()
+        };
+    }
+
+    Ok((suite, secret))
+}
+
 pub fn tls13_derive_psk(
     server_hello: &HandshakeHash,
     server_finished: &HandshakeHash,
@@ -98,6 +147,29 @@ pub fn tls13_derive_psk(
     Ok(psk)
 }
 
+/// Derives the client_early_traffic_secret which protects 0-RTT data and the EndOfEarlyData
+/// message. `client_hello` is the transcript up to and including the ClientHello.
+pub fn tls13_client_early_traffic_secret(
+    client_hello: &HandshakeHash,
+    psk: &Vec<u8>,
+) -> Result<(&'static SupportedCipherSuite, Prk), FnError> {
+    let client_random = &[1u8; 32]; // todo see op_random() https://github.com/tlspuffin/tlspuffin/issues/129
+    let suite = &crate::tls::rustls::tls13::TLS13_AES_128_GCM_SHA256; // todo see op_cipher_suites() https://github.com/tlspuffin/tlspuffin/issues/129
+    let hkdf_algorithm = suite
+        .tls13()
+        .ok_or_else(|| FnError::Crypto("No tls 1.3 suite".to_owned()))?
+        .hkdf_algorithm;
+
+    let early = KeyScheduleEarly::new(hkdf_algorithm, psk.as_slice());
+    let secret = early.client_early_traffic_secret(
+        &client_hello.get_current_hash(),
+        &NoKeyLog {},
+        client_random,
+    );
+
+    Ok((suite, secret))
+}
+
 pub fn dhe_key_schedule(
     suite: &SupportedCipherSuite,
     group: &NamedGroup,
@@ -7,17 +7,21 @@ use puffin::{algebra::error::FnError, define_signature, error::Error};
 mod key_exchange;
 mod key_schedule;
 
+pub mod der;
+pub mod hpke;
 pub mod rustls;
 pub mod seeds;
 pub mod trace_helper;
 pub mod violation;
 pub mod vulnerabilities;
+pub mod x509;
 
 /// This modules contains all the concrete implementations of function symbols.
 #[path = "."]
 pub mod fn_impl {
     pub mod fn_cert;
     pub mod fn_constants;
+    pub mod fn_ech;
     pub mod fn_extensions;
     pub mod fn_fields;
     pub mod fn_messages;
@@ -26,6 +30,7 @@ pub mod fn_impl {
 
     pub use fn_cert::*;
     pub use fn_constants::*;
+    pub use fn_ech::*;
     pub use fn_extensions::*;
     pub use fn_fields::*;
     pub use fn_messages::*;
@@ -77,6 +82,8 @@ define_signature!(
     fn_seq_15
     fn_seq_16
     fn_large_length
+    fn_pkcs1_block_type_1
+    fn_pkcs1_block_type_2
     fn_empty_bytes_vec
     fn_large_bytes_vec
     // messages
@@ -93,6 +100,7 @@ define_signature!(
     fn_client_key_exchange
     fn_empty_handshake_message
     fn_encrypted_extensions
+    fn_end_of_early_data
     fn_finished
     fn_heartbeat
     fn_heartbeat_fake_length
@@ -107,6 +115,20 @@ define_signature!(
     fn_server_hello
     fn_server_hello_done
     fn_server_key_exchange
+    fn_server_key_exchange_dhe
+    // record layer
+    fn_new_records
+    fn_append_record
+    fn_append_records
+    fn_message_record
+    fn_with_record_version
+    fn_new_fragment_sizes
+    fn_append_fragment_size
+    fn_fragment
+    fn_fragment_max
+    fn_new_messages
+    fn_append_message
+    fn_coalesce
     // extensions
     fn_client_extensions_new
     fn_client_extensions_append
@@ -182,6 +204,9 @@ define_signature!(
     // fields
     fn_protocol_version13
     fn_protocol_version12
+    fn_protocol_version11
+    fn_protocol_version10
+    fn_protocol_version_ssl3
     fn_new_session_id
     fn_empty_session_id
     fn_new_random
@@ -194,9 +219,23 @@ define_signature!(
     fn_verify_data
     fn_verify_data_server
     fn_sign_transcript
+    fn_sign_transcript_premaster
+    fn_sign_transcript_server_premaster
+    fn_sign_transcript_legacy
+    fn_sign_transcript_server_legacy
+    fn_sign_transcript_ems
+    fn_sign_transcript_premaster_ems
+    fn_sign_transcript_server_premaster_ems
     fn_new_cipher_suites
     fn_append_cipher_suite
     fn_cipher_suite12
+    fn_cipher_suite12_rsa_aes_128_gcm_sha256
+    fn_cipher_suite12_rsa_aes_128_cbc_sha
+    fn_cipher_suite12_dhe_rsa_aes_128_gcm_sha256
+    fn_cipher_suite12_chacha20_poly1305_sha256
+    fn_cipher_suite12_aes_128_cbc_sha
+    fn_cipher_suite12_aes_256_cbc_sha
+    fn_cipher_suite12_aes_128_cbc_sha256
     fn_cipher_suite13_aes_128_gcm_sha256
     fn_cipher_suite13_aes_256_gcm_sha384
     fn_cipher_suite13_aes_128_ccm_sha256
@@ -210,7 +249,11 @@ define_signature!(
     fn_psk
     fn_decrypt_application
     fn_encrypt_handshake
+    fn_encrypt_handshake_records
     fn_encrypt_application
+    fn_decrypt_application_updated
+    fn_encrypt_application_updated
+    fn_encrypt_early_data
     fn_derive_psk
     fn_derive_binder
     fn_fill_binder
@@ -218,10 +261,46 @@ define_signature!(
     fn_get_ticket_age_add
     fn_get_ticket_nonce
     fn_new_transcript12
+    fn_new_transcript_legacy
     fn_decode_ecdh_pubkey
     fn_encode_ec_pubkey12
     fn_new_pubkey12
     fn_encrypt12
+    fn_encrypt12_with_suite
+    fn_decrypt12_with_suite
+    fn_encrypt12_cbc
+    fn_decrypt12_cbc
+    fn_cbc_padding12
+    fn_encrypt12_premaster
+    fn_decrypt12_premaster
+    fn_encrypt_legacy
+    fn_encrypt12_ems
+    fn_encrypt12_premaster_ems
+    fn_decrypt12_premaster_ems
+    fn_rsa_premaster_secret12
+    fn_rsa_pkcs1_encode12
+    fn_rsa_pkcs1_encode12_malformed
+    fn_rsa_encrypt12
+    fn_encode_rsa_premaster12
+    fn_rsa_decrypt_premaster12
+    fn_ffdhe2048_prime
+    fn_ffdhe2048_generator
+    fn_dh_weak_prime
+    fn_dh_generator_one
+    fn_new_dh_pubkey12
+    fn_encode_dh_pubkey12
+    fn_dh_server_params12
+    fn_decode_dh_prime12
+    fn_decode_dh_generator12
+    fn_decode_dh_pubkey12
+    fn_decode_client_dh_pubkey12
+    fn_dh_premaster_secret12
+    fn_get_client_random
+    fn_get_server_random
+    fn_get_server_key_exchange
+    fn_get_client_key_exchange
+    fn_get_verify_data
+    fn_server_renegotiation_info
     fn_new_certificate
     fn_new_certificates
     fn_append_certificate
@@ -241,6 +320,7 @@ define_signature!(
     fn_alice_key
     fn_eve_cert
     fn_random_ec_cert
+    fn_certificate_from_der
     fn_certificate_entry
     fn_empty_certificate_chain
     fn_append_certificate_entry
@@ -248,10 +328,56 @@ define_signature!(
     fn_eve_pkcs1_signature
     fn_rsa_sign_client
     fn_rsa_sign_server
+    fn_rsa_sign_server_params12
     fn_ecdsa_sign_client
     fn_ecdsa_sign_server
+    fn_x509_name_empty
+    fn_x509_name_append_common_name
+    fn_x509_name_append_organization
+    fn_x509_name_append_country
+    fn_x509_common_name
+    fn_x509_subject
+    fn_x509_issuer
+    fn_x509_public_key
+    fn_x509_validity
+    fn_x509_validity_default
+    fn_x509_validity_expired
+    fn_x509_validity_not_yet_valid
+    fn_x509_extensions_new
+    fn_x509_extensions_append
+    fn_x509_extension
+    fn_x509_unknown_critical_extension
+    fn_x509_san_dns_extension
+    fn_x509_san_ip_extension
+    fn_x509_key_usage_extension
+    fn_x509_key_usage_leaf
+    fn_x509_key_usage_ca
+    fn_x509_basic_constraints_extension
+    fn_x509_basic_constraints_path_len_extension
+    fn_x509_server_auth_extension
+    fn_x509_client_auth_extension
+    fn_x509_tbs_certificate
+    fn_x509_sign
+    fn_x509_sign_broken
+    fn_x509_with_signature
     fn_rsa_pss_signature_algorithm
     fn_rsa_pkcs1_signature_algorithm
     fn_invalid_signature_algorithm
     fn_ecdsa_signature_algorithm
+    // encrypted client hello
+    fn_ech_retry_configs
+    fn_ech_config
+    fn_ech_config_with_public_key
+    fn_ech_inner_extension
+    fn_ech_grease_extension
+    fn_ech_outer_extensions_extension
+    fn_new_extension_types
+    fn_append_extension_type
+    fn_key_share_extension_type
+    fn_supported_versions_extension_type
+    fn_ech_extension_type
+    fn_ech_client_hello_outer
+    fn_ech_client_hello_outer_padded
+    fn_ech_client_hello_outer_mismatching_aad
+    fn_ech_zero_padding
 );
@@ -65,6 +65,7 @@ impl HandshakeHashBuffer {
                 false => None,
             },
             override_buffer: None,
+            legacy_buffer: None,
         }
     }
 }
@@ -85,6 +86,9 @@ pub struct HandshakeHash {
     client_auth: Option<Vec<u8>>,
 
     override_buffer: Option<Vec<u8>>,
+
+    /// Buffer of all messages for the MD5 and SHA-1 hash of TLS 1.1 and older
+    legacy_buffer: Option<Vec<u8>>,
 }
 
 impl HandshakeHash {
@@ -94,6 +98,7 @@ impl HandshakeHash {
             ctx,
             client_auth: None,
             override_buffer: None,
+            legacy_buffer: None,
         }
     }
 
@@ -104,6 +109,19 @@ impl HandshakeHash {
             ctx,
             client_auth: None,
             override_buffer: Some(static_buffer),
+            legacy_buffer: None,
+        }
+    }
+
+    /// Creates a handshake hash which additionally buffers all messages, such that the
+    /// transcript can be hashed with MD5 and SHA-1 for TLS 1.1 and older.
+    pub fn new_buffered(alg: &'static digest::Algorithm) -> HandshakeHash {
+        let ctx = digest::Context::new(alg);
+        HandshakeHash {
+            ctx,
+            client_auth: None,
+            override_buffer: None,
+            legacy_buffer: Some(Vec::new()),
         }
     }
 
@@ -130,6 +148,10 @@ impl HandshakeHash {
             buffer.extend_from_slice(buf);
         }
 
+        if let Some(buffer) = &mut self.legacy_buffer {
+            buffer.extend_from_slice(buf);
+        }
+
         self
     }
 
@@ -179,6 +201,12 @@ impl HandshakeHash {
         }
     }
 
+    /// All messages so far, if this hash was created by [`HandshakeHash::new_buffered`]. TLS 1.1
+    /// and older hash them with both MD5 and SHA-1.
+    pub fn get_legacy_buffer(&self) -> Option<&[u8]> {
+        self.legacy_buffer.as_deref()
+    }
+
     /// Takes this object's buffer containing all handshake messages
     /// so far.  This method only works once; it resets the buffer
     /// to empty.
@@ -196,7 +224,7 @@ impl HandshakeHash {
 mod test {
     use ring::digest;
 
-    use super::HandshakeHashBuffer;
+    use super::{HandshakeHash, HandshakeHashBuffer};
 
     #[test]
     fn hashes_correctly() {
@@ -253,4 +281,14 @@ mod test {
         assert_eq!(h[2], 0x18);
         assert_eq!(h[3], 0x5c);
     }
+
+    #[test]
+    fn buffers_legacy_messages() {
+        let mut hh = HandshakeHash::new_buffered(&digest::SHA1_FOR_LEGACY_USE_ONLY);
+        assert_eq!(hh.client_auth, None);
+        hh.update_raw(b"hello");
+        hh.update_raw(b"world");
+        assert_eq!(hh.get_legacy_buffer(), Some(&b"helloworld"[..]));
+        assert_eq!(hh.client_auth, None);
+    }
 }
@@ -239,6 +239,8 @@ enum_builder! {
         TransportParameters => 0x0039,
         NextProtocolNegotiation => 0x3374,
         ChannelId => 0x754f,
+        EchOuterExtensions => 0xfd00,
+        EncryptedClientHello => 0xfe0d,
         RenegotiationInfo => 0xff01,
         TransportParametersDraft => 0xffa5
     }
@@ -798,3 +800,57 @@ enum_builder! {
         OCSP => 0x01
     }
 }
+
+enum_builder! {
+    /// The `EchClientHelloType` TLS protocol enum.  Values in this enum are taken
+    /// from draft-ietf-tls-esni.
+    /// The `Unknown` item is used when processing unrecognised ordinals.
+    @U8
+    EnumName: EchClientHelloType;
+    EnumVal{
+        Outer => 0x00,
+        Inner => 0x01
+    }
+}
+
+enum_builder! {
+    /// The `HpkeKem` HPKE enum.  Values in this enum are taken
+    /// from RFC 9180 and are listed by IANA.
+    /// The `Unknown` item is used when processing unrecognised ordinals.
+    @U16
+    EnumName: HpkeKem;
+    EnumVal{
+        DHKEM_P256_HKDF_SHA256 => 0x0010,
+        DHKEM_P384_HKDF_SHA384 => 0x0011,
+        DHKEM_P521_HKDF_SHA512 => 0x0012,
+        DHKEM_X25519_HKDF_SHA256 => 0x0020,
+        DHKEM_X448_HKDF_SHA512 => 0x0021
+    }
+}
+
+enum_builder! {
+    /// The `HpkeKdf` HPKE enum.  Values in this enum are taken
+    /// from RFC 9180 and are listed by IANA.
+    /// The `Unknown` item is used when processing unrecognised ordinals.
+    @U16
+    EnumName: HpkeKdf;
+    EnumVal{
+        HKDF_SHA256 => 0x0001,
+        HKDF_SHA384 => 0x0002,
+        HKDF_SHA512 => 0x0003
+    }
+}
+
+enum_builder! {
+    /// The `HpkeAead` HPKE enum.  Values in this enum are taken
+    /// from RFC 9180 and are listed by IANA.
+    /// The `Unknown` item is used when processing unrecognised ordinals.
+    @U16
+    EnumName: HpkeAead;
+    EnumVal{
+        AES_128_GCM => 0x0001,
+        AES_256_GCM => 0x0002,
+        CHACHA20_POLY1305 => 0x0003,
+        EXPORT_ONLY => 0xffff
+    }
+}
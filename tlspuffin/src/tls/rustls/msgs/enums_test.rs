@@ -92,6 +92,9 @@ fn test_enums() {
         KeyUpdateRequest::UpdateRequested,
     );
     test_enum8::<CertificateStatusType>(CertificateStatusType::OCSP, CertificateStatusType::OCSP);
+    test_enum8::<EchClientHelloType>(EchClientHelloType::Outer, EchClientHelloType::Inner);
+    test_enum16::<HpkeKdf>(HpkeKdf::HKDF_SHA256, HpkeKdf::HKDF_SHA512);
+    test_enum16::<HpkeAead>(HpkeAead::AES_128_GCM, HpkeAead::CHACHA20_POLY1305);
 }
 
 #[test]
@@ -29,6 +29,17 @@ impl MessageFragmenter {
         Ok(new)
     }
 
+    /// Make a new fragmenter which produces fragments with at most `max_fragment_len` bytes of
+    /// payload. Unlike [`MessageFragmenter::new`], this does not enforce a sensible lower bound.
+    pub fn with_max_fragment_len(max_fragment_len: usize) -> Result<Self, Error> {
+        match max_fragment_len {
+            1..=MAX_FRAGMENT_LEN => Ok(Self {
+                max_frag: max_fragment_len,
+            }),
+            _ => Err(Error::BadMaxFragmentSize),
+        }
+    }
+
     /// Take the Message `msg` and re-fragment it into new
     /// messages whose fragment is no more than max_frag.
     /// The new messages are appended to the `out` deque.
@@ -170,4 +181,28 @@ mod tests {
         );
         assert_eq!(q.len(), 0);
     }
+
+    #[test]
+    fn tiny_fragments() {
+        let m = PlainMessage {
+            typ: ContentType::Handshake,
+            version: ProtocolVersion::TLSv1_2,
+            payload: Payload::new(b"\x01\x02\x03".to_vec()),
+        };
+
+        let frag = MessageFragmenter::with_max_fragment_len(1).unwrap();
+        let mut q = VecDeque::new();
+        frag.fragment(m, &mut q);
+        for byte in 1..=3u8 {
+            msg_eq(
+                q.pop_front(),
+                PACKET_OVERHEAD + 1,
+                &ContentType::Handshake,
+                &ProtocolVersion::TLSv1_2,
+                &[byte],
+            );
+        }
+        assert_eq!(q.len(), 0);
+        assert!(MessageFragmenter::with_max_fragment_len(0).is_err());
+    }
 }
@@ -11,9 +11,9 @@ use crate::tls::rustls::{
         base::{Payload, PayloadU16, PayloadU24, PayloadU8},
         enums::{
             CertificateStatusType, CipherSuite, ClientCertificateType, Compression, ECCurveType,
-            ECPointFormat, ExtensionType, HandshakeType, HashAlgorithm, KeyUpdateRequest,
-            NamedGroup, PSKKeyExchangeMode, ProtocolVersion, ServerNameType, SignatureAlgorithm,
-            SignatureScheme,
+            ECPointFormat, EchClientHelloType, ExtensionType, HandshakeType, HashAlgorithm,
+            HpkeAead, HpkeKdf, HpkeKem, KeyUpdateRequest, NamedGroup, PSKKeyExchangeMode,
+            ProtocolVersion, ServerNameType, SignatureAlgorithm, SignatureScheme,
         },
     },
     rand,
@@ -409,6 +409,212 @@ impl Codec for KeyShareEntry {
     }
 }
 
+// --- Encrypted Client Hello (draft-ietf-tls-esni) ---
+
+/// Version of the `ECHConfig` structure which is implemented
+pub const ECH_CONFIG_VERSION: u16 = 0xfe0d;
+
+#[derive(Clone, Debug)]
+pub struct HpkeSymmetricCipherSuite {
+    pub kdf_id: HpkeKdf,
+    pub aead_id: HpkeAead,
+}
+
+impl Codec for HpkeSymmetricCipherSuite {
+    fn encode(&self, bytes: &mut Vec<u8>) {
+        self.kdf_id.encode(bytes);
+        self.aead_id.encode(bytes);
+    }
+
+    fn read(r: &mut Reader) -> Option<Self> {
+        let kdf_id = HpkeKdf::read(r)?;
+        let aead_id = HpkeAead::read(r)?;
+
+        Some(Self { kdf_id, aead_id })
+    }
+}
+
+declare_u16_vec!(HpkeSymmetricCipherSuites, HpkeSymmetricCipherSuite);
+
+#[derive(Clone, Debug)]
+pub struct HpkeKeyConfig {
+    pub config_id: u8,
+    pub kem_id: HpkeKem,
+    pub public_key: PayloadU16,
+    pub symmetric_cipher_suites: HpkeSymmetricCipherSuites,
+}
+
+impl Codec for HpkeKeyConfig {
+    fn encode(&self, bytes: &mut Vec<u8>) {
+        self.config_id.encode(bytes);
+        self.kem_id.encode(bytes);
+        self.public_key.encode(bytes);
+        self.symmetric_cipher_suites.encode(bytes);
+    }
+
+    fn read(r: &mut Reader) -> Option<Self> {
+        let config_id = u8::read(r)?;
+        let kem_id = HpkeKem::read(r)?;
+        let public_key = PayloadU16::read(r)?;
+        let symmetric_cipher_suites = HpkeSymmetricCipherSuites::read(r)?;
+
+        Some(Self {
+            config_id,
+            kem_id,
+            public_key,
+            symmetric_cipher_suites,
+        })
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct EchConfigContents {
+    pub key_config: HpkeKeyConfig,
+    pub maximum_name_length: u8,
+    pub public_name: PayloadU8,
+    /// Encoded list of `ECHConfigExtension`s
+    pub extensions: PayloadU16,
+}
+
+impl Codec for EchConfigContents {
+    fn encode(&self, bytes: &mut Vec<u8>) {
+        self.key_config.encode(bytes);
+        self.maximum_name_length.encode(bytes);
+        self.public_name.encode(bytes);
+        self.extensions.encode(bytes);
+    }
+
+    fn read(r: &mut Reader) -> Option<Self> {
+        let key_config = HpkeKeyConfig::read(r)?;
+        let maximum_name_length = u8::read(r)?;
+        let public_name = PayloadU8::read(r)?;
+        let extensions = PayloadU16::read(r)?;
+
+        Some(Self {
+            key_config,
+            maximum_name_length,
+            public_name,
+            extensions,
+        })
+    }
+}
+
+#[derive(Clone, Debug)]
+pub enum EchConfig {
+    /// `ECHConfig` of version [`ECH_CONFIG_VERSION`]
+    Current(EchConfigContents),
+    /// `ECHConfig` of a different version, which clients are supposed to skip
+    Unknown { version: u16, contents: Payload },
+}
+
+impl Codec for EchConfig {
+    fn encode(&self, bytes: &mut Vec<u8>) {
+        let mut sub: Vec<u8> = Vec::new();
+        let version = match *self {
+            Self::Current(ref r) => {
+                r.encode(&mut sub);
+                ECH_CONFIG_VERSION
+            }
+            Self::Unknown {
+                version,
+                ref contents,
+            } => {
+                contents.encode(&mut sub);
+                version
+            }
+        };
+
+        version.encode(bytes);
+        (sub.len() as u16).encode(bytes);
+        bytes.append(&mut sub);
+    }
+
+    fn read(r: &mut Reader) -> Option<Self> {
+        let version = u16::read(r)?;
+        let len = u16::read(r)? as usize;
+        let mut sub = r.sub(len)?;
+
+        let config = match version {
+            ECH_CONFIG_VERSION => Self::Current(EchConfigContents::read(&mut sub)?),
+            _ => Self::Unknown {
+                version,
+                contents: Payload::read(&mut sub),
+            },
+        };
+
+        if sub.any_left() {
+            None
+        } else {
+            Some(config)
+        }
+    }
+}
+
+declare_u16_vec!(EchConfigList, EchConfig);
+
+#[derive(Clone, Debug)]
+pub struct EchClientHelloOuter {
+    pub cipher_suite: HpkeSymmetricCipherSuite,
+    pub config_id: u8,
+    /// Encapsulated key of the HPKE context
+    pub enc: PayloadU16,
+    /// Encrypted `EncodedClientHelloInner`
+    pub payload: PayloadU16,
+}
+
+impl Codec for EchClientHelloOuter {
+    fn encode(&self, bytes: &mut Vec<u8>) {
+        self.cipher_suite.encode(bytes);
+        self.config_id.encode(bytes);
+        self.enc.encode(bytes);
+        self.payload.encode(bytes);
+    }
+
+    fn read(r: &mut Reader) -> Option<Self> {
+        let cipher_suite = HpkeSymmetricCipherSuite::read(r)?;
+        let config_id = u8::read(r)?;
+        let enc = PayloadU16::read(r)?;
+        let payload = PayloadU16::read(r)?;
+
+        Some(Self {
+            cipher_suite,
+            config_id,
+            enc,
+            payload,
+        })
+    }
+}
+
+#[derive(Clone, Debug)]
+pub enum EchClientHello {
+    Outer(EchClientHelloOuter),
+    Inner,
+}
+
+impl Codec for EchClientHello {
+    fn encode(&self, bytes: &mut Vec<u8>) {
+        match *self {
+            Self::Outer(ref r) => {
+                EchClientHelloType::Outer.encode(bytes);
+                r.encode(bytes);
+            }
+            Self::Inner => EchClientHelloType::Inner.encode(bytes),
+        }
+    }
+
+    fn read(r: &mut Reader) -> Option<Self> {
+        match EchClientHelloType::read(r)? {
+            EchClientHelloType::Outer => Some(Self::Outer(EchClientHelloOuter::read(r)?)),
+            EchClientHelloType::Inner => Some(Self::Inner),
+            EchClientHelloType::Unknown(_) => None,
+        }
+    }
+}
+
+/// Types of the extensions which are copied from the ClientHelloOuter into the
+/// ClientHelloInner
+declare_u8_vec!(EchOuterExtensions, ExtensionType);
+
 // --- TLS 1.3 PresharedKey offers ---
 #[derive(Clone, Debug)]
 pub struct PresharedKeyIdentity {
@@ -572,6 +778,8 @@ pub enum ClientExtension {
     EarlyData,
     RenegotiationInfo(PayloadU8),
     SignatureAlgorithmsCert(SupportedSignatureSchemes),
+    EncryptedClientHello(EchClientHello),
+    EchOuterExtensions(EchOuterExtensions),
     Unknown(UnknownExtension),
 }
 
@@ -597,6 +805,8 @@ impl ClientExtension {
             Self::EarlyData => ExtensionType::EarlyData,
             ClientExtension::RenegotiationInfo(_) => ExtensionType::RenegotiationInfo,
             Self::SignatureAlgorithmsCert(_) => ExtensionType::SignatureAlgorithmsCert,
+            Self::EncryptedClientHello(_) => ExtensionType::EncryptedClientHello,
+            Self::EchOuterExtensions(_) => ExtensionType::EchOuterExtensions,
             Self::Unknown(ref r) => r.typ,
         }
     }
@@ -629,6 +839,8 @@ impl Codec for ClientExtension {
             }
             Self::RenegotiationInfo(ref r) => r.encode(&mut sub),
             Self::SignatureAlgorithmsCert(ref r) => r.encode(&mut sub),
+            Self::EncryptedClientHello(ref r) => r.encode(&mut sub),
+            Self::EchOuterExtensions(ref r) => r.encode(&mut sub),
             Self::Unknown(ref r) => r.encode(&mut sub),
         }
 
@@ -691,6 +903,12 @@ impl Codec for ClientExtension {
                 ClientExtension::SignatureAlgorithmsCert(schemes)
             }
             ExtensionType::EarlyData if !sub.any_left() => Self::EarlyData,
+            ExtensionType::EncryptedClientHello => {
+                Self::EncryptedClientHello(EchClientHello::read(&mut sub)?)
+            }
+            ExtensionType::EchOuterExtensions => {
+                Self::EchOuterExtensions(EchOuterExtensions::read(&mut sub)?)
+            }
             _ => Self::Unknown(UnknownExtension::read(typ, &mut sub)),
         };
 
@@ -751,6 +969,8 @@ pub enum ServerExtension {
     TransportParameters(Vec<u8>),
     TransportParametersDraft(Vec<u8>),
     EarlyData,
+    /// `retry_configs` of a server which rejected ECH
+    EncryptedClientHello(EchConfigList),
     Unknown(UnknownExtension),
 }
 
@@ -771,6 +991,7 @@ impl ServerExtension {
             Self::TransportParameters(_) => ExtensionType::TransportParameters,
             Self::TransportParametersDraft(_) => ExtensionType::TransportParametersDraft,
             Self::EarlyData => ExtensionType::EarlyData,
+            Self::EncryptedClientHello(_) => ExtensionType::EncryptedClientHello,
             Self::Unknown(ref r) => r.typ,
         }
     }
@@ -797,6 +1018,7 @@ impl Codec for ServerExtension {
             Self::TransportParameters(ref r) | Self::TransportParametersDraft(ref r) => {
                 sub.extend_from_slice(r)
             }
+            Self::EncryptedClientHello(ref r) => r.encode(&mut sub),
             Self::Unknown(ref r) => r.encode(&mut sub),
         }
 
@@ -835,6 +1057,9 @@ impl Codec for ServerExtension {
                 Self::TransportParametersDraft(sub.rest().to_vec())
             }
             ExtensionType::EarlyData => Self::EarlyData,
+            ExtensionType::EncryptedClientHello => {
+                Self::EncryptedClientHello(EchConfigList::read(&mut sub)?)
+            }
             _ => Self::Unknown(UnknownExtension::read(typ, &mut sub)),
         };
 
@@ -1066,6 +1291,8 @@ pub enum HelloRetryExtension {
     KeyShare(NamedGroup),
     Cookie(PayloadU16),
     SupportedVersions(ProtocolVersion),
+    /// Confirmation that the server accepted ECH
+    EncryptedClientHello(Payload),
     Unknown(UnknownExtension),
 }
 
@@ -1075,6 +1302,7 @@ impl HelloRetryExtension {
             HelloRetryExtension::KeyShare(_) => ExtensionType::KeyShare,
             HelloRetryExtension::Cookie(_) => ExtensionType::Cookie,
             HelloRetryExtension::SupportedVersions(_) => ExtensionType::SupportedVersions,
+            HelloRetryExtension::EncryptedClientHello(_) => ExtensionType::EncryptedClientHello,
             HelloRetryExtension::Unknown(ref r) => r.typ,
         }
     }
@@ -1089,6 +1317,7 @@ impl Codec for HelloRetryExtension {
             HelloRetryExtension::KeyShare(ref r) => r.encode(&mut sub),
             HelloRetryExtension::Cookie(ref r) => r.encode(&mut sub),
             HelloRetryExtension::SupportedVersions(ref r) => r.encode(&mut sub),
+            HelloRetryExtension::EncryptedClientHello(ref r) => r.encode(&mut sub),
             HelloRetryExtension::Unknown(ref r) => r.encode(&mut sub),
         }
 
@@ -1107,6 +1336,9 @@ impl Codec for HelloRetryExtension {
             ExtensionType::SupportedVersions => {
                 Self::SupportedVersions(ProtocolVersion::read(&mut sub)?)
             }
+            ExtensionType::EncryptedClientHello => {
+                Self::EncryptedClientHello(Payload::read(&mut sub))
+            }
             _ => Self::Unknown(UnknownExtension::read(typ, &mut sub)),
         };
 
@@ -1691,6 +1923,81 @@ impl Codec for ECDHEServerKeyExchange {
     }
 }
 
+#[derive(Debug)]
+pub struct ClientDHParams {
+    pub public: PayloadU16,
+}
+
+impl Codec for ClientDHParams {
+    fn encode(&self, bytes: &mut Vec<u8>) {
+        self.public.encode(bytes);
+    }
+
+    fn read(r: &mut Reader) -> Option<Self> {
+        let pb = PayloadU16::read(r)?;
+        Some(Self { public: pb })
+    }
+}
+
+// Arbitrary primes and generators are allowed, such that the attacker can offer weak or
+// malformed groups.
+#[derive(Debug, Clone)]
+pub struct ServerDHParams {
+    pub dh_p: PayloadU16,
+    pub dh_g: PayloadU16,
+    pub dh_ys: PayloadU16,
+}
+
+impl ServerDHParams {
+    pub fn new(prime: &[u8], generator: &[u8], pubkey: &[u8]) -> Self {
+        Self {
+            dh_p: PayloadU16::new(prime.to_vec()),
+            dh_g: PayloadU16::new(generator.to_vec()),
+            dh_ys: PayloadU16::new(pubkey.to_vec()),
+        }
+    }
+}
+
+impl Codec for ServerDHParams {
+    fn encode(&self, bytes: &mut Vec<u8>) {
+        self.dh_p.encode(bytes);
+        self.dh_g.encode(bytes);
+        self.dh_ys.encode(bytes);
+    }
+
+    fn read(r: &mut Reader) -> Option<Self> {
+        let p = PayloadU16::read(r)?;
+        let g = PayloadU16::read(r)?;
+        let ys = PayloadU16::read(r)?;
+
+        Some(Self {
+            dh_p: p,
+            dh_g: g,
+            dh_ys: ys,
+        })
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct DHEServerKeyExchange {
+    pub params: ServerDHParams,
+    pub dss: DigitallySignedStruct,
+}
+
+impl Codec for DHEServerKeyExchange {
+    fn encode(&self, bytes: &mut Vec<u8>) {
+        self.params.encode(bytes);
+        self.dss.encode(bytes);
+    }
+
+    fn read(r: &mut Reader) -> Option<Self> {
+        let params = ServerDHParams::read(r)?;
+        let dss = DigitallySignedStruct::read(r)?;
+
+        Some(Self { params, dss })
+    }
+}
+
 #[derive(Debug, Clone)]
 pub enum ServerKeyExchangePayload {
     ECDHE(ECDHEServerKeyExchange),
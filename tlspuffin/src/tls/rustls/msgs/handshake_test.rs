@@ -320,6 +320,97 @@ fn can_roundtrip_single_proto() {
     }
 }
 
+#[test]
+fn can_roundtrip_ech_client_exts() {
+    let outer = [
+        0xfe, 0x0d, 0x00, 0x0f, 0x00, 0x00, 0x01, 0x00, 0x01, 0x07, 0x00, 0x02, 0xaa, 0xbb, 0x00,
+        0x03, 0x01, 0x02, 0x03,
+    ];
+    let mut rd = Reader::init(&outer);
+    let ext = ClientExtension::read(&mut rd).unwrap();
+
+    assert_eq!(ext.get_type(), ExtensionType::EncryptedClientHello);
+    assert_eq!(outer.to_vec(), ext.get_encoding());
+    match ext {
+        ClientExtension::EncryptedClientHello(EchClientHello::Outer(outer)) => {
+            assert_eq!(outer.cipher_suite.kdf_id, HpkeKdf::HKDF_SHA256);
+            assert_eq!(outer.cipher_suite.aead_id, HpkeAead::AES_128_GCM);
+            assert_eq!(outer.config_id, 7);
+            assert_eq!(outer.enc.0, vec![0xaa, 0xbb]);
+            assert_eq!(outer.payload.0, vec![1, 2, 3]);
+        }
+        _ => unreachable!(),
+    }
+
+    let inner = [0xfe, 0x0d, 0x00, 0x01, 0x01];
+    let mut rd = Reader::init(&inner);
+    let ext = ClientExtension::read(&mut rd).unwrap();
+    assert!(matches!(
+        ext,
+        ClientExtension::EncryptedClientHello(EchClientHello::Inner)
+    ));
+    assert_eq!(inner.to_vec(), ext.get_encoding());
+
+    let outer_extensions = [0xfd, 0x00, 0x00, 0x05, 0x04, 0x00, 0x33, 0x00, 0x2b];
+    let mut rd = Reader::init(&outer_extensions);
+    let ext = ClientExtension::read(&mut rd).unwrap();
+    assert_eq!(ext.get_type(), ExtensionType::EchOuterExtensions);
+    assert_eq!(outer_extensions.to_vec(), ext.get_encoding());
+    match ext {
+        ClientExtension::EchOuterExtensions(types) => {
+            assert_eq!(
+                types.0,
+                vec![ExtensionType::KeyShare, ExtensionType::SupportedVersions]
+            );
+        }
+        _ => unreachable!(),
+    }
+}
+
+#[test]
+fn can_roundtrip_ech_retry_configs() {
+    let bytes = [
+        0xfe, 0x0d, 0x00, 0x24, 0x00, 0x22, // extension header and list length
+        0xfe, 0x0d, 0x00, 0x19, // ECHConfig version and length
+        0x01, 0x00, 0x20, 0x00, 0x02, 0xcc, 0xdd, // config id, KEM and public key
+        0x00, 0x08, 0x00, 0x01, 0x00, 0x01, 0x00, 0x01, 0x00, 0x03, // cipher suites
+        0x00, // maximum name length
+        0x04, b'p', b'u', b'f', b'f', // public name
+        0x00, 0x00, // extensions
+        0x12, 0x34, 0x00, 0x01, 0xff, // ECHConfig of an unknown version
+    ];
+    let mut rd = Reader::init(&bytes);
+    let ext = ServerExtension::read(&mut rd).unwrap();
+
+    assert_eq!(ext.get_type(), ExtensionType::EncryptedClientHello);
+    assert_eq!(bytes.to_vec(), ext.get_encoding());
+    match ext {
+        ServerExtension::EncryptedClientHello(configs) => {
+            assert_eq!(configs.0.len(), 2);
+            match &configs.0[0] {
+                EchConfig::Current(contents) => {
+                    assert_eq!(contents.key_config.config_id, 1);
+                    assert_eq!(
+                        contents.key_config.kem_id,
+                        HpkeKem::DHKEM_X25519_HKDF_SHA256
+                    );
+                    assert_eq!(contents.key_config.symmetric_cipher_suites.0.len(), 2);
+                    assert_eq!(contents.public_name.0, b"puff".to_vec());
+                }
+                _ => unreachable!(),
+            }
+            assert!(matches!(
+                configs.0[1],
+                EchConfig::Unknown {
+                    version: 0x1234,
+                    ..
+                }
+            ));
+        }
+        _ => unreachable!(),
+    }
+}
+
 #[test]
 fn decomposed_signature_scheme_has_correct_mappings() {
     assert_eq!(
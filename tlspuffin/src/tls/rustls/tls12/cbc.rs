@@ -0,0 +1,458 @@
+//! MAC-then-encrypt record protection of TLS 1.2 with AES in CBC mode
+//! (<https://datatracker.ietf.org/doc/html/rfc5246#section-6.2.3.2>).
+//!
+//! rustls never supported these cipher suites. They are used by the attacker to produce and read
+//! CBC records, including records with malformed padding or an invalid MAC.
+
+use aes::{
+    cipher::{generic_array::GenericArray, BlockDecrypt, BlockEncrypt, KeyInit},
+    Aes128, Aes256,
+};
+use puffin::codec;
+use ring::hmac;
+
+use crate::tls::rustls::{
+    cipher::{MessageDecrypter, MessageEncrypter},
+    conn::Side,
+    error::Error,
+    msgs::{
+        base::Payload,
+        enums::{CipherSuite, ContentType, ProtocolVersion},
+        fragmenter::MAX_FRAGMENT_LEN,
+        message::{BorrowedPlainMessage, OpaqueMessage, PlainMessage},
+    },
+};
+
+const BLOCK_LEN: usize = 16;
+
+/// The padding length is encoded in a single byte
+const MAX_PADDING_LEN: usize = 255;
+
+/// A cipher suite which protects records with HMAC and AES-CBC. In TLS 1.2 all of them use the
+/// SHA-256 based PRF.
+pub struct Tls12CbcCipherSuite {
+    pub suite: CipherSuite,
+    /// Length of the AES key, either 16 or 32 bytes
+    pub enc_key_len: usize,
+    pub hmac_algorithm: hmac::Algorithm,
+}
+
+/// The TLS1.2 ciphersuite TLS_ECDHE_RSA_WITH_AES_128_CBC_SHA
+pub static TLS_ECDHE_RSA_WITH_AES_128_CBC_SHA: Tls12CbcCipherSuite = Tls12CbcCipherSuite {
+    suite: CipherSuite::TLS_ECDHE_RSA_WITH_AES_128_CBC_SHA,
+    enc_key_len: 16,
+    hmac_algorithm: hmac::HMAC_SHA1_FOR_LEGACY_USE_ONLY,
+};
+
+/// The TLS1.2 ciphersuite TLS_ECDHE_RSA_WITH_AES_256_CBC_SHA
+pub static TLS_ECDHE_RSA_WITH_AES_256_CBC_SHA: Tls12CbcCipherSuite = Tls12CbcCipherSuite {
+    suite: CipherSuite::TLS_ECDHE_RSA_WITH_AES_256_CBC_SHA,
+    enc_key_len: 32,
+    hmac_algorithm: hmac::HMAC_SHA1_FOR_LEGACY_USE_ONLY,
+};
+
+/// The TLS1.2 ciphersuite TLS_ECDHE_RSA_WITH_AES_128_CBC_SHA256
+pub static TLS_ECDHE_RSA_WITH_AES_128_CBC_SHA256: Tls12CbcCipherSuite = Tls12CbcCipherSuite {
+    suite: CipherSuite::TLS_ECDHE_RSA_WITH_AES_128_CBC_SHA256,
+    enc_key_len: 16,
+    hmac_algorithm: hmac::HMAC_SHA256,
+};
+
+/// The ciphersuite TLS_RSA_WITH_AES_128_CBC_SHA, which is available in TLS 1.2 and all older
+/// versions
+pub static TLS_RSA_WITH_AES_128_CBC_SHA: Tls12CbcCipherSuite = Tls12CbcCipherSuite {
+    suite: CipherSuite::TLS_RSA_WITH_AES_128_CBC_SHA,
+    enc_key_len: 16,
+    hmac_algorithm: hmac::HMAC_SHA1_FOR_LEGACY_USE_ONLY,
+};
+
+pub static ALL_CBC_CIPHER_SUITES: &[&Tls12CbcCipherSuite] = &[
+    &TLS_RSA_WITH_AES_128_CBC_SHA,
+    &TLS_ECDHE_RSA_WITH_AES_128_CBC_SHA,
+    &TLS_ECDHE_RSA_WITH_AES_256_CBC_SHA,
+    &TLS_ECDHE_RSA_WITH_AES_128_CBC_SHA256,
+];
+
+/// Finds the CBC cipher suite which is identified by `suite`
+pub fn lookup_cbc_suite(suite: CipherSuite) -> Option<&'static Tls12CbcCipherSuite> {
+    ALL_CBC_CIPHER_SUITES
+        .iter()
+        .find(|cbc| cbc.suite == suite)
+        .copied()
+}
+
+impl Tls12CbcCipherSuite {
+    /// Length of the MAC and of the MAC key
+    pub fn mac_len(&self) -> usize {
+        self.hmac_algorithm.digest_algorithm().output_len
+    }
+
+    /// Length of the key block which holds the MAC and encryption keys of both sides. The IVs
+    /// are explicit, so they are not part of the key block.
+    pub fn key_block_len(&self) -> usize {
+        (self.mac_len() + self.enc_key_len) * 2
+    }
+
+    /// Splits the `key_block` into the keys of both sides and makes the decrypter and encrypter
+    /// of `side`
+    pub fn make_cipher_pair(
+        &'static self,
+        key_block: &[u8],
+        side: Side,
+    ) -> Result<(CbcMessageDecrypter, CbcMessageEncrypter), Error> {
+        let mac_key_len = self.mac_len();
+
+        let (client_mac_key, key_block) = key_block.split_at(mac_key_len);
+        let (server_mac_key, key_block) = key_block.split_at(mac_key_len);
+        let (client_write_key, key_block) = key_block.split_at(self.enc_key_len);
+        let (server_write_key, _) = key_block.split_at(self.enc_key_len);
+
+        let (write_key, write_mac_key, read_key, read_mac_key) = match side {
+            Side::Client => (
+                client_write_key,
+                client_mac_key,
+                server_write_key,
+                server_mac_key,
+            ),
+            Side::Server => (
+                server_write_key,
+                server_mac_key,
+                client_write_key,
+                client_mac_key,
+            ),
+        };
+
+        Ok((
+            CbcMessageDecrypter::new(self, read_key, read_mac_key)?,
+            CbcMessageEncrypter::new(self, write_key, write_mac_key)?,
+        ))
+    }
+
+    /// Returns well-formed padding, including the padding length byte, which aligns a fragment
+    /// of `fragment_len` bytes and its MAC to the block size. `extra_blocks` adds whole blocks
+    /// of padding, as long as the padding length fits into a byte.
+    pub fn padding(&self, fragment_len: usize, extra_blocks: usize) -> Vec<u8> {
+        let minimal = (BLOCK_LEN - (fragment_len + self.mac_len() + 1) % BLOCK_LEN) % BLOCK_LEN;
+        let extra_blocks = extra_blocks.min((MAX_PADDING_LEN - minimal) / BLOCK_LEN);
+        let padding_len = minimal + extra_blocks * BLOCK_LEN;
+
+        vec![padding_len as u8; padding_len + 1]
+    }
+}
+
+enum AesKey {
+    Aes128(Aes128),
+    Aes256(Aes256),
+}
+
+impl AesKey {
+    fn new(key: &[u8]) -> Result<Self, Error> {
+        let invalid = |_| Error::General("invalid AES key length".to_string());
+        match key.len() {
+            16 => Ok(AesKey::Aes128(
+                Aes128::new_from_slice(key).map_err(invalid)?,
+            )),
+            32 => Ok(AesKey::Aes256(
+                Aes256::new_from_slice(key).map_err(invalid)?,
+            )),
+            _ => Err(Error::General("invalid AES key length".to_string())),
+        }
+    }
+
+    /// Encrypts `data` in place. The length of `data` must be a multiple of the block size.
+    fn encrypt_cbc(&self, iv: &[u8], data: &mut [u8]) {
+        let mut previous = [0u8; BLOCK_LEN];
+        previous.copy_from_slice(iv);
+
+        for chunk in data.chunks_exact_mut(BLOCK_LEN) {
+            for (byte, previous) in chunk.iter_mut().zip(previous.iter()) {
+                *byte ^= previous;
+            }
+            let block = GenericArray::from_mut_slice(chunk);
+            match self {
+                AesKey::Aes128(cipher) => cipher.encrypt_block(block),
+                AesKey::Aes256(cipher) => cipher.encrypt_block(block),
+            }
+            previous.copy_from_slice(chunk);
+        }
+    }
+
+    /// Decrypts `data` in place. The length of `data` must be a multiple of the block size.
+    fn decrypt_cbc(&self, iv: &[u8], data: &mut [u8]) {
+        let mut previous = [0u8; BLOCK_LEN];
+        previous.copy_from_slice(iv);
+
+        for chunk in data.chunks_exact_mut(BLOCK_LEN) {
+            let mut ciphertext = [0u8; BLOCK_LEN];
+            ciphertext.copy_from_slice(chunk);
+
+            let block = GenericArray::from_mut_slice(chunk);
+            match self {
+                AesKey::Aes128(cipher) => cipher.decrypt_block(block),
+                AesKey::Aes256(cipher) => cipher.decrypt_block(block),
+            }
+            for (byte, previous) in chunk.iter_mut().zip(previous.iter()) {
+                *byte ^= previous;
+            }
+            previous = ciphertext;
+        }
+    }
+}
+
+fn make_mac(
+    key: &hmac::Key,
+    seq: u64,
+    typ: ContentType,
+    vers: ProtocolVersion,
+    fragment: &[u8],
+) -> hmac::Tag {
+    let mut header = [0u8; 8 + 1 + 2 + 2];
+    codec::put_u64(seq, &mut header[0..]);
+    header[8] = typ.get_u8();
+    codec::put_u16(vers.get_u16(), &mut header[9..]);
+    codec::put_u16(fragment.len() as u16, &mut header[11..]);
+
+    let mut context = hmac::Context::with_key(key);
+    context.update(&header);
+    context.update(fragment);
+    context.sign()
+}
+
+/// A `MessageEncrypter` for HMAC and AES-CBC ciphersuites. TLS 1.2 and TLS 1.1 only, as older
+/// versions chain the IV across records.
+pub struct CbcMessageEncrypter {
+    suite: &'static Tls12CbcCipherSuite,
+    enc_key: AesKey,
+    mac_key: hmac::Key,
+}
+
+/// A `MessageDecrypter` for HMAC and AES-CBC ciphersuites. TLS 1.2 and TLS 1.1 only, as older
+/// versions chain the IV across records.
+pub struct CbcMessageDecrypter {
+    suite: &'static Tls12CbcCipherSuite,
+    dec_key: AesKey,
+    mac_key: hmac::Key,
+}
+
+impl CbcMessageEncrypter {
+    pub fn new(
+        suite: &'static Tls12CbcCipherSuite,
+        enc_key: &[u8],
+        mac_key: &[u8],
+    ) -> Result<Self, Error> {
+        Ok(Self {
+            suite,
+            enc_key: AesKey::new(enc_key)?,
+            mac_key: hmac::Key::new(suite.hmac_algorithm, mac_key),
+        })
+    }
+
+    /// Encrypts `msg` followed by its MAC and `padding`. The padding includes the padding length
+    /// byte and is not checked, except that the record has to be aligned to the block size. If
+    /// `valid_mac` is false, the MAC is corrupted.
+    ///
+    /// The explicit IV is derived from the sequence number, such that records are reproducible.
+    pub fn encrypt_with(
+        &self,
+        msg: BorrowedPlainMessage,
+        seq: u64,
+        padding: &[u8],
+        valid_mac: bool,
+    ) -> Result<OpaqueMessage, Error> {
+        let mut mac =
+            Vec::from(make_mac(&self.mac_key, seq, msg.typ, msg.version, msg.payload).as_ref());
+        if !valid_mac {
+            if let Some(last) = mac.last_mut() {
+                *last ^= 0x01;
+            }
+        }
+
+        let mut iv = [0u8; BLOCK_LEN];
+        codec::put_u64(seq, &mut iv[8..]);
+
+        let mut payload =
+            Vec::with_capacity(BLOCK_LEN + msg.payload.len() + mac.len() + padding.len());
+        payload.extend_from_slice(&iv);
+        payload.extend_from_slice(msg.payload);
+        payload.extend_from_slice(&mac);
+        payload.extend_from_slice(padding);
+
+        if (payload.len() - BLOCK_LEN) % BLOCK_LEN != 0 {
+            return Err(Error::General(
+                "padding does not align the record to the block size".to_string(),
+            ));
+        }
+
+        self.enc_key.encrypt_cbc(&iv, &mut payload[BLOCK_LEN..]);
+
+        Ok(OpaqueMessage {
+            typ: msg.typ,
+            version: msg.version,
+            payload: Payload::new(payload),
+        })
+    }
+}
+
+impl MessageEncrypter for CbcMessageEncrypter {
+    fn encrypt(&self, msg: BorrowedPlainMessage, seq: u64) -> Result<OpaqueMessage, Error> {
+        let padding = self.suite.padding(msg.payload.len(), 0);
+        self.encrypt_with(msg, seq, &padding, true)
+    }
+}
+
+impl CbcMessageDecrypter {
+    pub fn new(
+        suite: &'static Tls12CbcCipherSuite,
+        dec_key: &[u8],
+        mac_key: &[u8],
+    ) -> Result<Self, Error> {
+        Ok(Self {
+            suite,
+            dec_key: AesKey::new(dec_key)?,
+            mac_key: hmac::Key::new(suite.hmac_algorithm, mac_key),
+        })
+    }
+
+    /// Decrypts `msg` and removes its MAC and padding. If `verify` is false, malformed padding
+    /// and an invalid MAC are ignored. The padding length byte is then only honored if it does
+    /// not exceed the record.
+    pub fn decrypt_with(
+        &self,
+        mut msg: OpaqueMessage,
+        seq: u64,
+        verify: bool,
+    ) -> Result<PlainMessage, Error> {
+        let mac_len = self.suite.mac_len();
+        let payload = &mut msg.payload.0;
+
+        if payload.len() < 2 * BLOCK_LEN || payload.len() % BLOCK_LEN != 0 {
+            return Err(Error::DecryptError);
+        }
+
+        let mut plain = payload.split_off(BLOCK_LEN);
+        self.dec_key.decrypt_cbc(payload, &mut plain);
+
+        if plain.len() < mac_len + 1 {
+            return Err(Error::DecryptError);
+        }
+
+        let padding_len = plain[plain.len() - 1] as usize + 1;
+        let padding_len = if padding_len + mac_len > plain.len() {
+            if verify {
+                return Err(Error::DecryptError);
+            }
+            1
+        } else {
+            padding_len
+        };
+
+        let (rest, padding) = plain.split_at(plain.len() - padding_len);
+        let (fragment, mac) = rest.split_at(rest.len() - mac_len);
+
+        if verify {
+            if padding.iter().any(|byte| *byte as usize != padding_len - 1) {
+                return Err(Error::DecryptError);
+            }
+
+            let expected = make_mac(&self.mac_key, seq, msg.typ, msg.version, fragment);
+            if expected.as_ref() != mac {
+                return Err(Error::DecryptError);
+            }
+        }
+
+        if fragment.len() > MAX_FRAGMENT_LEN {
+            return Err(Error::PeerSentOversizedRecord);
+        }
+
+        let fragment = fragment.to_vec();
+        Ok(PlainMessage {
+            typ: msg.typ,
+            version: msg.version,
+            payload: Payload::new(fragment),
+        })
+    }
+}
+
+impl MessageDecrypter for CbcMessageDecrypter {
+    fn decrypt(&self, msg: OpaqueMessage, seq: u64) -> Result<PlainMessage, Error> {
+        self.decrypt_with(msg, seq, true)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pair() -> (CbcMessageEncrypter, CbcMessageDecrypter) {
+        let suite = &TLS_ECDHE_RSA_WITH_AES_128_CBC_SHA;
+        (
+            CbcMessageEncrypter::new(suite, &[1; 16], &[2; 20]).unwrap(),
+            CbcMessageDecrypter::new(suite, &[1; 16], &[2; 20]).unwrap(),
+        )
+    }
+
+    fn message(payload: &[u8]) -> PlainMessage {
+        PlainMessage {
+            typ: ContentType::ApplicationData,
+            version: ProtocolVersion::TLSv1_2,
+            payload: Payload::new(payload.to_vec()),
+        }
+    }
+
+    #[test]
+    fn test_padding_alignment() {
+        let suite = &TLS_ECDHE_RSA_WITH_AES_128_CBC_SHA;
+        for len in 0..64 {
+            let padding = suite.padding(len, 0);
+            assert_eq!((len + suite.mac_len() + padding.len()) % BLOCK_LEN, 0);
+            assert!(padding.len() <= BLOCK_LEN);
+        }
+
+        assert_eq!(suite.padding(11, 1).len(), 1 + BLOCK_LEN);
+        assert!(suite.padding(11, 100).len() <= MAX_PADDING_LEN + 1);
+    }
+
+    #[test]
+    fn test_roundtrip() {
+        let (encrypter, decrypter) = pair();
+        let plain = message(b"hello");
+
+        let opaque = encrypter.encrypt(plain.borrow(), 3).unwrap();
+        let decrypted = decrypter.decrypt(opaque, 3).unwrap();
+        assert_eq!(decrypted.payload.0, b"hello");
+    }
+
+    #[test]
+    fn test_invalid_mac() {
+        let (encrypter, decrypter) = pair();
+        let plain = message(b"hello");
+        let padding = TLS_ECDHE_RSA_WITH_AES_128_CBC_SHA.padding(5, 0);
+
+        let opaque = encrypter
+            .encrypt_with(plain.borrow(), 0, &padding, false)
+            .unwrap();
+        assert!(decrypter.decrypt(opaque.clone(), 0).is_err());
+        assert_eq!(
+            decrypter.decrypt_with(opaque, 0, false).unwrap().payload.0,
+            b"hello"
+        );
+    }
+
+    #[test]
+    fn test_malformed_padding() {
+        let (encrypter, decrypter) = pair();
+        let plain = message(b"hello");
+        let mut padding = TLS_ECDHE_RSA_WITH_AES_128_CBC_SHA.padding(5, 0);
+        padding[0] ^= 0xff;
+
+        let opaque = encrypter
+            .encrypt_with(plain.borrow(), 0, &padding, true)
+            .unwrap();
+        assert!(decrypter.decrypt(opaque, 0).is_err());
+
+        // Padding which does not align the record is rejected
+        assert!(encrypter
+            .encrypt_with(plain.borrow(), 0, &[0], true)
+            .is_err());
+    }
+}
@@ -7,13 +7,15 @@ use crate::tls::rustls::{
     conn::ConnectionRandoms,
     kx,
     msgs::{
-        enums::{CipherSuite, SignatureScheme},
+        enums::{CipherSuite, ProtocolVersion, SignatureScheme},
         handshake::KeyExchangeAlgorithm,
     },
     suites::{BulkAlgorithm, CipherSuiteCommon, SupportedCipherSuite},
 };
 
+pub mod cbc;
 mod cipher;
+use cbc::{CbcMessageDecrypter, CbcMessageEncrypter, Tls12CbcCipherSuite};
 pub use cipher::{AesGcm, ChaCha20Poly1305, Tls12AeadAlgorithm};
 use puffin::codec::{Codec, Reader};
 
@@ -213,32 +215,51 @@ impl ConnectionSecrets {
         randoms: ConnectionRandoms,
         suite: &'static Tls12CipherSuite,
     ) -> Result<Self, Error> {
+        kx.complete(peer_pub_key, |secret| {
+            Ok(Self::from_premaster_secret(
+                secret, ems_seed, randoms, suite,
+            ))
+        })
+    }
+
+    /// Derives the master secret from a pre-master secret which was agreed on by any key
+    /// exchange, e.g. RSA or finite field Diffie-Hellman.
+    pub fn from_premaster_secret(
+        premaster_secret: &[u8],
+        ems_seed: Option<Digest>,
+        randoms: ConnectionRandoms,
+        suite: &'static Tls12CipherSuite,
+    ) -> Self {
         let mut ret = Self {
             randoms,
             suite,
             master_secret: [0u8; 48],
         };
+        ret.derive_master_secret(premaster_secret, ems_seed);
+        ret
+    }
 
+    fn derive_master_secret(&mut self, premaster_secret: &[u8], ems_seed: Option<Digest>) {
         let (label, seed) = match ems_seed {
             Some(seed) => ("extended master secret", Seed::Ems(seed)),
             None => (
                 "master secret",
-                Seed::Randoms(join_randoms(&ret.randoms.client, &ret.randoms.server)),
+                Seed::Randoms(join_randoms(&self.randoms.client, &self.randoms.server)),
             ),
         };
 
-        kx.complete(peer_pub_key, |secret| {
-            prf::prf(
-                &mut ret.master_secret,
-                suite.hmac_algorithm,
-                secret,
-                label.as_bytes(),
-                seed.as_ref(),
-            );
-            Ok(())
-        })?;
-
-        Ok(ret)
+        let mut master_secret = [0u8; 48];
+        self.prf(
+            &mut master_secret,
+            premaster_secret,
+            label.as_bytes(),
+            seed.as_ref(),
+        );
+        self.master_secret = master_secret;
+    }
+
+    fn prf(&self, out: &mut [u8], secret: &[u8], label: &[u8], seed: &[u8]) {
+        prf::prf(out, self.suite.hmac_algorithm, secret, label, seed)
     }
 
     pub fn new_resume(
@@ -302,6 +323,17 @@ impl ConnectionSecrets {
         )
     }
 
+    /// Make the encrypter and decrypter of the HMAC and AES-CBC suite `cbc_suite` from the
+    /// session's `secrets`. The key block is derived with the PRF of the negotiated suite.
+    pub fn make_cbc_cipher_pair(
+        &self,
+        cbc_suite: &'static Tls12CbcCipherSuite,
+        side: Side,
+    ) -> Result<(CbcMessageDecrypter, CbcMessageEncrypter), Error> {
+        let key_block = self.make_key_block_of_len(cbc_suite.key_block_len());
+        cbc_suite.make_cipher_pair(&key_block, side)
+    }
+
     fn make_key_block(&self) -> Vec<u8> {
         let suite = &self.suite;
         let common = &self.suite.common;
@@ -309,19 +341,17 @@ impl ConnectionSecrets {
         let len =
             (common.aead_algorithm.key_len() + suite.fixed_iv_len) * 2 + suite.explicit_nonce_len;
 
+        self.make_key_block_of_len(len)
+    }
+
+    fn make_key_block_of_len(&self, len: usize) -> Vec<u8> {
         let mut out = Vec::new();
         out.resize(len, 0u8);
 
         // NOTE: opposite order to above for no good reason.
         // Don't design security protocols on drugs, kids.
         let randoms = join_randoms(&self.randoms.server, &self.randoms.client);
-        prf::prf(
-            &mut out,
-            self.suite.hmac_algorithm,
-            &self.master_secret,
-            b"key expansion",
-            &randoms,
-        );
+        self.prf(&mut out, &self.master_secret, b"key expansion", &randoms);
 
         out
     }
@@ -340,9 +370,8 @@ impl ConnectionSecrets {
         let mut out = Vec::new();
         out.resize(12, 0u8);
 
-        prf::prf(
+        self.prf(
             &mut out,
-            self.suite.hmac_algorithm,
             &self.master_secret,
             label,
             handshake_hash.as_ref(),
@@ -368,14 +397,117 @@ impl ConnectionSecrets {
             randoms.extend_from_slice(context);
         }
 
-        prf::prf(
-            output,
-            self.suite.hmac_algorithm,
+        self.prf(output, &self.master_secret, label, &randoms)
+    }
+}
+
+/// Per-connection keying material of TLS 1.1 and older. These versions derive the keys with MD5
+/// and SHA-1 and protect records only with CBC suites.
+pub struct LegacyConnectionSecrets {
+    pub randoms: ConnectionRandoms,
+    version: ProtocolVersion,
+    suite: &'static Tls12CbcCipherSuite,
+    pub master_secret: [u8; 48],
+}
+
+impl LegacyConnectionSecrets {
+    /// Derives the master secret of `version` from a pre-master secret. SSL 3.0 has its own key
+    /// derivation, whereas TLS 1.0 and TLS 1.1 share the PRF of RFC 2246.
+    pub fn from_premaster_secret(
+        premaster_secret: &[u8],
+        randoms: ConnectionRandoms,
+        version: ProtocolVersion,
+        suite: &'static Tls12CbcCipherSuite,
+    ) -> Self {
+        let mut ret = Self {
+            randoms,
+            version,
+            suite,
+            master_secret: [0u8; 48],
+        };
+
+        let randoms = join_randoms(&ret.randoms.client, &ret.randoms.server);
+        let mut master_secret = [0u8; 48];
+        ret.prf(
+            &mut master_secret,
+            premaster_secret,
+            b"master secret",
+            &randoms,
+        );
+        ret.master_secret = master_secret;
+        ret
+    }
+
+    fn prf(&self, out: &mut [u8], secret: &[u8], label: &[u8], seed: &[u8]) {
+        match self.version {
+            ProtocolVersion::SSLv3 => prf::ssl3_prf(out, secret, seed),
+            _ => prf::legacy_prf(out, secret, label, seed),
+        }
+    }
+
+    /// Make the encrypter and decrypter of the CBC suite. Only TLS 1.1 records are supported:
+    /// TLS 1.0 and SSL 3.0 chain the IV across records and SSL 3.0 does not use HMAC.
+    pub fn make_cipher_pair(
+        &self,
+        side: Side,
+    ) -> Result<(CbcMessageDecrypter, CbcMessageEncrypter), Error> {
+        if self.version != ProtocolVersion::TLSv1_1 {
+            return Err(Error::General(format!(
+                "record protection of {:?} is not supported",
+                self.version
+            )));
+        }
+
+        let mut key_block = vec![0u8; self.suite.key_block_len()];
+        let randoms = join_randoms(&self.randoms.server, &self.randoms.client);
+        self.prf(
+            &mut key_block,
             &self.master_secret,
-            label,
+            b"key expansion",
             &randoms,
-        )
+        );
+
+        self.suite.make_cipher_pair(&key_block, side)
+    }
+
+    /// Client verify data over all `handshake_messages` so far
+    pub fn client_verify_data(&self, handshake_messages: &[u8]) -> Vec<u8> {
+        self.make_verify_data(handshake_messages, b"client finished", b"CLNT")
     }
+
+    /// Server verify data over all `handshake_messages` so far
+    pub fn server_verify_data(&self, handshake_messages: &[u8]) -> Vec<u8> {
+        self.make_verify_data(handshake_messages, b"server finished", b"SRVR")
+    }
+
+    /// SSL 3.0 hashes the `sender` into the verify data, TLS 1.0 and TLS 1.1 use the `label` with
+    /// the PRF.
+    fn make_verify_data(&self, handshake_messages: &[u8], label: &[u8], sender: &[u8]) -> Vec<u8> {
+        match self.version {
+            ProtocolVersion::SSLv3 => {
+                prf::ssl3_verify_data(&self.master_secret, handshake_messages, sender)
+            }
+            _ => {
+                let mut out = vec![0u8; 12];
+                self.prf(
+                    &mut out,
+                    &self.master_secret,
+                    label,
+                    &legacy_handshake_hash(handshake_messages),
+                );
+                out
+            }
+        }
+    }
+}
+
+/// The concatenation of the MD5 and SHA-1 hash of the `handshake_messages`
+fn legacy_handshake_hash(handshake_messages: &[u8]) -> Vec<u8> {
+    let mut hash = Vec::from(md5::compute(handshake_messages).0);
+    hash.extend_from_slice(
+        ring::digest::digest(&ring::digest::SHA1_FOR_LEGACY_USE_ONLY, handshake_messages).as_ref(),
+    );
+    hash
 }
 
 enum Seed {
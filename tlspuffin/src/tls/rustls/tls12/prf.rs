@@ -1,4 +1,4 @@
-use ring::hmac;
+use ring::{digest, hmac};
 
 fn concat_sign(key: &hmac::Key, a: &[u8], b: &[u8]) -> hmac::Tag {
     let mut ctx = hmac::Context::with_key(key);
@@ -35,6 +35,118 @@ pub fn prf(out: &mut [u8], alg: hmac::Algorithm, secret: &[u8], label: &[u8], se
     p(out, alg, secret, &joined_seed);
 }
 
+const MD5_BLOCK_LEN: usize = 64;
+
+/// HMAC-MD5 as defined in RFC 2104. ring does not offer MD5, so this is implemented here.
+fn hmac_md5(key: &[u8], a: &[u8], b: &[u8]) -> [u8; 16] {
+    let mut block = [0u8; MD5_BLOCK_LEN];
+    if key.len() > MD5_BLOCK_LEN {
+        block[..16].copy_from_slice(&md5::compute(key).0);
+    } else {
+        block[..key.len()].copy_from_slice(key);
+    }
+
+    let mut inner = md5::Context::new();
+    inner.consume(block.iter().map(|byte| byte ^ 0x36).collect::<Vec<u8>>());
+    inner.consume(a);
+    inner.consume(b);
+
+    let mut outer = md5::Context::new();
+    outer.consume(block.iter().map(|byte| byte ^ 0x5c).collect::<Vec<u8>>());
+    outer.consume(inner.compute().0);
+    outer.compute().0
+}
+
+fn p_md5(out: &mut [u8], secret: &[u8], seed: &[u8]) {
+    // A(1)
+    let mut current_a = hmac_md5(secret, seed, &[]);
+    for chunk in out.chunks_mut(16) {
+        // P_MD5[i] = HMAC_MD5(secret, A(i) + seed)
+        let p_term = hmac_md5(secret, &current_a, seed);
+        chunk.copy_from_slice(&p_term[..chunk.len()]);
+
+        // A(i+1) = HMAC_MD5(secret, A(i))
+        current_a = hmac_md5(secret, &current_a, &[]);
+    }
+}
+
+/// The PRF of TLS 1.0 and TLS 1.1 (RFC 2246, Section 5). The secret is split into two halves
+/// which share the middle byte if its length is odd. The output is the XOR of P_MD5 over the first
+/// and P_SHA-1 over the second half.
+pub fn legacy_prf(out: &mut [u8], secret: &[u8], label: &[u8], seed: &[u8]) {
+    let joined_seed = concat(label, seed);
+
+    let half_len = (secret.len() + 1) / 2;
+    let s1 = &secret[..half_len];
+    let s2 = &secret[secret.len() - half_len..];
+
+    p_md5(out, s1, &joined_seed);
+
+    let mut sha1_out = vec![0u8; out.len()];
+    p(
+        &mut sha1_out,
+        hmac::HMAC_SHA1_FOR_LEGACY_USE_ONLY,
+        s2,
+        &joined_seed,
+    );
+
+    for (byte, sha1_byte) in out.iter_mut().zip(sha1_out) {
+        *byte ^= sha1_byte;
+    }
+}
+
+/// The key derivation of SSL 3.0 (RFC 6101, Section 6.1 and 6.2.2). The i-th block of the output
+/// is MD5(secret + SHA-1(salt + secret + seed)), where the salt repeats the i-th letter of the
+/// alphabet i times. The output is limited to 26 blocks of 16 bytes.
+pub fn ssl3_prf(out: &mut [u8], secret: &[u8], seed: &[u8]) {
+    for (i, chunk) in out.chunks_mut(16).enumerate() {
+        let salt = vec![b'A' + i as u8; i + 1];
+
+        let mut sha1 = digest::Context::new(&digest::SHA1_FOR_LEGACY_USE_ONLY);
+        sha1.update(&salt);
+        sha1.update(secret);
+        sha1.update(seed);
+
+        let mut md5 = md5::Context::new();
+        md5.consume(secret);
+        md5.consume(sha1.finish());
+        chunk.copy_from_slice(&md5.compute().0[..chunk.len()]);
+    }
+}
+
+/// The verify data of an SSL 3.0 Finished (RFC 6101, Section 5.6.9), which is the MD5 hash followed
+/// by the SHA-1 hash of the `handshake_messages`, the `sender` and the `master_secret`
+pub fn ssl3_verify_data(master_secret: &[u8], handshake_messages: &[u8], sender: &[u8]) -> Vec<u8> {
+    const MD5_PAD_LEN: usize = 48;
+    const SHA1_PAD_LEN: usize = 40;
+
+    let mut inner = md5::Context::new();
+    inner.consume(handshake_messages);
+    inner.consume(sender);
+    inner.consume(master_secret);
+    inner.consume([0x36; MD5_PAD_LEN]);
+
+    let mut outer = md5::Context::new();
+    outer.consume(master_secret);
+    outer.consume([0x5c; MD5_PAD_LEN]);
+    outer.consume(inner.compute().0);
+    let mut verify_data = Vec::from(outer.compute().0);
+
+    let mut inner = digest::Context::new(&digest::SHA1_FOR_LEGACY_USE_ONLY);
+    inner.update(handshake_messages);
+    inner.update(sender);
+    inner.update(master_secret);
+    inner.update(&[0x36; SHA1_PAD_LEN]);
+
+    let mut outer = digest::Context::new(&digest::SHA1_FOR_LEGACY_USE_ONLY);
+    outer.update(master_secret);
+    outer.update(&[0x5c; SHA1_PAD_LEN]);
+    outer.update(inner.finish().as_ref());
+    verify_data.extend_from_slice(outer.finish().as_ref());
+
+    verify_data
+}
+
 #[cfg(test)]
 mod tests {
     use ring::hmac::{HMAC_SHA256, HMAC_SHA512};
@@ -64,4 +176,29 @@ mod tests {
         assert_eq!(expect.len(), output.len());
         assert_eq!(expect.to_vec(), output.to_vec());
     }
+
+    #[test]
+    fn check_legacy() {
+        let secret = b"\x9b\xbe\x43\x6b\xa9\x40\xf0\x17\xb1\x76\x52\x84\x9a\x71\xdb\x35\x42";
+        let seed = b"\xa0\xba\x9f\x93\x6c\xda\x31\x18\x27\xa6\xf7\x96\xff\xd5\x19\x8c";
+        let label = b"test label";
+        let expect = include_bytes!("../testdata/prf-result.legacy.bin");
+        let mut output = [0u8; 104];
+
+        super::legacy_prf(&mut output, secret, label, seed);
+        assert_eq!(expect.len(), output.len());
+        assert_eq!(expect.to_vec(), output.to_vec());
+    }
+
+    #[test]
+    fn check_ssl3() {
+        let secret = b"\x9b\xbe\x43\x6b\xa9\x40\xf0\x17\xb1\x76\x52\x84\x9a\x71\xdb\x35";
+        let seed = b"\xa0\xba\x9f\x93\x6c\xda\x31\x18\x27\xa6\xf7\x96\xff\xd5\x19\x8c";
+        let expect = include_bytes!("../testdata/prf-result.ssl3.bin");
+        let mut output = [0u8; 104];
+
+        super::ssl3_prf(&mut output, secret, seed);
+        assert_eq!(expect.len(), output.len());
+        assert_eq!(expect.to_vec(), output.to_vec());
+    }
 }
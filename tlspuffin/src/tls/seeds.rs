@@ -408,11 +408,38 @@ pub fn seed_successful12_with_tickets(
 }
 
 pub fn seed_successful12(client: AgentName, server: AgentName) -> Trace<TlsQueryMatcher> {
+    _seed_successful12_with_version(client, server, TLSVersion::V1_2)
+}
+
+/// Like [`seed_successful12`], but negotiates TLS 1.1
+pub fn seed_successful11(client: AgentName, server: AgentName) -> Trace<TlsQueryMatcher> {
+    _seed_successful12_with_version(client, server, TLSVersion::V1_1)
+}
+
+/// Like [`seed_successful12`], but negotiates TLS 1.0
+pub fn seed_successful10(client: AgentName, server: AgentName) -> Trace<TlsQueryMatcher> {
+    _seed_successful12_with_version(client, server, TLSVersion::V1_0)
+}
+
+/// Like [`seed_successful12`], but negotiates SSL 3.0. The ClientHello of SSL 3.0 carries no
+/// extensions, so the server does the elliptic curve Diffie-Hellman key exchange with its
+/// temporary secp384r1 key.
+pub fn seed_successful_ssl3(client: AgentName, server: AgentName) -> Trace<TlsQueryMatcher> {
+    _seed_successful12_with_version(client, server, TLSVersion::SSL3)
+}
+
+/// Full handshake between two agents which support at most `version`. TLS 1.1 and below share
+/// the message flow of TLS 1.2.
+fn _seed_successful12_with_version(
+    client: AgentName,
+    server: AgentName,
+    version: TLSVersion,
+) -> Trace<TlsQueryMatcher> {
     Trace {
         prior_traces: vec![],
         descriptors: vec![
-            AgentDescriptor::new_client(client, TLSVersion::V1_2),
-            AgentDescriptor::new_server(server, TLSVersion::V1_2),
+            AgentDescriptor::new_client(client, version),
+            AgentDescriptor::new_server(server, version),
         ],
         steps: vec![
             OutputAction::new_step(client),
@@ -940,8 +967,10 @@ pub fn seed_client_attacker_auth(server: AgentName) -> Trace<TlsQueryMatcher> {
     trace
 }
 
-pub fn seed_client_attacker(server: AgentName) -> Trace<TlsQueryMatcher> {
-    let client_hello = term! {
+/// TLS 1.3 ClientHello of the attacker which offers TLS_AES_128_GCM_SHA256 and a secp384r1 key
+/// share
+fn _client_hello13() -> Term<TlsQueryMatcher> {
+    term! {
           fn_client_hello(
             fn_protocol_version12,
             fn_new_random,
@@ -965,9 +994,12 @@ pub fn seed_client_attacker(server: AgentName) -> Trace<TlsQueryMatcher> {
                 fn_supported_versions13_extension
             ))
         )
-    };
+    }
+}
 
-    let client_finished = term! {
+/// Finished of the attacker as client in a TLS 1.3 handshake with `server`
+fn _client_finished13(server: AgentName) -> Term<TlsQueryMatcher> {
+    term! {
         fn_finished(
             (fn_verify_data(
                 (fn_server_finished_transcript(((server, 0)))),
@@ -977,7 +1009,12 @@ pub fn seed_client_attacker(server: AgentName) -> Trace<TlsQueryMatcher> {
                 fn_named_group_secp384r1
             ))
         )
-    };
+    }
+}
+
+pub fn seed_client_attacker(server: AgentName) -> Trace<TlsQueryMatcher> {
+    let client_hello = _client_hello13();
+    let client_finished = _client_finished13(server);
 
     let trace = Trace {
         prior_traces: vec![],
@@ -1021,40 +1058,95 @@ pub fn seed_client_attacker12(server: AgentName) -> Trace<TlsQueryMatcher> {
 pub fn _seed_client_attacker12(
     server: AgentName,
 ) -> (Trace<TlsQueryMatcher>, Term<TlsQueryMatcher>) {
-    let client_hello = term! {
+    // force TLS_ECDHE_RSA_WITH_AES_128_GCM_SHA256
+    let (mut trace, client_verify_data) =
+        _seed_client_attacker12_with_suite(server, term! { fn_cipher_suite12 });
+
+    trace.steps.push(Step {
+        agent: server,
+        action: Action::Input(InputAction {
+            recipe: term! {
+                fn_encrypt12(
+                    (fn_finished((@client_verify_data))),
+                    ((server, 0)),
+                    (fn_decode_ecdh_pubkey(
+                        ((server, 0)[Some(TlsQueryMatcher::Handshake(Some(HandshakeType::ServerKeyExchange)))]/Vec<u8>) // ServerECDHParams
+                    )),
+                    fn_named_group_secp384r1,
+                    fn_true,
+                    fn_seq_0
+                )
+            },
+        }),
+    });
+
+    (trace, client_verify_data)
+}
+
+/// TLS 1.2 ClientHello of the attacker which offers only `cipher_suite`
+fn _client_hello12(cipher_suite: Term<TlsQueryMatcher>) -> Term<TlsQueryMatcher> {
+    // Enable Renegotiation
+    _client_hello12_with_renegotiation_info(cipher_suite, Some(term! { fn_empty_bytes_vec }))
+}
+
+/// Like [`_client_hello12`], but the `renegotiation_info` extension carries
+/// `renegotiated_connection`. The extension is omitted if it is `None`.
+fn _client_hello12_with_renegotiation_info(
+    cipher_suite: Term<TlsQueryMatcher>,
+    renegotiated_connection: Option<Term<TlsQueryMatcher>>,
+) -> Term<TlsQueryMatcher> {
+    let extensions = term! {
+        fn_client_extensions_append(
+            (fn_client_extensions_append(
+                (fn_client_extensions_append(
+                    (fn_client_extensions_append(
+                        fn_client_extensions_new,
+                        (fn_support_group_extension(fn_named_group_secp384r1))
+                    )),
+                    fn_signature_algorithm_extension
+                )),
+                fn_ec_point_formats_extension
+            )),
+            fn_signed_certificate_timestamp_extension
+        )
+    };
+
+    let extensions = match renegotiated_connection {
+        Some(renegotiated_connection) => term! {
+            fn_client_extensions_append(
+                (@extensions),
+                (fn_renegotiation_info_extension((@renegotiated_connection)))
+            )
+        },
+        None => extensions,
+    };
+
+    term! {
           fn_client_hello(
             fn_protocol_version12,
             fn_new_random,
             fn_new_session_id,
             (fn_append_cipher_suite(
                 (fn_new_cipher_suites()),
-                // force TLS_ECDHE_RSA_WITH_AES_128_GCM_SHA256
-                fn_cipher_suite12
+                (@cipher_suite)
             )),
             fn_compressions,
             (fn_client_extensions_append(
-                (fn_client_extensions_append(
-                    (fn_client_extensions_append(
-                        (fn_client_extensions_append(
-                            (fn_client_extensions_append(
-                                (fn_client_extensions_append(
-                                    fn_client_extensions_new,
-                                    (fn_support_group_extension(fn_named_group_secp384r1))
-                                )),
-                                fn_signature_algorithm_extension
-                            )),
-                            fn_ec_point_formats_extension
-                        )),
-                        fn_signed_certificate_timestamp_extension
-                    )),
-                     // Enable Renegotiation
-                    (fn_renegotiation_info_extension(fn_empty_bytes_vec))
-                )),
+                (@extensions),
                 // Add signature cert extension
                 fn_signature_algorithm_cert_extension
             ))
         )
-    };
+    }
+}
+
+/// Full TLS 1.2 handshake which offers only `cipher_suite`, up to and excluding the encrypted
+/// client Finished. Returns the trace and the verify data of the client Finished.
+fn _seed_client_attacker12_with_suite(
+    server: AgentName,
+    cipher_suite: Term<TlsQueryMatcher>,
+) -> (Trace<TlsQueryMatcher>, Term<TlsQueryMatcher>) {
+    let client_hello = _client_hello12(cipher_suite);
 
     let server_hello_transcript = term! {
         fn_append_transcript(
@@ -1135,166 +1227,1965 @@ pub fn _seed_client_attacker12(
                     recipe: term! { fn_change_cipher_spec },
                 }),
             },
-            Step {
-                agent: server,
-                action: Action::Input(InputAction {
-                    recipe: term! {
-                        fn_encrypt12(
-                            (fn_finished((@client_verify_data))),
-                            ((server, 0)),
-                            (fn_decode_ecdh_pubkey(
-                                ((server, 0)[Some(TlsQueryMatcher::Handshake(Some(HandshakeType::ServerKeyExchange)))]/Vec<u8>) // ServerECDHParams
-                            )),
-                            fn_named_group_secp384r1,
-                            fn_true,
-                            fn_seq_0
-                        )
-                    },
-                }),
-            },
         ],
     };
 
     (trace, client_verify_data)
 }
 
-pub fn seed_session_resumption_dhe(
-    initial_server: AgentName,
+/// Like [`seed_client_attacker12`], but negotiates TLS_ECDHE_RSA_WITH_CHACHA20_POLY1305_SHA256
+pub fn seed_client_attacker12_chacha20(server: AgentName) -> Trace<TlsQueryMatcher> {
+    let (mut trace, client_verify_data) = _seed_client_attacker12_with_suite(
+        server,
+        term! { fn_cipher_suite12_chacha20_poly1305_sha256 },
+    );
+
+    trace.steps.push(Step {
+        agent: server,
+        action: Action::Input(InputAction {
+            recipe: term! {
+                fn_encrypt12_with_suite(
+                    (fn_finished((@client_verify_data))),
+                    ((server, 0)),
+                    (fn_decode_ecdh_pubkey(
+                        ((server, 0)[Some(TlsQueryMatcher::Handshake(Some(HandshakeType::ServerKeyExchange)))]/Vec<u8>) // ServerECDHParams
+                    )),
+                    fn_named_group_secp384r1,
+                    fn_cipher_suite12_chacha20_poly1305_sha256,
+                    fn_true,
+                    fn_seq_0
+                )
+            },
+        }),
+    });
+
+    trace
+}
+
+/// Like [`seed_client_attacker12`], but negotiates the MAC-then-encrypt suite
+/// TLS_ECDHE_RSA_WITH_AES_128_CBC_SHA
+pub fn seed_client_attacker12_cbc(server: AgentName) -> Trace<TlsQueryMatcher> {
+    let (mut trace, client_verify_data) =
+        _seed_client_attacker12_with_suite(server, term! { fn_cipher_suite12_aes_128_cbc_sha });
+
+    trace.steps.push(Step {
+        agent: server,
+        action: Action::Input(InputAction {
+            recipe: term! {
+                fn_encrypt12_with_suite(
+                    (fn_finished((@client_verify_data))),
+                    ((server, 0)),
+                    (fn_decode_ecdh_pubkey(
+                        ((server, 0)[Some(TlsQueryMatcher::Handshake(Some(HandshakeType::ServerKeyExchange)))]/Vec<u8>) // ServerECDHParams
+                    )),
+                    fn_named_group_secp384r1,
+                    fn_cipher_suite12_aes_128_cbc_sha,
+                    fn_true,
+                    fn_seq_0
+                )
+            },
+        }),
+    });
+
+    trace
+}
+
+fn _seed_client_attacker12_cbc_padding(
     server: AgentName,
+    extra_blocks: Term<TlsQueryMatcher>,
+    valid_mac: Term<TlsQueryMatcher>,
 ) -> Trace<TlsQueryMatcher> {
-    let initial_handshake = seed_client_attacker(initial_server);
+    let (mut trace, client_verify_data) =
+        _seed_client_attacker12_with_suite(server, term! { fn_cipher_suite12_aes_128_cbc_sha });
 
-    let new_ticket_message = term! {
-        fn_decrypt_application(
-            ((initial_server, 4)[Some(TlsQueryMatcher::ApplicationData)]), // Ticket from last session
-            (fn_server_hello_transcript(((initial_server, 0)))),
-            (fn_server_finished_transcript(((initial_server, 0)))),
-            (fn_get_server_key_share(((initial_server, 0)))),
-            fn_no_psk,
-            fn_named_group_secp384r1,
-            fn_true,
-            fn_seq_0 // sequence restarts at 0 because we are decrypting now traffic
-        )
-    };
+    let finished = term! { fn_finished((@client_verify_data)) };
 
-    let client_hello = term! {
-          fn_client_hello(
-            fn_protocol_version12,
-            fn_new_random,
-            fn_new_session_id,
-            (fn_append_cipher_suite(
-                (fn_new_cipher_suites()),
-                fn_cipher_suite13_aes_128_gcm_sha256
-            )),
-            fn_compressions,
-            (fn_client_extensions_append(
-                (fn_client_extensions_append(
-                    (fn_client_extensions_append(
-                        (fn_client_extensions_append(
-                            (fn_client_extensions_append(
-                                (fn_client_extensions_append(
-                                    fn_client_extensions_new,
-                                    (fn_support_group_extension(fn_named_group_secp384r1))
-                                )),
-                                fn_signature_algorithm_extension
-                            )),
-                            fn_supported_versions13_extension
-                        )),
-                        (fn_key_share_deterministic_extension(fn_named_group_secp384r1))
+    trace.steps.push(Step {
+        agent: server,
+        action: Action::Input(InputAction {
+            recipe: term! {
+                fn_encrypt12_cbc(
+                    (@finished),
+                    ((server, 0)),
+                    (fn_decode_ecdh_pubkey(
+                        ((server, 0)[Some(TlsQueryMatcher::Handshake(Some(HandshakeType::ServerKeyExchange)))]/Vec<u8>) // ServerECDHParams
                     )),
-                    fn_psk_exchange_mode_dhe_ke_extension
+                    fn_named_group_secp384r1,
+                    fn_cipher_suite12_aes_128_cbc_sha,
+                    fn_true,
+                    fn_seq_0,
+                    (fn_cbc_padding12(
+                        (@finished),
+                        fn_cipher_suite12_aes_128_cbc_sha,
+                        (@extra_blocks)
+                    )),
+                    (@valid_mac)
+                )
+            },
+        }),
+    });
+
+    trace
+}
+
+/// CBC handshake whose client Finished carries three additional blocks of valid padding
+pub fn seed_client_attacker12_cbc_long_padding(server: AgentName) -> Trace<TlsQueryMatcher> {
+    _seed_client_attacker12_cbc_padding(server, term! { fn_seq_3 }, term! { fn_true })
+}
+
+/// CBC handshake whose client Finished has well-formed padding, but an invalid MAC. The server
+/// has to reject the record.
+pub fn seed_client_attacker12_cbc_invalid_mac(server: AgentName) -> Trace<TlsQueryMatcher> {
+    _seed_client_attacker12_cbc_padding(server, term! { fn_seq_0 }, term! { fn_false })
+}
+
+/// TLS 1.2 handshake against a server which negotiates `cipher_suite` with the RSA or finite field
+/// Diffie-Hellman key exchange. `client_key_exchange` is the payload of the ClientKeyExchange
+/// and `premaster_secret` the secret which the attacker expects.
+fn _seed_client_attacker12_premaster(
+    server: AgentName,
+    cipher_suite: Term<TlsQueryMatcher>,
+    server_key_exchange: bool,
+    client_key_exchange: Term<TlsQueryMatcher>,
+    premaster_secret: Term<TlsQueryMatcher>,
+) -> Trace<TlsQueryMatcher> {
+    let client_hello = _client_hello12(cipher_suite);
+
+    let certificate_transcript = term! {
+        fn_append_transcript(
+            (fn_append_transcript(
+                (fn_append_transcript(
+                    fn_new_transcript12,
+                    (@client_hello) // ClientHello
                 )),
-                // https://datatracker.ietf.org/doc/html/rfc8446#section-2.2
-                // must be last in client_hello, and initially empty until filled by fn_fill_binder
-                (fn_preshared_keys_extension_empty_binder(
-                    (@new_ticket_message)
-                ))
-            ))
+                ((server, 0)[Some(TlsQueryMatcher::Handshake(Some(HandshakeType::ServerHello)))]) // plaintext ServerHello
+            )),
+            ((server, 0)[Some(TlsQueryMatcher::Handshake(Some(HandshakeType::Certificate)))]) // Certificate
         )
     };
 
-    let psk = term! {
-        fn_derive_psk(
-            (fn_server_hello_transcript(((initial_server, 0)))),
-            (fn_server_finished_transcript(((initial_server, 0)))),
-            (fn_client_finished_transcript(((initial_server, 0)))),
-            (fn_get_server_key_share(((initial_server, 0)[Some(TlsQueryMatcher::Handshake(Some(HandshakeType::ServerHello)))]))),
-            (fn_get_ticket_nonce((@new_ticket_message))),
-            fn_named_group_secp384r1
-        )
+    let server_key_exchange_transcript = if server_key_exchange {
+        term! {
+            fn_append_transcript(
+                (@certificate_transcript),
+                ((server, 0)[Some(TlsQueryMatcher::Handshake(Some(HandshakeType::ServerKeyExchange)))]) // ServerKeyExchange
+            )
+        }
+    } else {
+        certificate_transcript
     };
 
-    let binder = term! {
-        fn_derive_binder(
-            (@client_hello),
-            (@psk)
+    let server_hello_done_transcript = term! {
+        fn_append_transcript(
+            (@server_key_exchange_transcript),
+            ((server, 0)[Some(TlsQueryMatcher::Handshake(Some(HandshakeType::ServerHelloDone)))]) // ServerHelloDone
         )
     };
 
-    let full_client_hello = term! {
-        fn_fill_binder(
-            (@client_hello),
-            (@binder)
+    let client_key_exchange = term! {
+        fn_client_key_exchange(
+            (@client_key_exchange)
         )
     };
 
-    let resumption_client_finished = term! {
-        fn_finished(
-            (fn_verify_data(
-                (fn_server_finished_transcript(((server, 0)))),
-                (fn_server_hello_transcript(((server, 0)))),
-                (fn_get_server_key_share(((server, 0)[Some(TlsQueryMatcher::Handshake(Some(HandshakeType::ServerHello)))]))),
-                (fn_psk((@psk))),
-                fn_named_group_secp384r1
-            ))
+    let client_key_exchange_transcript = term! {
+        fn_append_transcript(
+            (@server_hello_done_transcript),
+            (@client_key_exchange)
         )
     };
 
-    let trace = Trace {
-        prior_traces: vec![initial_handshake],
-        descriptors: vec![AgentDescriptor::new_server(server, TLSVersion::V1_3)],
-        steps: vec![
-            Step {
-                agent: server,
-                action: Action::Input(InputAction {
-                    recipe: term! {
-                        @full_client_hello
-                    },
-                }),
-            },
-            Step {
-                agent: server,
-                action: Action::Input(InputAction {
-                    recipe: term! {
-                        fn_encrypt_handshake(
-                            (@resumption_client_finished),
-                            (fn_server_hello_transcript(((server, 0)))),
-                            (fn_get_server_key_share(((server, 0)[Some(TlsQueryMatcher::Handshake(Some(HandshakeType::ServerHello)))]))),
-                            (fn_psk((@psk))),
-                            fn_named_group_secp384r1,
-                            fn_true,
-                            fn_seq_0  // sequence 0
-                        )
-                    },
-                }),
-            },
-        ],
+    let client_verify_data = term! {
+        fn_sign_transcript_premaster(
+            fn_new_random,
+            ((server, 0)),
+            (@premaster_secret),
+            (@client_key_exchange_transcript)
+        )
     };
 
+    Trace {
+        prior_traces: vec![],
+        descriptors: vec![AgentDescriptor {
+            ffdhe: server_key_exchange,
+            ..AgentDescriptor::new_server(server, TLSVersion::V1_2)
+        }],
+        steps: vec![
+            InputAction::new_step(server, client_hello),
+            InputAction::new_step(server, client_key_exchange),
+            InputAction::new_step(server, term! { fn_change_cipher_spec }),
+            InputAction::new_step(
+                server,
+                term! {
+                    fn_encrypt12_premaster(
+                        (fn_finished((@client_verify_data))),
+                        fn_new_random,
+                        ((server, 0)),
+                        (@premaster_secret),
+                        fn_true,
+                        fn_seq_0
+                    )
+                },
+            ),
+        ],
+    }
+}
+
+/// TLS 1.2 handshake with the RSA key exchange against a server. The PKCS#1 encoded pre-master
+/// secret is given by `encoded_premaster`.
+fn _seed_client_attacker12_rsa(
+    server: AgentName,
+    encoded_premaster: Term<TlsQueryMatcher>,
+) -> Trace<TlsQueryMatcher> {
+    let client_key_exchange = term! {
+        fn_encode_rsa_premaster12(
+            (fn_rsa_encrypt12(
+                (@encoded_premaster),
+                fn_alice_cert
+            ))
+        )
+    };
+
+    _seed_client_attacker12_premaster(
+        server,
+        term! { fn_cipher_suite12_rsa_aes_128_gcm_sha256 },
+        false,
+        client_key_exchange,
+        term! { fn_rsa_premaster_secret12(fn_protocol_version12) },
+    )
+}
+
+pub fn seed_client_attacker12_rsa(server: AgentName) -> Trace<TlsQueryMatcher> {
+    _seed_client_attacker12_rsa(
+        server,
+        term! {
+            fn_rsa_pkcs1_encode12(
+                (fn_rsa_premaster_secret12(fn_protocol_version12)),
+                fn_alice_cert
+            )
+        },
+    )
+}
+
+/// Bleichenbacher-style RSA key exchange: the pre-master secret has the PKCS#1 block type 1
+/// instead of 2. The server has to continue with a random pre-master secret and reject the
+/// Finished.
+pub fn seed_client_attacker12_rsa_malformed_padding(server: AgentName) -> Trace<TlsQueryMatcher> {
+    _seed_client_attacker12_rsa(
+        server,
+        term! {
+            fn_rsa_pkcs1_encode12_malformed(
+                (fn_rsa_premaster_secret12(fn_protocol_version12)),
+                fn_alice_cert,
+                fn_pkcs1_block_type_1,
+                fn_true
+            )
+        },
+    )
+}
+
+/// TLS 1.2 handshake with the finite field Diffie-Hellman key exchange against a server. The
+/// attacker uses the group which the server offers.
+pub fn seed_client_attacker12_dhe(server: AgentName) -> Trace<TlsQueryMatcher> {
+    let server_key_exchange = term! {
+        ((server, 0)[Some(TlsQueryMatcher::Handshake(Some(HandshakeType::ServerKeyExchange)))]/Vec<u8>)
+    };
+
+    let prime = term! { fn_decode_dh_prime12((@server_key_exchange)) };
+
+    let client_key_exchange = term! {
+        fn_encode_dh_pubkey12(
+            (fn_new_dh_pubkey12(
+                (@prime),
+                (fn_decode_dh_generator12((@server_key_exchange)))
+            ))
+        )
+    };
+
+    let premaster_secret = term! {
+        fn_dh_premaster_secret12(
+            (@prime),
+            (fn_decode_dh_pubkey12((@server_key_exchange)))
+        )
+    };
+
+    _seed_client_attacker12_premaster(
+        server,
+        term! { fn_cipher_suite12_dhe_rsa_aes_128_gcm_sha256 },
+        true,
+        client_key_exchange,
+        premaster_secret,
+    )
+}
+
+/// TLS 1.2 handshake of the attacker as server against a client, which negotiates `cipher_suite`
+/// with the RSA or finite field Diffie-Hellman key exchange. The attacker authenticates with the
+/// key of Alice.
+fn _seed_server_attacker12(
+    client: AgentName,
+    cipher_suite: Term<TlsQueryMatcher>,
+    server_key_exchange: Option<Term<TlsQueryMatcher>>,
+    premaster_secret: Term<TlsQueryMatcher>,
+) -> Trace<TlsQueryMatcher> {
+    _seed_server_attacker12_with_certificate(
+        client,
+        cipher_suite,
+        server_key_exchange,
+        premaster_secret,
+        term! { fn_alice_cert },
+    )
+}
+
+/// Like [`_seed_server_attacker12`], but the attacker sends the DER encoded `certificate`
+fn _seed_server_attacker12_with_certificate(
+    client: AgentName,
+    cipher_suite: Term<TlsQueryMatcher>,
+    server_key_exchange: Option<Term<TlsQueryMatcher>>,
+    premaster_secret: Term<TlsQueryMatcher>,
+    certificate: Term<TlsQueryMatcher>,
+) -> Trace<TlsQueryMatcher> {
+    _seed_server_attacker12_with_verify_data(
+        client,
+        cipher_suite,
+        server_key_exchange,
+        premaster_secret,
+        certificate,
+        false,
+    )
+    .0
+}
+
+/// Like [`_seed_server_attacker12_with_certificate`], but additionally returns the verify data of
+/// the client and server Finished. If `coalesce`, then the ServerHello up to the ServerHelloDone
+/// are sent in a single record.
+fn _seed_server_attacker12_with_verify_data(
+    client: AgentName,
+    cipher_suite: Term<TlsQueryMatcher>,
+    server_key_exchange: Option<Term<TlsQueryMatcher>>,
+    premaster_secret: Term<TlsQueryMatcher>,
+    certificate: Term<TlsQueryMatcher>,
+    coalesce: bool,
+) -> (
+    Trace<TlsQueryMatcher>,
+    Term<TlsQueryMatcher>,
+    Term<TlsQueryMatcher>,
+) {
+    let server_hello = term! {
+        fn_server_hello(
+            fn_protocol_version12,
+            fn_new_random,
+            ((client, 0)[Some(TlsQueryMatcher::Handshake(Some(HandshakeType::ClientHello)))]),
+            (@cipher_suite),
+            fn_compression,
+            (fn_server_extensions_append(
+                fn_server_extensions_new,
+                (fn_renegotiation_info_server_extension(fn_empty_bytes_vec))
+            ))
+        )
+    };
+
+    let certificate = term! {
+        fn_certificate(
+            (fn_append_certificate(
+                fn_new_certificates,
+                (fn_certificate_from_der((@certificate)))
+            ))
+        )
+    };
+
+    let certificate_transcript = term! {
+        fn_append_transcript(
+            (fn_append_transcript(
+                (fn_append_transcript(
+                    fn_new_transcript12,
+                    ((client, 0)[Some(TlsQueryMatcher::Handshake(Some(HandshakeType::ClientHello)))]) // ClientHello
+                )),
+                (@server_hello) // plaintext ServerHello
+            )),
+            (@certificate) // Certificate
+        )
+    };
+
+    let mut server_flight = vec![server_hello, certificate];
+
+    let server_key_exchange_transcript = match server_key_exchange {
+        Some(server_key_exchange) => {
+            server_flight.push(server_key_exchange.clone());
+            term! {
+                fn_append_transcript(
+                    (@certificate_transcript),
+                    (@server_key_exchange) // ServerKeyExchange
+                )
+            }
+        }
+        None => certificate_transcript,
+    };
+
+    let client_key_exchange_transcript = term! {
+        fn_append_transcript(
+            (fn_append_transcript(
+                (@server_key_exchange_transcript),
+                fn_server_hello_done // ServerHelloDone
+            )),
+            ((client, 0)[Some(TlsQueryMatcher::Handshake(Some(HandshakeType::ClientKeyExchange)))]) // ClientKeyExchange
+        )
+    };
+
+    let client_verify_data = term! {
+        fn_sign_transcript_premaster(
+            ((client, 0)),
+            fn_new_random,
+            (@premaster_secret),
+            (@client_key_exchange_transcript)
+        )
+    };
+
+    let client_finished_transcript = term! {
+        fn_append_transcript(
+            (@client_key_exchange_transcript),
+            (fn_finished((@client_verify_data))) // Finished of the client, which is computed instead of decrypted
+        )
+    };
+
+    let server_verify_data = term! {
+        fn_sign_transcript_server_premaster(
+            ((client, 0)),
+            fn_new_random,
+            (@premaster_secret),
+            (@client_finished_transcript)
+        )
+    };
+
+    server_flight.push(term! { fn_server_hello_done });
+
+    let mut steps = vec![OutputAction::new_step(client)];
+    if coalesce {
+        let messages =
+            server_flight
+                .into_iter()
+                .fold(term! { fn_new_messages }, |messages, message| {
+                    term! { fn_append_message((@messages), (@message)) }
+                });
+        steps.push(InputAction::new_step(
+            client,
+            term! { fn_coalesce((@messages)) },
+        ));
+    } else {
+        steps.extend(
+            server_flight
+                .into_iter()
+                .map(|message| InputAction::new_step(client, message)),
+        );
+    }
+
+    steps.extend([
+        OutputAction::new_step(client),
+        InputAction::new_step(client, term! { fn_change_cipher_spec }),
+        InputAction::new_step(
+            client,
+            term! {
+                fn_encrypt12_premaster(
+                    (fn_finished((@server_verify_data))),
+                    ((client, 0)),
+                    fn_new_random,
+                    (@premaster_secret),
+                    fn_false,
+                    fn_seq_0
+                )
+            },
+        ),
+    ]);
+
+    (
+        Trace {
+            prior_traces: vec![],
+            descriptors: vec![AgentDescriptor::new_client(client, TLSVersion::V1_2)],
+            steps,
+        },
+        client_verify_data,
+        server_verify_data,
+    )
+}
+
+pub fn seed_server_attacker12_rsa(client: AgentName) -> Trace<TlsQueryMatcher> {
+    let premaster_secret = term! {
+        fn_rsa_decrypt_premaster12(
+            ((client, 0)[Some(TlsQueryMatcher::Handshake(Some(HandshakeType::ClientKeyExchange)))]/Vec<u8>),
+            fn_alice_key
+        )
+    };
+
+    _seed_server_attacker12(
+        client,
+        term! { fn_cipher_suite12_rsa_aes_128_gcm_sha256 },
+        None,
+        premaster_secret,
+    )
+}
+
+/// TLS 1.1 handshake of the attacker as server against a client with the RSA key exchange. TLS 1.1
+/// has no AEAD suites, so the attacker negotiates TLS_RSA_WITH_AES_128_CBC_SHA. All records carry
+/// the negotiated version.
+pub fn seed_server_attacker11_rsa(client: AgentName) -> Trace<TlsQueryMatcher> {
+    let server_hello = term! {
+        fn_server_hello(
+            fn_protocol_version11,
+            fn_new_random,
+            ((client, 0)[Some(TlsQueryMatcher::Handshake(Some(HandshakeType::ClientHello)))]),
+            fn_cipher_suite12_rsa_aes_128_cbc_sha,
+            fn_compression,
+            (fn_server_extensions_append(
+                fn_server_extensions_new,
+                (fn_renegotiation_info_server_extension(fn_empty_bytes_vec))
+            ))
+        )
+    };
+
+    let certificate = term! {
+        fn_certificate(
+            (fn_append_certificate(
+                fn_new_certificates,
+                (fn_certificate_from_der(fn_alice_cert))
+            ))
+        )
+    };
+
+    let premaster_secret = term! {
+        fn_rsa_decrypt_premaster12(
+            ((client, 0)[Some(TlsQueryMatcher::Handshake(Some(HandshakeType::ClientKeyExchange)))]/Vec<u8>),
+            fn_alice_key
+        )
+    };
+
+    let client_key_exchange_transcript = term! {
+        fn_append_transcript(
+            (fn_append_transcript(
+                (fn_append_transcript(
+                    (fn_append_transcript(
+                        (fn_append_transcript(
+                            fn_new_transcript_legacy,
+                            ((client, 0)[Some(TlsQueryMatcher::Handshake(Some(HandshakeType::ClientHello)))]) // ClientHello
+                        )),
+                        (@server_hello) // ServerHello
+                    )),
+                    (@certificate) // Certificate
+                )),
+                fn_server_hello_done // ServerHelloDone
+            )),
+            ((client, 0)[Some(TlsQueryMatcher::Handshake(Some(HandshakeType::ClientKeyExchange)))]) // ClientKeyExchange
+        )
+    };
+
+    let client_verify_data = term! {
+        fn_sign_transcript_legacy(
+            ((client, 0)),
+            fn_new_random,
+            (@premaster_secret),
+            fn_protocol_version11,
+            (@client_key_exchange_transcript)
+        )
+    };
+
+    let client_finished_transcript = term! {
+        fn_append_transcript(
+            (@client_key_exchange_transcript),
+            (fn_finished((@client_verify_data))) // Finished of the client, which is computed instead of decrypted
+        )
+    };
+
+    let server_verify_data = term! {
+        fn_sign_transcript_server_legacy(
+            ((client, 0)),
+            fn_new_random,
+            (@premaster_secret),
+            fn_protocol_version11,
+            (@client_finished_transcript)
+        )
+    };
+
+    Trace {
+        prior_traces: vec![],
+        descriptors: vec![AgentDescriptor::new_client(client, TLSVersion::V1_1)],
+        steps: vec![
+            OutputAction::new_step(client),
+            InputAction::new_step(
+                client,
+                term! { fn_with_record_version((@server_hello), fn_protocol_version11) },
+            ),
+            InputAction::new_step(
+                client,
+                term! { fn_with_record_version((@certificate), fn_protocol_version11) },
+            ),
+            InputAction::new_step(
+                client,
+                term! { fn_with_record_version(fn_server_hello_done, fn_protocol_version11) },
+            ),
+            OutputAction::new_step(client),
+            InputAction::new_step(
+                client,
+                term! { fn_with_record_version(fn_change_cipher_spec, fn_protocol_version11) },
+            ),
+            InputAction::new_step(
+                client,
+                term! {
+                    fn_encrypt_legacy(
+                        (fn_with_record_version(
+                            (fn_finished((@server_verify_data))),
+                            fn_protocol_version11
+                        )),
+                        ((client, 0)),
+                        fn_new_random,
+                        (@premaster_secret),
+                        fn_protocol_version11,
+                        fn_cipher_suite12_rsa_aes_128_cbc_sha,
+                        fn_false,
+                        fn_seq_0
+                    )
+                },
+            ),
+        ],
+    }
+}
+
+/// Leaf certificate for the key of Alice which is issued by the static certificate of Alice
+fn _x509_alice_leaf_tbs() -> Term<TlsQueryMatcher> {
+    term! {
+        fn_x509_tbs_certificate(
+            fn_seq_1,
+            fn_rsa_pkcs1_signature_algorithm,
+            (fn_x509_subject(fn_alice_cert)),
+            fn_x509_validity_default,
+            (fn_x509_name_append_common_name(
+                fn_x509_name_empty,
+                fn_x509_common_name
+            )),
+            (fn_x509_public_key(fn_alice_cert)),
+            (fn_x509_extensions_append(
+                (fn_x509_extensions_append(
+                    (fn_x509_extensions_append(
+                        fn_x509_extensions_new,
+                        (fn_x509_key_usage_extension(fn_x509_key_usage_leaf))
+                    )),
+                    (fn_x509_basic_constraints_extension(fn_false))
+                )),
+                fn_x509_server_auth_extension
+            ))
+        )
+    }
+}
+
+fn _seed_server_attacker12_rsa_x509(
+    client: AgentName,
+    certificate: Term<TlsQueryMatcher>,
+) -> Trace<TlsQueryMatcher> {
+    let premaster_secret = term! {
+        fn_rsa_decrypt_premaster12(
+            ((client, 0)[Some(TlsQueryMatcher::Handshake(Some(HandshakeType::ClientKeyExchange)))]/Vec<u8>),
+            fn_alice_key
+        )
+    };
+
+    _seed_server_attacker12_with_certificate(
+        client,
+        term! { fn_cipher_suite12_rsa_aes_128_gcm_sha256 },
+        None,
+        premaster_secret,
+        certificate,
+    )
+}
+
+/// Like [`seed_server_attacker12_rsa`], but the certificate is constructed from its fields and
+/// signed with the key of Alice
+pub fn seed_server_attacker12_rsa_x509(client: AgentName) -> Trace<TlsQueryMatcher> {
+    let tbs = _x509_alice_leaf_tbs();
+    _seed_server_attacker12_rsa_x509(client, term! { fn_x509_sign((@tbs), fn_alice_key) })
+}
+
+/// Like [`seed_server_attacker12_rsa_x509`], but the signature of the certificate is broken
+pub fn seed_server_attacker12_rsa_x509_broken_signature(
+    client: AgentName,
+) -> Trace<TlsQueryMatcher> {
+    let tbs = _x509_alice_leaf_tbs();
+    _seed_server_attacker12_rsa_x509(client, term! { fn_x509_sign_broken((@tbs), fn_alice_key) })
+}
+
+/// Like [`seed_client_attacker12`], but negotiates the extended master secret. The master secret
+/// is derived from the session hash of the handshake up to the ClientKeyExchange.
+pub fn seed_client_attacker12_ems(server: AgentName) -> Trace<TlsQueryMatcher> {
+    let client_hello = term! {
+          fn_client_hello(
+            fn_protocol_version12,
+            fn_new_random,
+            fn_new_session_id,
+            (fn_append_cipher_suite(
+                (fn_new_cipher_suites()),
+                fn_cipher_suite12
+            )),
+            fn_compressions,
+            (fn_client_extensions_append(
+                (fn_client_extensions_append(
+                    (fn_client_extensions_append(
+                        (fn_client_extensions_append(
+                            (fn_client_extensions_append(
+                                fn_client_extensions_new,
+                                (fn_support_group_extension(fn_named_group_secp384r1))
+                            )),
+                            fn_signature_algorithm_extension
+                        )),
+                        fn_ec_point_formats_extension
+                    )),
+                    (fn_renegotiation_info_extension(fn_empty_bytes_vec))
+                )),
+                fn_extended_master_secret_extension
+            ))
+        )
+    };
+
+    let server_hello_done_transcript = term! {
+        fn_append_transcript(
+            (fn_append_transcript(
+                (fn_append_transcript(
+                    (fn_append_transcript(
+                        (fn_append_transcript(
+                            fn_new_transcript12,
+                            (@client_hello) // ClientHello
+                        )),
+                        ((server, 0)[Some(TlsQueryMatcher::Handshake(Some(HandshakeType::ServerHello)))]) // plaintext ServerHello
+                    )),
+                    ((server, 0)[Some(TlsQueryMatcher::Handshake(Some(HandshakeType::Certificate)))]) // Certificate
+                )),
+                ((server, 0)[Some(TlsQueryMatcher::Handshake(Some(HandshakeType::ServerKeyExchange)))]) // ServerKeyExchange
+            )),
+            ((server, 0)[Some(TlsQueryMatcher::Handshake(Some(HandshakeType::ServerHelloDone)))]) // ServerHelloDone
+        )
+    };
+
+    let client_key_exchange = term! {
+        fn_client_key_exchange(
+            (fn_encode_ec_pubkey12(
+                (fn_new_pubkey12(fn_named_group_secp384r1))
+            ))
+        )
+    };
+
+    // The session hash is also the transcript of the client Finished
+    let session_hash = term! {
+        fn_append_transcript(
+            (@server_hello_done_transcript),
+            (@client_key_exchange)
+        )
+    };
+
+    let server_ecdh_pubkey = term! {
+        fn_decode_ecdh_pubkey(
+            ((server, 0)[Some(TlsQueryMatcher::Handshake(Some(HandshakeType::ServerKeyExchange)))]/Vec<u8>) // ServerECDHParams
+        )
+    };
+
+    let client_verify_data = term! {
+        fn_sign_transcript_ems(
+            ((server, 0)),
+            (@server_ecdh_pubkey),
+            (@session_hash),
+            (@session_hash),
+            fn_named_group_secp384r1
+        )
+    };
+
+    Trace {
+        prior_traces: vec![],
+        descriptors: vec![AgentDescriptor::new_server(server, TLSVersion::V1_2)],
+        steps: vec![
+            Step {
+                agent: server,
+                action: Action::Input(InputAction {
+                    recipe: client_hello,
+                }),
+            },
+            Step {
+                agent: server,
+                action: Action::Input(InputAction {
+                    recipe: client_key_exchange,
+                }),
+            },
+            Step {
+                agent: server,
+                action: Action::Input(InputAction {
+                    recipe: term! { fn_change_cipher_spec },
+                }),
+            },
+            Step {
+                agent: server,
+                action: Action::Input(InputAction {
+                    recipe: term! {
+                        fn_encrypt12_ems(
+                            (fn_finished((@client_verify_data))),
+                            ((server, 0)),
+                            (@server_ecdh_pubkey),
+                            fn_named_group_secp384r1,
+                            (@session_hash),
+                            fn_true,
+                            fn_seq_0
+                        )
+                    },
+                }),
+            },
+        ],
+    }
+}
+
+/// Client-initiated renegotiation after [`seed_client_attacker12`]. The renegotiating ClientHello
+/// carries the verify data of the previous client Finished in its `renegotiation_info` extension.
+/// The second handshake is protected with the keys of the first one, up to the ChangeCipherSpec.
+pub fn seed_client_attacker12_renegotiation(server: AgentName) -> Trace<TlsQueryMatcher> {
+    let (mut trace, client_verify_data) = _seed_client_attacker12(server);
+
+    let client_hello = _client_hello12_with_renegotiation_info(
+        term! { fn_cipher_suite12 },
+        Some(client_verify_data),
+    );
+
+    let server_ecdh_pubkey = term! {
+        fn_decode_ecdh_pubkey(
+            ((server, 0)[Some(TlsQueryMatcher::Handshake(Some(HandshakeType::ServerKeyExchange)))]/Vec<u8>) // ServerECDHParams
+        )
+    };
+
+    let encrypt = |message: Term<TlsQueryMatcher>, sequence: Term<TlsQueryMatcher>| {
+        term! {
+            fn_encrypt12(
+                (@message),
+                ((server, 0)),
+                (@server_ecdh_pubkey),
+                fn_named_group_secp384r1,
+                fn_true,
+                (@sequence)
+            )
+        }
+    };
+
+    // The encrypted messages of the server follow its Finished of the first handshake
+    let decrypt = |counter: u16, sequence: Term<TlsQueryMatcher>| {
+        term! {
+            fn_decrypt12_with_suite(
+                ((server, counter)[Some(TlsQueryMatcher::Handshake(None))]),
+                ((server, 0)),
+                (@server_ecdh_pubkey),
+                fn_named_group_secp384r1,
+                fn_cipher_suite12,
+                fn_true,
+                (@sequence)
+            )
+        }
+    };
+
+    let server_hello = decrypt(1, term! { fn_seq_1 });
+    let certificate = decrypt(2, term! { fn_seq_2 });
+    let server_key_exchange = decrypt(3, term! { fn_seq_3 });
+    let server_hello_done = decrypt(4, term! { fn_seq_4 });
+
+    let server_random = term! { fn_get_server_random((@server_hello)) };
+    let renegotiated_ecdh_pubkey = term! {
+        fn_decode_ecdh_pubkey(
+            (fn_get_server_key_exchange((@server_key_exchange)))
+        )
+    };
+
+    let client_key_exchange = term! {
+        fn_client_key_exchange(
+            (fn_encode_ec_pubkey12(
+                (fn_new_pubkey12(fn_named_group_secp384r1))
+            ))
+        )
+    };
+
+    let client_key_exchange_transcript = term! {
+        fn_append_transcript(
+            (fn_append_transcript(
+                (fn_append_transcript(
+                    (fn_append_transcript(
+                        (fn_append_transcript(
+                            (fn_append_transcript(
+                                fn_new_transcript12,
+                                (@client_hello) // renegotiating ClientHello
+                            )),
+                            (@server_hello)
+                        )),
+                        (@certificate)
+                    )),
+                    (@server_key_exchange)
+                )),
+                (@server_hello_done)
+            )),
+            (@client_key_exchange)
+        )
+    };
+
+    let renegotiated_verify_data = term! {
+        fn_sign_transcript(
+            (@server_random),
+            (@renegotiated_ecdh_pubkey),
+            (@client_key_exchange_transcript),
+            fn_named_group_secp384r1
+        )
+    };
+
+    trace.steps.extend([
+        InputAction::new_step(server, encrypt(client_hello, term! { fn_seq_1 })),
+        InputAction::new_step(server, encrypt(client_key_exchange, term! { fn_seq_2 })),
+        InputAction::new_step(
+            server,
+            encrypt(term! { fn_change_cipher_spec }, term! { fn_seq_3 }),
+        ),
+        InputAction::new_step(
+            server,
+            term! {
+                fn_encrypt12(
+                    (fn_finished((@renegotiated_verify_data))),
+                    (@server_random),
+                    (@renegotiated_ecdh_pubkey),
+                    fn_named_group_secp384r1,
+                    fn_true,
+                    fn_seq_0
+                )
+            },
+        ),
+    ]);
+
+    trace
+}
+
+/// Like [`seed_client_attacker12_renegotiation`], but the renegotiating ClientHello lacks the
+/// `renegotiation_info` extension. The server has to refuse the renegotiation.
+pub fn seed_client_attacker12_renegotiation_insecure(server: AgentName) -> Trace<TlsQueryMatcher> {
+    let (mut trace, _) = _seed_client_attacker12(server);
+
+    let client_hello = _client_hello12_with_renegotiation_info(term! { fn_cipher_suite12 }, None);
+
+    trace.steps.push(InputAction::new_step(
+        server,
+        term! {
+            fn_encrypt12(
+                (@client_hello),
+                ((server, 0)),
+                (fn_decode_ecdh_pubkey(
+                    ((server, 0)[Some(TlsQueryMatcher::Handshake(Some(HandshakeType::ServerKeyExchange)))]/Vec<u8>) // ServerECDHParams
+                )),
+                fn_named_group_secp384r1,
+                fn_true,
+                fn_seq_1
+            )
+        },
+    ));
+
+    trace
+}
+
+/// Server-initiated renegotiation after [`seed_server_attacker12_rsa`]. The attacker sends a
+/// HelloRequest and answers the renegotiating ClientHello with a ServerHello, Certificate and
+/// ServerHelloDone. These are protected with the keys of the first handshake. The client answers
+/// with the encrypted ClientKeyExchange and Finished of the second handshake, which the attacker
+/// decrypts to continue on the renegotiated keys. If `secure`, then the ServerHello carries the
+/// verify data of both previous Finished in its `renegotiation_info` extension.
+fn _seed_server_attacker12_renegotiation(
+    client: AgentName,
+    secure: bool,
+) -> Trace<TlsQueryMatcher> {
+    let premaster_secret = term! {
+        fn_rsa_decrypt_premaster12(
+            ((client, 0)[Some(TlsQueryMatcher::Handshake(Some(HandshakeType::ClientKeyExchange)))]/Vec<u8>),
+            fn_alice_key
+        )
+    };
+
+    let (mut trace, client_verify_data, server_verify_data) =
+        _seed_server_attacker12_with_verify_data(
+            client,
+            term! { fn_cipher_suite12_rsa_aes_128_gcm_sha256 },
+            None,
+            premaster_secret.clone(),
+            term! { fn_alice_cert },
+            false,
+        );
+
+    let encrypt = |message: Term<TlsQueryMatcher>, sequence: Term<TlsQueryMatcher>| {
+        term! {
+            fn_encrypt12_premaster(
+                (@message),
+                ((client, 0)),
+                fn_new_random,
+                (@premaster_secret),
+                fn_false,
+                (@sequence)
+            )
+        }
+    };
+
+    // The encrypted ClientHello follows the client Finished of the first handshake
+    let client_hello = term! {
+        fn_decrypt12_premaster(
+            ((client, 1)[Some(TlsQueryMatcher::Handshake(None))]),
+            ((client, 0)),
+            fn_new_random,
+            (@premaster_secret),
+            fn_false,
+            fn_seq_1
+        )
+    };
+
+    let server_extensions = if secure {
+        term! {
+            fn_server_extensions_append(
+                fn_server_extensions_new,
+                (fn_renegotiation_info_server_extension(
+                    (fn_server_renegotiation_info(
+                        (@client_verify_data),
+                        (@server_verify_data)
+                    ))
+                ))
+            )
+        }
+    } else {
+        term! { fn_server_extensions_new }
+    };
+
+    let server_hello = term! {
+        fn_server_hello(
+            fn_protocol_version12,
+            fn_new_random,
+            fn_new_session_id,
+            fn_cipher_suite12_rsa_aes_128_gcm_sha256,
+            fn_compression,
+            (@server_extensions)
+        )
+    };
+
+    let certificate = term! {
+        fn_certificate(
+            (fn_append_certificate(
+                fn_new_certificates,
+                (fn_certificate_from_der(fn_alice_cert))
+            ))
+        )
+    };
+
+    let server_hello_done_transcript = term! {
+        fn_append_transcript(
+            (fn_append_transcript(
+                (fn_append_transcript(
+                    (fn_append_transcript(
+                        fn_new_transcript12,
+                        (@client_hello) // renegotiating ClientHello
+                    )),
+                    (@server_hello)
+                )),
+                (@certificate)
+            )),
+            fn_server_hello_done
+        )
+    };
+
+    trace.steps.extend([
+        InputAction::new_step(
+            client,
+            encrypt(term! { fn_hello_request }, term! { fn_seq_1 }),
+        ),
+        InputAction::new_step(client, encrypt(server_hello, term! { fn_seq_2 })),
+        InputAction::new_step(client, encrypt(certificate, term! { fn_seq_3 })),
+        InputAction::new_step(
+            client,
+            encrypt(term! { fn_server_hello_done }, term! { fn_seq_4 }),
+        ),
+    ]);
+
+    let client_key_exchange = term! {
+        fn_decrypt12_premaster(
+            ((client, 2)[Some(TlsQueryMatcher::Handshake(None))]),
+            ((client, 0)),
+            fn_new_random,
+            (@premaster_secret),
+            fn_false,
+            fn_seq_2
+        )
+    };
+
+    let client_random = term! { fn_get_client_random((@client_hello)) };
+
+    let renegotiated_premaster_secret = term! {
+        fn_rsa_decrypt_premaster12(
+            (fn_get_client_key_exchange((@client_key_exchange))),
+            fn_alice_key
+        )
+    };
+
+    // The Finished of the client is the first message on the renegotiated keys
+    let client_finished = term! {
+        fn_decrypt12_premaster(
+            ((client, 3)[Some(TlsQueryMatcher::Handshake(None))]),
+            (@client_random),
+            fn_new_random,
+            (@renegotiated_premaster_secret),
+            fn_false,
+            fn_seq_0
+        )
+    };
+
+    let client_finished_transcript = term! {
+        fn_append_transcript(
+            (fn_append_transcript(
+                (@server_hello_done_transcript),
+                (@client_key_exchange)
+            )),
+            (@client_finished)
+        )
+    };
+
+    let server_verify_data = term! {
+        fn_sign_transcript_server_premaster(
+            (@client_random),
+            fn_new_random,
+            (@renegotiated_premaster_secret),
+            (@client_finished_transcript)
+        )
+    };
+
+    trace.steps.extend([
+        InputAction::new_step(
+            client,
+            encrypt(term! { fn_change_cipher_spec }, term! { fn_seq_5 }),
+        ),
+        InputAction::new_step(
+            client,
+            term! {
+                fn_encrypt12_premaster(
+                    (fn_finished((@server_verify_data))),
+                    (@client_random),
+                    fn_new_random,
+                    (@renegotiated_premaster_secret),
+                    fn_false,
+                    fn_seq_0
+                )
+            },
+        ),
+    ]);
+
+    trace
+}
+
+/// Secure server-initiated renegotiation
+pub fn seed_server_attacker12_renegotiation(client: AgentName) -> Trace<TlsQueryMatcher> {
+    _seed_server_attacker12_renegotiation(client, true)
+}
+
+/// Like [`seed_server_attacker12_renegotiation`], but the renegotiating ServerHello lacks the
+/// `renegotiation_info` extension. The client must not complete the renegotiation.
+pub fn seed_server_attacker12_renegotiation_insecure(client: AgentName) -> Trace<TlsQueryMatcher> {
+    _seed_server_attacker12_renegotiation(client, false)
+}
+
+/// Like [`seed_client_attacker12`], but the ClientHello is split into records of one, four and
+/// the remaining bytes. The first two records split the handshake header.
+pub fn seed_client_attacker12_fragmented(server: AgentName) -> Trace<TlsQueryMatcher> {
+    let (mut trace, _) = _seed_client_attacker12(server);
+
+    if let Action::Input(input) = &mut trace.steps[0].action {
+        let client_hello = input.recipe.clone();
+        input.recipe = term! {
+            fn_fragment(
+                (@client_hello),
+                (fn_append_fragment_size(
+                    (fn_append_fragment_size(
+                        fn_new_fragment_sizes,
+                        fn_seq_1
+                    )),
+                    fn_seq_4
+                ))
+            )
+        };
+    }
+
+    trace
+}
+
+/// Like [`seed_server_attacker12_rsa`], but the ServerHello, Certificate and ServerHelloDone are
+/// sent in a single record
+pub fn seed_server_attacker12_rsa_coalesced(client: AgentName) -> Trace<TlsQueryMatcher> {
+    let premaster_secret = term! {
+        fn_rsa_decrypt_premaster12(
+            ((client, 0)[Some(TlsQueryMatcher::Handshake(Some(HandshakeType::ClientKeyExchange)))]/Vec<u8>),
+            fn_alice_key
+        )
+    };
+
+    _seed_server_attacker12_with_verify_data(
+        client,
+        term! { fn_cipher_suite12_rsa_aes_128_gcm_sha256 },
+        None,
+        premaster_secret,
+        term! { fn_alice_cert },
+        true,
+    )
+    .0
+}
+
+/// Like [`seed_client_attacker`], but the encrypted Finished is split into records of one and the
+/// remaining bytes
+pub fn seed_client_attacker_fragmented(server: AgentName) -> Trace<TlsQueryMatcher> {
+    let client_finished = _client_finished13(server);
+
+    Trace {
+        prior_traces: vec![],
+        descriptors: vec![AgentDescriptor::new_server(server, TLSVersion::V1_3)],
+        steps: vec![
+            InputAction::new_step(server, _client_hello13()),
+            InputAction::new_step(
+                server,
+                term! {
+                    fn_encrypt_handshake_records(
+                        (fn_fragment(
+                            (@client_finished),
+                            (fn_append_fragment_size(fn_new_fragment_sizes, fn_seq_1))
+                        )),
+                        (fn_server_hello_transcript(((server, 0)))),
+                        (fn_get_server_key_share(((server, 0)))),
+                        fn_no_psk,
+                        fn_named_group_secp384r1,
+                        fn_true,
+                        fn_seq_0
+                    )
+                },
+            ),
+            OutputAction::new_step(server),
+        ],
+    }
+}
+
+/// Finite field Diffie-Hellman key exchange of the attacker as server in the group of `prime`
+/// and `generator`
+fn _seed_server_attacker12_dhe(
+    client: AgentName,
+    prime: Term<TlsQueryMatcher>,
+    generator: Term<TlsQueryMatcher>,
+) -> Trace<TlsQueryMatcher> {
+    let params = term! {
+        fn_dh_server_params12(
+            (@prime),
+            (@generator),
+            (fn_new_dh_pubkey12((@prime), (@generator)))
+        )
+    };
+
+    let server_key_exchange = term! {
+        fn_server_key_exchange_dhe(
+            (@params),
+            fn_rsa_pkcs1_signature_algorithm,
+            (fn_rsa_sign_server_params12(
+                ((client, 0)),
+                fn_new_random,
+                (@params),
+                fn_alice_key,
+                fn_rsa_pkcs1_signature_algorithm
+            ))
+        )
+    };
+
+    let premaster_secret = term! {
+        fn_dh_premaster_secret12(
+            (@prime),
+            (fn_decode_client_dh_pubkey12(
+                ((client, 0)[Some(TlsQueryMatcher::Handshake(Some(HandshakeType::ClientKeyExchange)))]/Vec<u8>)
+            ))
+        )
+    };
+
+    _seed_server_attacker12(
+        client,
+        term! { fn_cipher_suite12_dhe_rsa_aes_128_gcm_sha256 },
+        Some(server_key_exchange),
+        premaster_secret,
+    )
+}
+
+pub fn seed_server_attacker12_dhe(client: AgentName) -> Trace<TlsQueryMatcher> {
+    _seed_server_attacker12_dhe(
+        client,
+        term! { fn_ffdhe2048_prime },
+        term! { fn_ffdhe2048_generator },
+    )
+}
+
+/// The attacker offers a 64-bit prime, which the client has to reject
+pub fn seed_server_attacker12_dhe_weak_prime(client: AgentName) -> Trace<TlsQueryMatcher> {
+    _seed_server_attacker12_dhe(
+        client,
+        term! { fn_dh_weak_prime },
+        term! { fn_ffdhe2048_generator },
+    )
+}
+
+pub fn seed_session_resumption_dhe(
+    initial_server: AgentName,
+    server: AgentName,
+) -> Trace<TlsQueryMatcher> {
+    let initial_handshake = seed_client_attacker(initial_server);
+
+    let new_ticket_message = term! {
+        fn_decrypt_application(
+            ((initial_server, 4)[Some(TlsQueryMatcher::ApplicationData)]), // Ticket from last session
+            (fn_server_hello_transcript(((initial_server, 0)))),
+            (fn_server_finished_transcript(((initial_server, 0)))),
+            (fn_get_server_key_share(((initial_server, 0)))),
+            fn_no_psk,
+            fn_named_group_secp384r1,
+            fn_true,
+            fn_seq_0 // sequence restarts at 0 because we are decrypting now traffic
+        )
+    };
+
+    let client_hello = term! {
+          fn_client_hello(
+            fn_protocol_version12,
+            fn_new_random,
+            fn_new_session_id,
+            (fn_append_cipher_suite(
+                (fn_new_cipher_suites()),
+                fn_cipher_suite13_aes_128_gcm_sha256
+            )),
+            fn_compressions,
+            (fn_client_extensions_append(
+                (fn_client_extensions_append(
+                    (fn_client_extensions_append(
+                        (fn_client_extensions_append(
+                            (fn_client_extensions_append(
+                                (fn_client_extensions_append(
+                                    fn_client_extensions_new,
+                                    (fn_support_group_extension(fn_named_group_secp384r1))
+                                )),
+                                fn_signature_algorithm_extension
+                            )),
+                            fn_supported_versions13_extension
+                        )),
+                        (fn_key_share_deterministic_extension(fn_named_group_secp384r1))
+                    )),
+                    fn_psk_exchange_mode_dhe_ke_extension
+                )),
+                // https://datatracker.ietf.org/doc/html/rfc8446#section-2.2
+                // must be last in client_hello, and initially empty until filled by fn_fill_binder
+                (fn_preshared_keys_extension_empty_binder(
+                    (@new_ticket_message)
+                ))
+            ))
+        )
+    };
+
+    let psk = term! {
+        fn_derive_psk(
+            (fn_server_hello_transcript(((initial_server, 0)))),
+            (fn_server_finished_transcript(((initial_server, 0)))),
+            (fn_client_finished_transcript(((initial_server, 0)))),
+            (fn_get_server_key_share(((initial_server, 0)[Some(TlsQueryMatcher::Handshake(Some(HandshakeType::ServerHello)))]))),
+            (fn_get_ticket_nonce((@new_ticket_message))),
+            fn_named_group_secp384r1
+        )
+    };
+
+    let binder = term! {
+        fn_derive_binder(
+            (@client_hello),
+            (@psk)
+        )
+    };
+
+    let full_client_hello = term! {
+        fn_fill_binder(
+            (@client_hello),
+            (@binder)
+        )
+    };
+
+    let resumption_client_finished = term! {
+        fn_finished(
+            (fn_verify_data(
+                (fn_server_finished_transcript(((server, 0)))),
+                (fn_server_hello_transcript(((server, 0)))),
+                (fn_get_server_key_share(((server, 0)[Some(TlsQueryMatcher::Handshake(Some(HandshakeType::ServerHello)))]))),
+                (fn_psk((@psk))),
+                fn_named_group_secp384r1
+            ))
+        )
+    };
+
+    let trace = Trace {
+        prior_traces: vec![initial_handshake],
+        descriptors: vec![AgentDescriptor::new_server(server, TLSVersion::V1_3)],
+        steps: vec![
+            Step {
+                agent: server,
+                action: Action::Input(InputAction {
+                    recipe: term! {
+                        @full_client_hello
+                    },
+                }),
+            },
+            Step {
+                agent: server,
+                action: Action::Input(InputAction {
+                    recipe: term! {
+                        fn_encrypt_handshake(
+                            (@resumption_client_finished),
+                            (fn_server_hello_transcript(((server, 0)))),
+                            (fn_get_server_key_share(((server, 0)[Some(TlsQueryMatcher::Handshake(Some(HandshakeType::ServerHello)))]))),
+                            (fn_psk((@psk))),
+                            fn_named_group_secp384r1,
+                            fn_true,
+                            fn_seq_0  // sequence 0
+                        )
+                    },
+                }),
+            },
+        ],
+    };
+
+    trace
+}
+
+pub fn seed_session_resumption_ke(
+    initial_server: AgentName,
+    server: AgentName,
+) -> Trace<TlsQueryMatcher> {
+    let initial_handshake = seed_client_attacker(initial_server);
+
+    let new_ticket_message = term! {
+        fn_decrypt_application(
+            ((initial_server, 4)[Some(TlsQueryMatcher::ApplicationData)]), // Ticket from last session
+            (fn_server_hello_transcript(((initial_server, 0)))),
+            (fn_server_finished_transcript(((initial_server, 0)))),
+            (fn_get_server_key_share(((initial_server, 0)))),
+            fn_no_psk,
+            fn_named_group_secp384r1,
+            fn_true,
+            fn_seq_0 // sequence restarts at 0 because we are decrypting now traffic
+        )
+    };
+
+    let client_hello = term! {
+          fn_client_hello(
+            fn_protocol_version12,
+            fn_new_random,
+            fn_new_session_id,
+            (fn_append_cipher_suite(
+                (fn_new_cipher_suites()),
+                fn_cipher_suite13_aes_128_gcm_sha256
+            )),
+            fn_compressions,
+            (fn_client_extensions_append(
+                (fn_client_extensions_append(
+                    (fn_client_extensions_append(
+                        (fn_client_extensions_append(
+                            (fn_client_extensions_append(
+                                (fn_client_extensions_append(
+                                    fn_client_extensions_new,
+                                    (fn_support_group_extension(fn_named_group_secp384r1))
+                                )),
+                                fn_signature_algorithm_extension
+                            )),
+                            fn_supported_versions13_extension
+                        )),
+                        (fn_key_share_deterministic_extension(fn_named_group_secp384r1))
+                    )),
+                    fn_psk_exchange_mode_ke_extension
+                )),
+                // https://datatracker.ietf.org/doc/html/rfc8446#section-2.2
+                // must be last in client_hello, and initially empty until filled by fn_fill_binder
+                (fn_preshared_keys_extension_empty_binder(
+                    (@new_ticket_message)
+                ))
+            ))
+        )
+    };
+
+    let psk = term! {
+        fn_derive_psk(
+            (fn_server_hello_transcript(((initial_server, 0)))),
+            (fn_server_finished_transcript(((initial_server, 0)))),
+            (fn_client_finished_transcript(((initial_server, 0)))),
+            (fn_get_server_key_share(((initial_server, 0)[Some(TlsQueryMatcher::Handshake(Some(HandshakeType::ServerHello)))]))),
+            (fn_get_ticket_nonce((@new_ticket_message))),
+            fn_named_group_secp384r1
+        )
+    };
+
+    let binder = term! {
+        fn_derive_binder(
+            (@client_hello),
+            (@psk)
+        )
+    };
+
+    let full_client_hello = term! {
+        fn_fill_binder(
+            (@client_hello),
+            (@binder)
+        )
+    };
+
+    let resumption_client_finished = term! {
+        fn_finished(
+            (fn_verify_data(
+                (fn_server_finished_transcript(((server, 0)))),
+                (fn_server_hello_transcript(((server, 0)))),
+                fn_no_key_share,
+                (fn_psk((@psk))),
+                fn_named_group_secp384r1
+            ))
+        )
+    };
+
+    let trace = Trace {
+        prior_traces: vec![initial_handshake],
+        descriptors: vec![AgentDescriptor::new_server(server, TLSVersion::V1_3)],
+        steps: vec![
+            Step {
+                agent: server,
+                action: Action::Input(InputAction {
+                    recipe: term! {
+                        @full_client_hello
+                    },
+                }),
+            },
+            Step {
+                agent: server,
+                action: Action::Input(InputAction {
+                    recipe: term! {
+                        fn_encrypt_handshake(
+                            (@resumption_client_finished),
+                            (fn_server_hello_transcript(((server, 0)))),
+                            fn_no_key_share,
+                            (fn_psk((@psk))),
+                            fn_named_group_secp384r1,
+                            fn_true,
+                            fn_seq_0  // sequence 0
+                        )
+                    },
+                }),
+            },
+        ],
+    };
+
+    trace
+}
+
+pub fn seed_client_attacker_full(server: AgentName) -> Trace<TlsQueryMatcher> {
+    _seed_client_attacker_full(server).0
+}
+
+/// Seed which contains the whole transcript in the tree. This is rather huge >300 symbols
+pub fn _seed_client_attacker_full(
+    server: AgentName,
+) -> (
+    Trace<TlsQueryMatcher>,
+    Term<TlsQueryMatcher>,
+    Term<TlsQueryMatcher>,
+    Term<TlsQueryMatcher>,
+) {
+    let client_hello = term! {
+          fn_client_hello(
+            fn_protocol_version12,
+            fn_new_random,
+            fn_new_session_id,
+            (fn_append_cipher_suite(
+                (fn_new_cipher_suites()),
+                fn_cipher_suite13_aes_128_gcm_sha256
+            )),
+            fn_compressions,
+            (fn_client_extensions_append(
+                (fn_client_extensions_append(
+                    (fn_client_extensions_append(
+                        (fn_client_extensions_append(
+                            fn_client_extensions_new,
+                            (fn_support_group_extension(fn_named_group_secp384r1))
+                        )),
+                        fn_signature_algorithm_extension
+                    )),
+                    (fn_key_share_deterministic_extension(fn_named_group_secp384r1))
+                )),
+                fn_supported_versions13_extension
+            ))
+        )
+    };
+
+    let server_hello_transcript = term! {
+        fn_append_transcript(
+            (fn_append_transcript(
+                fn_new_transcript,
+                (@client_hello) // ClientHello
+            )),
+            ((server, 0)[Some(TlsQueryMatcher::Handshake(Some(HandshakeType::ServerHello)))]) // plaintext ServerHello
+        )
+    };
+
+    // ((0, 1)) could be a CCS the server sends one
+
+    let encrypted_extensions = term! {
+        fn_decrypt_handshake(
+            ((server, 0)[Some(TlsQueryMatcher::ApplicationData)]), // Encrypted Extensions
+            (@server_hello_transcript),
+            (fn_get_server_key_share(((server, 0)[Some(TlsQueryMatcher::Handshake(Some(HandshakeType::ServerHello)))]))),
+            fn_no_psk,
+            fn_named_group_secp384r1,
+            fn_true,
+            fn_seq_0  // sequence 0
+        )
+    };
+
+    let encrypted_extension_transcript = term! {
+        fn_append_transcript(
+            (@server_hello_transcript),
+            (@encrypted_extensions) // plaintext Encrypted Extensions
+        )
+    };
+
+    let server_certificate = term! {
+        fn_decrypt_handshake(
+            ((server, 1)[Some(TlsQueryMatcher::ApplicationData)]),// Server Certificate
+            (@server_hello_transcript),
+            (fn_get_server_key_share(((server, 0)))),
+            fn_no_psk,
+            fn_named_group_secp384r1,
+            fn_true,
+            fn_seq_1 // sequence 1
+        )
+    };
+
+    let server_certificate_transcript = term! {
+        fn_append_transcript(
+            (@encrypted_extension_transcript),
+            (@server_certificate) // plaintext Server Certificate
+        )
+    };
+
+    let server_certificate_verify = term! {
+        fn_decrypt_handshake(
+            ((server, 2)[Some(TlsQueryMatcher::ApplicationData)]), // Server Certificate Verify
+            (@server_hello_transcript),
+            (fn_get_server_key_share(((server, 0)))),
+            fn_no_psk,
+            fn_named_group_secp384r1,
+            fn_true,
+            fn_seq_2 // sequence 2
+        )
+    };
+
+    let server_certificate_verify_transcript = term! {
+        fn_append_transcript(
+            (@server_certificate_transcript),
+            (@server_certificate_verify) // plaintext Server Certificate Verify
+        )
+    };
+
+    let server_finished = term! {
+        fn_decrypt_handshake(
+            ((server, 3)[Some(TlsQueryMatcher::ApplicationData)]), // Server Handshake Finished
+            (@server_hello_transcript),
+            (fn_get_server_key_share(((server, 0)))),
+            fn_no_psk,
+            fn_named_group_secp384r1,
+            fn_true,
+            fn_seq_3 // sequence 3
+        )
+    };
+
+    let server_finished_transcript = term! {
+        fn_append_transcript(
+            (@server_certificate_verify_transcript),
+            (@server_finished) // plaintext Server Handshake Finished
+        )
+    };
+
+    let client_finished = term! {
+        fn_finished(
+            (fn_verify_data(
+                (@server_finished_transcript),
+                (@server_hello_transcript),
+                (fn_get_server_key_share(((server, 0)))),
+                fn_no_psk,
+                fn_named_group_secp384r1
+            ))
+        )
+    };
+
+    let client_finished_transcript = term! {
+        fn_append_transcript(
+            (@server_finished_transcript),
+            (@client_finished)
+        )
+    };
+
+    let trace = Trace {
+        prior_traces: vec![],
+        descriptors: vec![AgentDescriptor::new_server(server, TLSVersion::V1_3)],
+        steps: vec![
+            Step {
+                agent: server,
+                action: Action::Input(InputAction {
+                    recipe: term! {
+                        @client_hello
+                    },
+                }),
+            },
+            OutputAction::new_step(server),
+            Step {
+                agent: server,
+                action: Action::Input(InputAction {
+                    recipe: term! {
+                        fn_encrypt_handshake(
+                            (@client_finished),
+                            (@server_hello_transcript),
+                            (fn_get_server_key_share(((server, 0)))),
+                            fn_no_psk,
+                            fn_named_group_secp384r1,
+                            fn_true,
+                            fn_seq_0  // sequence 0
+                        )
+                    },
+                }),
+            },
+            OutputAction::new_step(server),
+            /*Step {
+                agent: server,
+                action: Action::Input(InputAction {
+                    recipe: term! {
+                         fn_encrypt_application(
+                            fn_alert_close_notify,
+                            (@server_hello_transcript),
+                            (@server_finished_transcript),
+                            (fn_get_server_key_share(((server, 0)))),
+                            fn_no_psk,
+                            fn_named_group_secp384r1,
+                            fn_seq_0  // sequence 0
+                        )
+                    },
+                }),
+            },
+            OutputAction::new_step(server),*/
+        ],
+    };
+
+    (
+        trace,
+        server_hello_transcript,
+        server_finished_transcript,
+        client_finished_transcript,
+    )
+}
+
+/// Extends [`seed_client_attacker_full`] with `messages` which are sent after the handshake. Each
+/// message is encrypted with the client application traffic secret of the given generation and
+/// the given sequence number.
+fn seed_client_attacker_application(
+    server: AgentName,
+    messages: Vec<(
+        Term<TlsQueryMatcher>,
+        Term<TlsQueryMatcher>,
+        Term<TlsQueryMatcher>,
+    )>,
+) -> Trace<TlsQueryMatcher> {
+    let (mut trace, server_hello_transcript, server_finished_transcript, _) =
+        _seed_client_attacker_full(server);
+
+    for (message, generation, sequence) in messages {
+        trace.steps.push(Step {
+            agent: server,
+            action: Action::Input(InputAction {
+                recipe: term! {
+                    fn_encrypt_application_updated(
+                        (@message),
+                        (@server_hello_transcript),
+                        (@server_finished_transcript),
+                        (fn_get_server_key_share(((server, 0)))),
+                        fn_no_psk,
+                        fn_named_group_secp384r1,
+                        fn_true,
+                        (@generation),
+                        (@sequence)
+                    )
+                },
+            }),
+        });
+        trace.steps.push(OutputAction::new_step(server));
+    }
+
     trace
 }
 
-pub fn seed_session_resumption_ke(
+/// The client requests a KeyUpdate and continues with the next generation of its traffic secret
+pub fn seed_client_attacker_key_update(server: AgentName) -> Trace<TlsQueryMatcher> {
+    seed_client_attacker_application(
+        server,
+        vec![
+            (
+                term! { fn_key_update },
+                term! { fn_seq_0 },
+                term! { fn_seq_0 },
+            ),
+            (
+                term! { fn_application_data(fn_empty_bytes_vec) },
+                term! { fn_seq_1 },
+                term! { fn_seq_0 },
+            ),
+        ],
+    )
+}
+
+/// The client updates its traffic secret several times in a row without sending data in between
+pub fn seed_client_attacker_key_update_repeated(server: AgentName) -> Trace<TlsQueryMatcher> {
+    seed_client_attacker_application(
+        server,
+        vec![
+            (
+                term! { fn_key_update_not_requested },
+                term! { fn_seq_0 },
+                term! { fn_seq_0 },
+            ),
+            (
+                term! { fn_key_update_not_requested },
+                term! { fn_seq_1 },
+                term! { fn_seq_0 },
+            ),
+            (
+                term! { fn_key_update },
+                term! { fn_seq_2 },
+                term! { fn_seq_0 },
+            ),
+            (
+                term! { fn_application_data(fn_empty_bytes_vec) },
+                term! { fn_seq_3 },
+                term! { fn_seq_0 },
+            ),
+        ],
+    )
+}
+
+/// KeyUpdates are interleaved with application data, while the server sends its post-handshake
+/// messages like NewSessionTickets and answers the requested KeyUpdates.
+pub fn seed_client_attacker_key_update_interleaved(server: AgentName) -> Trace<TlsQueryMatcher> {
+    seed_client_attacker_application(
+        server,
+        vec![
+            (
+                term! { fn_application_data(fn_empty_bytes_vec) },
+                term! { fn_seq_0 },
+                term! { fn_seq_0 },
+            ),
+            (
+                term! { fn_key_update },
+                term! { fn_seq_0 },
+                term! { fn_seq_1 },
+            ),
+            (
+                term! { fn_application_data(fn_empty_bytes_vec) },
+                term! { fn_seq_1 },
+                term! { fn_seq_0 },
+            ),
+            (
+                term! { fn_key_update_not_requested },
+                term! { fn_seq_1 },
+                term! { fn_seq_1 },
+            ),
+            (
+                term! { fn_key_update },
+                term! { fn_seq_2 },
+                term! { fn_seq_0 },
+            ),
+            (
+                term! { fn_application_data(fn_empty_bytes_vec) },
+                term! { fn_seq_3 },
+                term! { fn_seq_0 },
+            ),
+        ],
+    )
+}
+
+/// The client sends an unsolicited KeyUpdate before its Finished, encrypted with the handshake
+/// traffic secret. The server has to reject it.
+pub fn seed_client_attacker_key_update_during_handshake(
+    server: AgentName,
+) -> Trace<TlsQueryMatcher> {
+    let (mut trace, server_hello_transcript, server_finished_transcript, _) =
+        _seed_client_attacker_full(server);
+
+    let client_finished = term! {
+        fn_finished(
+            (fn_verify_data(
+                (@server_finished_transcript),
+                (@server_hello_transcript),
+                (fn_get_server_key_share(((server, 0)))),
+                fn_no_psk,
+                fn_named_group_secp384r1
+            ))
+        )
+    };
+
+    // Only the ClientHello and the response of the server are kept. The KeyUpdate is sent before
+    // the Finished, which therefore has the next sequence number.
+    trace.steps.truncate(2);
+    trace.steps.extend([
+        Step {
+            agent: server,
+            action: Action::Input(InputAction {
+                recipe: term! {
+                    fn_encrypt_handshake(
+                        fn_key_update,
+                        (@server_hello_transcript),
+                        (fn_get_server_key_share(((server, 0)))),
+                        fn_no_psk,
+                        fn_named_group_secp384r1,
+                        fn_true,
+                        fn_seq_0  // sequence 0
+                    )
+                },
+            }),
+        },
+        Step {
+            agent: server,
+            action: Action::Input(InputAction {
+                recipe: term! {
+                    fn_encrypt_handshake(
+                        (@client_finished),
+                        (@server_hello_transcript),
+                        (fn_get_server_key_share(((server, 0)))),
+                        fn_no_psk,
+                        fn_named_group_secp384r1,
+                        fn_true,
+                        fn_seq_1  // sequence 1
+                    )
+                },
+            }),
+        },
+        OutputAction::new_step(server),
+    ]);
+
+    trace
+}
+
+/// Seed which contains the whole transcript in the tree. This is rather huge 10k symbols. It grows
+/// exponentially.
+pub fn seed_session_resumption_dhe_full(
     initial_server: AgentName,
     server: AgentName,
 ) -> Trace<TlsQueryMatcher> {
-    let initial_handshake = seed_client_attacker(initial_server);
+    let (
+        initial_handshake,
+        server_hello_transcript,
+        server_finished_transcript,
+        client_finished_transcript,
+    ) = _seed_client_attacker_full(initial_server);
 
     let new_ticket_message = term! {
         fn_decrypt_application(
-            ((initial_server, 4)[Some(TlsQueryMatcher::ApplicationData)]), // Ticket from last session
-            (fn_server_hello_transcript(((initial_server, 0)))),
-            (fn_server_finished_transcript(((initial_server, 0)))),
+            ((initial_server, 4)[Some(TlsQueryMatcher::ApplicationData)]), // Ticket?
+            (@server_hello_transcript),
+            (@server_finished_transcript),
             (fn_get_server_key_share(((initial_server, 0)))),
             fn_no_psk,
             fn_named_group_secp384r1,
@@ -1328,7 +3219,7 @@ pub fn seed_session_resumption_ke(
                         )),
                         (fn_key_share_deterministic_extension(fn_named_group_secp384r1))
                     )),
-                    fn_psk_exchange_mode_ke_extension
+                    fn_psk_exchange_mode_dhe_ke_extension
                 )),
                 // https://datatracker.ietf.org/doc/html/rfc8446#section-2.2
                 // must be last in client_hello, and initially empty until filled by fn_fill_binder
@@ -1341,9 +3232,9 @@ pub fn seed_session_resumption_ke(
 
     let psk = term! {
         fn_derive_psk(
-            (fn_server_hello_transcript(((initial_server, 0)))),
-            (fn_server_finished_transcript(((initial_server, 0)))),
-            (fn_client_finished_transcript(((initial_server, 0)))),
+            (@server_hello_transcript),
+            (@server_finished_transcript),
+            (@client_finished_transcript),
             (fn_get_server_key_share(((initial_server, 0)[Some(TlsQueryMatcher::Handshake(Some(HandshakeType::ServerHello)))]))),
             (fn_get_ticket_nonce((@new_ticket_message))),
             fn_named_group_secp384r1
@@ -1364,220 +3255,87 @@ pub fn seed_session_resumption_ke(
         )
     };
 
-    let resumption_client_finished = term! {
-        fn_finished(
-            (fn_verify_data(
-                (fn_server_finished_transcript(((server, 0)))),
-                (fn_server_hello_transcript(((server, 0)))),
-                fn_no_key_share,
-                (fn_psk((@psk))),
-                fn_named_group_secp384r1
-            ))
-        )
-    };
-
-    let trace = Trace {
-        prior_traces: vec![initial_handshake],
-        descriptors: vec![AgentDescriptor::new_server(server, TLSVersion::V1_3)],
-        steps: vec![
-            Step {
-                agent: server,
-                action: Action::Input(InputAction {
-                    recipe: term! {
-                        @full_client_hello
-                    },
-                }),
-            },
-            Step {
-                agent: server,
-                action: Action::Input(InputAction {
-                    recipe: term! {
-                        fn_encrypt_handshake(
-                            (@resumption_client_finished),
-                            (fn_server_hello_transcript(((server, 0)))),
-                            fn_no_key_share,
-                            (fn_psk((@psk))),
-                            fn_named_group_secp384r1,
-                            fn_true,
-                            fn_seq_0  // sequence 0
-                        )
-                    },
-                }),
-            },
-        ],
-    };
-
-    trace
-}
-
-pub fn seed_client_attacker_full(server: AgentName) -> Trace<TlsQueryMatcher> {
-    _seed_client_attacker_full(server).0
-}
-
-/// Seed which contains the whole transcript in the tree. This is rather huge >300 symbols
-pub fn _seed_client_attacker_full(
-    server: AgentName,
-) -> (
-    Trace<TlsQueryMatcher>,
-    Term<TlsQueryMatcher>,
-    Term<TlsQueryMatcher>,
-    Term<TlsQueryMatcher>,
-) {
-    let client_hello = term! {
-          fn_client_hello(
-            fn_protocol_version12,
-            fn_new_random,
-            fn_new_session_id,
-            (fn_append_cipher_suite(
-                (fn_new_cipher_suites()),
-                fn_cipher_suite13_aes_128_gcm_sha256
-            )),
-            fn_compressions,
-            (fn_client_extensions_append(
-                (fn_client_extensions_append(
-                    (fn_client_extensions_append(
-                        (fn_client_extensions_append(
-                            fn_client_extensions_new,
-                            (fn_support_group_extension(fn_named_group_secp384r1))
-                        )),
-                        fn_signature_algorithm_extension
-                    )),
-                    (fn_key_share_deterministic_extension(fn_named_group_secp384r1))
-                )),
-                fn_supported_versions13_extension
-            ))
-        )
-    };
-
-    let server_hello_transcript = term! {
+    let resumption_server_hello_transcript = term! {
         fn_append_transcript(
             (fn_append_transcript(
                 fn_new_transcript,
-                (@client_hello) // ClientHello
+                (@full_client_hello) // ClientHello
             )),
             ((server, 0)[Some(TlsQueryMatcher::Handshake(Some(HandshakeType::ServerHello)))]) // plaintext ServerHello
         )
     };
 
-    // ((0, 1)) could be a CCS the server sends one
-
-    let encrypted_extensions = term! {
+    let resumption_encrypted_extensions = term! {
         fn_decrypt_handshake(
             ((server, 0)[Some(TlsQueryMatcher::ApplicationData)]), // Encrypted Extensions
-            (@server_hello_transcript),
-            (fn_get_server_key_share(((server, 0)[Some(TlsQueryMatcher::Handshake(Some(HandshakeType::ServerHello)))]))),
-            fn_no_psk,
+            (@resumption_server_hello_transcript),
+            (fn_get_server_key_share(((server, 0)[Some(TlsQueryMatcher::Handshake(Some(HandshakeType::ServerHello)))]))), //
+            (fn_psk((@psk))),
             fn_named_group_secp384r1,
             fn_true,
             fn_seq_0  // sequence 0
         )
     };
 
-    let encrypted_extension_transcript = term! {
-        fn_append_transcript(
-            (@server_hello_transcript),
-            (@encrypted_extensions) // plaintext Encrypted Extensions
-        )
-    };
-
-    let server_certificate = term! {
-        fn_decrypt_handshake(
-            ((server, 1)[Some(TlsQueryMatcher::ApplicationData)]),// Server Certificate
-            (@server_hello_transcript),
-            (fn_get_server_key_share(((server, 0)))),
-            fn_no_psk,
-            fn_named_group_secp384r1,
-            fn_true,
-            fn_seq_1 // sequence 1
-        )
-    };
-
-    let server_certificate_transcript = term! {
-        fn_append_transcript(
-            (@encrypted_extension_transcript),
-            (@server_certificate) // plaintext Server Certificate
-        )
-    };
-
-    let server_certificate_verify = term! {
-        fn_decrypt_handshake(
-            ((server, 2)[Some(TlsQueryMatcher::ApplicationData)]), // Server Certificate Verify
-            (@server_hello_transcript),
-            (fn_get_server_key_share(((server, 0)))),
-            fn_no_psk,
-            fn_named_group_secp384r1,
-            fn_true,
-            fn_seq_2 // sequence 2
-        )
-    };
-
-    let server_certificate_verify_transcript = term! {
+    let resumption_encrypted_extension_transcript = term! {
         fn_append_transcript(
-            (@server_certificate_transcript),
-            (@server_certificate_verify) // plaintext Server Certificate Verify
+            (@resumption_server_hello_transcript),
+            (@resumption_encrypted_extensions) // plaintext Encrypted Extensions
         )
     };
 
-    let server_finished = term! {
+    let resumption_server_finished = term! {
         fn_decrypt_handshake(
-            ((server, 3)[Some(TlsQueryMatcher::ApplicationData)]), // Server Handshake Finished
-            (@server_hello_transcript),
-            (fn_get_server_key_share(((server, 0)))),
-            fn_no_psk,
+            ((server, 1)[Some(TlsQueryMatcher::ApplicationData)]), // Server Handshake Finished
+            (@resumption_server_hello_transcript),
+            (fn_get_server_key_share(((server, 0)[Some(TlsQueryMatcher::Handshake(Some(HandshakeType::ServerHello)))]))), //
+            (fn_psk((@psk))),
             fn_named_group_secp384r1,
             fn_true,
-            fn_seq_3 // sequence 3
+            fn_seq_1 // sequence 1
         )
     };
 
-    let server_finished_transcript = term! {
+    let resumption_server_finished_transcript = term! {
         fn_append_transcript(
-            (@server_certificate_verify_transcript),
-            (@server_finished) // plaintext Server Handshake Finished
+            (@resumption_encrypted_extension_transcript),
+            (@resumption_server_finished) // plaintext Server Handshake Finished
         )
     };
 
-    let client_finished = term! {
+    let resumption_client_finished = term! {
         fn_finished(
             (fn_verify_data(
-                (@server_finished_transcript),
-                (@server_hello_transcript),
-                (fn_get_server_key_share(((server, 0)))),
-                fn_no_psk,
+                (@resumption_server_finished_transcript),
+                (@resumption_server_hello_transcript),
+                (fn_get_server_key_share(((server, 0)[Some(TlsQueryMatcher::Handshake(Some(HandshakeType::ServerHello)))]))),
+                (fn_psk((@psk))),
                 fn_named_group_secp384r1
             ))
         )
     };
 
-    let client_finished_transcript = term! {
-        fn_append_transcript(
-            (@server_finished_transcript),
-            (@client_finished)
-        )
-    };
-
     let trace = Trace {
-        prior_traces: vec![],
+        prior_traces: vec![initial_handshake],
         descriptors: vec![AgentDescriptor::new_server(server, TLSVersion::V1_3)],
         steps: vec![
             Step {
                 agent: server,
                 action: Action::Input(InputAction {
                     recipe: term! {
-                        @client_hello
+                        @full_client_hello
                     },
                 }),
             },
-            OutputAction::new_step(server),
             Step {
                 agent: server,
                 action: Action::Input(InputAction {
                     recipe: term! {
                         fn_encrypt_handshake(
-                            (@client_finished),
-                            (@server_hello_transcript),
-                            (fn_get_server_key_share(((server, 0)))),
-                            fn_no_psk,
+                            (@resumption_client_finished),
+                            (@resumption_server_hello_transcript),
+                            (fn_get_server_key_share(((server, 0)[Some(TlsQueryMatcher::Handshake(Some(HandshakeType::ServerHello)))]))),
+                            (fn_psk((@psk))),
                             fn_named_group_secp384r1,
                             fn_true,
                             fn_seq_0  // sequence 0
@@ -1585,53 +3343,47 @@ pub fn _seed_client_attacker_full(
                     },
                 }),
             },
-            OutputAction::new_step(server),
             /*Step {
                 agent: server,
                 action: Action::Input(InputAction {
                     recipe: term! {
                          fn_encrypt_application(
                             fn_alert_close_notify,
-                            (@server_hello_transcript),
-                            (@server_finished_transcript),
+                            (@resumption_server_hello_transcript),
+                            (@resumption_server_finished_transcript),
                             (fn_get_server_key_share(((server, 0)))),
-                            fn_no_psk,
-                            fn_named_group_secp384r1,
+                            (fn_psk((@psk))),
                             fn_seq_0  // sequence 0
                         )
                     },
                 }),
-            },
-            OutputAction::new_step(server),*/
+            },*/
         ],
     };
 
-    (
-        trace,
-        server_hello_transcript,
-        server_finished_transcript,
-        client_finished_transcript,
-    )
+    trace
 }
 
-/// Seed which contains the whole transcript in the tree. This is rather huge 10k symbols. It grows
-/// exponentially.
-pub fn seed_session_resumption_dhe_full(
+/// Resumes the session of [`seed_client_attacker`] and sends 0-RTT data followed by the
+/// EndOfEarlyData message before finishing the handshake. Returns the trace, the ClientHello and
+/// the encrypted early data.
+fn _seed_session_resumption_early_data(
     initial_server: AgentName,
     server: AgentName,
-) -> Trace<TlsQueryMatcher> {
-    let (
-        initial_handshake,
-        server_hello_transcript,
-        server_finished_transcript,
-        client_finished_transcript,
-    ) = _seed_client_attacker_full(initial_server);
+) -> (
+    Trace<TlsQueryMatcher>,
+    Term<TlsQueryMatcher>,
+    Term<TlsQueryMatcher>,
+) {
+    // The server needs to allow early data already when issuing the ticket
+    let mut initial_handshake = seed_client_attacker(initial_server);
+    initial_handshake.descriptors[0].early_data = true;
 
     let new_ticket_message = term! {
         fn_decrypt_application(
-            ((initial_server, 4)[Some(TlsQueryMatcher::ApplicationData)]), // Ticket?
-            (@server_hello_transcript),
-            (@server_finished_transcript),
+            ((initial_server, 4)[Some(TlsQueryMatcher::ApplicationData)]), // Ticket from last session
+            (fn_server_hello_transcript(((initial_server, 0)))),
+            (fn_server_finished_transcript(((initial_server, 0)))),
             (fn_get_server_key_share(((initial_server, 0)))),
             fn_no_psk,
             fn_named_group_secp384r1,
@@ -1656,16 +3408,19 @@ pub fn seed_session_resumption_dhe_full(
                         (fn_client_extensions_append(
                             (fn_client_extensions_append(
                                 (fn_client_extensions_append(
-                                    fn_client_extensions_new,
-                                    (fn_support_group_extension(fn_named_group_secp384r1))
+                                    (fn_client_extensions_append(
+                                        fn_client_extensions_new,
+                                        (fn_support_group_extension(fn_named_group_secp384r1))
+                                    )),
+                                    fn_signature_algorithm_extension
                                 )),
-                                fn_signature_algorithm_extension
+                                fn_supported_versions13_extension
                             )),
-                            fn_supported_versions13_extension
+                            (fn_key_share_deterministic_extension(fn_named_group_secp384r1))
                         )),
-                        (fn_key_share_deterministic_extension(fn_named_group_secp384r1))
+                        fn_psk_exchange_mode_dhe_ke_extension
                     )),
-                    fn_psk_exchange_mode_dhe_ke_extension
+                    fn_early_data_extension
                 )),
                 // https://datatracker.ietf.org/doc/html/rfc8446#section-2.2
                 // must be last in client_hello, and initially empty until filled by fn_fill_binder
@@ -1678,9 +3433,9 @@ pub fn seed_session_resumption_dhe_full(
 
     let psk = term! {
         fn_derive_psk(
-            (@server_hello_transcript),
-            (@server_finished_transcript),
-            (@client_finished_transcript),
+            (fn_server_hello_transcript(((initial_server, 0)))),
+            (fn_server_finished_transcript(((initial_server, 0)))),
+            (fn_client_finished_transcript(((initial_server, 0)))),
             (fn_get_server_key_share(((initial_server, 0)[Some(TlsQueryMatcher::Handshake(Some(HandshakeType::ServerHello)))]))),
             (fn_get_ticket_nonce((@new_ticket_message))),
             fn_named_group_secp384r1
@@ -1701,75 +3456,333 @@ pub fn seed_session_resumption_dhe_full(
         )
     };
 
+    let client_hello_transcript = term! {
+        fn_append_transcript(
+            fn_new_transcript,
+            (@full_client_hello) // ClientHello
+        )
+    };
+
+    let early_data = term! {
+        fn_encrypt_early_data(
+            (fn_application_data(fn_large_bytes_vec)),
+            (@client_hello_transcript),
+            (@psk),
+            fn_seq_0  // sequence 0
+        )
+    };
+
     let resumption_server_hello_transcript = term! {
+        fn_append_transcript(
+            (@client_hello_transcript),
+            ((server, 0)[Some(TlsQueryMatcher::Handshake(Some(HandshakeType::ServerHello)))]) // plaintext ServerHello
+        )
+    };
+
+    let resumption_encrypted_extensions = term! {
+        fn_decrypt_handshake(
+            ((server, 0)[Some(TlsQueryMatcher::ApplicationData)]), // Encrypted Extensions
+            (@resumption_server_hello_transcript),
+            (fn_get_server_key_share(((server, 0)[Some(TlsQueryMatcher::Handshake(Some(HandshakeType::ServerHello)))]))),
+            (fn_psk((@psk))),
+            fn_named_group_secp384r1,
+            fn_true,
+            fn_seq_0  // sequence 0
+        )
+    };
+
+    let resumption_server_finished = term! {
+        fn_decrypt_handshake(
+            ((server, 1)[Some(TlsQueryMatcher::ApplicationData)]), // Server Handshake Finished
+            (@resumption_server_hello_transcript),
+            (fn_get_server_key_share(((server, 0)[Some(TlsQueryMatcher::Handshake(Some(HandshakeType::ServerHello)))]))),
+            (fn_psk((@psk))),
+            fn_named_group_secp384r1,
+            fn_true,
+            fn_seq_1 // sequence 1
+        )
+    };
+
+    // The client Finished covers the EndOfEarlyData message
+    let resumption_end_of_early_data_transcript = term! {
         fn_append_transcript(
             (fn_append_transcript(
-                fn_new_transcript,
-                (@full_client_hello) // ClientHello
+                (fn_append_transcript(
+                    (@resumption_server_hello_transcript),
+                    (@resumption_encrypted_extensions) // plaintext Encrypted Extensions
+                )),
+                (@resumption_server_finished) // plaintext Server Handshake Finished
             )),
-            ((server, 0)[Some(TlsQueryMatcher::Handshake(Some(HandshakeType::ServerHello)))]) // plaintext ServerHello
+            fn_end_of_early_data
+        )
+    };
+
+    let resumption_client_finished = term! {
+        fn_finished(
+            (fn_verify_data(
+                (@resumption_end_of_early_data_transcript),
+                (@resumption_server_hello_transcript),
+                (fn_get_server_key_share(((server, 0)[Some(TlsQueryMatcher::Handshake(Some(HandshakeType::ServerHello)))]))),
+                (fn_psk((@psk))),
+                fn_named_group_secp384r1
+            ))
+        )
+    };
+
+    let trace = Trace {
+        prior_traces: vec![initial_handshake],
+        descriptors: vec![AgentDescriptor {
+            early_data: true,
+            ..AgentDescriptor::new_server(server, TLSVersion::V1_3)
+        }],
+        steps: vec![
+            Step {
+                agent: server,
+                action: Action::Input(InputAction {
+                    recipe: term! {
+                        @full_client_hello
+                    },
+                }),
+            },
+            Step {
+                agent: server,
+                action: Action::Input(InputAction {
+                    recipe: term! {
+                        @early_data
+                    },
+                }),
+            },
+            Step {
+                agent: server,
+                action: Action::Input(InputAction {
+                    recipe: term! {
+                        fn_encrypt_early_data(
+                            fn_end_of_early_data,
+                            (@client_hello_transcript),
+                            (@psk),
+                            fn_seq_1  // sequence 1
+                        )
+                    },
+                }),
+            },
+            Step {
+                agent: server,
+                action: Action::Input(InputAction {
+                    recipe: term! {
+                        fn_encrypt_handshake(
+                            (@resumption_client_finished),
+                            (@resumption_server_hello_transcript),
+                            (fn_get_server_key_share(((server, 0)[Some(TlsQueryMatcher::Handshake(Some(HandshakeType::ServerHello)))]))),
+                            (fn_psk((@psk))),
+                            fn_named_group_secp384r1,
+                            fn_true,
+                            fn_seq_0  // sequence 0
+                        )
+                    },
+                }),
+            },
+        ],
+    };
+
+    (trace, full_client_hello, early_data)
+}
+
+/// Resumes a session and sends 0-RTT data which is accepted by the server
+pub fn seed_session_resumption_early_data(
+    initial_server: AgentName,
+    server: AgentName,
+) -> Trace<TlsQueryMatcher> {
+    _seed_session_resumption_early_data(initial_server, server).0
+}
+
+/// Replays the ClientHello and the early data of [`seed_session_resumption_early_data`] to the
+/// same server. The server must not accept the early data a second time.
+pub fn seed_session_resumption_early_data_replay(
+    initial_server: AgentName,
+    server: AgentName,
+) -> Trace<TlsQueryMatcher> {
+    let (resumption, full_client_hello, early_data) =
+        _seed_session_resumption_early_data(initial_server, server);
+
+    Trace {
+        prior_traces: vec![resumption],
+        descriptors: vec![AgentDescriptor {
+            early_data: true,
+            ..AgentDescriptor::new_server(server, TLSVersion::V1_3)
+        }],
+        steps: vec![
+            Step {
+                agent: server,
+                action: Action::Input(InputAction {
+                    recipe: term! {
+                        @full_client_hello
+                    },
+                }),
+            },
+            Step {
+                agent: server,
+                action: Action::Input(InputAction {
+                    recipe: term! {
+                        @early_data
+                    },
+                }),
+            },
+        ],
+    }
+}
+
+/// Like [`seed_client_attacker`], but the server offers ECH and the ClientHello contains a GREASE
+/// ECH extension. The server rejects ECH and sends its `retry_configs` in the
+/// EncryptedExtensions.
+pub fn seed_client_attacker_ech_grease(server: AgentName) -> Trace<TlsQueryMatcher> {
+    let mut trace = seed_client_attacker(server);
+    trace.descriptors[0].ech = true;
+
+    let client_hello = term! {
+          fn_client_hello(
+            fn_protocol_version12,
+            fn_new_random,
+            fn_new_session_id,
+            (fn_append_cipher_suite(
+                (fn_new_cipher_suites()),
+                fn_cipher_suite13_aes_128_gcm_sha256
+            )),
+            fn_compressions,
+            (fn_client_extensions_append(
+                (fn_client_extensions_append(
+                    (fn_client_extensions_append(
+                        (fn_client_extensions_append(
+                            (fn_client_extensions_append(
+                                fn_client_extensions_new,
+                                (fn_support_group_extension(fn_named_group_secp384r1))
+                            )),
+                            fn_signature_algorithm_extension
+                        )),
+                        (fn_key_share_deterministic_extension(fn_named_group_secp384r1))
+                    )),
+                    fn_supported_versions13_extension
+                )),
+                fn_ech_grease_extension
+            ))
         )
     };
 
-    let resumption_encrypted_extensions = term! {
+    if let Action::Input(input) = &mut trace.steps[0].action {
+        input.recipe = client_hello;
+    }
+
+    trace
+}
+
+/// Learns the ECH config from the `retry_configs` of [`seed_client_attacker_ech_grease`] and
+/// performs a handshake with the encrypted ClientHelloInner
+pub fn seed_client_attacker_ech(
+    initial_server: AgentName,
+    server: AgentName,
+) -> Trace<TlsQueryMatcher> {
+    let initial_handshake = seed_client_attacker_ech_grease(initial_server);
+
+    let encrypted_extensions = term! {
         fn_decrypt_handshake(
-            ((server, 0)[Some(TlsQueryMatcher::ApplicationData)]), // Encrypted Extensions
-            (@resumption_server_hello_transcript),
-            (fn_get_server_key_share(((server, 0)[Some(TlsQueryMatcher::Handshake(Some(HandshakeType::ServerHello)))]))), //
-            (fn_psk((@psk))),
+            ((initial_server, 0)[Some(TlsQueryMatcher::ApplicationData)]), // Encrypted Extensions
+            (fn_server_hello_transcript(((initial_server, 0)))),
+            (fn_get_server_key_share(((initial_server, 0)))),
+            fn_no_psk,
             fn_named_group_secp384r1,
             fn_true,
-            fn_seq_0  // sequence 0
+            fn_seq_0
         )
     };
 
-    let resumption_encrypted_extension_transcript = term! {
-        fn_append_transcript(
-            (@resumption_server_hello_transcript),
-            (@resumption_encrypted_extensions) // plaintext Encrypted Extensions
-        )
+    let ech_config = term! {
+        fn_ech_config((fn_ech_retry_configs((@encrypted_extensions))))
     };
 
-    let resumption_server_finished = term! {
-        fn_decrypt_handshake(
-            ((server, 1)[Some(TlsQueryMatcher::ApplicationData)]), // Server Handshake Finished
-            (@resumption_server_hello_transcript),
-            (fn_get_server_key_share(((server, 0)[Some(TlsQueryMatcher::Handshake(Some(HandshakeType::ServerHello)))]))), //
-            (fn_psk((@psk))),
-            fn_named_group_secp384r1,
-            fn_true,
-            fn_seq_1 // sequence 1
+    // The server copies the session id of the ClientHelloOuter
+    let client_hello_inner = term! {
+          fn_client_hello(
+            fn_protocol_version12,
+            fn_new_random,
+            fn_empty_session_id,
+            (fn_append_cipher_suite(
+                (fn_new_cipher_suites()),
+                fn_cipher_suite13_aes_128_gcm_sha256
+            )),
+            fn_compressions,
+            (fn_client_extensions_append(
+                (fn_client_extensions_append(
+                    (fn_client_extensions_append(
+                        (fn_client_extensions_append(
+                            (fn_client_extensions_append(
+                                fn_client_extensions_new,
+                                (fn_support_group_extension(fn_named_group_secp384r1))
+                            )),
+                            fn_signature_algorithm_extension
+                        )),
+                        (fn_key_share_deterministic_extension(fn_named_group_secp384r1))
+                    )),
+                    fn_supported_versions13_extension
+                )),
+                fn_ech_inner_extension
+            ))
         )
     };
 
-    let resumption_server_finished_transcript = term! {
-        fn_append_transcript(
-            (@resumption_encrypted_extension_transcript),
-            (@resumption_server_finished) // plaintext Server Handshake Finished
+    let client_hello_outer = term! {
+          fn_client_hello(
+            fn_protocol_version12,
+            fn_new_random,
+            fn_new_session_id,
+            (fn_append_cipher_suite(
+                (fn_new_cipher_suites()),
+                fn_cipher_suite13_aes_128_gcm_sha256
+            )),
+            fn_compressions,
+            (fn_client_extensions_append(
+                (fn_client_extensions_append(
+                    (fn_client_extensions_append(
+                        (fn_client_extensions_append(
+                            fn_client_extensions_new,
+                            (fn_support_group_extension(fn_named_group_secp384r1))
+                        )),
+                        fn_signature_algorithm_extension
+                    )),
+                    (fn_key_share_deterministic_extension(fn_named_group_secp384r1))
+                )),
+                fn_supported_versions13_extension
+            ))
         )
     };
 
-    let resumption_client_finished = term! {
+    // The transcripts of the server are computed over the ClientHelloInner
+    let client_finished = term! {
         fn_finished(
             (fn_verify_data(
-                (@resumption_server_finished_transcript),
-                (@resumption_server_hello_transcript),
-                (fn_get_server_key_share(((server, 0)[Some(TlsQueryMatcher::Handshake(Some(HandshakeType::ServerHello)))]))),
-                (fn_psk((@psk))),
+                (fn_server_finished_transcript(((server, 0)))),
+                (fn_server_hello_transcript(((server, 0)))),
+                (fn_get_server_key_share(((server, 0)))),
+                fn_no_psk,
                 fn_named_group_secp384r1
             ))
         )
     };
 
-    let trace = Trace {
+    Trace {
         prior_traces: vec![initial_handshake],
-        descriptors: vec![AgentDescriptor::new_server(server, TLSVersion::V1_3)],
+        descriptors: vec![AgentDescriptor {
+            ech: true,
+            ..AgentDescriptor::new_server(server, TLSVersion::V1_3)
+        }],
         steps: vec![
             Step {
                 agent: server,
                 action: Action::Input(InputAction {
                     recipe: term! {
-                        @full_client_hello
+                        fn_ech_client_hello_outer(
+                            (@client_hello_outer),
+                            (@client_hello_inner),
+                            (@ech_config)
+                        )
                     },
                 }),
             },
@@ -1778,10 +3791,10 @@ pub fn seed_session_resumption_dhe_full(
                 action: Action::Input(InputAction {
                     recipe: term! {
                         fn_encrypt_handshake(
-                            (@resumption_client_finished),
-                            (@resumption_server_hello_transcript),
-                            (fn_get_server_key_share(((server, 0)[Some(TlsQueryMatcher::Handshake(Some(HandshakeType::ServerHello)))]))),
-                            (fn_psk((@psk))),
+                            (@client_finished),
+                            (fn_server_hello_transcript(((server, 0)))),
+                            (fn_get_server_key_share(((server, 0)))),
+                            fn_no_psk,
                             fn_named_group_secp384r1,
                             fn_true,
                             fn_seq_0  // sequence 0
@@ -1789,25 +3802,9 @@ pub fn seed_session_resumption_dhe_full(
                     },
                 }),
             },
-            /*Step {
-                agent: server,
-                action: Action::Input(InputAction {
-                    recipe: term! {
-                         fn_encrypt_application(
-                            fn_alert_close_notify,
-                            (@resumption_server_hello_transcript),
-                            (@resumption_server_finished_transcript),
-                            (fn_get_server_key_share(((server, 0)))),
-                            (fn_psk((@psk))),
-                            fn_seq_0  // sequence 0
-                        )
-                    },
-                }),
-            },*/
+            OutputAction::new_step(server),
         ],
-    };
-
-    trace
+    }
 }
 
 macro_rules! corpus {
@@ -1833,13 +3830,54 @@ pub fn create_corpus() -> Vec<(Trace<TlsQueryMatcher>, &'static str)> {
         seed_successful_with_tickets: cfg(feature = "tls13"),
         seed_successful12: cfg(not(feature = "tls12-session-resumption")),
         seed_successful12_with_tickets: cfg(feature = "tls12-session-resumption"),
+        seed_successful11: cfg(feature = "tls11"),
+        seed_successful10: cfg(feature = "tls10"),
+        seed_successful_ssl3: cfg(feature = "ssl3"),
         // Client Attackers
         seed_client_attacker: cfg(feature = "tls13"),
         seed_client_attacker_auth: cfg(all(feature = "tls13", feature = "client-authentication-transcript-extraction")),
         seed_client_attacker12: cfg(feature = "tls13"),
+        seed_client_attacker12_chacha20: cfg(feature = "tls12"),
+        // CBC record protection
+        seed_client_attacker12_cbc: cfg(feature = "tls12"),
+        seed_client_attacker12_cbc_long_padding: cfg(feature = "tls12"),
+        seed_client_attacker12_cbc_invalid_mac: cfg(feature = "tls12"),
+        // RSA and finite field Diffie-Hellman key exchange
+        seed_client_attacker12_rsa: cfg(feature = "tls12"),
+        seed_client_attacker12_rsa_malformed_padding: cfg(feature = "tls12"),
+        seed_client_attacker12_dhe: cfg(all(feature = "tls12", feature = "openssl-binding")),
+        seed_server_attacker12_rsa: cfg(feature = "tls12"),
+        seed_server_attacker11_rsa: cfg(feature = "tls11"),
+        seed_server_attacker12_dhe: cfg(feature = "tls12"),
+        seed_server_attacker12_dhe_weak_prime: cfg(feature = "tls12"),
+        // Structural X.509 certificates
+        seed_server_attacker12_rsa_x509: cfg(feature = "tls12"),
+        seed_server_attacker12_rsa_x509_broken_signature: cfg(feature = "tls12"),
+        // Extended master secret
+        seed_client_attacker12_ems: cfg(feature = "tls12"),
+        // Renegotiation
+        seed_client_attacker12_renegotiation: cfg(feature = "tls12-renegotiation"),
+        seed_client_attacker12_renegotiation_insecure: cfg(feature = "tls12-renegotiation"),
+        seed_server_attacker12_renegotiation: cfg(feature = "tls12-renegotiation"),
+        seed_server_attacker12_renegotiation_insecure: cfg(feature = "tls12-renegotiation"),
+        // Record layer fragmentation and coalescing
+        seed_client_attacker12_fragmented: cfg(feature = "tls12"),
+        seed_server_attacker12_rsa_coalesced: cfg(feature = "tls12"),
+        seed_client_attacker_fragmented: cfg(feature = "tls13"),
+        // Key updates
+        seed_client_attacker_key_update: cfg(feature = "tls13"),
+        seed_client_attacker_key_update_repeated: cfg(feature = "tls13"),
+        seed_client_attacker_key_update_interleaved: cfg(feature = "tls13"),
+        seed_client_attacker_key_update_during_handshake: cfg(feature = "tls13"),
         // Session resumption
         seed_session_resumption_dhe: cfg(all(feature = "tls13", feature = "tls13-session-resumption")),
         seed_session_resumption_ke: cfg(all(feature = "tls13", feature = "tls13-session-resumption")),
+        // Early data
+        seed_session_resumption_early_data: cfg(all(feature = "tls13", feature = "tls13-early-data")),
+        seed_session_resumption_early_data_replay: cfg(all(feature = "tls13", feature = "tls13-early-data")),
+        // Encrypted Client Hello
+        seed_client_attacker_ech_grease: cfg(all(feature = "tls13", feature = "tls13-ech")),
+        seed_client_attacker_ech: cfg(all(feature = "tls13", feature = "tls13-ech")),
         // Server Attackers
         seed_server_attacker_full: cfg(feature = "tls13")
     )
@@ -1849,13 +3887,22 @@ pub fn create_corpus() -> Vec<(Trace<TlsQueryMatcher>, &'static str)> {
 pub mod tests {
     use std::io::Write;
 
-    use puffin::{agent::AgentName, trace::Action};
+    use puffin::{
+        agent::AgentName,
+        claims::SecurityViolationPolicy,
+        error::Error,
+        trace::{Action, TraceContext},
+    };
     use test_log::test;
 
     use super::*;
     use crate::{
+        claims::{ClaimData, ClaimDataMessage},
         put_registry::TLS_PUT_REGISTRY,
-        tls::trace_helper::{TraceExecutor, TraceHelper},
+        tls::{
+            trace_helper::{TraceExecutor, TraceHelper},
+            violation::{find_replayed_early_data, TlsSecurityViolationPolicy},
+        },
     };
 
     #[test]
@@ -1863,6 +3910,27 @@ pub mod tests {
         TLS_PUT_REGISTRY.version_strings();
     }
 
+    #[test]
+    #[cfg(feature = "tls11")]
+    fn test_seed_successful11() {
+        let ctx = seed_successful11.execute_trace();
+        assert!(ctx.agents_successful());
+    }
+
+    #[test]
+    #[cfg(feature = "tls10")]
+    fn test_seed_successful10() {
+        let ctx = seed_successful10.execute_trace();
+        assert!(ctx.agents_successful());
+    }
+
+    #[test]
+    #[cfg(feature = "ssl3")]
+    fn test_seed_successful_ssl3() {
+        let ctx = seed_successful_ssl3.execute_trace();
+        assert!(ctx.agents_successful());
+    }
+
     #[test]
     #[cfg(feature = "tls12")]
     fn test_seed_client_attacker12() {
@@ -1870,6 +3938,170 @@ pub mod tests {
         assert!(ctx.agents_successful());
     }
 
+    #[test]
+    #[cfg(feature = "tls12")]
+    fn test_seed_client_attacker12_chacha20() {
+        let ctx = seed_client_attacker12_chacha20.execute_trace();
+        assert!(ctx.agents_successful());
+    }
+
+    #[test]
+    #[cfg(feature = "tls12")]
+    fn test_seed_client_attacker12_cbc() {
+        let ctx = seed_client_attacker12_cbc.execute_trace();
+        assert!(ctx.agents_successful());
+    }
+
+    #[test]
+    #[cfg(feature = "tls12")]
+    fn test_seed_client_attacker12_cbc_long_padding() {
+        let ctx = seed_client_attacker12_cbc_long_padding.execute_trace();
+        assert!(ctx.agents_successful());
+    }
+
+    #[test]
+    #[cfg(feature = "tls12")]
+    fn test_seed_client_attacker12_cbc_invalid_mac() {
+        let ctx = seed_client_attacker12_cbc_invalid_mac.execute_trace();
+        assert!(!ctx.agents_successful());
+    }
+
+    #[test]
+    #[cfg(feature = "tls12")]
+    fn test_seed_client_attacker12_rsa() {
+        let ctx = seed_client_attacker12_rsa.execute_trace();
+        assert!(ctx.agents_successful());
+    }
+
+    #[test]
+    #[cfg(feature = "tls12")]
+    fn test_seed_client_attacker12_rsa_malformed_padding() {
+        let ctx = seed_client_attacker12_rsa_malformed_padding.execute_trace();
+        assert!(!ctx.agents_successful());
+    }
+
+    #[test]
+    #[cfg(all(feature = "tls12", feature = "openssl-binding"))] // the server needs DH parameters
+    fn test_seed_client_attacker12_dhe() {
+        let ctx = seed_client_attacker12_dhe.execute_trace();
+        assert!(ctx.agents_successful());
+    }
+
+    #[test]
+    #[cfg(feature = "tls12")]
+    fn test_seed_server_attacker12_rsa() {
+        let ctx = seed_server_attacker12_rsa.execute_trace();
+        assert!(ctx.agents_successful());
+    }
+
+    #[test]
+    #[cfg(feature = "tls11")]
+    fn test_seed_server_attacker11_rsa() {
+        let ctx = seed_server_attacker11_rsa.execute_trace();
+        assert!(ctx.agents_successful());
+    }
+
+    #[test]
+    #[cfg(feature = "tls12")]
+    fn test_seed_server_attacker12_rsa_x509() {
+        let ctx = seed_server_attacker12_rsa_x509.execute_trace();
+        assert!(ctx.agents_successful());
+    }
+
+    #[test]
+    #[cfg(feature = "tls12")]
+    fn test_seed_server_attacker12_rsa_x509_broken_signature() {
+        let ctx = seed_server_attacker12_rsa_x509_broken_signature.execute_trace();
+        assert!(!ctx.agents_successful());
+    }
+
+    #[test]
+    #[cfg(feature = "tls12")]
+    fn test_seed_client_attacker12_ems() {
+        let ctx = seed_client_attacker12_ems.execute_trace();
+        assert!(ctx.agents_successful());
+    }
+
+    #[test]
+    #[cfg(feature = "tls12-renegotiation")]
+    fn test_seed_client_attacker12_renegotiation() {
+        let ctx = seed_client_attacker12_renegotiation.execute_trace();
+        assert!(ctx.agents_successful());
+    }
+
+    #[test]
+    #[cfg(feature = "tls12-renegotiation")]
+    fn test_seed_client_attacker12_renegotiation_insecure() {
+        let mut ctx = TraceContext::new(&TLS_PUT_REGISTRY);
+        ctx.set_deterministic(true);
+        let result = seed_client_attacker12_renegotiation_insecure
+            .build_trace()
+            .execute(&mut ctx);
+        // The PUT refuses the renegotiation instead of reporting a violation
+        assert!(matches!(result, Err(Error::Put(_))));
+
+        let claims = ctx.claims().deref_borrow();
+        assert!(TlsSecurityViolationPolicy::check_violation(claims.slice()).is_none());
+    }
+
+    #[test]
+    #[cfg(feature = "tls12-renegotiation")]
+    fn test_seed_server_attacker12_renegotiation() {
+        let ctx = seed_server_attacker12_renegotiation.execute_trace();
+        assert!(ctx.agents_successful());
+    }
+
+    #[test]
+    #[cfg(feature = "tls12-renegotiation")]
+    fn test_seed_server_attacker12_renegotiation_insecure() {
+        let mut ctx = TraceContext::new(&TLS_PUT_REGISTRY);
+        ctx.set_deterministic(true);
+        let result = seed_server_attacker12_renegotiation_insecure
+            .build_trace()
+            .execute(&mut ctx);
+        // The PUT refuses the renegotiation instead of reporting a violation
+        assert!(matches!(result, Err(Error::Put(_))));
+
+        let claims = ctx.claims().deref_borrow();
+        assert!(TlsSecurityViolationPolicy::check_violation(claims.slice()).is_none());
+    }
+
+    #[test]
+    #[cfg(feature = "tls12")]
+    fn test_seed_client_attacker12_fragmented() {
+        let ctx = seed_client_attacker12_fragmented.execute_trace();
+        assert!(ctx.agents_successful());
+    }
+
+    #[test]
+    #[cfg(feature = "tls12")]
+    fn test_seed_server_attacker12_rsa_coalesced() {
+        let ctx = seed_server_attacker12_rsa_coalesced.execute_trace();
+        assert!(ctx.agents_successful());
+    }
+
+    #[cfg(feature = "tls13")]
+    #[cfg(feature = "transcript-extraction")]
+    #[test]
+    fn test_seed_client_attacker_fragmented() {
+        let ctx = seed_client_attacker_fragmented.execute_trace();
+        assert!(ctx.agents_successful());
+    }
+
+    #[test]
+    #[cfg(feature = "tls12")]
+    fn test_seed_server_attacker12_dhe() {
+        let ctx = seed_server_attacker12_dhe.execute_trace();
+        assert!(ctx.agents_successful());
+    }
+
+    #[test]
+    #[cfg(feature = "tls12")]
+    fn test_seed_server_attacker12_dhe_weak_prime() {
+        let ctx = seed_server_attacker12_dhe_weak_prime.execute_trace();
+        assert!(!ctx.agents_successful());
+    }
+
     #[cfg(feature = "tls13")] // require version which supports TLS 1.3
     #[cfg(feature = "transcript-extraction")] // this depends on extracted transcripts -> claims are required
     #[test]
@@ -1893,6 +4125,34 @@ pub mod tests {
         assert!(ctx.agents_successful());
     }
 
+    #[cfg(feature = "tls13")] // require version which supports TLS 1.3
+    #[test]
+    fn test_seed_client_attacker_key_update() {
+        let ctx = seed_client_attacker_key_update.execute_trace();
+        assert!(ctx.agents_successful());
+    }
+
+    #[cfg(feature = "tls13")] // require version which supports TLS 1.3
+    #[test]
+    fn test_seed_client_attacker_key_update_repeated() {
+        let ctx = seed_client_attacker_key_update_repeated.execute_trace();
+        assert!(ctx.agents_successful());
+    }
+
+    #[cfg(feature = "tls13")] // require version which supports TLS 1.3
+    #[test]
+    fn test_seed_client_attacker_key_update_interleaved() {
+        let ctx = seed_client_attacker_key_update_interleaved.execute_trace();
+        assert!(ctx.agents_successful());
+    }
+
+    #[cfg(feature = "tls13")] // require version which supports TLS 1.3
+    #[test]
+    fn test_seed_client_attacker_key_update_during_handshake() {
+        let ctx = seed_client_attacker_key_update_during_handshake.execute_trace();
+        assert!(!ctx.agents_successful());
+    }
+
     #[cfg(feature = "tls13")] // require version which supports TLS 1.3
     #[test]
     fn test_seed_server_attacker_full() {
@@ -1921,6 +4181,53 @@ pub mod tests {
         assert!(ctx.agents_successful());
     }
 
+    #[cfg(all(feature = "tls13", feature = "tls13-early-data"))]
+    #[test]
+    fn test_seed_session_resumption_early_data() {
+        let ctx = seed_session_resumption_early_data.execute_trace();
+        assert!(ctx.agents_successful());
+    }
+
+    #[cfg(all(feature = "tls13", feature = "tls13-early-data"))]
+    #[test]
+    fn test_seed_session_resumption_early_data_replay() {
+        let mut ctx = TraceContext::new(&TLS_PUT_REGISTRY);
+        ctx.set_deterministic(true);
+        let result = seed_session_resumption_early_data_replay
+            .build_trace()
+            .execute(&mut ctx);
+        assert!(!matches!(result, Err(Error::SecurityClaim(_))));
+
+        let claims = ctx.claims().deref_borrow();
+        let accepted_early_data = claims
+            .iter()
+            .filter(|claim| {
+                claim.origin == AgentType::Server
+                    && matches!(
+                        &claim.data,
+                        ClaimData::Message(ClaimDataMessage::EarlyData(data)) if !data.outbound
+                    )
+            })
+            .count();
+        // Only the resumption of the prior trace accepts the early data
+        assert_eq!(accepted_early_data, 1);
+        assert!(find_replayed_early_data(claims.slice()).is_none());
+    }
+
+    #[cfg(all(feature = "tls13", feature = "tls13-ech"))]
+    #[test]
+    fn test_seed_client_attacker_ech_grease() {
+        let ctx = seed_client_attacker_ech_grease.execute_trace();
+        assert!(ctx.agents_successful());
+    }
+
+    #[cfg(all(feature = "tls13", feature = "tls13-ech"))]
+    #[test]
+    fn test_seed_client_attacker_ech() {
+        let ctx = seed_client_attacker_ech.execute_trace();
+        assert!(ctx.agents_successful());
+    }
+
     #[cfg(feature = "tls13")] // require version which supports TLS 1.3
     #[test]
     fn test_seed_successful() {
@@ -1,3 +1,5 @@
+use std::cmp;
+
 use itertools::Itertools;
 use puffin::{
     agent::{AgentType, TLSVersion},
@@ -5,7 +7,7 @@ use puffin::{
 };
 
 use crate::{
-    claims::{ClaimData, ClaimDataMessage, Finished, TlsClaim},
+    claims::{ClaimData, ClaimDataMessage, EarlyData, Finished, TlsClaim},
     static_certs::{ALICE_CERT, BOB_CERT},
 };
 
@@ -13,6 +15,14 @@ pub struct TlsSecurityViolationPolicy;
 
 impl SecurityViolationPolicy<TlsClaim> for TlsSecurityViolationPolicy {
     fn check_violation(claims: &[TlsClaim]) -> Option<&'static str> {
+        if find_replayed_early_data(claims).is_some() {
+            return Some("Early data replayed");
+        }
+
+        if find_insecure_renegotiation(claims).is_some() {
+            return Some("Insecure renegotiation");
+        }
+
         if let Some((claim_a, claim_b)) = find_two_finished_messages(claims) {
             if let Some(((client_claim, client), (server_claim, server))) =
                 get_client_server(claim_a, claim_b)
@@ -21,6 +31,35 @@ impl SecurityViolationPolicy<TlsClaim> for TlsSecurityViolationPolicy {
                     return Some("Mismatching versions");
                 }
 
+                if let (Some(client_version), Some(server_version)) =
+                    (client.negotiated_version, server.negotiated_version)
+                {
+                    if client_version != server_version {
+                        return Some("Mismatching negotiated versions");
+                    }
+
+                    // Both agents support the versions up to the one of their descriptor,
+                    // therefore they should never agree on a version below the highest common one
+                    let supported = cmp::min_by_key(
+                        client_claim.protocol_version,
+                        server_claim.protocol_version,
+                        |version| version_rank(*version),
+                    );
+                    if is_downgrade(supported, client_version) {
+                        return Some("Protocol downgrade");
+                    }
+                }
+
+                // Agents which disagree on EMS can still share a master secret if the attacker
+                // synchronized it, like in the triple handshake attack
+                if let (Some(client_ems), Some(server_ems)) =
+                    (client.extended_master_secret, server.extended_master_secret)
+                {
+                    if client_ems != server_ems {
+                        return Some("Mismatching extended master secret");
+                    }
+                }
+
                 if client.master_secret != server.master_secret {
                     return Some("Mismatching master secrets");
                 }
@@ -51,8 +90,8 @@ impl SecurityViolationPolicy<TlsClaim> for TlsSecurityViolationPolicy {
                 }
 
                 match client_claim.protocol_version {
-                    TLSVersion::V1_2 => {
-                        // TLS 1.2 Checks
+                    TLSVersion::V1_2 | TLSVersion::V1_1 | TLSVersion::V1_0 | TLSVersion::SSL3 => {
+                        // TLS 1.2 and legacy checks
 
                         // https://datatracker.ietf.org/doc/html/rfc5077#section-3.4
                         if !server.session_id.is_empty() && client.session_id != server.session_id {
@@ -152,6 +191,56 @@ pub fn find_two_finished_messages(
     two_finishes
 }
 
+/// Finds early data which was accepted twice by servers. Replayed 0-RTT data carries the same
+/// client random and is protected with the same early secret.
+pub fn find_replayed_early_data(claims: &[TlsClaim]) -> Option<(&EarlyData, &EarlyData)> {
+    claims
+        .iter()
+        .filter_map(|claim| match &claim.data {
+            ClaimData::Message(ClaimDataMessage::EarlyData(data)) => {
+                if data.outbound || claim.origin != AgentType::Server {
+                    None
+                } else {
+                    Some(data)
+                }
+            }
+            _ => None,
+        })
+        .tuple_combinations()
+        .find(|(a, b)| a.client_random == b.client_random && a.early_secret == b.early_secret)
+}
+
+/// Finds a renegotiation which an agent completed although the `renegotiation_info` extension has
+/// not been negotiated. Such a handshake is not bound to the previous one and allows the attacker
+/// to prefix the connection of the victim (CVE-2009-3555).
+pub fn find_insecure_renegotiation(claims: &[TlsClaim]) -> Option<&Finished> {
+    claims.iter().find_map(|claim| match &claim.data {
+        ClaimData::Message(ClaimDataMessage::Finished(data)) => {
+            if !data.outbound && data.renegotiation && data.secure_renegotiation == Some(false) {
+                Some(data)
+            } else {
+                None
+            }
+        }
+        _ => None,
+    })
+}
+
+fn version_rank(version: TLSVersion) -> u8 {
+    match version {
+        TLSVersion::SSL3 => 0,
+        TLSVersion::V1_0 => 1,
+        TLSVersion::V1_1 => 2,
+        TLSVersion::V1_2 => 3,
+        TLSVersion::V1_3 => 4,
+    }
+}
+
+/// Checks whether the `negotiated` version is lower than the `supported` one.
+pub fn is_downgrade(supported: TLSVersion, negotiated: TLSVersion) -> bool {
+    version_rank(negotiated) < version_rank(supported)
+}
+
 pub fn get_client_server<'a, T>(
     a: (&'a TlsClaim, &'a T),
     b: (&'a TlsClaim, &'a T),
@@ -167,3 +256,153 @@ pub fn get_client_server<'a, T>(
         },
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use puffin::{
+        agent::{AgentName, AgentType, TLSVersion},
+        claims::SecurityViolationPolicy,
+    };
+    use smallvec::SmallVec;
+
+    use crate::{
+        claims::{ClaimData, ClaimDataMessage, EarlyData, Finished, TlsClaim},
+        tls::violation::{
+            find_insecure_renegotiation, find_replayed_early_data, is_downgrade,
+            TlsSecurityViolationPolicy,
+        },
+    };
+
+    /// Creates the claim of a finished handshake which agrees with the peer in everything except
+    /// what the caller changes afterwards.
+    fn finished(
+        agent_name: AgentName,
+        origin: AgentType,
+        protocol_version: TLSVersion,
+        negotiated_version: TLSVersion,
+    ) -> TlsClaim {
+        TlsClaim {
+            agent_name,
+            origin,
+            protocol_version,
+            data: ClaimData::Message(ClaimDataMessage::Finished(Finished {
+                outbound: false,
+                client_random: SmallVec::from_slice(&[1; 32]),
+                server_random: SmallVec::from_slice(&[2; 32]),
+                session_id: SmallVec::from_slice(&[3; 32]),
+                negotiated_version: Some(negotiated_version),
+                authenticate_peer: false,
+                peer_certificate: SmallVec::new(),
+                master_secret: SmallVec::from_slice(&[4; 32]),
+                extended_master_secret: None,
+                renegotiation: false,
+                secure_renegotiation: None,
+                chosen_cipher: 0xc02f,
+                available_ciphers: SmallVec::new(),
+                signature_algorithm: 0,
+                peer_signature_algorithm: 0,
+            })),
+        }
+    }
+
+    fn handshake(protocol_version: TLSVersion, negotiated_version: TLSVersion) -> [TlsClaim; 2] {
+        let client = AgentName::first();
+        [
+            finished(
+                client,
+                AgentType::Client,
+                protocol_version,
+                negotiated_version,
+            ),
+            finished(
+                client.next(),
+                AgentType::Server,
+                protocol_version,
+                negotiated_version,
+            ),
+        ]
+    }
+
+    fn early_data(agent_name: AgentName, client_random: u8) -> TlsClaim {
+        TlsClaim {
+            agent_name,
+            origin: AgentType::Server,
+            protocol_version: TLSVersion::V1_3,
+            data: ClaimData::Message(ClaimDataMessage::EarlyData(EarlyData {
+                outbound: false,
+                client_random: SmallVec::from_slice(&[client_random; 32]),
+                early_secret: SmallVec::from_slice(&[0x42; 32]),
+            })),
+        }
+    }
+
+    #[test]
+    fn test_replayed_early_data() {
+        let server = AgentName::first();
+        let replay_server = server.next();
+
+        let claims = [early_data(server, 1), early_data(replay_server, 1)];
+        assert!(find_replayed_early_data(&claims).is_some());
+        assert_eq!(
+            TlsSecurityViolationPolicy::check_violation(&claims),
+            Some("Early data replayed")
+        );
+
+        let claims = [early_data(server, 1), early_data(replay_server, 2)];
+        assert!(find_replayed_early_data(&claims).is_none());
+        assert_eq!(TlsSecurityViolationPolicy::check_violation(&claims), None);
+    }
+
+    #[test]
+    fn test_downgrade() {
+        assert!(is_downgrade(TLSVersion::V1_3, TLSVersion::V1_2));
+        assert!(!is_downgrade(TLSVersion::V1_2, TLSVersion::V1_2));
+        assert!(!is_downgrade(TLSVersion::V1_2, TLSVersion::V1_3));
+
+        let claims = handshake(TLSVersion::V1_2, TLSVersion::V1_2);
+        assert_eq!(TlsSecurityViolationPolicy::check_violation(&claims), None);
+
+        let claims = handshake(TLSVersion::V1_3, TLSVersion::V1_2);
+        assert_eq!(
+            TlsSecurityViolationPolicy::check_violation(&claims),
+            Some("Protocol downgrade")
+        );
+    }
+
+    #[test]
+    fn test_mismatching_extended_master_secret() {
+        let mut claims = handshake(TLSVersion::V1_2, TLSVersion::V1_2);
+        for (claim, ems) in claims.iter_mut().zip([true, false]) {
+            if let ClaimData::Message(ClaimDataMessage::Finished(finished)) = &mut claim.data {
+                finished.extended_master_secret = Some(ems);
+            }
+        }
+
+        assert_eq!(
+            TlsSecurityViolationPolicy::check_violation(&claims),
+            Some("Mismatching extended master secret")
+        );
+    }
+
+    #[test]
+    fn test_insecure_renegotiation() {
+        let mut claims = handshake(TLSVersion::V1_2, TLSVersion::V1_2);
+        for claim in &mut claims {
+            if let ClaimData::Message(ClaimDataMessage::Finished(finished)) = &mut claim.data {
+                finished.renegotiation = true;
+                finished.secure_renegotiation = Some(true);
+            }
+        }
+        assert!(find_insecure_renegotiation(&claims).is_none());
+        assert_eq!(TlsSecurityViolationPolicy::check_violation(&claims), None);
+
+        if let ClaimData::Message(ClaimDataMessage::Finished(finished)) = &mut claims[1].data {
+            finished.secure_renegotiation = Some(false);
+        }
+        assert!(find_insecure_renegotiation(&claims).is_some());
+        assert_eq!(
+            TlsSecurityViolationPolicy::check_violation(&claims),
+            Some("Insecure renegotiation")
+        );
+    }
+}
@@ -0,0 +1,434 @@
+//! Structural encoding of X.509 certificates (<https://datatracker.ietf.org/doc/html/rfc5280>).
+//!
+//! The attacker builds certificates from their fields instead of using fixed DER bytes. This
+//! allows the fuzzer to mutate names, validity periods, extensions and signatures of certificate
+//! chains individually.
+
+use puffin::algebra::error::FnError;
+
+use crate::tls::{der::*, rustls::msgs::enums::SignatureScheme};
+
+/// Encoded object identifiers, without tag and length
+pub const OID_COMMON_NAME: &[u8] = &[0x55, 0x04, 0x03];
+pub const OID_COUNTRY: &[u8] = &[0x55, 0x04, 0x06];
+pub const OID_ORGANIZATION: &[u8] = &[0x55, 0x04, 0x0a];
+pub const OID_KEY_USAGE: &[u8] = &[0x55, 0x1d, 0x0f];
+pub const OID_SUBJECT_ALT_NAME: &[u8] = &[0x55, 0x1d, 0x11];
+pub const OID_BASIC_CONSTRAINTS: &[u8] = &[0x55, 0x1d, 0x13];
+pub const OID_EXTENDED_KEY_USAGE: &[u8] = &[0x55, 0x1d, 0x25];
+pub const OID_SERVER_AUTH: &[u8] = &[0x2b, 0x06, 0x01, 0x05, 0x05, 0x07, 0x03, 0x01];
+pub const OID_CLIENT_AUTH: &[u8] = &[0x2b, 0x06, 0x01, 0x05, 0x05, 0x07, 0x03, 0x02];
+/// An OID below the private enterprise arc which no implementation knows
+pub const OID_UNKNOWN: &[u8] = &[0x2b, 0x06, 0x01, 0x04, 0x01, 0x83, 0xb2, 0x43, 0x01];
+
+const OID_SHA256_WITH_RSA: &[u8] = &[0x2a, 0x86, 0x48, 0x86, 0xf7, 0x0d, 0x01, 0x01, 0x0b];
+const OID_SHA384_WITH_RSA: &[u8] = &[0x2a, 0x86, 0x48, 0x86, 0xf7, 0x0d, 0x01, 0x01, 0x0c];
+const OID_SHA512_WITH_RSA: &[u8] = &[0x2a, 0x86, 0x48, 0x86, 0xf7, 0x0d, 0x01, 0x01, 0x0d];
+const OID_ECDSA_WITH_SHA256: &[u8] = &[0x2a, 0x86, 0x48, 0xce, 0x3d, 0x04, 0x03, 0x02];
+
+/// AlgorithmIdentifier of RSASSA-PSS with SHA-256, MGF1 with SHA-256 and a salt of 32 bytes
+const RSA_PSS_SHA256_ALGORITHM: &[u8] = &[
+    0x30, 0x41, 0x06, 0x09, 0x2a, 0x86, 0x48, 0x86, 0xf7, 0x0d, 0x01, 0x01, 0x0a, 0x30, 0x34, 0xa0,
+    0x0f, 0x30, 0x0d, 0x06, 0x09, 0x60, 0x86, 0x48, 0x01, 0x65, 0x03, 0x04, 0x02, 0x01, 0x05, 0x00,
+    0xa1, 0x1c, 0x30, 0x1a, 0x06, 0x09, 0x2a, 0x86, 0x48, 0x86, 0xf7, 0x0d, 0x01, 0x01, 0x08, 0x30,
+    0x0d, 0x06, 0x09, 0x60, 0x86, 0x48, 0x01, 0x65, 0x03, 0x04, 0x02, 0x01, 0x05, 0x00, 0xa2, 0x03,
+    0x02, 0x01, 0x20,
+];
+
+/// DER encoded `Name` of a subject or issuer
+#[derive(Clone, Debug)]
+pub struct X509Name(pub Vec<u8>);
+
+/// DER encoded `notBefore` and `notAfter` times
+#[derive(Clone, Debug)]
+pub struct X509Validity {
+    pub not_before: Vec<u8>,
+    pub not_after: Vec<u8>,
+}
+
+/// DER encoded `SubjectPublicKeyInfo`
+#[derive(Clone, Debug)]
+pub struct X509PublicKey(pub Vec<u8>);
+
+#[derive(Clone, Debug)]
+pub struct X509Extension {
+    /// Encoded object identifier, without tag and length
+    pub oid: Vec<u8>,
+    pub critical: bool,
+    /// DER encoded value which is wrapped in an OCTET STRING
+    pub value: Vec<u8>,
+}
+
+#[derive(Clone, Debug)]
+pub struct X509Extensions(pub Vec<X509Extension>);
+
+/// The fields of a certificate which are covered by its signature
+#[derive(Clone, Debug)]
+pub struct X509TbsCertificate {
+    pub serial: u64,
+    pub signature_scheme: SignatureScheme,
+    pub issuer: X509Name,
+    pub validity: X509Validity,
+    pub subject: X509Name,
+    pub public_key: X509PublicKey,
+    pub extensions: X509Extensions,
+}
+
+impl X509Name {
+    pub fn empty() -> Self {
+        X509Name(der_encode(DER_SEQUENCE, &[]))
+    }
+
+    /// Appends a relative distinguished name with a single attribute
+    pub fn append(&self, oid: &[u8], string_tag: u8, value: &[u8]) -> Result<Self, FnError> {
+        let (rdns, _) = der_expect(&self.0, DER_SEQUENCE)
+            .ok_or_else(|| FnError::Unknown("Malformed X.509 name".to_string()))?;
+
+        let attribute = der_encode(
+            DER_SEQUENCE,
+            &[der_encode(DER_OID, oid), der_encode(string_tag, value)].concat(),
+        );
+
+        let mut rdns = rdns.to_vec();
+        rdns.extend(der_encode(DER_SET, &attribute));
+        Ok(X509Name(der_encode(DER_SEQUENCE, &rdns)))
+    }
+
+    pub fn append_common_name(&self, value: &[u8]) -> Result<Self, FnError> {
+        self.append(OID_COMMON_NAME, DER_UTF8_STRING, value)
+    }
+
+    pub fn append_organization(&self, value: &[u8]) -> Result<Self, FnError> {
+        self.append(OID_ORGANIZATION, DER_UTF8_STRING, value)
+    }
+
+    pub fn append_country(&self, value: &[u8]) -> Result<Self, FnError> {
+        self.append(OID_COUNTRY, DER_PRINTABLE_STRING, value)
+    }
+}
+
+impl X509Validity {
+    /// Validity between two points in time, given in seconds since the UNIX epoch
+    pub fn new(not_before: u64, not_after: u64) -> Self {
+        X509Validity {
+            not_before: encode_time(not_before),
+            not_after: encode_time(not_after),
+        }
+    }
+}
+
+impl X509Extension {
+    pub fn new(oid: &[u8], critical: bool, value: Vec<u8>) -> Self {
+        X509Extension {
+            oid: oid.to_vec(),
+            critical,
+            value,
+        }
+    }
+
+    /// SubjectAltName with a single `dNSName` (`[2]`) or `iPAddress` (`[7]`)
+    pub fn subject_alt_name(tag: u8, name: &[u8]) -> Self {
+        Self::new(
+            OID_SUBJECT_ALT_NAME,
+            false,
+            der_encode(DER_SEQUENCE, &der_encode(tag, name)),
+        )
+    }
+
+    /// KeyUsage where bit `i` of `usage` is the named bit `i` of RFC 5280, e.g. bit 0 is
+    /// `digitalSignature` and bit 5 `keyCertSign`.
+    pub fn key_usage(usage: u64) -> Self {
+        let bits = (usage as u16).reverse_bits().to_be_bytes();
+        let used = bits
+            .iter()
+            .rposition(|byte| *byte != 0)
+            .map_or(0, |i| i + 1);
+        let unused_bits = bits[..used]
+            .last()
+            .map_or(0, |byte| byte.trailing_zeros() as u8);
+
+        let mut bit_string = vec![unused_bits];
+        bit_string.extend_from_slice(&bits[..used]);
+        Self::new(OID_KEY_USAGE, true, der_encode(DER_BIT_STRING, &bit_string))
+    }
+
+    pub fn basic_constraints(ca: bool, path_len: Option<u64>) -> Self {
+        let mut constraints = Vec::new();
+        if ca {
+            // DEFAULT FALSE must not be encoded
+            constraints.extend(der_encode(DER_BOOLEAN, &[0xff]));
+        }
+        if let Some(path_len) = path_len {
+            constraints.extend(der_encode(DER_INTEGER, &encode_unsigned(path_len)));
+        }
+        Self::new(
+            OID_BASIC_CONSTRAINTS,
+            true,
+            der_encode(DER_SEQUENCE, &constraints),
+        )
+    }
+
+    pub fn extended_key_usage(purpose: &[u8]) -> Self {
+        Self::new(
+            OID_EXTENDED_KEY_USAGE,
+            false,
+            der_encode(DER_SEQUENCE, &der_encode(DER_OID, purpose)),
+        )
+    }
+
+    fn encode(&self) -> Vec<u8> {
+        let mut extension = der_encode(DER_OID, &self.oid);
+        if self.critical {
+            extension.extend(der_encode(DER_BOOLEAN, &[0xff]));
+        }
+        extension.extend(der_encode(DER_OCTET_STRING, &self.value));
+        der_encode(DER_SEQUENCE, &extension)
+    }
+}
+
+impl X509TbsCertificate {
+    pub fn encode(&self) -> Result<Vec<u8>, FnError> {
+        let mut tbs = der_encode(DER_CONTEXT_0, &der_encode(DER_INTEGER, &[2])); // v3
+        tbs.extend(der_encode(DER_INTEGER, &encode_unsigned(self.serial)));
+        tbs.extend(signature_algorithm(&self.signature_scheme)?);
+        tbs.extend_from_slice(&self.issuer.0);
+        tbs.extend(der_encode(
+            DER_SEQUENCE,
+            &[
+                self.validity.not_before.as_slice(),
+                self.validity.not_after.as_slice(),
+            ]
+            .concat(),
+        ));
+        tbs.extend_from_slice(&self.subject.0);
+        tbs.extend_from_slice(&self.public_key.0);
+
+        if !self.extensions.0.is_empty() {
+            let extensions = self
+                .extensions
+                .0
+                .iter()
+                .flat_map(|extension| extension.encode())
+                .collect::<Vec<u8>>();
+            tbs.extend(der_encode(
+                DER_CONTEXT_3,
+                &der_encode(DER_SEQUENCE, &extensions),
+            ));
+        }
+
+        Ok(der_encode(DER_SEQUENCE, &tbs))
+    }
+
+    /// Encodes the certificate with an arbitrary `signature`
+    pub fn encode_signed(&self, signature: &[u8]) -> Result<Vec<u8>, FnError> {
+        let mut certificate = self.encode()?;
+        certificate.extend(signature_algorithm(&self.signature_scheme)?);
+
+        let mut bit_string = vec![0u8];
+        bit_string.extend_from_slice(signature);
+        certificate.extend(der_encode(DER_BIT_STRING, &bit_string));
+
+        Ok(der_encode(DER_SEQUENCE, &certificate))
+    }
+}
+
+/// Returns the encoded issuer, subject and public key of a DER encoded certificate
+pub fn parse_certificate(certificate: &[u8]) -> Option<(X509Name, X509Name, X509PublicKey)> {
+    let (certificate, _) = der_expect(certificate, DER_SEQUENCE)?;
+    let (tbs_certificate, _) = der_expect(certificate, DER_SEQUENCE)?;
+
+    let mut rest = tbs_certificate;
+    if rest.first() == Some(&DER_CONTEXT_0) {
+        // version
+        rest = der_read(rest)?.2;
+    }
+    let (_serial, rest) = der_expect(rest, DER_INTEGER)?;
+    let (_signature, rest) = der_expect(rest, DER_SEQUENCE)?;
+    let (issuer, rest) = der_element(rest)?;
+    let (_validity, rest) = der_expect(rest, DER_SEQUENCE)?;
+    let (subject, rest) = der_element(rest)?;
+    let (public_key, _) = der_element(rest)?;
+
+    Some((
+        X509Name(issuer.to_vec()),
+        X509Name(subject.to_vec()),
+        X509PublicKey(public_key.to_vec()),
+    ))
+}
+
+fn signature_algorithm(scheme: &SignatureScheme) -> Result<Vec<u8>, FnError> {
+    let (oid, null_parameters) = match scheme {
+        SignatureScheme::RSA_PKCS1_SHA256 => (OID_SHA256_WITH_RSA, true),
+        SignatureScheme::RSA_PKCS1_SHA384 => (OID_SHA384_WITH_RSA, true),
+        SignatureScheme::RSA_PKCS1_SHA512 => (OID_SHA512_WITH_RSA, true),
+        SignatureScheme::ECDSA_NISTP256_SHA256 => (OID_ECDSA_WITH_SHA256, false),
+        SignatureScheme::RSA_PSS_SHA256 => return Ok(RSA_PSS_SHA256_ALGORITHM.to_vec()),
+        _ => {
+            return Err(FnError::Unknown(
+                "Signature scheme is not supported in certificates".to_string(),
+            ))
+        }
+    };
+
+    let mut algorithm = der_encode(DER_OID, oid);
+    if null_parameters {
+        algorithm.extend(der_encode(DER_NULL, &[]));
+    }
+    Ok(der_encode(DER_SEQUENCE, &algorithm))
+}
+
+/// Encodes seconds since the UNIX epoch as UTCTime between 1950 and 2049 and as GeneralizedTime
+/// otherwise
+fn encode_time(seconds: u64) -> Vec<u8> {
+    let days = (seconds / 86400) as i64;
+    let seconds_of_day = seconds % 86400;
+    let (year, month, day) = civil_from_days(days);
+    let time = format!(
+        "{:02}{:02}{:02}{:02}{:02}Z",
+        month,
+        day,
+        seconds_of_day / 3600,
+        (seconds_of_day / 60) % 60,
+        seconds_of_day % 60
+    );
+
+    if (1950..2050).contains(&year) {
+        der_encode(
+            DER_UTC_TIME,
+            format!("{:02}{}", year % 100, time).as_bytes(),
+        )
+    } else {
+        der_encode(
+            DER_GENERALIZED_TIME,
+            format!("{:04}{}", year, time).as_bytes(),
+        )
+    }
+}
+
+/// Converts days since the UNIX epoch to a date of the proleptic Gregorian calendar
+/// (<http://howardhinnant.github.io/date_algorithms.html#civil_from_days>)
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let day_of_era = z.rem_euclid(146097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = (day_of_year - (153 * month_index + 2) / 5 + 1) as u32;
+    let month = if month_index < 10 {
+        month_index + 3
+    } else {
+        month_index - 9
+    } as u32;
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+
+    (year, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{static_certs::ALICE_CERT, tls::fn_impl::*};
+
+    fn leaf_certificate(
+        sign: fn(&X509TbsCertificate, &Vec<u8>) -> Result<Vec<u8>, FnError>,
+    ) -> Vec<u8> {
+        let alice_cert = fn_alice_cert().unwrap();
+        let extensions = [
+            fn_x509_san_dns_extension(&b"example.com".to_vec()).unwrap(),
+            fn_x509_key_usage_extension(&fn_x509_key_usage_leaf().unwrap()).unwrap(),
+            fn_x509_basic_constraints_extension(&false).unwrap(),
+            fn_x509_server_auth_extension().unwrap(),
+        ]
+        .iter()
+        .fold(
+            fn_x509_extensions_new().unwrap(),
+            |extensions, extension| fn_x509_extensions_append(&extensions, extension).unwrap(),
+        );
+
+        let tbs = fn_x509_tbs_certificate(
+            &1,
+            &fn_rsa_pkcs1_signature_algorithm().unwrap(),
+            &fn_x509_subject(&alice_cert).unwrap(),
+            &fn_x509_validity_default().unwrap(),
+            &fn_x509_name_append_common_name(
+                &fn_x509_name_empty().unwrap(),
+                &fn_x509_common_name().unwrap(),
+            )
+            .unwrap(),
+            &fn_x509_public_key(&alice_cert).unwrap(),
+            &extensions,
+        )
+        .unwrap();
+
+        sign(&tbs, &fn_alice_key().unwrap()).unwrap()
+    }
+
+    fn verify_with_alice(certificate: &[u8]) -> Result<(), webpki::Error> {
+        let alice_cert = fn_alice_cert().unwrap();
+        let trust_anchors = [webpki::TrustAnchor::try_from_cert_der(&alice_cert).unwrap()];
+
+        webpki::EndEntityCert::try_from(certificate)?.verify_is_valid_tls_server_cert(
+            &[&webpki::RSA_PKCS1_2048_8192_SHA256],
+            &webpki::TlsServerTrustAnchors(&trust_anchors),
+            &[],
+            webpki::Time::from_seconds_since_unix_epoch(1700000000),
+        )
+    }
+
+    #[test]
+    fn test_sign_with_alice() {
+        assert_eq!(verify_with_alice(&leaf_certificate(fn_x509_sign)), Ok(()));
+    }
+
+    #[test]
+    fn test_sign_broken() {
+        assert_eq!(
+            verify_with_alice(&leaf_certificate(fn_x509_sign_broken)),
+            Err(webpki::Error::InvalidSignatureForPublicKey)
+        );
+    }
+
+    #[test]
+    fn test_parse_certificate() {
+        let (issuer, subject, public_key) = parse_certificate(ALICE_CERT.1).unwrap();
+        // The static certificates are self-signed
+        assert_eq!(issuer.0, subject.0);
+        assert_eq!(public_key.0[0], DER_SEQUENCE);
+    }
+
+    #[test]
+    fn test_encode_time() {
+        assert_eq!(encode_time(0), der_encode(DER_UTC_TIME, b"700101000000Z"));
+        // 2022-07-05 10:18:12, the notBefore of the static certificates
+        assert_eq!(
+            encode_time(1657016292),
+            der_encode(DER_UTC_TIME, b"220705101812Z")
+        );
+        // 2050-01-01 00:00:00
+        assert_eq!(
+            encode_time(2524608000),
+            der_encode(DER_GENERALIZED_TIME, b"20500101000000Z")
+        );
+    }
+
+    #[test]
+    fn test_key_usage() {
+        // digitalSignature and keyEncipherment
+        assert_eq!(
+            X509Extension::key_usage(0b101).value,
+            vec![DER_BIT_STRING, 0x02, 0x05, 0xa0]
+        );
+        // keyCertSign and cRLSign
+        assert_eq!(
+            X509Extension::key_usage(0b1100000).value,
+            vec![DER_BIT_STRING, 0x02, 0x01, 0x06]
+        );
+        // decipherOnly
+        assert_eq!(
+            X509Extension::key_usage(1 << 8).value,
+            vec![DER_BIT_STRING, 0x03, 0x07, 0x00, 0x80]
+        );
+    }
+}
@@ -295,6 +295,9 @@ impl WolfSSL {
         let mut ctx = match descriptor.tls_version {
             TLSVersion::V1_3 => SslContext::new(SslMethod::tls_client_13())?,
             TLSVersion::V1_2 => SslContext::new(SslMethod::tls_client_12())?,
+            TLSVersion::V1_1 => SslContext::new(SslMethod::tls_client_11())?,
+            TLSVersion::V1_0 => SslContext::new(SslMethod::tls_client_10())?,
+            TLSVersion::SSL3 => SslContext::new(SslMethod::ssl_client_3())?,
         };
 
         ctx.disable_session_cache()?;
@@ -348,6 +351,9 @@ impl WolfSSL {
         let mut ctx = match descriptor.tls_version {
             TLSVersion::V1_3 => SslContext::new(SslMethod::tls_server_13())?,
             TLSVersion::V1_2 => SslContext::new(SslMethod::tls_server_12())?,
+            TLSVersion::V1_1 => SslContext::new(SslMethod::tls_server_11())?,
+            TLSVersion::V1_0 => SslContext::new(SslMethod::tls_server_10())?,
+            TLSVersion::SSL3 => SslContext::new(SslMethod::ssl_server_3())?,
         };
 
         // Mitigates "2. Misuse of sessions of different TLS versions (1.2, 1.3) from the session cache"
@@ -387,6 +393,13 @@ impl WolfSSL {
         // We expect two tickets like in OpenSSL
         #[cfg(not(feature = "wolfssl430"))]
         ctx.set_num_tickets(2)?;
+
+        // The key pair is generated once per context, clients learn it through the retry_configs
+        #[cfg(feature = "tls13-ech")]
+        if descriptor.ech {
+            // DHKEM(X25519, HKDF-SHA256), HKDF-SHA256, AES-128-GCM
+            ctx.generate_ech_config("tlspuffin", 0x0020, 0x0001, 0x0001)?;
+        }
         Ok(ctx)
     }
 
@@ -475,12 +488,16 @@ impl WolfSSL {
                                 client_random: Default::default(), // TODO
                                 server_random: Default::default(), // TODO
                                 session_id: Default::default(),    // TODO
+                                negotiated_version: to_tls_version(context.version()),
                                 authenticate_peer,
                                 peer_certificate: context
                                     .get_peer_certificate()
                                     .map(|cert| SmallVec::from_vec(cert))
                                     .unwrap_or_else(|| SmallVec::new()),
                                 master_secret: Default::default(), // TODO
+                                extended_master_secret: None,      // TODO
+                                renegotiation: false,              // TODO
+                                secure_renegotiation: None,        // TODO
                                 chosen_cipher: 0,                  // TODO
                                 available_ciphers: Default::default(), // TODO
                                 signature_algorithm: 0,            // TODO
@@ -526,6 +543,17 @@ impl WolfSSL {
     }
 }
 
+fn to_tls_version((major, minor): (u8, u8)) -> Option<TLSVersion> {
+    match (major, minor) {
+        (3, 4) => Some(TLSVersion::V1_3),
+        (3, 3) => Some(TLSVersion::V1_2),
+        (3, 2) => Some(TLSVersion::V1_1),
+        (3, 1) => Some(TLSVersion::V1_0),
+        (3, 0) => Some(TLSVersion::SSL3),
+        _ => None,
+    }
+}
+
 pub enum MaybeError {
     Ok,
     Err(Error),